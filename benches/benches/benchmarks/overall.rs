@@ -43,6 +43,7 @@ fn block_assembler_config() -> BlockAssemblerConfig {
         hash_type: hash_type.into(),
         args,
         message: Default::default(),
+        reward_shares: vec![],
     }
 }
 
@@ -4,6 +4,7 @@ use ckb_channel::{self as channel, select, Sender};
 use ckb_error::{Error, InternalErrorKind};
 use ckb_logger::{self, debug, error, info, log_enabled, trace, warn};
 use ckb_metrics::{metrics, Timer};
+use ckb_notify::ReorgEvent;
 use ckb_proposal_table::ProposalTable;
 #[cfg(debug_assertions)]
 use ckb_rust_unstable_port::IsSorted;
@@ -179,7 +180,7 @@ impl ChainService {
                     },
                     recv(truncate_receiver) -> msg => match msg {
                         Ok(Request { responder, arguments: target_tip_hash }) => {
-                            let _ = responder.send(self.truncate(&target_tip_hash));
+                            let _ = responder.send(self.truncate(&target_tip_hash).map(|_| ()));
                         },
                         _ => {
                             error!("truncate_receiver closed");
@@ -214,9 +215,10 @@ impl ChainService {
         fork
     }
 
-    // Truncate the main chain
-    // Use for testing only
-    pub(crate) fn truncate(&mut self, target_tip_hash: &Byte32) -> Result<(), Error> {
+    // Truncate the main chain to `target_tip_hash`, the blocks after it are detached and
+    // deleted with their exts and epochs, so they will be verified again if received later.
+    // Returns the fork changes, the detached blocks are in ascending order.
+    pub fn truncate(&mut self, target_tip_hash: &Byte32) -> Result<ForkChanges, Error> {
         let snapshot = Arc::clone(&self.shared.snapshot());
         assert!(snapshot.is_main_chain(target_tip_hash));
 
@@ -235,8 +237,15 @@ impl ChainService {
         db_txn.insert_tip_header(&target_tip_header)?;
         db_txn.insert_current_epoch_ext(&target_epoch_ext)?;
 
-        for blk in fork.attached_blocks() {
-            db_txn.delete_block(&blk.hash(), blk.transactions().len())?;
+        for blk in fork.detached_blocks() {
+            let hash = blk.hash();
+            // the epoch started after this block
+            if let Some(epoch_ext) = snapshot.get_epoch_ext(&hash) {
+                db_txn.delete_epoch_ext(&hash, &epoch_ext)?;
+            }
+            db_txn.delete_block_epoch_index(&hash)?;
+            db_txn.delete_block_ext(&hash)?;
+            db_txn.delete_block(&hash, blk.transactions().len())?;
         }
         db_txn.commit()?;
 
@@ -257,7 +266,7 @@ impl ChainService {
 
         // NOTE: Dont update tx-pool when truncate
 
-        Ok(())
+        Ok(fork)
     }
 
     // process_block will do block verify
@@ -306,7 +315,7 @@ impl ChainService {
             return Ok(false);
         }
         // non-contextual verify
-        if !switch.disable_non_contextual() {
+        if !switch.skip_non_contextual() {
             self.non_contextual_verify(&block)?;
         }
 
@@ -442,6 +451,12 @@ impl ChainService {
                     error!("notify new_uncle error {}", e);
                 }
             }
+            if fork.has_detached() {
+                self.shared.notify_controller().notify_reorg(ReorgEvent {
+                    detached_blocks: fork.detached_blocks().iter().map(|b| b.header()).collect(),
+                    attached_blocks: fork.attached_blocks().iter().map(|b| b.header()).collect(),
+                });
+            }
             let block_ref: &BlockView = &block;
             self.shared
                 .notify_controller()
@@ -2,6 +2,7 @@
 
 use bitflags::bitflags;
 use ckb_verification::Switch as VerificationSwitch;
+use std::str::FromStr;
 
 bitflags! {
     pub struct Switch: u32 {
@@ -16,6 +17,9 @@ bitflags! {
                                     Self::DISABLE_TWO_PHASE_COMMIT.bits | Self::DISABLE_DAOHEADER.bits |
                                     Self::DISABLE_REWARD.bits |
                                     Self::DISABLE_NON_CONTEXTUAL.bits;
+        // The caller has done the non-contextual verification, e.g. the import workers.
+        // Unlike DISABLE_NON_CONTEXTUAL it is not part of DISABLE_ALL, so scripts still run.
+        const NON_CONTEXTUAL_VERIFIED   = 0b01000000;
     }
 }
 
@@ -27,6 +31,35 @@ impl Switch {
     pub fn disable_non_contextual(self) -> bool {
         self.contains(Switch::DISABLE_NON_CONTEXTUAL)
     }
+
+    pub fn skip_non_contextual(self) -> bool {
+        self.disable_non_contextual() || self.contains(Switch::NON_CONTEXTUAL_VERIFIED)
+    }
+}
+
+impl FromStr for Switch {
+    type Err = String;
+
+    /// Parses a comma separated list of flag names, e.g. `disable-epoch,disable-reward`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .try_fold(Switch::NONE, |switch, name| {
+                let flag = match name {
+                    "none" => Switch::NONE,
+                    "disable-epoch" => Switch::DISABLE_EPOCH,
+                    "disable-uncles" => Switch::DISABLE_UNCLES,
+                    "disable-two-phase-commit" => Switch::DISABLE_TWO_PHASE_COMMIT,
+                    "disable-daoheader" => Switch::DISABLE_DAOHEADER,
+                    "disable-reward" => Switch::DISABLE_REWARD,
+                    "disable-non-contextual" => Switch::DISABLE_NON_CONTEXTUAL,
+                    "disable-all" => Switch::DISABLE_ALL,
+                    _ => return Err(format!("unknown switch {}", name)),
+                };
+                Ok(switch | flag)
+            })
+    }
 }
 
 impl VerificationSwitch for Switch {
@@ -1,10 +1,10 @@
 use crate::chain::ChainService;
 use crate::{chain::ChainController, switch::Switch};
-use ckb_app_config::BlockAssemblerConfig;
-use ckb_chain_spec::consensus::Consensus;
+use ckb_app_config::{BlockAssemblerConfig, RewardShareConfig};
+use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder, MAX_REWARD_SHARES};
 use ckb_dao_utils::genesis_dao_data;
 use ckb_jsonrpc_types::BlockTemplate;
-use ckb_jsonrpc_types::ScriptHashType;
+use ckb_jsonrpc_types::{JsonBytes, ScriptHashType};
 use ckb_shared::shared::Shared;
 use ckb_shared::shared::SharedBuilder;
 use ckb_shared::Snapshot;
@@ -17,7 +17,7 @@ use ckb_types::{
         TransactionBuilder, TransactionView,
     },
     h256,
-    packed::{Block, CellInput, CellOutput, CellOutputBuilder, OutPoint},
+    packed::{Block, CellInput, CellOutput, CellOutputBuilder, OutPoint, SplitCellbaseWitness},
     prelude::*,
     H256,
 };
@@ -26,16 +26,24 @@ use lazy_static::lazy_static;
 use std::sync::Arc;
 
 fn start_chain(consensus: Option<Consensus>) -> (ChainController, Shared) {
-    let mut builder = SharedBuilder::default();
-    if let Some(consensus) = consensus {
-        builder = builder.consensus(consensus);
-    }
     let config = BlockAssemblerConfig {
         code_hash: h256!("0x0"),
         args: Default::default(),
         hash_type: ScriptHashType::Data,
         message: Default::default(),
+        reward_shares: vec![],
     };
+    start_chain_with_block_assembler(consensus, config)
+}
+
+fn start_chain_with_block_assembler(
+    consensus: Option<Consensus>,
+    config: BlockAssemblerConfig,
+) -> (ChainController, Shared) {
+    let mut builder = SharedBuilder::default();
+    if let Some(consensus) = consensus {
+        builder = builder.consensus(consensus);
+    }
     let (shared, table) = builder
         .block_assembler_config(Some(config))
         .build()
@@ -87,6 +95,51 @@ fn test_get_block_template() {
     assert!(block_verify.verify(&block).is_ok());
 }
 
+#[test]
+fn test_get_block_template_with_reward_shares() {
+    let share = |args: u8, weight: u32| RewardShareConfig {
+        code_hash: h256!("0x1"),
+        hash_type: ScriptHashType::Data,
+        args: JsonBytes::from_vec(vec![args]),
+        weight,
+    };
+    let mut config = BlockAssemblerConfig {
+        code_hash: h256!("0x0"),
+        args: Default::default(),
+        hash_type: ScriptHashType::Data,
+        message: Default::default(),
+        reward_shares: vec![share(1, 1), share(2, 2), share(3, 3)],
+    };
+    assert!(config.check_reward_shares().is_ok());
+
+    let consensus = ConsensusBuilder::default()
+        .split_reward_activation(Some(0))
+        .build();
+    let (_chain_controller, shared) =
+        start_chain_with_block_assembler(Some(consensus), config.clone());
+    let block_template = shared
+        .tx_pool_controller()
+        .get_block_template(None, None, None)
+        .unwrap()
+        .unwrap();
+    let block: Block = block_template.into();
+    let block = block.as_advanced_builder().build();
+
+    let witness = block.transactions()[0].witnesses().get(0).unwrap();
+    let split = SplitCellbaseWitness::from_slice(&witness.raw_data()).unwrap();
+    assert_eq!(split.shares().len(), 3);
+    let block_verify = BlockVerifier::new(shared.consensus());
+    assert!(block_verify.verify(&block).is_ok());
+
+    // the configs whose witness is rejected by the cellbase verifier are rejected too
+    config.reward_shares.push(share(4, 0));
+    assert!(config.check_reward_shares().is_err());
+    config.reward_shares = (0..=MAX_REWARD_SHARES as u8)
+        .map(|args| share(args, 1))
+        .collect();
+    assert!(config.check_reward_shares().is_err());
+}
+
 fn gen_block(parent_header: &HeaderView, nonce: u128, epoch: &EpochExt) -> BlockView {
     let number = parent_header.number() + 1;
     let cellbase = create_cellbase(number, epoch);
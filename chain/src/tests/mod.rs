@@ -6,6 +6,8 @@ mod find_fork;
 mod load_input_data_hash_cell;
 mod non_contextual_block_txs_verify;
 mod reward;
+mod switch;
 mod truncate;
+mod tx_status;
 mod uncle;
 mod util;
@@ -0,0 +1,23 @@
+use crate::switch::Switch;
+
+#[test]
+fn test_switch_from_str() {
+    assert_eq!("".parse::<Switch>(), Ok(Switch::NONE));
+    assert_eq!("none".parse::<Switch>(), Ok(Switch::NONE));
+    assert_eq!("disable-all".parse::<Switch>(), Ok(Switch::DISABLE_ALL));
+    assert_eq!(
+        "disable-epoch, disable-reward".parse::<Switch>(),
+        Ok(Switch::DISABLE_EPOCH | Switch::DISABLE_REWARD)
+    );
+    assert!("disable-script".parse::<Switch>().is_err());
+}
+
+#[test]
+fn test_non_contextual_verified() {
+    let switch = Switch::DISABLE_ALL - Switch::DISABLE_NON_CONTEXTUAL;
+    assert!(!switch.skip_non_contextual());
+    let switch = switch | Switch::NON_CONTEXTUAL_VERIFIED;
+    assert!(switch.skip_non_contextual());
+    assert!(!switch.disable_non_contextual());
+    assert!(!switch.disable_all());
+}
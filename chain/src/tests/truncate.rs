@@ -42,7 +42,13 @@ fn test_truncate() {
             .unwrap();
     }
 
-    chain_service.truncate(&target.hash()).unwrap();
+    let fork = chain_service.truncate(&target.hash()).unwrap();
 
     assert_eq!(shared.snapshot().tip_header(), &target);
+    assert_eq!(fork.detached_blocks().len(), 10);
+    for blk in fork.detached_blocks() {
+        assert!(shared.store().get_block_header(&blk.hash()).is_none());
+        assert!(shared.store().get_block_ext(&blk.hash()).is_none());
+        assert!(shared.store().get_block_epoch_index(&blk.hash()).is_none());
+    }
 }
@@ -0,0 +1,57 @@
+use crate::tests::util::{create_transaction, start_chain};
+use ckb_tx_pool::{pool::TxPoolStatus, PlugTarget, TxEntry};
+use ckb_types::{
+    bytes::Bytes,
+    core::{capacity_bytes, Capacity, TransactionBuilder},
+    packed::CellOutput,
+    prelude::*,
+};
+
+#[test]
+fn test_tx_status() {
+    let (_chain_controller, shared, _parent) = start_chain(None);
+    let tx_pool = shared.tx_pool_controller();
+    let genesis = shared.consensus().genesis_block().clone();
+    let genesis_tx = |index: usize| genesis.transactions()[index].hash();
+
+    let pending_tx = create_transaction(&genesis_tx(1), 1);
+    let proposed_tx = create_transaction(&genesis_tx(2), 2);
+    let entry = |tx| TxEntry::new(tx, 0, Capacity::shannons(100), 100, vec![]);
+    tx_pool
+        .plug_entry(vec![entry(pending_tx.clone())], PlugTarget::Pending)
+        .unwrap();
+    tx_pool
+        .plug_entry(vec![entry(proposed_tx.clone())], PlugTarget::Proposed)
+        .unwrap();
+    match tx_pool.fetch_tx_status(pending_tx.hash()).unwrap() {
+        Some(TxPoolStatus::Pending(_)) => {}
+        status => panic!("unexpected status {:?}", status),
+    }
+    match tx_pool.fetch_tx_status(proposed_tx.hash()).unwrap() {
+        Some(TxPoolStatus::Proposed(_)) => {}
+        status => panic!("unexpected status {:?}", status),
+    }
+
+    // the batch is rejected because of its second tx, which has no inputs
+    let first_tx = create_transaction(&genesis_tx(3), 3);
+    let invalid_tx = TransactionBuilder::default()
+        .output(
+            CellOutput::new_builder()
+                .capacity(capacity_bytes!(100).pack())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+    let last_tx = create_transaction(&genesis_tx(4), 4);
+    let ret = tx_pool
+        .submit_txs(vec![first_tx.clone(), invalid_tx.clone(), last_tx.clone()])
+        .unwrap();
+    assert!(ret.is_err());
+    match tx_pool.fetch_tx_status(invalid_tx.hash()).unwrap() {
+        Some(TxPoolStatus::Rejected(_)) => {}
+        status => panic!("unexpected status {:?}", status),
+    }
+    // only the tx which caused the error is recorded
+    assert_eq!(tx_pool.fetch_tx_status(first_tx.hash()).unwrap(), None);
+    assert_eq!(tx_pool.fetch_tx_status(last_tx.hash()).unwrap(), None);
+}
@@ -29,6 +29,15 @@ pub fn run_app(version: Version) -> Result<(), ExitCode> {
             }
             _ => {}
         },
+        (cli::CMD_CONFIG, Some(matches)) => match matches.subcommand() {
+            (cli::CMD_CHECK, Some(matches)) => {
+                return subcommand::config_check(Setup::root_dir_from_matches(&matches)?);
+            }
+            (cli::CMD_MIGRATE, Some(matches)) => {
+                return subcommand::config_migrate(Setup::root_dir_from_matches(&matches)?);
+            }
+            _ => {}
+        },
         _ => {
             // continue
         }
@@ -45,6 +54,9 @@ pub fn run_app(version: Version) -> Result<(), ExitCode> {
         (cli::CMD_IMPORT, Some(matches)) => subcommand::import(setup.import(&matches)?),
         (cli::CMD_STATS, Some(matches)) => subcommand::stats(setup.stats(&matches)?),
         (cli::CMD_RESET_DATA, Some(matches)) => subcommand::reset_data(setup.reset_data(&matches)?),
+        (cli::CMD_BACKUP, Some(matches)) => subcommand::backup(setup.backup(&matches)?),
+        (cli::CMD_DB_CHECK, Some(matches)) => subcommand::db_check(setup.db_check(&matches)?),
+        (cli::CMD_ROLLBACK, Some(matches)) => subcommand::rollback(setup.rollback(&matches)?),
         _ => unreachable!(),
     }
 }
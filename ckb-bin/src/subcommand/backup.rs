@@ -0,0 +1,48 @@
+use ckb_app_config::{BackupArgs, ExitCode};
+use ckb_db::RocksDB;
+use ckb_instrument::Backup;
+use ckb_network::peer_store::PeerStore;
+use ckb_store::{ChainDB, COLUMNS};
+
+// The databases are opened as they are, without migrations or any services, so the backup
+// doesn't change the data it copies.
+pub fn backup(args: BackupArgs) -> Result<(), ExitCode> {
+    let db = RocksDB::open_with_check(&args.config.db, COLUMNS).map_err(|err| {
+        eprintln!("Backup error: {}", err);
+        eprintln!("Use the `backup_database` RPC in the Debug module if the node is running");
+        ExitCode::Failure
+    })?;
+
+    let mut backup = Backup::new(ChainDB::new(db, args.config.store), args.target);
+    if args.config.indexer.db.path.exists() {
+        let db = RocksDB::open_with_check(&args.config.indexer.db, ckb_indexer::COLUMNS).map_err(
+            |err| {
+                eprintln!("Backup error: {}", err);
+                ExitCode::Failure
+            },
+        )?;
+        backup = backup.indexer_db(db);
+    }
+    let peer_store_path = args.config.network.peer_store_path();
+    if peer_store_path.exists() {
+        let db = PeerStore::load_from_dir_or_default(peer_store_path)
+            .flushed_db()
+            .map_err(|err| {
+                eprintln!("Backup error: {}", err);
+                ExitCode::Failure
+            })?;
+        if let Some(db) = db {
+            backup = backup.peer_store_db(db);
+        }
+    }
+
+    let manifest = backup.execute().map_err(|err| {
+        eprintln!("Backup error: {}", err);
+        ExitCode::Failure
+    })?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&manifest).expect("serialize backup manifest")
+    );
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use ckb_app_config::{check_config, migrate_config, ConfigFile, ExitCode};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONFIG_FILES: &[ConfigFile] = &[ConfigFile::CKB, ConfigFile::Miner];
+
+pub fn config_check(root_dir: PathBuf) -> Result<(), ExitCode> {
+    let mut passed = true;
+    for (file, path) in config_files(&root_dir)? {
+        let name = file.file_name();
+        let content = fs::read_to_string(&path)?;
+        match check_config(file, &content) {
+            Ok(issues) if issues.is_empty() => println!("{}: ok", name),
+            Ok(issues) => {
+                passed = false;
+                for issue in issues {
+                    println!("{}: {}", name, issue);
+                }
+            }
+            Err(err) => {
+                passed = false;
+                eprintln!("{}: {}", name, err);
+            }
+        }
+    }
+
+    if passed {
+        Ok(())
+    } else {
+        println!("run `ckb config migrate` to rewrite the deprecated keys");
+        Err(ExitCode::Config)
+    }
+}
+
+pub fn config_migrate(root_dir: PathBuf) -> Result<(), ExitCode> {
+    let mut passed = true;
+    for (file, path) in config_files(&root_dir)? {
+        let name = file.file_name();
+        let content = fs::read_to_string(&path)?;
+        let migration = migrate_config(file, &content).map_err(|err| {
+            eprintln!("Config migrate error: {}", err);
+            ExitCode::Config
+        })?;
+
+        if migration.is_changed() {
+            let backup = path.with_file_name(format!("{}.bak", name));
+            fs::copy(&path, &backup)?;
+            fs::write(&path, &migration.content)?;
+            for change in migration.changes.iter() {
+                println!("{}: migrated {}", name, change);
+            }
+            println!(
+                "{}: migrated, the original file is saved as {}",
+                name,
+                backup.display()
+            );
+        } else {
+            println!("{}: up to date", name);
+        }
+        for issue in migration.remaining.iter() {
+            passed = false;
+            println!("{}: {}, it must be fixed by hand", name, issue);
+        }
+    }
+
+    if passed {
+        Ok(())
+    } else {
+        Err(ExitCode::Config)
+    }
+}
+
+fn config_files(root_dir: &Path) -> Result<Vec<(ConfigFile, PathBuf)>, ExitCode> {
+    let files: Vec<_> = CONFIG_FILES
+        .iter()
+        .map(|file| (*file, root_dir.join(file.file_name())))
+        .filter(|(_, path)| path.exists())
+        .collect();
+    if files.is_empty() {
+        eprintln!("Not a CKB directory, initialize one with `ckb init`.");
+        return Err(ExitCode::Config);
+    }
+    Ok(files)
+}
@@ -0,0 +1,50 @@
+use ckb_app_config::{DBCheckArgs, ExitCode};
+use ckb_instrument::DBCheck;
+use ckb_shared::shared::SharedBuilder;
+
+pub fn db_check(args: DBCheckArgs) -> Result<(), ExitCode> {
+    let (shared, _) = SharedBuilder::with_db_config(&args.config.db)
+        .consensus(args.consensus)
+        .build()
+        .map_err(|err| {
+            eprintln!("DB check error: {:?}", err);
+            ExitCode::Failure
+        })?;
+
+    let report = DBCheck::new(shared)
+        .repair(args.repair)
+        .execute()
+        .map_err(|err| {
+            eprintln!("DB check error: {}", err);
+            ExitCode::Failure
+        })?;
+
+    for inconsistency in report.inconsistencies.iter() {
+        println!("{}", inconsistency);
+    }
+    let unrepairable = report
+        .inconsistencies
+        .iter()
+        .filter(|inconsistency| !inconsistency.repairable)
+        .count();
+    println!(
+        "checked {} blocks, found {} inconsistencies, {} unrepairable",
+        report.checked_blocks,
+        report.inconsistencies.len(),
+        unrepairable
+    );
+
+    if report.inconsistencies.is_empty() {
+        Ok(())
+    } else if report.repaired && unrepairable == 0 {
+        println!("all the inconsistencies are repaired");
+        Ok(())
+    } else {
+        if !report.repaired {
+            println!("run with --repair to rebuild the repairable ones");
+        } else {
+            println!("the unrepairable ones require `ckb reset-data --database` and a resync");
+        }
+        Err(ExitCode::Failure)
+    }
+}
@@ -1,8 +1,16 @@
 use ckb_app_config::{ExitCode, ExportArgs};
-use ckb_instrument::Export;
+use ckb_instrument::{Compression, Export, Format};
 use ckb_shared::shared::SharedBuilder;
 
 pub fn export(args: ExportArgs) -> Result<(), ExitCode> {
+    let format: Format = args.format.parse().map_err(|err| {
+        eprintln!("Export error: {}", err);
+        ExitCode::Cli
+    })?;
+    let compression: Compression = args.compression.parse().map_err(|err| {
+        eprintln!("Export error: {}", err);
+        ExitCode::Cli
+    })?;
     let (shared, _) = SharedBuilder::with_db_config(&args.config.db)
         .consensus(args.consensus)
         .build()
@@ -10,8 +18,13 @@ pub fn export(args: ExportArgs) -> Result<(), ExitCode> {
             eprintln!("Export error: {:?}", err);
             ExitCode::Failure
         })?;
-    Export::new(shared, args.target).execute().map_err(|err| {
-        eprintln!("Export error: {:?}", err);
-        ExitCode::Failure
-    })
+    Export::new(shared, args.target)
+        .format(format)
+        .compression(compression)
+        .range(args.from, args.to)
+        .execute()
+        .map_err(|err| {
+            eprintln!("Export error: {:?}", err);
+            ExitCode::Failure
+        })
 }
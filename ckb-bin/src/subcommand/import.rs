@@ -1,9 +1,13 @@
 use ckb_app_config::{ExitCode, ImportArgs};
-use ckb_chain::chain::ChainService;
+use ckb_chain::{chain::ChainService, switch::Switch};
 use ckb_instrument::Import;
 use ckb_shared::shared::SharedBuilder;
 
 pub fn import(args: ImportArgs) -> Result<(), ExitCode> {
+    let switch: Switch = args.switch.parse().map_err(|err| {
+        eprintln!("Import error: {}", err);
+        ExitCode::Cli
+    })?;
     let (shared, table) = SharedBuilder::with_db_config(&args.config.db)
         .consensus(args.consensus)
         .build()
@@ -12,10 +16,11 @@ pub fn import(args: ImportArgs) -> Result<(), ExitCode> {
             ExitCode::Failure
         })?;
 
-    let chain_service = ChainService::new(shared, table);
+    let chain_service = ChainService::new(shared.clone(), table);
     let chain_controller = chain_service.start::<&str>(Some("ImportChainService"));
 
-    Import::new(chain_controller, args.source)
+    Import::new(chain_controller, shared, args.source)
+        .switch(switch)
         .execute()
         .map_err(|err| {
             eprintln!("Import error: {:?}", err);
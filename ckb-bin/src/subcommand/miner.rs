@@ -1,12 +1,19 @@
-use ckb_app_config::{ExitCode, MinerArgs, MinerConfig};
+use ckb_app_config::{
+    EaglesongSimpleConfig, ExitCode, ExtraHashFunction, MinerArgs, MinerConfig, MinerWorkerConfig,
+};
 use ckb_channel::unbounded;
-use ckb_miner::{Client, Miner};
+use ckb_miner::{benchmark_eaglesong, Client, Miner};
 use std::thread;
+use std::time::Duration;
 
 pub fn miner(args: MinerArgs) -> Result<(), ExitCode> {
     let (new_work_tx, new_work_rx) = unbounded();
     let MinerConfig { client, workers } = args.config;
 
+    if let Some(duration) = args.benchmark {
+        return benchmark(&workers, duration);
+    }
+
     let mut client = Client::new(new_work_tx, client);
     let mut miner = Miner::new(
         args.pow_engine,
@@ -26,3 +33,37 @@ pub fn miner(args: MinerArgs) -> Result<(), ExitCode> {
     miner.run();
     Ok(())
 }
+
+fn benchmark(workers: &[MinerWorkerConfig], duration: Duration) -> Result<(), ExitCode> {
+    let configs = workers
+        .iter()
+        .filter_map(|worker| match worker {
+            MinerWorkerConfig::EaglesongSimple(config) => Some(config),
+            MinerWorkerConfig::Dummy(_) => None,
+        })
+        .collect::<Vec<&EaglesongSimpleConfig>>();
+    if configs.is_empty() {
+        eprintln!("No EaglesongSimple worker is configured to benchmark");
+        return Err(ExitCode::Config);
+    }
+
+    for (name, extra_hash_function) in &[
+        ("Eaglesong", None),
+        ("EaglesongBlake2b", Some(ExtraHashFunction::Blake2b)),
+    ] {
+        println!("{} benchmark, {}s", name, duration.as_secs());
+        let mut total = 0.0;
+        for config in &configs {
+            for hash_rate in benchmark_eaglesong(config.threads, *extra_hash_function, duration) {
+                println!(
+                    "  {}: {:>12.3} H/s",
+                    hash_rate.worker,
+                    hash_rate.hash_rate()
+                );
+                total += hash_rate.hash_rate();
+            }
+        }
+        println!("  total: {:>12.3} H/s", total);
+    }
+    Ok(())
+}
@@ -1,3 +1,6 @@
+mod backup;
+mod config;
+mod db_check;
 mod export;
 mod import;
 mod init;
@@ -6,9 +9,13 @@ mod miner;
 mod peer_id;
 mod replay;
 mod reset_data;
+mod rollback;
 mod run;
 mod stats;
 
+pub use self::backup::backup;
+pub use self::config::{config_check, config_migrate};
+pub use self::db_check::db_check;
 pub use self::export::export;
 pub use self::import::import;
 pub use self::init::init;
@@ -17,5 +24,6 @@ pub use self::miner::miner;
 pub use self::peer_id::peer_id;
 pub use self::replay::replay;
 pub use self::reset_data::reset_data;
+pub use self::rollback::rollback;
 pub use self::run::run;
 pub use self::stats::stats;
@@ -0,0 +1,103 @@
+use ckb_app_config::{ExitCode, RollbackArgs};
+use ckb_chain::chain::ChainService;
+use ckb_indexer::DefaultIndexerStore;
+use ckb_shared::shared::{Shared, SharedBuilder};
+use ckb_store::ChainStore;
+use ckb_types::{
+    core::{BlockNumber, BlockView},
+    packed::Byte32,
+    prelude::*,
+    H256,
+};
+use std::str::FromStr;
+
+pub fn rollback(args: RollbackArgs) -> Result<(), ExitCode> {
+    let (shared, table) = SharedBuilder::with_db_config(&args.config.db)
+        .consensus(args.consensus)
+        .build()
+        .map_err(|err| {
+            eprintln!("Rollback error: {:?}", err);
+            ExitCode::Failure
+        })?;
+
+    let target = resolve_target(&shared, &args.to).map_err(|err| {
+        eprintln!("Rollback error: {}", err);
+        ExitCode::Failure
+    })?;
+    let tip = shared.snapshot().tip_header().clone();
+    if target == tip.hash() {
+        println!("the tip is already #{} {:#x}", tip.number(), tip.hash());
+        return Ok(());
+    }
+
+    // The indexer reads the detached blocks and the cells they spend from the chain store, so it
+    // is rewound before the truncation deletes them. If the truncation fails, the indexer is just
+    // behind the tip and catches up on the next start.
+    if args.config.indexer.db.path.exists() {
+        let store = DefaultIndexerStore::new(&args.config.indexer, shared.clone());
+        store.rollback(&blocks_after(&shared, &target));
+    }
+
+    let fork = ChainService::new(shared.clone(), table)
+        .truncate(&target)
+        .map_err(|err| {
+            eprintln!("Rollback error: {}", err);
+            ExitCode::Failure
+        })?;
+    let detached_blocks: Vec<_> = fork.detached_blocks().iter().cloned().collect();
+
+    for block in detached_blocks.iter() {
+        println!(
+            "detached #{} {:#x} ({} transactions)",
+            block.number(),
+            block.hash(),
+            block.transactions().len()
+        );
+    }
+    let tip = shared.snapshot().tip_header().clone();
+    println!(
+        "rolled back to #{} {:#x}, {} blocks detached",
+        tip.number(),
+        tip.hash(),
+        detached_blocks.len()
+    );
+    Ok(())
+}
+
+// The main chain blocks after the target, in ascending order
+fn blocks_after(shared: &Shared, target: &Byte32) -> Vec<BlockView> {
+    let snapshot = shared.snapshot();
+    let target_number = snapshot
+        .get_block_number(target)
+        .expect("target is on the main chain");
+    (target_number + 1..=snapshot.tip_header().number())
+        .filter_map(|number| {
+            snapshot
+                .get_block_hash(number)
+                .and_then(|hash| snapshot.get_block(&hash))
+        })
+        .collect()
+}
+
+fn resolve_target(shared: &Shared, to: &str) -> Result<Byte32, String> {
+    let snapshot = shared.snapshot();
+    if to.starts_with("0x") {
+        let hash = H256::from_str(&to[2..])
+            .map_err(|err| format!("invalid block hash {}: {}", to, err))?
+            .pack();
+        if !snapshot.is_main_chain(&hash) {
+            return Err(format!("block {} is not on the main chain", to));
+        }
+        Ok(hash)
+    } else {
+        let number = BlockNumber::from_str(to)
+            .map_err(|err| format!("invalid block number {}: {}", to, err))?;
+        snapshot.get_block_hash(number).ok_or_else(|| {
+            format!(
+                "block #{} is beyond the tip #{}",
+                number,
+                snapshot.tip_header().number()
+            )
+        })
+    }
+}
@@ -2,19 +2,21 @@ use crate::helper::deadlock_detection;
 use ckb_app_config::{BlockAssemblerConfig, ExitCode, RunArgs};
 use ckb_build_info::Version;
 use ckb_chain::chain::ChainService;
+use ckb_config_reloader::ConfigReloader;
 use ckb_jsonrpc_types::ScriptHashType;
-use ckb_logger::info_target;
+use ckb_logger::{error_target, info_target};
 use ckb_network::{
     CKBProtocol, DefaultExitHandler, ExitHandler, NetworkService, NetworkState, SupportProtocols,
 };
 use ckb_network_alert::alert_relayer::AlertRelayer;
 use ckb_resource::Resource;
-use ckb_rpc::{RpcServer, ServiceBuilder};
+use ckb_rpc::{HealthCheck, RpcServer, ServiceBuilder};
 use ckb_shared::shared::{Shared, SharedBuilder};
 use ckb_store::ChainStore;
 use ckb_sync::{NetTimeProtocol, Relayer, SyncShared, Synchronizer};
 use ckb_types::{core::cell::setup_system_cell_cache, prelude::*};
 use ckb_verification::{GenesisVerifier, Verifier};
+use std::path::Path;
 use std::sync::Arc;
 
 const SECP256K1_BLAKE160_SIGHASH_ALL_ARG_LEN: usize = 20;
@@ -25,6 +27,8 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
     let block_assembler_config = sanitize_block_assembler_config(&args)?;
     let miner_enable = block_assembler_config.is_some();
     let exit_handler = DefaultExitHandler::default();
+    // the config applied to the node, the reloaded config is compared with it
+    let applied_config = args.config.as_ref().clone();
 
     let (shared, table) = SharedBuilder::with_db_config(&args.config.db)
         .consensus(args.consensus)
@@ -40,6 +44,7 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
 
     // Verify genesis every time starting node
     verify_genesis(&shared)?;
+    verify_restored_backup(&shared, &args.config.data_dir)?;
 
     setup_system_cell_cache(
         shared.consensus().genesis_block(),
@@ -83,7 +88,6 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
     let relayer = Relayer::new(
         chain_controller.clone(),
         Arc::clone(&sync_shared),
-        args.config.tx_pool.min_fee_rate,
         args.config.tx_pool.max_tx_verify_cycles,
     );
     let net_timer = NetTimeProtocol::default();
@@ -133,12 +137,31 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
     .start(Some("NetworkService"))
     .expect("Start network service failed");
 
+    let config_reloader = Arc::new(
+        ConfigReloader::new(
+            args.root_dir,
+            applied_config,
+            shared.clone(),
+            network_controller.clone(),
+        )
+        .block_assembler_advanced(args.block_assembler_advanced),
+    );
+
+    let health_check = HealthCheck::new(
+        &args.config.rpc,
+        Arc::clone(&sync_shared),
+        network_controller.clone(),
+    )
+    .map_err(|err| {
+        eprintln!("Run error: failed to start the health check: {}", err);
+        ExitCode::Failure
+    })?;
+
     let builder = ServiceBuilder::new(&args.config.rpc)
         .enable_chain(shared.clone())
         .enable_pool(
             shared.clone(),
             Arc::clone(&sync_shared),
-            args.config.tx_pool.min_fee_rate,
             args.config.rpc.reject_ill_transactions,
         )
         .enable_miner(
@@ -151,18 +174,23 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
         .enable_stats(shared.clone(), synchronizer, Arc::clone(&alert_notifier))
         .enable_experiment(shared.clone())
         .enable_integration_test(shared.clone(), network_controller.clone(), chain_controller)
-        .enable_alert(alert_verifier, alert_notifier, network_controller)
+        .enable_alert(alert_verifier, alert_notifier, network_controller.clone())
         .enable_indexer(&args.config.indexer, shared.clone())
-        .enable_debug();
+        .enable_debug(
+            shared.clone(),
+            network_controller,
+            Some(Arc::clone(&config_reloader)),
+        );
     let io_handler = builder.build();
 
-    let _rpc_server = RpcServer::new(args.config.rpc, io_handler, shared.notify_controller());
+    let _rpc_server = RpcServer::new(
+        args.config.rpc,
+        io_handler,
+        shared.notify_controller(),
+        health_check,
+    );
 
-    let exit_handler_clone = exit_handler.clone();
-    ctrlc::set_handler(move || {
-        exit_handler_clone.notify_exit();
-    })
-    .expect("Error setting Ctrl-C handler");
+    handle_signals(exit_handler.clone(), config_reloader);
     exit_handler.wait_for_exit();
 
     info_target!(crate::LOG_TARGET_MAIN, "Finishing work, please wait...");
@@ -170,6 +198,40 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
     Ok(())
 }
 
+// SIGHUP reloads the config, SIGINT and SIGTERM stop the node
+#[cfg(unix)]
+fn handle_signals(exit_handler: DefaultExitHandler, config_reloader: Arc<ConfigReloader>) {
+    use signal_hook::{iterator::Signals, SIGHUP, SIGINT, SIGTERM};
+    use std::thread;
+
+    let signals = Signals::new(&[SIGINT, SIGTERM, SIGHUP]).expect("Error setting signal handler");
+    thread::Builder::new()
+        .name("SignalHandler".to_string())
+        .spawn(move || {
+            for signal in signals.forever() {
+                if signal == SIGHUP {
+                    info_target!(crate::LOG_TARGET_MAIN, "SIGHUP received, reload ckb.toml");
+                    if let Err(err) = config_reloader.reload() {
+                        error_target!(crate::LOG_TARGET_MAIN, "reload config error: {}", err);
+                    }
+                } else {
+                    exit_handler.notify_exit();
+                    break;
+                }
+            }
+        })
+        .expect("Start signal handler failed");
+}
+
+// the config can only be reloaded by the `reload_config` RPC
+#[cfg(not(unix))]
+fn handle_signals(exit_handler: DefaultExitHandler, _config_reloader: Arc<ConfigReloader>) {
+    ctrlc::set_handler(move || {
+        exit_handler.notify_exit();
+    })
+    .expect("Error setting Ctrl-C handler");
+}
+
 fn verify_genesis(shared: &Shared) -> Result<(), ExitCode> {
     GenesisVerifier::new()
         .verify(shared.consensus())
@@ -179,6 +241,25 @@ fn verify_genesis(shared: &Shared) -> Result<(), ExitCode> {
         })
 }
 
+fn verify_restored_backup(shared: &Shared, data_dir: &Path) -> Result<(), ExitCode> {
+    match ckb_instrument::verify_restored_backup(shared.store(), data_dir) {
+        Ok(Some(manifest)) => {
+            info_target!(
+                crate::LOG_TARGET_MAIN,
+                "verified the data restored from backup #{} {:#x}",
+                manifest.tip_number.value(),
+                manifest.tip_hash
+            );
+            Ok(())
+        }
+        Ok(None) => Ok(()),
+        Err(err) => {
+            eprintln!("backup error: {}", err);
+            Err(ExitCode::Failure)
+        }
+    }
+}
+
 fn sanitize_block_assembler_config(
     args: &RunArgs,
 ) -> Result<Option<BlockAssemblerConfig>, ExitCode> {
@@ -187,6 +268,10 @@ fn sanitize_block_assembler_config(
         args.config.block_assembler.clone(),
     ) {
         (true, Some(block_assembler)) => {
+            if let Err(err) = block_assembler.check_reward_shares() {
+                eprintln!("Invalid block assembler config: {}", err);
+                return Err(ExitCode::Config);
+            }
             let check_lock_code_hash = |code_hash| -> Result<bool, ExitCode> {
                 let secp_cell_data =
                     Resource::bundled("specs/cells/secp256k1_blake160_sighash_all".to_string())
@@ -1,18 +1,52 @@
 use ckb_app_config::{ExitCode, StatsArgs};
+use ckb_chain_iter::ChainIterator;
+use ckb_jsonrpc_types::Script;
 use ckb_shared::shared::{Shared, SharedBuilder};
+use ckb_shared::Snapshot;
 use ckb_store::ChainStore;
-use ckb_types::core::BlockNumber;
+use ckb_types::{
+    core::{
+        cell::{resolve_transaction, CellProvider, CellStatus},
+        BlockNumber, BlockView, Capacity, Cycle, EpochNumber,
+    },
+    packed,
+    prelude::*,
+    H256,
+};
+use ckb_verification::ScriptVerifier;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Number of lock scripts listed in the top lock scripts report.
+const TOP_LOCKS: usize = 10;
+/// Cell data of a DAO deposit cell.
+const DAO_DEPOSIT_DATA: [u8; 8] = [0; 8];
 
 pub fn stats(args: StatsArgs) -> Result<(), ExitCode> {
     let stats = Statics::build(args)?;
-    stats.print_uncle_rate()?;
+    let report = stats.report()?;
+    match stats.format {
+        StatsFormat::Text => report.print_text(),
+        StatsFormat::Csv => report.print_csv(),
+        StatsFormat::Json => report.print_json(),
+    }
     Ok(())
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum StatsFormat {
+    Text,
+    Csv,
+    Json,
+}
+
 struct Statics {
     shared: Shared,
     from: BlockNumber,
     to: BlockNumber,
+    format: StatsFormat,
+    /// Re-runs scripts to collect cycles
+    cycles: bool,
 }
 
 impl Statics {
@@ -34,11 +68,24 @@ impl Statics {
             return Err(ExitCode::Cli);
         }
 
-        Ok(Statics { shared, from, to })
+        let format = match args.format.as_str() {
+            "text" => StatsFormat::Text,
+            "csv" => StatsFormat::Csv,
+            "json" => StatsFormat::Json,
+            _ => return Err(ExitCode::Cli),
+        };
+
+        Ok(Statics {
+            shared,
+            from,
+            to,
+            format,
+            cycles: args.cycles,
+        })
     }
 
     // exclusively below and above inclusively (from..to]
-    pub fn print_uncle_rate(&self) -> Result<(), ExitCode> {
+    fn uncle_rate(&self) -> Result<UncleRate, ExitCode> {
         let store = self.shared.store();
         let to_ext = store
             .get_block_hash(self.to)
@@ -49,15 +96,646 @@ impl Statics {
             .and_then(|hash| store.get_block_ext(&hash))
             .ok_or_else(|| ExitCode::IO)?;
 
-        let block_nums = self.to - self.from;
-        let uncle_nums = to_ext.total_uncles_count - from_ext.total_uncles_count;
+        let blocks = self.to - self.from;
+        let uncles = to_ext.total_uncles_count - from_ext.total_uncles_count;
+
+        Ok(UncleRate {
+            uncles,
+            blocks,
+            rate: uncles as f64 / blocks as f64,
+        })
+    }
+
+    // exclusively below and above inclusively (from..to]
+    fn report(&self) -> Result<Report, ExitCode> {
+        let snapshot = self.shared.snapshot();
+        let consensus = snapshot.consensus();
+        let dao_type_hash = consensus.dao_type_hash();
+        let max_block_cycles = consensus.max_block_cycles();
+
+        let mut report = Report {
+            from: self.from,
+            to: self.to,
+            uncle_rate: self.uncle_rate()?,
+            ..Default::default()
+        };
+        if self.cycles {
+            report.cycles = Some(CycleStats {
+                max_block_cycles,
+                ..Default::default()
+            });
+        }
+
+        let mut parent_timestamp = store_timestamp(snapshot.as_ref(), self.from)?;
+        let mut locks = HashMap::new();
+        for block in
+            ChainIterator::with_range(snapshot.as_ref(), Some(self.from + 1), Some(self.to))
+        {
+            let header = block.header();
+            let epoch = report
+                .epochs
+                .entry(header.epoch().number())
+                .or_insert_with(|| EpochStats {
+                    difficulty: format!("{:#x}", header.difficulty()),
+                    blocks: 0,
+                });
+            epoch.blocks += 1;
+
+            report
+                .block_interval
+                .add(header.timestamp().saturating_sub(parent_timestamp) / 1000);
+            parent_timestamp = header.timestamp();
+
+            report.txs_count.add(block.transactions().len() as u64);
+            for tx in block.transactions() {
+                report
+                    .tx_size
+                    .add(tx.data().serialized_size_in_block() as u64);
+            }
+
+            if let Some(ext) = snapshot.get_block_ext(&block.hash()) {
+                report.total_fees += ext.txs_fees.iter().map(|fee| fee.as_u64()).sum::<u64>();
+            }
+
+            if let Some(ref dao_type_hash) = dao_type_hash {
+                report
+                    .dao
+                    .add_block(snapshot.as_ref(), &block, dao_type_hash);
+            }
+
+            for output in block
+                .transactions()
+                .iter()
+                .flat_map(|tx| tx.outputs().into_iter())
+            {
+                let capacity: Capacity = output.capacity().unpack();
+                let lock = output.lock();
+                let entry = locks
+                    .entry(lock.calc_script_hash())
+                    .or_insert_with(|| LockStats {
+                        lock_hash: lock.calc_script_hash().unpack(),
+                        lock: lock.into(),
+                        capacity: 0,
+                        cells: 0,
+                    });
+                entry.capacity += capacity.as_u64();
+                entry.cells += 1;
+            }
+
+            if let Some(ref mut cycles) = report.cycles {
+                cycles.add(block_cycles(snapshot.as_ref(), &block, max_block_cycles)?);
+            }
+        }
+
+        let mut locks: Vec<_> = locks.into_iter().map(|(_, stats)| stats).collect();
+        locks.sort_by(|a, b| b.capacity.cmp(&a.capacity));
+        locks.truncate(TOP_LOCKS);
+        report.top_locks = locks;
+
+        Ok(report)
+    }
+}
+
+fn store_timestamp(snapshot: &Snapshot, number: BlockNumber) -> Result<u64, ExitCode> {
+    snapshot
+        .get_block_hash(number)
+        .and_then(|hash| snapshot.get_block_header(&hash))
+        .map(|header| header.timestamp())
+        .ok_or_else(|| ExitCode::IO)
+}
+
+// Re-runs the scripts of a committed block, cellbase excluded
+fn block_cycles(
+    snapshot: &Snapshot,
+    block: &BlockView,
+    max_block_cycles: Cycle,
+) -> Result<Cycle, ExitCode> {
+    let provider = HistoricalCellProvider(snapshot);
+    let mut cycles = 0;
+    for tx in block.transactions().into_iter().skip(1) {
+        let resolved =
+            resolve_transaction(tx, &mut HashSet::new(), &provider, snapshot).map_err(|err| {
+                eprintln!("Stats error: {}", err);
+                ExitCode::Failure
+            })?;
+        cycles += ScriptVerifier::new(&resolved, snapshot)
+            .verify(max_block_cycles)
+            .map_err(|err| {
+                eprintln!("Stats error: {}", err);
+                ExitCode::Failure
+            })?;
+    }
+    Ok(cycles)
+}
+
+// Resolves cells regardless of live or dead, so committed transactions can be resolved again.
+struct HistoricalCellProvider<'a>(&'a Snapshot);
 
+impl<'a> CellProvider for HistoricalCellProvider<'a> {
+    fn cell(&self, out_point: &packed::OutPoint, with_data: bool) -> CellStatus {
+        let tx_hash = out_point.tx_hash();
+        let index = out_point.index().unpack();
+        self.0
+            .get_cell_meta(&tx_hash, index)
+            .map(|mut cell_meta| {
+                if with_data {
+                    cell_meta.mem_cell_data = self.0.get_cell_data(&tx_hash, index);
+                }
+                CellStatus::live_cell(cell_meta)
+            })
+            .unwrap_or(CellStatus::Unknown)
+    }
+}
+
+#[derive(Default, Serialize)]
+struct Report {
+    from: BlockNumber,
+    to: BlockNumber,
+    uncle_rate: UncleRate,
+    epochs: BTreeMap<EpochNumber, EpochStats>,
+    /// Seconds between a block and its parent
+    block_interval: Histogram,
+    txs_count: Histogram,
+    /// Serialized transaction size in bytes
+    tx_size: Histogram,
+    cycles: Option<CycleStats>,
+    /// Sum of transaction fees in shannons
+    total_fees: u64,
+    dao: DaoStats,
+    top_locks: Vec<LockStats>,
+}
+
+#[derive(Default, Serialize)]
+struct UncleRate {
+    uncles: u64,
+    blocks: u64,
+    rate: f64,
+}
+
+#[derive(Serialize)]
+struct EpochStats {
+    difficulty: String,
+    blocks: u64,
+}
+
+/// Histogram with power of two buckets, keyed by the lower bound of each bucket.
+#[derive(Default, Serialize)]
+struct Histogram {
+    count: u64,
+    sum: u64,
+    max: u64,
+    buckets: BTreeMap<u64, u64>,
+}
+
+impl Histogram {
+    fn add(&mut self, value: u64) {
+        let lower = if value == 0 {
+            0
+        } else {
+            1 << (63 - value.leading_zeros())
+        };
+        *self.buckets.entry(lower).or_insert(0) += 1;
+        self.count += 1;
+        self.sum += value;
+        self.max = self.max.max(value);
+    }
+
+    fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum as f64 / self.count as f64
+        }
+    }
+
+    fn ranges(&self) -> impl Iterator<Item = (String, u64)> + '_ {
+        self.buckets.iter().map(|(lower, count)| {
+            let upper = if *lower == 0 { 0 } else { lower * 2 - 1 };
+            (format!("{}-{}", lower, upper), *count)
+        })
+    }
+}
+
+#[derive(Default, Serialize)]
+struct CycleStats {
+    max_block_cycles: Cycle,
+    total: Cycle,
+    max: Cycle,
+    /// Average ratio of block cycles to `max_block_cycles`
+    average_usage: f64,
+    blocks: u64,
+}
+
+impl CycleStats {
+    fn add(&mut self, cycles: Cycle) {
+        self.total += cycles;
+        self.max = self.max.max(cycles);
+        self.blocks += 1;
+        self.average_usage = self.total as f64 / self.blocks as f64 / self.max_block_cycles as f64;
+    }
+}
+
+/// DAO volumes in shannons
+#[derive(Default, Serialize)]
+struct DaoStats {
+    deposit: u64,
+    deposit_count: u64,
+    withdraw: u64,
+    withdraw_count: u64,
+}
+
+impl DaoStats {
+    fn add_block(
+        &mut self,
+        snapshot: &Snapshot,
+        block: &BlockView,
+        dao_type_hash: &packed::Byte32,
+    ) {
+        let is_dao = |output: &packed::CellOutput| {
+            output
+                .type_()
+                .to_opt()
+                .map(|script| &script.calc_script_hash() == dao_type_hash)
+                .unwrap_or(false)
+        };
+
+        for tx in block.transactions().iter().skip(1) {
+            for (output, data) in tx.outputs_with_data_iter() {
+                if is_dao(&output) && data[..] == DAO_DEPOSIT_DATA[..] {
+                    let capacity: Capacity = output.capacity().unpack();
+                    self.deposit += capacity.as_u64();
+                    self.deposit_count += 1;
+                }
+            }
+
+            // withdrawing from DAO always requires header deps
+            if tx.header_deps().is_empty() {
+                continue;
+            }
+            for out_point in tx.input_pts_iter() {
+                let tx_hash = out_point.tx_hash();
+                let index = out_point.index().unpack();
+                if let Some(cell_meta) = snapshot.get_cell_meta(&tx_hash, index) {
+                    if !is_dao(&cell_meta.cell_output) {
+                        continue;
+                    }
+                    let withdrawing = snapshot
+                        .get_cell_data(&tx_hash, index)
+                        .map(|(data, _)| data[..] != DAO_DEPOSIT_DATA[..])
+                        .unwrap_or(false);
+                    if withdrawing {
+                        let capacity: Capacity = cell_meta.cell_output.capacity().unpack();
+                        self.withdraw += capacity.as_u64();
+                        self.withdraw_count += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LockStats {
+    lock_hash: H256,
+    lock: Script,
+    /// Output capacity in shannons
+    capacity: u64,
+    cells: u64,
+}
+
+impl Report {
+    fn print_text(&self) {
+        println!("range: ({}, {}]", self.from, self.to);
         println!(
             "uncle_rate: {}/{}({})",
-            uncle_nums,
-            block_nums,
-            uncle_nums as f64 / block_nums as f64
+            self.uncle_rate.uncles, self.uncle_rate.blocks, self.uncle_rate.rate
+        );
+
+        println!("epochs:");
+        for (number, epoch) in self.epochs.iter() {
+            println!(
+                "  {}: difficulty {}, blocks {}",
+                number, epoch.difficulty, epoch.blocks
+            );
+        }
+
+        for (name, histogram) in self.histograms() {
+            println!(
+                "{}: count {}, mean {:.2}, max {}",
+                name,
+                histogram.count,
+                histogram.mean(),
+                histogram.max
+            );
+            for (range, count) in histogram.ranges() {
+                println!("  {}: {}", range, count);
+            }
+        }
+
+        if let Some(ref cycles) = self.cycles {
+            println!(
+                "cycles: total {}, max {}/{}, average usage {:.4}",
+                cycles.total, cycles.max, cycles.max_block_cycles, cycles.average_usage
+            );
+        }
+
+        println!("total_fees: {}", self.total_fees);
+        println!(
+            "dao: deposit {} in {} cells, withdraw {} in {} cells",
+            self.dao.deposit, self.dao.deposit_count, self.dao.withdraw, self.dao.withdraw_count
+        );
+
+        println!("top_locks:");
+        for lock in self.top_locks.iter() {
+            println!(
+                "  {:#x}: capacity {}, cells {}",
+                lock.lock_hash, lock.capacity, lock.cells
+            );
+        }
+    }
+
+    fn print_csv(&self) {
+        for row in self.csv_rows() {
+            println!("{}", row);
+        }
+    }
+
+    // Every row is `section,key,value`
+    fn csv_rows(&self) -> Vec<String> {
+        let mut rows = vec!["section,key,value".to_owned()];
+        rows.push(format!("range,from,{}", self.from));
+        rows.push(format!("range,to,{}", self.to));
+        rows.push(format!("uncle_rate,uncles,{}", self.uncle_rate.uncles));
+        rows.push(format!("uncle_rate,blocks,{}", self.uncle_rate.blocks));
+        rows.push(format!("uncle_rate,rate,{}", self.uncle_rate.rate));
+
+        for (number, epoch) in self.epochs.iter() {
+            rows.push(format!("epoch_difficulty,{},{}", number, epoch.difficulty));
+            rows.push(format!("epoch_blocks,{},{}", number, epoch.blocks));
+        }
+
+        for (name, histogram) in self.histograms() {
+            rows.push(format!("{},count,{}", name, histogram.count));
+            rows.push(format!("{},mean,{}", name, histogram.mean()));
+            rows.push(format!("{},max,{}", name, histogram.max));
+            for (range, count) in histogram.ranges() {
+                rows.push(format!("{},{},{}", name, range, count));
+            }
+        }
+
+        if let Some(ref cycles) = self.cycles {
+            rows.push(format!(
+                "cycles,max_block_cycles,{}",
+                cycles.max_block_cycles
+            ));
+            rows.push(format!("cycles,total,{}", cycles.total));
+            rows.push(format!("cycles,max,{}", cycles.max));
+            rows.push(format!("cycles,average_usage,{}", cycles.average_usage));
+        }
+
+        rows.push(format!("fees,total,{}", self.total_fees));
+        rows.push(format!("dao,deposit,{}", self.dao.deposit));
+        rows.push(format!("dao,deposit_count,{}", self.dao.deposit_count));
+        rows.push(format!("dao,withdraw,{}", self.dao.withdraw));
+        rows.push(format!("dao,withdraw_count,{}", self.dao.withdraw_count));
+
+        for lock in self.top_locks.iter() {
+            rows.push(format!(
+                "top_locks_capacity,{:#x},{}",
+                lock.lock_hash, lock.capacity
+            ));
+            rows.push(format!(
+                "top_locks_cells,{:#x},{}",
+                lock.lock_hash, lock.cells
+            ));
+        }
+
+        rows
+    }
+
+    fn print_json(&self) {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(self).expect("stats report serialize")
+        );
+    }
+
+    fn histograms(&self) -> Vec<(&'static str, &Histogram)> {
+        vec![
+            ("block_interval", &self.block_interval),
+            ("txs_count", &self.txs_count),
+            ("tx_size", &self.tx_size),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_chain::{chain::ChainService, switch::Switch};
+    use ckb_test_chain_utils::{always_success_cell, always_success_consensus};
+    use ckb_types::{
+        bytes::Bytes,
+        core::{BlockBuilder, HeaderView, TransactionBuilder, TransactionView},
+        packed::{CellInput, CellOutput},
+    };
+    use std::sync::Arc;
+
+    const HEIGHT: BlockNumber = 10;
+    const UNCLE_INCLUDED_AT: BlockNumber = 5;
+
+    fn cellbase(number: BlockNumber, lock_args: u8) -> TransactionView {
+        let (_, _, always_success_script) = always_success_cell();
+        let lock = always_success_script
+            .as_builder()
+            .args(Bytes::from(vec![lock_args]).pack())
+            .build();
+        let output = CellOutput::new_builder()
+            .capacity(Capacity::bytes(1000).unwrap().pack())
+            .lock(lock)
+            .build();
+        TransactionBuilder::default()
+            .input(CellInput::new_cellbase_input(number))
+            .output(output)
+            .output_data(Bytes::new().pack())
+            .witness(always_success_script.clone().into_witness())
+            .build()
+    }
+
+    fn block_builder(shared: &Shared, parent: &HeaderView, lock_args: u8) -> BlockBuilder {
+        let number = parent.number() + 1;
+        BlockBuilder::default()
+            .transaction(cellbase(number, lock_args))
+            .parent_hash(parent.hash())
+            .number(number.pack())
+            .epoch(
+                shared
+                    .consensus()
+                    .genesis_epoch_ext()
+                    .number_with_fraction(number)
+                    .pack(),
+            )
+            .timestamp((parent.timestamp() + 1000).pack())
+            .compact_target(parent.compact_target().pack())
+    }
+
+    // A chain in which the first 3 blocks pay to one lock and the others pay to another one,
+    // and the block #5 includes an uncle
+    fn setup_chain() -> Shared {
+        let (shared, table) = SharedBuilder::default()
+            .consensus(always_success_consensus())
+            .build()
+            .unwrap();
+        let chain = ChainService::new(shared.clone(), table).start::<&str>(None);
+        let mut parent = shared.consensus().genesis_block().header();
+        let mut uncle = None;
+        for number in 1..=HEIGHT {
+            let lock_args = if number <= 3 { 1 } else { 2 };
+            let mut builder = block_builder(&shared, &parent, lock_args);
+            if number == UNCLE_INCLUDED_AT - 1 {
+                uncle = Some(
+                    block_builder(&shared, &parent, 3)
+                        .nonce(1u128.pack())
+                        .build(),
+                );
+            }
+            if number == UNCLE_INCLUDED_AT {
+                builder = builder.uncle(uncle.take().unwrap().as_uncle());
+            }
+            let block = builder.build();
+            chain
+                .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+                .unwrap();
+            parent = block.header();
+        }
+        shared
+    }
+
+    fn statics(shared: &Shared, from: BlockNumber, to: BlockNumber, cycles: bool) -> Statics {
+        Statics {
+            shared: shared.clone(),
+            from,
+            to,
+            format: StatsFormat::Csv,
+            cycles,
+        }
+    }
+
+    #[test]
+    fn test_uncle_rate() {
+        let shared = setup_chain();
+
+        let uncle_rate = statics(&shared, 0, HEIGHT, false).uncle_rate().unwrap();
+        assert_eq!((uncle_rate.uncles, uncle_rate.blocks), (1, HEIGHT));
+        assert!((uncle_rate.rate - 0.1).abs() < f64::EPSILON);
+
+        let uncle_rate = statics(&shared, 1, UNCLE_INCLUDED_AT, false)
+            .uncle_rate()
+            .unwrap();
+        assert_eq!((uncle_rate.uncles, uncle_rate.blocks), (1, 4));
+
+        // the range excludes the lower bound
+        let uncle_rate = statics(&shared, UNCLE_INCLUDED_AT, HEIGHT, false)
+            .uncle_rate()
+            .unwrap();
+        assert_eq!((uncle_rate.uncles, uncle_rate.blocks), (0, 5));
+    }
+
+    #[test]
+    fn test_report() {
+        let shared = setup_chain();
+        let report = statics(&shared, 0, HEIGHT, true).report().unwrap();
+
+        let epochs: Vec<_> = report
+            .epochs
+            .iter()
+            .map(|(number, epoch)| (*number, epoch.blocks))
+            .collect();
+        assert_eq!(epochs, vec![(0, HEIGHT)]);
+
+        assert_eq!(report.block_interval.count, HEIGHT);
+        assert_eq!(report.block_interval.max, 1);
+        assert_eq!(
+            report.block_interval.buckets.iter().collect::<Vec<_>>(),
+            vec![(&1, &HEIGHT)]
+        );
+        assert_eq!(report.txs_count.sum, HEIGHT);
+        let cellbase_size = cellbase(1, 1).data().serialized_size_in_block() as u64;
+        assert_eq!(report.tx_size.max, cellbase_size);
+
+        // only the cellbases, whose scripts are not run
+        let cycles = report.cycles.as_ref().expect("cycles");
+        assert_eq!((cycles.blocks, cycles.total), (HEIGHT, 0));
+
+        let top_locks: Vec<_> = report
+            .top_locks
+            .iter()
+            .map(|lock| {
+                (
+                    lock.lock.args.as_bytes().to_vec(),
+                    lock.capacity,
+                    lock.cells,
+                )
+            })
+            .collect();
+        let capacity = Capacity::bytes(1000).unwrap().as_u64();
+        assert_eq!(
+            top_locks,
+            vec![(vec![2], capacity * 7, 7), (vec![1], capacity * 3, 3),]
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::default();
+        for value in &[0, 1, 2, 3, 4, 1000] {
+            histogram.add(*value);
+        }
+        assert_eq!(
+            (histogram.count, histogram.sum, histogram.max),
+            (6, 1010, 1000)
+        );
+        assert!((histogram.mean() - 1010.0 / 6.0).abs() < f64::EPSILON);
+        assert_eq!(
+            histogram.ranges().collect::<Vec<_>>(),
+            vec![
+                ("0-0".to_owned(), 1),
+                ("1-1".to_owned(), 1),
+                ("2-3".to_owned(), 2),
+                ("4-7".to_owned(), 1),
+                ("512-1023".to_owned(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        let shared = setup_chain();
+        let report = statics(&shared, 0, HEIGHT, false).report().unwrap();
+        let rows = report.csv_rows();
+
+        assert_eq!(rows[0], "section,key,value");
+        for row in rows.iter() {
+            assert_eq!(row.split(',').count(), 3, "{}", row);
+        }
+        for expected in &[
+            "range,from,0",
+            "range,to,10",
+            "uncle_rate,uncles,1",
+            "uncle_rate,rate,0.1",
+            "epoch_blocks,0,10",
+            "block_interval,1-1,10",
+            "txs_count,count,10",
+            "fees,total,0",
+        ] {
+            assert!(rows.iter().any(|row| row == expected), "{}", expected);
+        }
+        assert!(!rows.iter().any(|row| row.starts_with("cycles,")));
+        assert_eq!(
+            rows.iter()
+                .filter(|row| row.starts_with("top_locks_cells,"))
+                .count(),
+            2
         );
-        Ok(())
     }
 }
@@ -1,5 +1,6 @@
 use crate::db::cf_handle;
 use crate::{internal_error, Col, Result};
+use ckb_metrics::metrics;
 use libc::{self, c_char, size_t};
 use rocksdb::ops::{GetPinnedCF, Iterate, IterateCF, Read};
 use rocksdb::{
@@ -32,7 +33,9 @@ impl RocksDBSnapshot {
 
     pub fn get_pinned(&self, col: Col, key: &[u8]) -> Result<Option<DBPinnableSlice>> {
         let cf = cf_handle(&self.db, col)?;
-        self.get_pinned_cf_full(Some(cf), &key, None)
+        metrics!(counter, "ckb-db.read", 1, "column" => col);
+        self
+            .get_pinned_cf_full(Some(cf), &key, None)
             .map_err(internal_error)
     }
 }
@@ -1,5 +1,6 @@
 use crate::db::cf_handle;
 use crate::{internal_error, Col, Result};
+use ckb_metrics::{metrics, Timer};
 use rocksdb::ops::{DeleteCF, GetCF, PutCF};
 pub use rocksdb::{DBPinnableSlice, DBVector};
 use rocksdb::{
@@ -15,6 +16,7 @@ pub struct RocksDBTransaction {
 impl RocksDBTransaction {
     pub fn get(&self, col: Col, key: &[u8]) -> Result<Option<DBVector>> {
         let cf = cf_handle(&self.db, col)?;
+        metrics!(counter, "ckb-db.read", 1, "column" => col);
         self.inner.get_cf(cf, key).map_err(internal_error)
     }
 
@@ -43,7 +45,10 @@ impl RocksDBTransaction {
     }
 
     pub fn commit(&self) -> Result<()> {
-        self.inner.commit().map_err(internal_error)
+        let timer = Timer::start();
+        let ret = self.inner.commit().map_err(internal_error);
+        metrics!(timing, "ckb-db.write", timer.stop(), "operation" => "commit");
+        ret
     }
 
     pub fn rollback(&self) -> Result<()> {
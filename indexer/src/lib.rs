@@ -2,5 +2,5 @@ mod migrations;
 mod store;
 mod types;
 
-pub use store::{DefaultIndexerStore, IndexerStore};
+pub use store::{DefaultIndexerStore, IndexerStore, COLUMNS};
 pub use types::{CellTransaction, LiveCell, TransactionPoint};
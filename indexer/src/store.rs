@@ -20,7 +20,7 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
-const COLUMNS: u32 = 4;
+pub const COLUMNS: u32 = 4;
 
 /// +---------------------------------+---------------+--------------------------+
 /// |             Column              |      Key      |          Value           |
@@ -303,6 +303,11 @@ impl DefaultIndexerStore {
         }
     }
 
+    /// The underlying database, used to create a checkpoint of the indexer
+    pub fn db(&self) -> &RocksDB {
+        &self.db
+    }
+
     pub fn start<S: ToString>(self, thread_name: Option<S>) {
         let mut thread_builder = thread::Builder::new();
         if let Some(name) = thread_name {
@@ -422,6 +427,40 @@ impl DefaultIndexerStore {
         debug!("End sync index states with chain store");
     }
 
+    /// Rewind the index states after the chain is truncated.
+    ///
+    /// `detached_blocks` are the blocks truncated from the main chain in ascending order, they
+    /// may have been deleted from the chain store, so the index can't be rewound by
+    /// `sync_index_states`.
+    pub fn rollback(&self, detached_blocks: &[core::BlockView]) {
+        let first = match detached_blocks.first() {
+            Some(block) => block,
+            None => return,
+        };
+        let sync_lock = self.sync_lock.lock();
+        let target_index_state = LockHashIndexState {
+            block_number: first.header().number() - 1,
+            block_hash: first.header().parent_hash(),
+        };
+        let lock_hash_index_states = self.get_lock_hash_index_states();
+        self.commit_txn(|txn| {
+            lock_hash_index_states
+                .iter()
+                .filter(|(_, index_state)| index_state.block_number >= first.header().number())
+                .for_each(|(lock_hash, index_state)| {
+                    let mut index_lock_hashes = HashSet::new();
+                    index_lock_hashes.insert(lock_hash.to_owned());
+                    detached_blocks
+                        .iter()
+                        .rev()
+                        .filter(|block| block.header().number() <= index_state.block_number)
+                        .for_each(|block| self.detach_block(txn, &index_lock_hashes, block));
+                    txn.insert_lock_hash_index_state(lock_hash, &target_index_state);
+                });
+        });
+        drop(sync_lock);
+    }
+
     fn detach_block(
         &self,
         txn: &IndexerStoreTransaction,
@@ -1290,6 +1329,88 @@ mod tests {
         assert_eq!(tx32.hash(), transactions[1].created_by.tx_hash);
     }
 
+    #[test]
+    fn rollback() {
+        let (store, chain, shared) = setup("rollback");
+        let script1 = ScriptBuilder::default()
+            .code_hash(CODE_HASH_DAO.pack())
+            .hash_type(ScriptHashType::Data.into())
+            .build();
+        store.insert_lock_hash(&script1.calc_script_hash(), None);
+
+        let tx11 = TransactionBuilder::default()
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(1000).pack())
+                    .lock(script1.clone())
+                    .build(),
+            )
+            .output_data(Default::default())
+            .build();
+
+        let block1 = BlockBuilder::default()
+            .transaction(tx11.clone())
+            .header(
+                HeaderBuilder::default()
+                    .compact_target(DIFF_TWO.pack())
+                    .number(1.pack())
+                    .parent_hash(shared.genesis_hash())
+                    .build(),
+            )
+            .build();
+
+        let tx21 = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::new(tx11.hash(), 0), 0))
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(1000).pack())
+                    .lock(script1.clone())
+                    .build(),
+            )
+            .output_data(Default::default())
+            .build();
+
+        let block2 = BlockBuilder::default()
+            .transaction(tx21.clone())
+            .header(
+                HeaderBuilder::default()
+                    .compact_target(DIFF_TWO.pack())
+                    .number(2.pack())
+                    .parent_hash(block1.header().hash())
+                    .build(),
+            )
+            .build();
+
+        chain
+            .internal_process_block(Arc::new(block1.clone()), Switch::DISABLE_ALL)
+            .unwrap();
+        chain
+            .internal_process_block(Arc::new(block2.clone()), Switch::DISABLE_ALL)
+            .unwrap();
+        store.sync_index_states();
+
+        let cells = store.get_live_cells(&script1.calc_script_hash(), 0, 100, false);
+        assert_eq!(1, cells.len());
+        assert_eq!(tx21.hash(), cells[0].created_by.tx_hash);
+
+        chain.truncate(block1.hash()).unwrap();
+        store.rollback(&[block2]);
+
+        let cells = store.get_live_cells(&script1.calc_script_hash(), 0, 100, false);
+        assert_eq!(1, cells.len());
+        assert_eq!(tx11.hash(), cells[0].created_by.tx_hash);
+        let transactions = store.get_transactions(&script1.calc_script_hash(), 0, 100, false);
+        assert_eq!(1, transactions.len());
+        assert!(transactions[0].consumed_by.is_none());
+        assert_eq!(
+            Some(&block1.hash()),
+            store
+                .get_lock_hash_index_states()
+                .get(&script1.calc_script_hash())
+                .map(|index_state| &index_state.block_hash)
+        );
+    }
+
     #[test]
     fn consume_txs_in_same_block() {
         let (store, chain, shared) = setup("consume_txs_in_same_block");
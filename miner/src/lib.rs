@@ -6,6 +6,7 @@ mod worker;
 pub use crate::client::Client;
 pub use crate::error::Error;
 pub use crate::miner::Miner;
+pub use crate::worker::{benchmark_eaglesong, HashRate};
 
 use ckb_jsonrpc_types::BlockTemplate;
 use ckb_types::packed::Block;
@@ -1,9 +1,10 @@
 use crate::client::Client;
-use crate::worker::{start_worker, WorkerController, WorkerMessage};
+use crate::worker::{start_worker, HashRate, WorkerController, WorkerMessage};
 use crate::Work;
 use ckb_app_config::MinerWorkerConfig;
 use ckb_channel::{select, unbounded, Receiver};
 use ckb_logger::{debug, error, info};
+use ckb_metrics::metrics;
 use ckb_pow::PowEngine;
 use ckb_types::{
     packed::{Byte32, Header},
@@ -12,10 +13,13 @@ use ckb_types::{
 };
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use lru_cache::LruCache;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
 const WORK_CACHE_SIZE: usize = 32;
+const HASH_RATE_LOG_INTERVAL: Duration = Duration::from_secs(60);
 
 pub struct Miner {
     pub pow: Arc<dyn PowEngine>,
@@ -24,6 +28,9 @@ pub struct Miner {
     pub worker_controllers: Vec<WorkerController>,
     pub work_rx: Receiver<Work>,
     pub nonce_rx: Receiver<(Byte32, u128)>,
+    pub hash_rate_rx: Receiver<HashRate>,
+    pub hash_rates: BTreeMap<String, f64>,
+    pub hash_rate_logged_at: Instant,
     pub pb: ProgressBar,
     pub nonces_found: u128,
     pub stderr_is_tty: bool,
@@ -39,11 +46,20 @@ impl Miner {
         limit: u128,
     ) -> Miner {
         let (nonce_tx, nonce_rx) = unbounded();
+        let (hash_rate_tx, hash_rate_rx) = unbounded();
         let mp = MultiProgress::new();
 
         let worker_controllers = workers
             .iter()
-            .map(|config| start_worker(Arc::clone(&pow), config, nonce_tx.clone(), &mp))
+            .map(|config| {
+                start_worker(
+                    Arc::clone(&pow),
+                    config,
+                    nonce_tx.clone(),
+                    hash_rate_tx.clone(),
+                    &mp,
+                )
+            })
             .collect();
 
         let pb = mp.add(ProgressBar::new(100));
@@ -63,6 +79,9 @@ impl Miner {
             worker_controllers,
             work_rx,
             nonce_rx,
+            hash_rate_rx,
+            hash_rates: BTreeMap::new(),
+            hash_rate_logged_at: Instant::now(),
             pb,
             stderr_is_tty,
             limit,
@@ -97,7 +116,10 @@ impl Miner {
                         error!("nonce_rx closed");
                         break;
                     },
-                }
+                },
+                recv(self.hash_rate_rx) -> msg => if let Ok(hash_rate) = msg {
+                    self.record_hash_rate(hash_rate);
+                },
             };
         }
     }
@@ -138,6 +160,25 @@ impl Miner {
         }
     }
 
+    fn record_hash_rate(&mut self, hash_rate: HashRate) {
+        let rate = hash_rate.hash_rate();
+        metrics!(gauge, "ckb-miner.hash_rate", rate as i64, "worker" => hash_rate.worker.clone());
+        self.hash_rates.insert(hash_rate.worker, rate);
+
+        if self.hash_rate_logged_at.elapsed() >= HASH_RATE_LOG_INTERVAL {
+            let total: f64 = self.hash_rates.values().sum();
+            metrics!(gauge, "ckb-miner.hash_rate", total as i64, "worker" => "total");
+            let workers = self
+                .hash_rates
+                .iter()
+                .map(|(worker, rate)| format!("{}: {:.3}", worker, rate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            info!("hash rate: {:.3} H/s in total, {}", total, workers);
+            self.hash_rate_logged_at = Instant::now();
+        }
+    }
+
     fn notify_workers(&self, message: WorkerMessage) {
         for controller in self.worker_controllers.iter() {
             controller.send_message(message.clone());
@@ -1,4 +1,4 @@
-use super::{Worker, WorkerMessage};
+use super::{HashRate, Worker, WorkerMessage};
 use ckb_app_config::ExtraHashFunction;
 use ckb_channel::{Receiver, Sender};
 use ckb_hash::blake2b_256;
@@ -11,32 +11,60 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 pub struct EaglesongSimple {
+    name: String,
     start: bool,
     pow_hash: Option<Byte32>,
     target: U256,
     nonce_tx: Sender<(Byte32, u128)>,
     worker_rx: Receiver<WorkerMessage>,
+    hash_rate_tx: Sender<HashRate>,
     nonces_found: u128,
     pub(crate) extra_hash_function: Option<ExtraHashFunction>,
 }
 
 impl EaglesongSimple {
     pub fn new(
+        name: String,
         nonce_tx: Sender<(Byte32, u128)>,
         worker_rx: Receiver<WorkerMessage>,
+        hash_rate_tx: Sender<HashRate>,
         extra_hash_function: Option<ExtraHashFunction>,
     ) -> Self {
         Self {
+            name,
             start: true,
             pow_hash: None,
             target: U256::zero(),
             nonce_tx,
             worker_rx,
+            hash_rate_tx,
             nonces_found: 0,
             extra_hash_function,
         }
     }
 
+    /// Keep solving `pow_hash` for `duration` without polling worker messages,
+    /// the target is zero so that no nonce will be sent.
+    pub(crate) fn benchmark<G: FnMut() -> u128>(
+        &mut self,
+        pow_hash: &Byte32,
+        mut rng: G,
+        duration: Duration,
+    ) -> HashRate {
+        self.target = U256::zero();
+        let mut hashes = 0u64;
+        let start = Instant::now();
+        while start.elapsed() < duration {
+            self.solve(pow_hash, rng());
+            hashes += 1;
+        }
+        HashRate {
+            worker: self.name.clone(),
+            hashes,
+            elapsed: start.elapsed(),
+        }
+    }
+
     fn poll_worker_message(&mut self) {
         if let Ok(msg) = self.worker_rx.try_recv() {
             match msg {
@@ -93,16 +121,20 @@ impl Worker for EaglesongSimple {
 
                     let elapsed = start.elapsed();
                     if elapsed.as_millis() > STATE_UPDATE_DURATION_MILLIS {
-                        let elapsed_nanos: f64 = (elapsed.as_secs() * 1_000_000_000
-                            + u64::from(elapsed.subsec_nanos()))
-                            as f64
-                            / 1_000_000_000.0;
+                        let hash_rate = HashRate {
+                            worker: self.name.clone(),
+                            hashes: state_update_counter as u64,
+                            elapsed,
+                        };
                         progress_bar.set_message(&format!(
                             "hash rate: {:>10.3} / nonces found: {:>10}",
-                            state_update_counter as f64 / elapsed_nanos,
+                            hash_rate.hash_rate(),
                             self.nonces_found,
                         ));
                         progress_bar.inc(1);
+                        if let Err(err) = self.hash_rate_tx.send(hash_rate) {
+                            error!("hash_rate_tx send error {:?}", err);
+                        }
                         state_update_counter = 0;
                         start = Instant::now();
                     }
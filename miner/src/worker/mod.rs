@@ -1,11 +1,11 @@
 mod dummy;
 mod eaglesong_simple;
 
-use ckb_app_config::MinerWorkerConfig;
+use ckb_app_config::{ExtraHashFunction, MinerWorkerConfig};
 use ckb_channel::{unbounded, Sender};
 use ckb_logger::error;
 use ckb_pow::{DummyPowEngine, EaglesongBlake2bPowEngine, EaglesongPowEngine, PowEngine};
-use ckb_types::{packed::Byte32, U256};
+use ckb_types::{packed::Byte32, prelude::*, U256};
 use dummy::Dummy;
 use eaglesong_simple::EaglesongSimple;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
@@ -13,6 +13,7 @@ use rand::{random, Rng};
 use std::ops::Range;
 use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
 
 #[derive(Clone)]
 pub enum WorkerMessage {
@@ -21,6 +22,26 @@ pub enum WorkerMessage {
     NewWork { pow_hash: Byte32, target: U256 },
 }
 
+/// Hashes a worker thread tried during `elapsed`, reported back to the miner periodically.
+#[derive(Clone, Debug)]
+pub struct HashRate {
+    pub worker: String,
+    pub hashes: u64,
+    pub elapsed: Duration,
+}
+
+impl HashRate {
+    /// Hashes per second
+    pub fn hash_rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.hashes as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
 pub struct WorkerController {
     inner: Vec<Sender<WorkerMessage>>,
 }
@@ -62,6 +83,7 @@ pub fn start_worker(
     pow: Arc<dyn PowEngine>,
     config: &MinerWorkerConfig,
     nonce_tx: Sender<(Byte32, u128)>,
+    hash_rate_tx: Sender<HashRate>,
     mp: &MultiProgress,
 ) -> WorkerController {
     match config {
@@ -107,11 +129,17 @@ pub fn start_worker(
 
                         let (worker_tx, worker_rx) = unbounded();
                         let nonce_tx = nonce_tx.clone();
+                        let hash_rate_tx = hash_rate_tx.clone();
                         thread::Builder::new()
-                            .name(worker_name)
+                            .name(worker_name.clone())
                             .spawn(move || {
-                                let mut worker =
-                                    EaglesongSimple::new(nonce_tx, worker_rx, extra_hash_function);
+                                let mut worker = EaglesongSimple::new(
+                                    worker_name,
+                                    nonce_tx,
+                                    worker_rx,
+                                    hash_rate_tx,
+                                    extra_hash_function,
+                                );
                                 let rng = nonce_generator(nonce_range);
                                 worker.run(rng, pb);
                             })
@@ -128,6 +156,49 @@ pub fn start_worker(
     }
 }
 
+/// Run the `EaglesongSimple` workers against a synthetic work for `duration`,
+/// returns the hash rate of every thread.
+pub fn benchmark_eaglesong(
+    threads: usize,
+    extra_hash_function: Option<ExtraHashFunction>,
+    duration: Duration,
+) -> Vec<HashRate> {
+    let pow_hash: Byte32 = random::<[u8; 32]>().pack();
+    let handles = (0..threads)
+        .map(|i| {
+            let worker_name = format!("EaglesongSimple-Benchmark-{}", i);
+            let nonce_range = partition_nonce(i as u128, threads as u128);
+            let pow_hash = pow_hash.clone();
+            thread::Builder::new()
+                .name(worker_name.clone())
+                .spawn(move || {
+                    let (nonce_tx, _nonce_rx) = unbounded();
+                    let (hash_rate_tx, _hash_rate_rx) = unbounded();
+                    let (_worker_tx, worker_rx) = unbounded();
+                    let mut worker = EaglesongSimple::new(
+                        worker_name,
+                        nonce_tx,
+                        worker_rx,
+                        hash_rate_tx,
+                        extra_hash_function,
+                    );
+                    let rng = nonce_generator(nonce_range);
+                    worker.benchmark(&pow_hash, rng, duration)
+                })
+                .expect("Start `EaglesongSimple` benchmark thread failed")
+        })
+        .collect::<Vec<_>>();
+
+    handles
+        .into_iter()
+        .map(|handle| {
+            handle
+                .join()
+                .expect("`EaglesongSimple` benchmark thread panicked")
+        })
+        .collect()
+}
+
 pub trait Worker {
     fn run<G: FnMut() -> u128>(&mut self, rng: G, progress_bar: ProgressBar);
 }
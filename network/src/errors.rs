@@ -36,6 +36,8 @@ pub enum PeerStoreError {
     /// indicate the peer store is full
     EvictionFailed,
     Serde(serde_json::Error),
+    /// failed to read or write the peer store database
+    DB(ckb_error::Error),
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -1,4 +1,5 @@
 use crate::errors::{Error, P2PError};
+use crate::network_group::AsnMap;
 use crate::peer_registry::{ConnectionStatus, PeerRegistry};
 use crate::peer_store::{
     types::{AddrInfo, BannedAddr, IpPort, MultiaddrExt},
@@ -20,6 +21,7 @@ use crate::{
     Behaviour, CKBProtocol, Peer, PeerIndex, ProtocolId, ProtocolVersion, PublicKey, ServiceControl,
 };
 use ckb_app_config::NetworkConfig;
+use ckb_db::RocksDB;
 use ckb_logger::{debug, error, info, trace, warn};
 use ckb_stop_handler::{SignalSender, StopHandler};
 use ckb_util::{Condvar, Mutex, RwLock};
@@ -68,9 +70,12 @@ pub struct SessionInfo {
 pub struct NetworkState {
     pub(crate) peer_registry: RwLock<PeerRegistry>,
     pub(crate) peer_store: Mutex<PeerStore>,
+    /// Addrs are grouped by it if it is configured, otherwise by IP prefix
+    asn_map: Option<Arc<AsnMap>>,
     /// Node listened addresses
     pub(crate) listened_addrs: RwLock<Vec<Multiaddr>>,
-    dialing_addrs: RwLock<HashMap<PeerId, Instant>>,
+    /// The addresses being dialed and when the dials are sent
+    dialing_addrs: RwLock<HashMap<PeerId, (Multiaddr, Instant)>>,
     /// Node public addresses,
     /// includes manually public addrs and remote peer observed addrs
     public_addrs: RwLock<HashMap<Multiaddr, u8>>,
@@ -79,7 +84,9 @@ pub struct NetworkState {
     disconnecting_sessions: RwLock<HashSet<SessionId>>,
     local_private_key: secio::SecioKeyPair,
     local_peer_id: PeerId,
-    bootnodes: Vec<(PeerId, Multiaddr)>,
+    // the whitelist and the bootnodes are replaced when the config is reloaded
+    bootnodes: RwLock<Vec<(PeerId, Multiaddr)>>,
+    whitelist_peers: RwLock<Vec<(PeerId, Multiaddr)>>,
     pub(crate) config: NetworkConfig,
     pub(crate) active: AtomicBool,
     /// Node supported protocols
@@ -98,27 +105,38 @@ impl NetworkState {
             .chain(config.public_addresses.iter())
             .map(|addr| (addr.to_owned(), std::u8::MAX))
             .collect();
-        let peer_store = Mutex::new(PeerStore::load_from_dir_or_default(
+        // the ASN map must be loaded before the peer store, which groups addrs into buckets
+        let asn_map = match config.asmap {
+            Some(ref path) => {
+                let asn_map = AsnMap::load(path)?;
+                info!("Load {} prefixes from asmap {:?}", asn_map.len(), path);
+                Some(Arc::new(asn_map))
+            }
+            None => None,
+        };
+        let peer_store = Mutex::new(PeerStore::load_from_dir_with_asn_map(
             config.peer_store_path(),
+            asn_map.clone(),
         ));
         let bootnodes = config.bootnodes()?;
 
-        let whitelist_peers = config
-            .whitelist_peers()?
-            .iter()
-            .map(|(peer_id, _)| peer_id.to_owned())
-            .collect::<Vec<_>>();
+        let whitelist_peers = config.whitelist_peers()?;
         let peer_registry = PeerRegistry::new(
             config.max_inbound_peers(),
             config.max_outbound_peers(),
             config.whitelist_only,
-            whitelist_peers,
+            whitelist_peers
+                .iter()
+                .map(|(peer_id, _)| peer_id.to_owned())
+                .collect(),
         );
 
         Ok(NetworkState {
             peer_store,
+            asn_map,
             config,
-            bootnodes,
+            bootnodes: RwLock::new(bootnodes),
+            whitelist_peers: RwLock::new(whitelist_peers),
             peer_registry: RwLock::new(peer_registry),
             dialing_addrs: RwLock::new(HashMap::default()),
             public_addrs: RwLock::new(public_addrs),
@@ -132,6 +150,41 @@ impl NetworkState {
         })
     }
 
+    pub(crate) fn asn_map(&self) -> Option<&AsnMap> {
+        self.asn_map.as_deref()
+    }
+
+    pub(crate) fn whitelist_peers(&self) -> Vec<(PeerId, Multiaddr)> {
+        self.whitelist_peers.read().clone()
+    }
+
+    pub(crate) fn is_whitelist_only(&self) -> bool {
+        self.with_peer_registry(|registry| registry.is_whitelist_only())
+    }
+
+    /// Replace the whitelist and the bootnodes with the ones in the reloaded config, nothing
+    /// is changed if any of the addresses is invalid. Returns the sessions which are not
+    /// allowed any more in whitelist_only mode
+    pub(crate) fn update_whitelist_and_bootnodes(
+        &self,
+        config: &NetworkConfig,
+    ) -> Result<Vec<SessionId>, Error> {
+        let whitelist_peers = config.whitelist_peers()?;
+        let bootnodes = config.bootnodes()?;
+        let rejected_sessions = self.with_peer_registry_mut(|registry| {
+            registry.update_whitelist(
+                config.whitelist_only,
+                whitelist_peers
+                    .iter()
+                    .map(|(peer_id, _)| peer_id.to_owned())
+                    .collect(),
+            )
+        });
+        *self.whitelist_peers.write() = whitelist_peers;
+        *self.bootnodes.write() = bootnodes;
+        Ok(rejected_sessions)
+    }
+
     pub(crate) fn report_session(
         &self,
         p2p_control: &ServiceControl,
@@ -368,7 +421,7 @@ impl NetworkState {
             return false;
         }
 
-        if let Some(dial_started) = self.dialing_addrs.read().get(peer_id) {
+        if let Some((_, dial_started)) = self.dialing_addrs.read().get(peer_id) {
             trace!(
                 "Do not repeat send dial command to network service: {:?}, {}",
                 peer_id,
@@ -428,13 +481,42 @@ impl NetworkState {
 
         let addr = addr.attach_p2p(peer_id)?;
         debug!("dialing {} with {:?}", addr, target);
-        p2p_control.dial(addr, target)?;
+        p2p_control.dial(addr.clone(), target)?;
         self.dialing_addrs
             .write()
-            .insert(peer_id.to_owned(), Instant::now());
+            .insert(peer_id.to_owned(), (addr, Instant::now()));
         Ok(())
     }
 
+    /// The addresses being dialed as outbound peers, the feeler, whitelist and self dials are
+    /// excluded.
+    pub(crate) fn dialing_outbound_addrs(&self) -> Vec<Multiaddr> {
+        let dialing: Vec<_> = self
+            .dialing_addrs
+            .read()
+            .iter()
+            .map(|(peer_id, (addr, _))| (peer_id.clone(), addr.clone()))
+            .collect();
+        let whitelist_peers: HashSet<_> = self
+            .whitelist_peers()
+            .into_iter()
+            .map(|(peer_id, _)| peer_id)
+            .collect();
+        self.with_peer_registry(|registry| {
+            dialing
+                .into_iter()
+                .filter(|(peer_id, _)| {
+                    peer_id != self.local_peer_id()
+                        && !whitelist_peers.contains(peer_id)
+                        && !registry.is_feeler(peer_id)
+                        // the session is opened, it's counted as a connected peer
+                        && registry.get_key_by_peer_id(peer_id).is_none()
+                })
+                .map(|(_, addr)| addr)
+                .collect()
+        })
+    }
+
     /// Dial just identify protocol
     pub fn dial_identify(&self, p2p_control: &ServiceControl, peer_id: &PeerId, addr: Multiaddr) {
         if let Err(err) = self.dial_inner(
@@ -980,16 +1062,27 @@ impl<T: ExitHandler> NetworkService<T> {
         let config = self.network_state.config.clone();
 
         // dial whitelist_nodes
-        for (peer_id, addr) in self.network_state.config.whitelist_peers()? {
+        for (peer_id, addr) in self.network_state.whitelist_peers() {
             debug!("dial whitelist_peers {:?} {:?}", peer_id, addr);
             self.network_state
                 .dial_identify(self.p2p_service.control(), &peer_id, addr);
         }
 
+        // dial anchors, the outbound peers saved by the last run
+        let anchors = self
+            .network_state
+            .with_peer_store_mut(|peer_store| peer_store.take_anchors());
+        for AddrInfo { peer_id, addr, .. } in anchors.iter().cloned() {
+            debug!("dial anchor {:?} {:?}", peer_id, addr);
+            self.network_state
+                .dial_identify(self.p2p_service.control(), &peer_id, addr);
+        }
+
         // get bootnodes
         // try get addrs from peer_store, if peer_store have no enough addrs then use bootnodes
         let bootnodes = self.network_state.with_peer_store_mut(|peer_store| {
-            let count = max((config.max_outbound_peers >> 1) as usize, 1);
+            let count =
+                max((config.max_outbound_peers >> 1) as usize, 1).saturating_sub(anchors.len());
             let mut addrs: Vec<_> = peer_store
                 .fetch_addrs_to_attempt(count)
                 .into_iter()
@@ -998,6 +1091,7 @@ impl<T: ExitHandler> NetworkService<T> {
             addrs.extend(
                 self.network_state
                     .bootnodes
+                    .read()
                     .iter()
                     .take(count.saturating_sub(addrs.len()))
                     .cloned(),
@@ -1198,6 +1292,32 @@ impl NetworkController {
             .unban_network(address);
     }
 
+    /// Apply the whitelist and the bootnodes in the reloaded config, disconnect the peers
+    /// which are not in the whitelist in whitelist_only mode, and dial the whitelist peers
+    /// which are not connected yet
+    pub fn update_whitelist_and_bootnodes(&self, config: &NetworkConfig) -> Result<(), Error> {
+        for session_id in self.network_state.update_whitelist_and_bootnodes(config)? {
+            if let Err(err) =
+                disconnect_with_message(&self.p2p_control, session_id, "not in the whitelist")
+            {
+                debug!("Disconnect failed {:?}, error: {:?}", session_id, err);
+            }
+        }
+        for (peer_id, addr) in self.network_state.whitelist_peers() {
+            if self.network_state.query_session_id(&peer_id).is_none() {
+                debug!("dial whitelist_peers {:?} {:?}", peer_id, addr);
+                self.network_state
+                    .dial_identify(&self.p2p_control, &peer_id, addr);
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush the peer store and get its database, see `PeerStore::flushed_db`
+    pub fn peer_store_db(&self) -> Result<Option<RocksDB>, Error> {
+        self.network_state.peer_store.lock().flushed_db()
+    }
+
     pub fn connected_peers(&self) -> Vec<(PeerIndex, Peer)> {
         self.network_state.with_peer_registry(|reg| {
             reg.peers()
@@ -1,6 +1,12 @@
 use crate::peer_store::types::MultiaddrExt;
+use ipnetwork::IpNetwork;
 use p2p::multiaddr::Multiaddr;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
 use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
 
 #[derive(Hash, Eq, PartialEq, Debug)]
 pub enum Group {
@@ -8,14 +14,17 @@ pub enum Group {
     LocalNetwork,
     IP4([u8; 2]),
     IP6([u8; 4]),
+    /// Autonomous system number from the ASN map
+    Asn(u32),
 }
 
 pub trait NetworkGroup {
-    fn network_group(&self) -> Group;
+    /// Group by the ASN map if it is loaded and has the IP, otherwise by IP prefix.
+    fn network_group(&self, asn_map: Option<&AsnMap>) -> Group;
 }
 
 impl NetworkGroup for Multiaddr {
-    fn network_group(&self) -> Group {
+    fn network_group(&self, asn_map: Option<&AsnMap>) -> Group {
         if let Ok(ip_addr) = self.extract_ip_addr().map(|ip_port| ip_port.ip) {
             if ip_addr.is_loopback() {
                 return Group::LocalNetwork;
@@ -26,6 +35,11 @@ impl NetworkGroup for Multiaddr {
             //     return Group::GlobalNetwork
             // }
 
+            // ASN NetworkGroup
+            if let Some(asn) = asn_map.and_then(|asn_map| asn_map.lookup(ip_addr)) {
+                return Group::Asn(asn);
+            }
+
             // IPv4 NetworkGroup
             if let IpAddr::V4(ipv4) = ip_addr {
                 let bits = ipv4.octets();
@@ -45,3 +59,98 @@ impl NetworkGroup for Multiaddr {
         Group::NoGroup
     }
 }
+
+/// Map from IP prefixes to autonomous system numbers, like the asmap of Bitcoin.
+///
+/// Grouping addrs by the AS which announces them is harder to bypass than grouping by IP
+/// prefix, since an attacker can easily get addresses in many /16 from a single provider.
+///
+/// The text format has one `<prefix> <asn>` entry per line, e.g. `1.0.0.0/24 AS13335`,
+/// empty lines and lines starting with `#` are ignored. IPs are mapped by the longest
+/// matching prefix.
+#[derive(Default, Debug)]
+pub struct AsnMap {
+    // (prefix length, masked bits) -> asn, IPv4 prefixes are stored as IPv4-mapped IPv6
+    prefixes: HashMap<(u8, u128), u32>,
+    // prefix lengths present in the map, in descending order
+    prefix_lens: Vec<u8>,
+}
+
+impl AsnMap {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn insert(&mut self, network: IpNetwork, asn: u32) {
+        let (bits, len) = match network {
+            IpNetwork::V4(net) => (u128::from(net.ip().to_ipv6_mapped()), net.prefix() + 96),
+            IpNetwork::V6(net) => (u128::from(net.ip()), net.prefix()),
+        };
+        self.prefixes.insert((len, mask(bits, len)), asn);
+        if let Err(pos) = self.prefix_lens.binary_search_by(|probe| len.cmp(probe)) {
+            self.prefix_lens.insert(pos, len);
+        }
+    }
+
+    pub fn lookup(&self, ip: IpAddr) -> Option<u32> {
+        let bits = match ip {
+            IpAddr::V4(ipv4) => u128::from(ipv4.to_ipv6_mapped()),
+            IpAddr::V6(ipv6) => u128::from(ipv6),
+        };
+        self.prefix_lens
+            .iter()
+            .find_map(|len| self.prefixes.get(&(*len, mask(bits, *len))))
+            .cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+}
+
+impl FromStr for AsnMap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut asn_map = AsnMap::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid asmap entry at line {}: {}", i + 1, line),
+                )
+            };
+            let mut parts = line.split_whitespace();
+            let network = parts
+                .next()
+                .and_then(|prefix| prefix.parse::<IpNetwork>().ok())
+                .ok_or_else(invalid)?;
+            let asn = parts
+                .next()
+                .map(|asn| asn.trim_start_matches("AS"))
+                .and_then(|asn| asn.parse::<u32>().ok())
+                .ok_or_else(invalid)?;
+            if parts.next().is_some() {
+                return Err(invalid());
+            }
+            asn_map.insert(network, asn);
+        }
+        Ok(asn_map)
+    }
+}
+
+fn mask(bits: u128, len: u8) -> u128 {
+    if len == 0 {
+        0
+    } else {
+        bits & (!0u128 << (128 - u32::from(len)))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::network_group::{Group, NetworkGroup};
+use crate::network_group::{AsnMap, Group, NetworkGroup};
 use crate::{multiaddr::Multiaddr, ProtocolId, ProtocolVersion, SessionType};
 use p2p::{secio::PeerId, SessionId};
 use std::collections::HashMap;
@@ -58,8 +58,8 @@ impl Peer {
         self.session_type.is_inbound()
     }
 
-    pub fn network_group(&self) -> Group {
-        self.connected_addr.network_group()
+    pub fn network_group(&self, asn_map: Option<&AsnMap>) -> Group {
+        self.connected_addr.network_group(asn_map)
     }
 
     pub fn protocol_version(&self, protocol_id: ProtocolId) -> Option<ProtocolVersion> {
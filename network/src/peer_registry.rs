@@ -4,6 +4,7 @@ use crate::{
     Peer, PeerId, SessionType,
 };
 use ckb_logger::debug;
+use ckb_metrics::metrics;
 use p2p::{multiaddr::Multiaddr, SessionId};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
@@ -104,11 +105,12 @@ impl PeerRegistry {
         peer_store.add_connected_peer(peer_id.clone(), remote_addr.clone(), session_type)?;
         let peer = Peer::new(session_id, session_type, peer_id, remote_addr, is_whitelist);
         self.peers.insert(session_id, peer);
+        self.report_metrics();
         Ok(evicted_peer)
     }
 
     // try to evict an inbound peer
-    fn try_evict_inbound_peer(&self, _peer_store: &PeerStore) -> Option<SessionId> {
+    fn try_evict_inbound_peer(&self, peer_store: &PeerStore) -> Option<SessionId> {
         let mut candidate_peers = {
             self.peers
                 .values()
@@ -160,7 +162,7 @@ impl PeerRegistry {
             .into_iter()
             .fold(HashMap::new(), |mut groups, peer| {
                 groups
-                    .entry(peer.network_group())
+                    .entry(peer.network_group(peer_store.asn_map()))
                     .or_insert_with(Vec::new)
                     .push(peer);
                 groups
@@ -178,6 +180,34 @@ impl PeerRegistry {
         })
     }
 
+    /// Replace the whitelist and update the whitelist flags of the connected peers, returns the
+    /// sessions of the peers which are not in the whitelist when `whitelist_only` is on, the
+    /// caller should disconnect them
+    pub(crate) fn update_whitelist(
+        &mut self,
+        whitelist_only: bool,
+        whitelist_peers: Vec<PeerId>,
+    ) -> Vec<SessionId> {
+        self.whitelist_only = whitelist_only;
+        self.whitelist_peers = HashSet::from_iter(whitelist_peers);
+        for peer in self.peers.values_mut() {
+            peer.is_whitelist = self.whitelist_peers.contains(&peer.peer_id);
+        }
+        if whitelist_only {
+            self.peers
+                .values()
+                .filter(|peer| !peer.is_whitelist)
+                .map(|peer| peer.session_id)
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub(crate) fn is_whitelist_only(&self) -> bool {
+        self.whitelist_only
+    }
+
     pub fn add_feeler(&mut self, peer_id: PeerId) {
         self.feeler_peers.insert(peer_id);
     }
@@ -199,7 +229,9 @@ impl PeerRegistry {
     }
 
     pub(crate) fn remove_peer(&mut self, session_id: SessionId) -> Option<Peer> {
-        self.peers.remove(&session_id)
+        let peer = self.peers.remove(&session_id);
+        self.report_metrics();
+        peer
     }
 
     pub fn get_key_by_peer_id(&self, peer_id: &PeerId) -> Option<SessionId> {
@@ -214,7 +246,7 @@ impl PeerRegistry {
 
     pub(crate) fn remove_peer_by_peer_id(&mut self, peer_id: &PeerId) -> Option<Peer> {
         self.get_key_by_peer_id(peer_id)
-            .and_then(|session_id| self.peers.remove(&session_id))
+            .and_then(|session_id| self.remove_peer(session_id))
     }
 
     pub fn peers(&self) -> &HashMap<SessionId, Peer> {
@@ -225,6 +257,18 @@ impl PeerRegistry {
         self.peers.keys().cloned().collect()
     }
 
+    // report the number of connected peers by direction, the whitelist peers included
+    fn report_metrics(&self) {
+        let outbound = self
+            .peers
+            .values()
+            .filter(|peer| peer.is_outbound())
+            .count();
+        let inbound = self.peers.len() - outbound;
+        metrics!(gauge, "ckb-net.peers", inbound as i64, "direction" => "inbound");
+        metrics!(gauge, "ckb-net.peers", outbound as i64, "direction" => "outbound");
+    }
+
     pub(crate) fn connection_status(&self) -> ConnectionStatus {
         let total = self.peers.len() as u32;
         let mut non_whitelist_inbound: u32 = 0;
@@ -1,16 +1,79 @@
-use crate::peer_store::types::{AddrInfo, IpPort};
+use crate::network_group::{AsnMap, NetworkGroup};
+use crate::peer_store::{
+    types::{AddrInfo, IpPort},
+    BUCKET_SIZE, NEW_BUCKET_COUNT, TRIED_BUCKET_COUNT,
+};
 use rand::Rng;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Addresses are placed into buckets by their network group, the "new" table holds addresses
+/// we have never connected to and the "tried" table holds addresses we have connected to.
+///
+/// A bucket holds at most `BUCKET_SIZE` addresses, so a single network group can not flood the
+/// store with addresses.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+struct Bucket {
+    tried: bool,
+    index: u64,
+}
 
-#[derive(Default)]
 pub struct AddrManager {
     next_id: u64,
     addr_to_id: HashMap<IpPort, u64>,
     id_to_info: HashMap<u64, AddrInfo>,
     random_ids: Vec<u64>,
+    /// secret mixed into the bucket hash, so buckets are unpredictable to other nodes
+    bucket_key: u64,
+    buckets: HashMap<Bucket, Vec<u64>>,
+    id_to_bucket: HashMap<u64, Bucket>,
+    /// addrs changed since the last `take_changes`
+    changes: HashSet<IpPort>,
+    /// addrs are grouped by it if it is loaded
+    asn_map: Option<Arc<AsnMap>>,
+}
+
+impl Default for AddrManager {
+    fn default() -> Self {
+        Self::with_bucket_key(rand::thread_rng().gen())
+    }
 }
 
 impl AddrManager {
+    pub fn with_bucket_key(bucket_key: u64) -> Self {
+        AddrManager {
+            next_id: 0,
+            addr_to_id: HashMap::default(),
+            id_to_info: HashMap::default(),
+            random_ids: Vec::new(),
+            bucket_key,
+            buckets: HashMap::default(),
+            id_to_bucket: HashMap::default(),
+            changes: HashSet::default(),
+            asn_map: None,
+        }
+    }
+
+    pub fn bucket_key(&self) -> u64 {
+        self.bucket_key
+    }
+
+    /// Set the ASN map to group addrs by, it must be set before any addr is added since the
+    /// buckets are derived from the groups.
+    pub fn set_asn_map(&mut self, asn_map: Option<Arc<AsnMap>>) {
+        debug_assert!(
+            self.id_to_info.is_empty(),
+            "set asn map on empty addr manager"
+        );
+        self.asn_map = asn_map;
+    }
+
+    pub fn asn_map(&self) -> Option<&AsnMap> {
+        self.asn_map.as_deref()
+    }
+
     pub fn add(&mut self, mut addr_info: AddrInfo) {
         let id = self.next_id;
         let key = addr_info.ip_port();
@@ -24,10 +87,17 @@ impl AddrManager {
                 return;
             }
         }
+        let bucket = self.bucket_of(&addr_info);
+        if !self.make_room(bucket) {
+            return;
+        }
         self.addr_to_id.insert(key, id);
         addr_info.random_id_pos = self.random_ids.len();
         self.id_to_info.insert(id, addr_info);
         self.random_ids.push(id);
+        self.buckets.entry(bucket).or_default().push(id);
+        self.id_to_bucket.insert(id, bucket);
+        self.changes.insert(key);
         self.next_id += 1;
     }
 
@@ -76,6 +146,15 @@ impl AddrManager {
             // swap with last index, then remove the last index
             self.swap_random_id(random_id_pos, self.random_ids.len() - 1);
             self.random_ids.pop();
+            if let Some(bucket) = self.id_to_bucket.remove(&id) {
+                if let Some(ids) = self.buckets.get_mut(&bucket) {
+                    ids.retain(|bucket_id| *bucket_id != id);
+                    if ids.is_empty() {
+                        self.buckets.remove(&bucket);
+                    }
+                }
+            }
+            self.changes.insert(*addr);
             self.id_to_info.remove(&id)
         } else {
             None
@@ -90,12 +169,65 @@ impl AddrManager {
 
     pub fn get_mut(&mut self, addr: &IpPort) -> Option<&mut AddrInfo> {
         if let Some(id) = self.addr_to_id.get(addr) {
+            self.changes.insert(*addr);
             self.id_to_info.get_mut(&id)
         } else {
             None
         }
     }
 
+    /// Take the addrs added, modified or removed since the last call.
+    pub fn take_changes(&mut self) -> HashSet<IpPort> {
+        std::mem::replace(&mut self.changes, HashSet::default())
+    }
+
+    fn bucket_of(&self, addr_info: &AddrInfo) -> Bucket {
+        let tried = addr_info.last_connected_at_ms > 0;
+        let count = if tried {
+            TRIED_BUCKET_COUNT
+        } else {
+            NEW_BUCKET_COUNT
+        };
+        let mut hasher = DefaultHasher::new();
+        self.bucket_key.hash(&mut hasher);
+        tried.hash(&mut hasher);
+        addr_info
+            .addr
+            .network_group(self.asn_map())
+            .hash(&mut hasher);
+        Bucket {
+            tried,
+            index: hasher.finish() % count,
+        }
+    }
+
+    /// Make sure the bucket has room for a new addr, return false if the addr should be dropped.
+    ///
+    /// A full new bucket only evicts terrible addrs, while a full tried bucket evicts the addr
+    /// which has not been connected for the longest time.
+    fn make_room(&mut self, bucket: Bucket) -> bool {
+        let victim = match self.buckets.get(&bucket) {
+            Some(ids) if ids.len() >= BUCKET_SIZE => {
+                let mut infos = ids.iter().map(|id| &self.id_to_info[id]);
+                let victim = if bucket.tried {
+                    infos.min_by_key(|info| info.last_connected_at_ms)
+                } else {
+                    let now_ms = faketime::unix_time_as_millis();
+                    infos.find(|info| info.is_terrible(now_ms))
+                };
+                victim.map(AddrInfo::ip_port)
+            }
+            _ => return true,
+        };
+        match victim {
+            Some(key) => {
+                self.remove(&key);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// swap random_id i and j,
     /// this function keep random_id_pos in consistency
     fn swap_random_id(&mut self, i: usize, j: usize) {
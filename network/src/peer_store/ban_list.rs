@@ -2,7 +2,7 @@ use crate::peer_store::types::{ip_to_network, BannedAddr, MultiaddrExt};
 use crate::peer_store::Multiaddr;
 use faketime::unix_time_as_millis;
 use ipnetwork::IpNetwork;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
 
 const CLEAR_EXPIRES_PERIOD: usize = 1024;
@@ -10,6 +10,8 @@ const CLEAR_EXPIRES_PERIOD: usize = 1024;
 pub struct BanList {
     inner: HashMap<IpNetwork, BannedAddr>,
     insert_count: usize,
+    /// networks changed since the last `take_changes`
+    changes: HashSet<IpNetwork>,
 }
 
 impl Default for BanList {
@@ -23,10 +25,12 @@ impl BanList {
         BanList {
             inner: HashMap::default(),
             insert_count: 0,
+            changes: HashSet::default(),
         }
     }
 
     pub fn ban(&mut self, banned_addr: BannedAddr) {
+        self.changes.insert(banned_addr.address);
         self.inner.insert(banned_addr.address, banned_addr);
         let (insert_count, _) = self.insert_count.overflowing_add(1);
         self.insert_count = insert_count;
@@ -36,7 +40,9 @@ impl BanList {
     }
 
     pub fn unban_network(&mut self, ip_network: &IpNetwork) {
-        self.inner.remove(&ip_network);
+        if self.inner.remove(&ip_network).is_some() {
+            self.changes.insert(*ip_network);
+        }
     }
 
     fn is_ip_banned_until(&self, ip: IpAddr, now_ms: u64) -> bool {
@@ -65,13 +71,28 @@ impl BanList {
         false
     }
 
+    pub fn get(&self, ip_network: &IpNetwork) -> Option<&BannedAddr> {
+        self.inner.get(ip_network)
+    }
+
     pub fn get_banned_addrs(&self) -> Vec<BannedAddr> {
         self.inner.values().map(ToOwned::to_owned).collect()
     }
 
+    /// Take the networks banned or unbanned since the last call.
+    pub fn take_changes(&mut self) -> HashSet<IpNetwork> {
+        std::mem::replace(&mut self.changes, HashSet::default())
+    }
+
     fn clear_expires(&mut self) {
         let now = unix_time_as_millis();
-        self.inner
-            .retain(|_, banned_addr| banned_addr.ban_until.gt(&now));
+        let changes = &mut self.changes;
+        self.inner.retain(|ip_network, banned_addr| {
+            let retain = banned_addr.ban_until.gt(&now);
+            if !retain {
+                changes.insert(*ip_network);
+            }
+            retain
+        });
     }
 }
@@ -12,6 +12,14 @@ pub use peer_store_impl::PeerStore;
 
 /// peer store evict peers after reach this limitation
 pub(crate) const ADDR_COUNT_LIMIT: usize = 16384;
+/// Number of buckets for addrs we have never connected to
+pub(crate) const NEW_BUCKET_COUNT: u64 = 1024;
+/// Number of buckets for addrs we have connected to
+pub(crate) const TRIED_BUCKET_COUNT: u64 = 256;
+/// Max addrs in one bucket, all addrs of a network group fall into the same bucket
+pub(crate) const BUCKET_SIZE: usize = 64;
+/// Max outbound peers saved as anchors, they are reconnected first on the next start
+pub(crate) const MAX_ANCHORS: usize = 2;
 /// Consider we never seen a peer if peer's last_connected_at beyond this timeout
 const ADDR_TIMEOUT_MS: u64 = 7 * 24 * 3600 * 1000;
 const ADDR_MAX_RETRIES: u32 = 3;
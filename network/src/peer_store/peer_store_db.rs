@@ -1,19 +1,37 @@
 use crate::{
     errors::{Error, PeerStoreError},
+    network_group::AsnMap,
     peer_store::{
         addr_manager::AddrManager,
         ban_list::BanList,
-        types::{AddrInfo, BannedAddr},
+        types::{AddrInfo, BannedAddr, IpPort},
         PeerStore,
     },
 };
-use ckb_logger::{debug, error};
-use std::fs::{copy, create_dir_all, remove_file, rename, File, OpenOptions};
+use ckb_app_config::DBConfig;
+use ckb_db::{Col, RocksDB};
+use ckb_logger::{debug, error, info};
+use ipnetwork::IpNetwork;
+use std::collections::HashSet;
+use std::fs::{create_dir_all, rename, File};
 use std::io::{Read, Write};
+use std::net::IpAddr;
 use std::path::Path;
+use std::sync::Arc;
 
 const DEFAULT_ADDR_MANAGER_DB: &str = "addr_manager.db";
 const DEFAULT_BAN_LIST_DB: &str = "ban_list.db";
+/// RocksDB directory under the peer store path
+const DEFAULT_PEER_STORE_DB: &str = "db";
+/// JSON files of old versions are renamed with this extension after migration
+const MIGRATED_EXTENSION: &str = "db.migrated";
+
+const COLUMNS: u32 = 3;
+const COLUMN_ADDR: Col = "0";
+const COLUMN_BAN: Col = "1";
+const COLUMN_META: Col = "2";
+const META_BUCKET_KEY: &[u8] = b"bucket-key";
+const META_ANCHORS: &[u8] = b"anchors";
 
 impl AddrManager {
     pub fn load<R: Read>(r: R) -> Result<Self, Error> {
@@ -48,86 +66,356 @@ impl BanList {
     }
 }
 
+/// RocksDB backend of the peer store, every addr and banned network is stored under its own key
+/// so only the changed entries are written on flush.
+pub(crate) struct PeerStoreDB {
+    db: RocksDB,
+}
+
+impl PeerStoreDB {
+    fn open(path: &Path) -> Result<Self, Error> {
+        create_dir_all(path)?;
+        let config = DBConfig {
+            path: path.join(DEFAULT_PEER_STORE_DB),
+            ..Default::default()
+        };
+        let db = RocksDB::open_with_check(&config, COLUMNS).map_err(PeerStoreError::DB)?;
+        Ok(PeerStoreDB { db })
+    }
+
+    fn load(&self, asn_map: Option<Arc<AsnMap>>) -> Result<(AddrManager, BanList), Error> {
+        let bucket_key = self
+            .db
+            .get_pinned(COLUMN_META, META_BUCKET_KEY)
+            .map_err(PeerStoreError::DB)?
+            .filter(|value| value.len() == 8)
+            .map(|value| {
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(value.as_ref());
+                u64::from_le_bytes(buf)
+            });
+        let mut addr_manager = match bucket_key {
+            Some(bucket_key) => AddrManager::with_bucket_key(bucket_key),
+            None => {
+                let addr_manager = AddrManager::default();
+                let txn = self.db.transaction();
+                txn.put(
+                    COLUMN_META,
+                    META_BUCKET_KEY,
+                    &addr_manager.bucket_key().to_le_bytes(),
+                )
+                .and_then(|_| txn.commit())
+                .map_err(PeerStoreError::DB)?;
+                addr_manager
+            }
+        };
+
+        addr_manager.set_asn_map(asn_map);
+
+        // addrs that no longer fit in their buckets are deleted from the database
+        let mut loaded_keys = Vec::new();
+        let mut dropped_keys = Vec::new();
+        self.db
+            .traverse(COLUMN_ADDR, |key, value| {
+                match serde_json::from_slice::<AddrInfo>(value) {
+                    Ok(addr) => {
+                        loaded_keys.push((key.to_vec(), addr.ip_port()));
+                        addr_manager.add(addr);
+                    }
+                    Err(err) => {
+                        error!("Failed to decode addr from peer store db, error: {:?}", err);
+                        dropped_keys.push(key.to_vec());
+                    }
+                }
+                Ok(())
+            })
+            .map_err(PeerStoreError::DB)?;
+        addr_manager.take_changes();
+        dropped_keys.extend(
+            loaded_keys
+                .into_iter()
+                .filter(|(_, ip_port)| addr_manager.get(ip_port).is_none())
+                .map(|(key, _)| key),
+        );
+
+        // expired bans are deleted from the database
+        let now_ms = faketime::unix_time_as_millis();
+        let mut ban_list = BanList::default();
+        let mut dropped_ban_keys = Vec::new();
+        self.db
+            .traverse(COLUMN_BAN, |key, value| {
+                match serde_json::from_slice::<BannedAddr>(value) {
+                    Ok(banned_addr) if banned_addr.ban_until > now_ms => ban_list.ban(banned_addr),
+                    Ok(_) => dropped_ban_keys.push(key.to_vec()),
+                    Err(err) => {
+                        error!(
+                            "Failed to decode banned addr from peer store db, error: {:?}",
+                            err
+                        );
+                        dropped_ban_keys.push(key.to_vec());
+                    }
+                }
+                Ok(())
+            })
+            .map_err(PeerStoreError::DB)?;
+        ban_list.take_changes();
+
+        if !dropped_keys.is_empty() || !dropped_ban_keys.is_empty() {
+            debug!(
+                "delete {} addrs and {} banned addrs from peer store db",
+                dropped_keys.len(),
+                dropped_ban_keys.len()
+            );
+            let txn = self.db.transaction();
+            for key in dropped_keys {
+                txn.delete(COLUMN_ADDR, &key).map_err(PeerStoreError::DB)?;
+            }
+            for key in dropped_ban_keys {
+                txn.delete(COLUMN_BAN, &key).map_err(PeerStoreError::DB)?;
+            }
+            txn.commit().map_err(PeerStoreError::DB)?;
+        }
+
+        Ok((addr_manager, ban_list))
+    }
+
+    fn write_changes(
+        &self,
+        addr_manager: &AddrManager,
+        addr_changes: HashSet<IpPort>,
+        ban_list: &BanList,
+        ban_changes: HashSet<IpNetwork>,
+    ) -> Result<(), Error> {
+        let txn = self.db.transaction();
+        for ip_port in addr_changes {
+            let key = addr_key(&ip_port);
+            match addr_manager.get(&ip_port) {
+                Some(addr) => {
+                    let value = serde_json::to_vec(addr).map_err(PeerStoreError::Serde)?;
+                    txn.put(COLUMN_ADDR, &key, &value)
+                }
+                None => txn.delete(COLUMN_ADDR, &key),
+            }
+            .map_err(PeerStoreError::DB)?;
+        }
+        for ip_network in ban_changes {
+            let key = ip_network.to_string();
+            match ban_list.get(&ip_network) {
+                Some(banned_addr) => {
+                    let value = serde_json::to_vec(banned_addr).map_err(PeerStoreError::Serde)?;
+                    txn.put(COLUMN_BAN, key.as_bytes(), &value)
+                }
+                None => txn.delete(COLUMN_BAN, key.as_bytes()),
+            }
+            .map_err(PeerStoreError::DB)?;
+        }
+        txn.commit().map_err(PeerStoreError::DB)?;
+        Ok(())
+    }
+
+    fn take_anchors(&self) -> Result<Vec<AddrInfo>, Error> {
+        let anchors = match self
+            .db
+            .get_pinned(COLUMN_META, META_ANCHORS)
+            .map_err(PeerStoreError::DB)?
+        {
+            Some(value) => serde_json::from_slice(value.as_ref()).map_err(PeerStoreError::Serde)?,
+            None => return Ok(Vec::new()),
+        };
+        let txn = self.db.transaction();
+        txn.delete(COLUMN_META, META_ANCHORS)
+            .and_then(|_| txn.commit())
+            .map_err(PeerStoreError::DB)?;
+        Ok(anchors)
+    }
+
+    fn save_anchors(&self, anchors: &[AddrInfo]) -> Result<(), Error> {
+        let value = serde_json::to_vec(anchors).map_err(PeerStoreError::Serde)?;
+        let txn = self.db.transaction();
+        txn.put(COLUMN_META, META_ANCHORS, &value)
+            .and_then(|_| txn.commit())
+            .map_err(PeerStoreError::DB)?;
+        Ok(())
+    }
+}
+
+fn addr_key(ip_port: &IpPort) -> Vec<u8> {
+    let mut key = match ip_port.ip {
+        IpAddr::V4(ip) => ip.octets().to_vec(),
+        IpAddr::V6(ip) => ip.octets().to_vec(),
+    };
+    key.extend_from_slice(&ip_port.port.to_be_bytes());
+    key
+}
+
 impl PeerStore {
+    /// Load the peer store from the database under `path`, the JSON files written by old
+    /// versions are migrated into the database on the first load.
+    ///
+    /// Fall back to a memory only peer store if the database can't be opened.
     pub fn load_from_dir_or_default<P: AsRef<Path>>(path: P) -> Self {
-        let addr_manager_path = path.as_ref().join(DEFAULT_ADDR_MANAGER_DB);
-        let ban_list_path = path.as_ref().join(DEFAULT_BAN_LIST_DB);
+        Self::load_from_dir_with_asn_map(path, None)
+    }
 
-        let addr_manager = File::open(&addr_manager_path)
+    /// Like `load_from_dir_or_default`, the addrs are grouped by the ASN map if it is given.
+    pub fn load_from_dir_with_asn_map<P: AsRef<Path>>(
+        path: P,
+        asn_map: Option<Arc<AsnMap>>,
+    ) -> Self {
+        let path = path.as_ref();
+        let db = PeerStoreDB::open(path)
             .map_err(|err| {
-                debug!(
-                    "Failed to open AddrManager db, file: {:?}, error: {:?}",
-                    addr_manager_path, err
+                error!(
+                    "Failed to open peer store db, path: {:?}, error: {:?}",
+                    path, err
                 )
             })
-            .and_then(|file| {
-                AddrManager::load(file).map_err(|err| {
-                    error!(
-                        "Failed to load AddrManager db, file: {:?}, error: {:?}",
-                        addr_manager_path, err
-                    )
-                })
+            .ok();
+        let (mut addr_manager, mut ban_list) = db
+            .as_ref()
+            .and_then(|db| {
+                db.load(asn_map.clone())
+                    .map_err(|err| {
+                        error!(
+                            "Failed to load peer store db, path: {:?}, error: {:?}",
+                            path, err
+                        )
+                    })
+                    .ok()
             })
-            .unwrap_or_default();
+            .unwrap_or_else(|| {
+                let mut addr_manager = AddrManager::default();
+                addr_manager.set_asn_map(asn_map);
+                (addr_manager, BanList::default())
+            });
+        migrate_json_files(path, &mut addr_manager, &mut ban_list, db.is_some());
 
-        let ban_list = File::open(&ban_list_path)
-            .map_err(|err| {
+        let mut peer_store = PeerStore::new(addr_manager, ban_list);
+        if let Some(db) = db {
+            peer_store = peer_store.with_db(db);
+        }
+        if let Err(err) = peer_store.flush() {
+            error!("Failed to save migrated peer store, error: {:?}", err);
+        }
+        peer_store
+    }
+
+    /// Take the anchor peers saved by the last run.
+    ///
+    /// Anchors are removed from the database once taken, so we won't reconnect to them again
+    /// if the node crashes before saving the new anchors.
+    pub fn take_anchors(&mut self) -> Vec<AddrInfo> {
+        match self.db() {
+            Some(db) => db.take_anchors().unwrap_or_else(|err| {
+                error!(
+                    "Failed to load anchors from peer store db, error: {:?}",
+                    err
+                );
+                Vec::new()
+            }),
+            None => Vec::new(),
+        }
+    }
+
+    /// Save the outbound peers to reconnect on the next start.
+    pub fn save_anchors(&mut self, anchors: &[AddrInfo]) -> Result<(), Error> {
+        match self.db() {
+            Some(db) => {
+                debug!("save {} anchors", anchors.len());
+                db.save_anchors(anchors)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Write the addrs and banned networks changed since the last flush into the database.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        let addr_changes = self.mut_addr_manager().take_changes();
+        let ban_changes = self.mut_ban_list().take_changes();
+        match self.db() {
+            Some(db) => {
                 debug!(
-                    "Failed to open BanList db, file: {:?}, error: {:?}",
-                    ban_list_path, err
+                    "flush {} addrs and {} banned addrs",
+                    addr_changes.len(),
+                    ban_changes.len()
+                );
+                db.write_changes(
+                    self.addr_manager(),
+                    addr_changes,
+                    &self.ban_list(),
+                    ban_changes,
                 )
-            })
-            .and_then(|file| {
-                BanList::load(file).map_err(|err| {
-                    error!(
-                        "Failed to load BanList db, file: {:?}, error: {:?}",
-                        ban_list_path, err
-                    )
-                })
-            })
-            .unwrap_or_default();
-
-        PeerStore::new(addr_manager, ban_list)
-    }
-
-    pub fn dump_to_dir<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
-        // create dir
-        create_dir_all(&path)?;
-        // dump file to temp dir
-        let tmp_dir = tempfile::tempdir()?;
-        // make sure temp dir exists
-        create_dir_all(tmp_dir.path())?;
-        let tmp_addr_manager = tmp_dir.path().join(DEFAULT_ADDR_MANAGER_DB);
-        let tmp_ban_list = tmp_dir.path().join(DEFAULT_BAN_LIST_DB);
-        self.addr_manager().dump(
-            OpenOptions::new()
-                .write(true)
-                .create(true)
-                .append(false)
-                .open(&tmp_addr_manager)?,
-        )?;
-        move_file(
-            tmp_addr_manager,
-            path.as_ref().join(DEFAULT_ADDR_MANAGER_DB),
-        )?;
-        self.ban_list().dump(
-            OpenOptions::new()
-                .write(true)
-                .create(true)
-                .append(false)
-                .open(&tmp_ban_list)?,
-        )?;
-        move_file(tmp_ban_list, path.as_ref().join(DEFAULT_BAN_LIST_DB))?;
-        Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Flush the pending changes and get the database, the returned handle is used to create
+    /// a checkpoint of the peer store. `None` if the peer store is memory only.
+    pub fn flushed_db(&mut self) -> Result<Option<RocksDB>, Error> {
+        self.flush()?;
+        Ok(self.db().map(|db| db.db.clone()))
+    }
+}
+
+/// Merge the JSON files of old versions into the loaded peer store, the files are renamed
+/// after migration when `persisted` is true, so they are only migrated once.
+fn migrate_json_files(
+    path: &Path,
+    addr_manager: &mut AddrManager,
+    ban_list: &mut BanList,
+    persisted: bool,
+) {
+    let addr_manager_path = path.join(DEFAULT_ADDR_MANAGER_DB);
+    if let Ok(file) = File::open(&addr_manager_path) {
+        match AddrManager::load(file) {
+            Ok(old) => {
+                info!("Migrate {} addrs from {:?}", old.count(), addr_manager_path);
+                old.addrs_iter()
+                    .cloned()
+                    .for_each(|addr| addr_manager.add(addr));
+            }
+            Err(err) => error!(
+                "Failed to load AddrManager db, file: {:?}, error: {:?}",
+                addr_manager_path, err
+            ),
+        }
+        if persisted {
+            rename_migrated_file(&addr_manager_path);
+        }
+    }
+
+    let ban_list_path = path.join(DEFAULT_BAN_LIST_DB);
+    if let Ok(file) = File::open(&ban_list_path) {
+        match BanList::load(file) {
+            Ok(old) => {
+                let banned_addrs = old.get_banned_addrs();
+                info!(
+                    "Migrate {} banned addrs from {:?}",
+                    banned_addrs.len(),
+                    ban_list_path
+                );
+                banned_addrs
+                    .into_iter()
+                    .for_each(|banned_addr| ban_list.ban(banned_addr));
+            }
+            Err(err) => error!(
+                "Failed to load BanList db, file: {:?}, error: {:?}",
+                ban_list_path, err
+            ),
+        }
+        if persisted {
+            rename_migrated_file(&ban_list_path);
+        }
     }
 }
 
-/// This function use `copy` then `remove_file` as a fallback when `rename` failed,
-/// this maybe happen when src and dst on different file systems.
-fn move_file<P: AsRef<Path>>(src: P, dst: P) -> Result<(), Error> {
-    if rename(&src, &dst).is_err() {
-        copy(&src, &dst)?;
-        remove_file(&src)?;
+fn rename_migrated_file(path: &Path) {
+    let migrated_path = path.with_extension(MIGRATED_EXTENSION);
+    if let Err(err) = rename(path, &migrated_path) {
+        error!(
+            "Failed to rename migrated file {:?} to {:?}, error: {:?}",
+            path, migrated_path, err
+        );
     }
-    Ok(())
 }
@@ -1,9 +1,10 @@
 use crate::{
     errors::{PeerStoreError, Result},
-    network_group::{Group, NetworkGroup},
+    network_group::{AsnMap, Group, NetworkGroup},
     peer_store::{
         addr_manager::AddrManager,
         ban_list::BanList,
+        peer_store_db::PeerStoreDB,
         types::{ip_to_network, AddrInfo, BannedAddr, MultiaddrExt, PeerInfo},
         Behaviour, Multiaddr, PeerScoreConfig, ReportResult, Status, ADDR_COUNT_LIMIT,
         ADDR_TIMEOUT_MS,
@@ -20,6 +21,7 @@ pub struct PeerStore {
     ban_list: RefCell<BanList>,
     peers: RefCell<HashMap<PeerId, PeerInfo>>,
     score_config: PeerScoreConfig,
+    db: Option<PeerStoreDB>,
 }
 
 impl PeerStore {
@@ -29,9 +31,20 @@ impl PeerStore {
             ban_list: RefCell::new(ban_list),
             peers: Default::default(),
             score_config: Default::default(),
+            db: None,
         }
     }
 
+    /// Persist changes into the database on `flush`
+    pub(crate) fn with_db(mut self, db: PeerStoreDB) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    pub(crate) fn db(&self) -> Option<&PeerStoreDB> {
+        self.db.as_ref()
+    }
+
     /// Add a peer and address into peer_store
     /// this method will assume peer is connected, which implies address is "verified".
     pub fn add_connected_peer(
@@ -81,6 +94,11 @@ impl PeerStore {
         Ok(())
     }
 
+    /// The ASN map which the addrs are grouped by
+    pub fn asn_map(&self) -> Option<&AsnMap> {
+        self.addr_manager.asn_map()
+    }
+
     pub fn addr_manager(&self) -> &AddrManager {
         &self.addr_manager
     }
@@ -220,7 +238,7 @@ impl PeerStore {
             // find candidate peers by network group
             let mut peers_by_network_group: HashMap<Group, Vec<_>> = HashMap::default();
             for addr in self.addr_manager.addrs_iter() {
-                let network_group = addr.addr.network_group();
+                let network_group = addr.addr.network_group(self.asn_map());
                 peers_by_network_group
                     .entry(network_group)
                     .or_default()
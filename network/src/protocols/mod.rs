@@ -9,6 +9,7 @@ pub(crate) mod support_protocols;
 mod test;
 
 use ckb_logger::{debug, trace};
+use ckb_metrics::metrics;
 use futures::{Future, FutureExt};
 use p2p::{
     builder::MetaBuilder,
@@ -32,7 +33,7 @@ pub type BoxedFutureTask = Pin<Box<dyn Future<Output = ()> + 'static + Send>>;
 use crate::{
     compress::{compress, decompress},
     network::disconnect_with_message,
-    Behaviour, Error, NetworkState, Peer, ProtocolVersion,
+    Behaviour, Error, NetworkState, Peer, ProtocolVersion, SupportProtocols,
 };
 
 pub trait CKBProtocolContext: Send {
@@ -293,6 +294,7 @@ impl ServiceProtocol for CKBHandler {
             send_paused,
         };
         let peer_index = context.session.id;
+        metrics_message("received", self.proto_id, &data);
         self.handler.received(Arc::new(nc), peer_index, data);
     }
 
@@ -329,6 +331,29 @@ impl ServiceProtocol for CKBHandler {
     }
 }
 
+// count the messages and bytes of each protocol and message type, a broadcast is counted once
+fn metrics_message(direction: &'static str, proto_id: ProtocolId, data: &[u8]) {
+    let (protocol, message) = SupportProtocols::from_protocol_id(proto_id)
+        .map(|protocol| (protocol.static_name(), protocol.message_name(data)))
+        .unwrap_or(("unknown", "unknown"));
+    metrics!(
+        counter,
+        "ckb-net.messages",
+        1,
+        "direction" => direction,
+        "protocol" => protocol,
+        "message" => message
+    );
+    metrics!(
+        counter,
+        "ckb-net.bytes",
+        data.len() as u64,
+        "direction" => direction,
+        "protocol" => protocol,
+        "message" => message
+    );
+}
+
 struct DefaultCKBProtocolContext {
     proto_id: ProtocolId,
     network_state: Arc<NetworkState>,
@@ -359,6 +384,7 @@ impl CKBProtocolContext for DefaultCKBProtocolContext {
             peer_index,
             data.len()
         );
+        metrics_message("sent", proto_id, &data);
         self.p2p_control
             .quick_send_message_to(peer_index, proto_id, data)?;
         Ok(())
@@ -370,11 +396,13 @@ impl CKBProtocolContext for DefaultCKBProtocolContext {
             peer_index,
             data.len()
         );
+        metrics_message("sent", self.proto_id, &data);
         self.p2p_control
             .quick_send_message_to(peer_index, self.proto_id, data)?;
         Ok(())
     }
     fn quick_filter_broadcast(&self, target: TargetSession, data: Bytes) -> Result<(), Error> {
+        metrics_message("sent", self.proto_id, &data);
         self.p2p_control
             .quick_filter_broadcast(target, self.proto_id, data)?;
         Ok(())
@@ -400,6 +428,7 @@ impl CKBProtocolContext for DefaultCKBProtocolContext {
             peer_index,
             data.len()
         );
+        metrics_message("sent", proto_id, &data);
         self.p2p_control
             .send_message_to(peer_index, proto_id, data)?;
         Ok(())
@@ -411,11 +440,13 @@ impl CKBProtocolContext for DefaultCKBProtocolContext {
             peer_index,
             data.len()
         );
+        metrics_message("sent", self.proto_id, &data);
         self.p2p_control
             .send_message_to(peer_index, self.proto_id, data)?;
         Ok(())
     }
     fn filter_broadcast(&self, target: TargetSession, data: Bytes) -> Result<(), Error> {
+        metrics_message("sent", self.proto_id, &data);
         self.p2p_control
             .filter_broadcast(target, self.proto_id, data)?;
         Ok(())
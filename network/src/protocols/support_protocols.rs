@@ -6,6 +6,34 @@ use p2p::{
 };
 use tokio_util::codec::length_delimited;
 
+// The item names of the `SyncMessage` union, in the order of their ids
+pub(crate) const SYNC_MESSAGE_ITEMS: &[&str] = &[
+    "GetHeaders",
+    "SendHeaders",
+    "GetBlocks",
+    "SendBlock",
+    "SetFilter",
+    "AddFilter",
+    "ClearFilter",
+    "FilteredBlock",
+    "InIBD",
+];
+
+// The item names of the `RelayMessage` union, in the order of their ids
+pub(crate) const RELAY_MESSAGE_ITEMS: &[&str] = &[
+    "CompactBlock",
+    "RelayTransactions",
+    "RelayTransactionHashes",
+    "GetRelayTransactions",
+    "GetBlockTransactions",
+    "BlockTransactions",
+    "GetBlockProposal",
+    "BlockProposal",
+    "SendCompactBlock",
+    "RelayTransactionPackage",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SupportProtocols {
     Ping,
     Discovery,
@@ -34,7 +62,29 @@ impl SupportProtocols {
         .into()
     }
 
+    /// Returns the protocol of the given id, `None` if the id is unknown
+    pub fn from_protocol_id(id: ProtocolId) -> Option<Self> {
+        [
+            SupportProtocols::Ping,
+            SupportProtocols::Discovery,
+            SupportProtocols::Identify,
+            SupportProtocols::Feeler,
+            SupportProtocols::DisconnectMessage,
+            SupportProtocols::Sync,
+            SupportProtocols::Relay,
+            SupportProtocols::Time,
+            SupportProtocols::Alert,
+        ]
+        .iter()
+        .find(|protocol| protocol.protocol_id() == id)
+        .copied()
+    }
+
     pub fn name(&self) -> String {
+        self.static_name().to_owned()
+    }
+
+    pub(crate) fn static_name(&self) -> &'static str {
         match self {
             SupportProtocols::Ping => "/ckb/ping",
             SupportProtocols::Discovery => "/ckb/discovery",
@@ -46,7 +96,23 @@ impl SupportProtocols {
             SupportProtocols::Time => "/ckb/tim",
             SupportProtocols::Alert => "/ckb/alt",
         }
-        .to_owned()
+    }
+
+    /// The message type for the metrics. It's the item name of the molecule union for the
+    /// sync and relay protocols, which carry many message types, and the protocol name for the
+    /// others. The item id is read from the union header, the message is not verified.
+    pub(crate) fn message_name(&self, data: &[u8]) -> &'static str {
+        let items = match self {
+            SupportProtocols::Sync => SYNC_MESSAGE_ITEMS,
+            SupportProtocols::Relay => RELAY_MESSAGE_ITEMS,
+            _ => return self.static_name(),
+        };
+        // the header of a molecule union is the item id in u32 little endian
+        if data.len() < 4 {
+            return "unknown";
+        }
+        let item_id = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        items.get(item_id as usize).copied().unwrap_or("unknown")
     }
 
     pub fn support_versions(&self) -> Vec<String> {
@@ -60,7 +126,7 @@ impl SupportProtocols {
             SupportProtocols::Feeler => vec!["0.0.1".to_owned()],
             SupportProtocols::DisconnectMessage => vec!["0.0.1".to_owned()],
             SupportProtocols::Sync => vec!["1".to_owned()],
-            SupportProtocols::Relay => vec!["1".to_owned()],
+            SupportProtocols::Relay => vec!["1".to_owned(), "2".to_owned(), "3".to_owned()],
             SupportProtocols::Time => vec!["1".to_owned()],
             SupportProtocols::Alert => vec!["1".to_owned()],
         }
@@ -116,6 +116,8 @@ fn net_service_start(name: String) -> Node {
         upnp: false,
         bootnode_mode: true,
         max_send_buffer: None,
+        max_outbound_peers_per_group: None,
+        asmap: None,
         sync: None,
     };
 
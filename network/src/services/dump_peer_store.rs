@@ -1,3 +1,4 @@
+use crate::peer_store::{types::MultiaddrExt, MAX_ANCHORS};
 use crate::NetworkState;
 use ckb_logger::{debug, warn};
 use futures::{Future, Stream};
@@ -9,7 +10,8 @@ use std::{
 };
 use tokio::time::Interval;
 
-const DEFAULT_DUMP_INTERVAL: Duration = Duration::from_secs(3600); // 1 hour
+// only the changed entries are written, so flush the peer store frequently
+const DEFAULT_DUMP_INTERVAL: Duration = Duration::from_secs(60); // 1 minute
 
 pub struct DumpPeerStoreService {
     network_state: Arc<NetworkState>,
@@ -25,12 +27,40 @@ impl DumpPeerStoreService {
     }
 
     fn dump_peer_store(&self) {
-        let path = self.network_state.config.peer_store_path();
         self.network_state.with_peer_store_mut(|peer_store| {
-            if let Err(err) = peer_store.dump_to_dir(&path) {
-                warn!("Dump peer store error, path: {:?} error: {}", path, err);
+            if let Err(err) = peer_store.flush() {
+                warn!("Flush peer store error: {}", err);
             } else {
-                debug!("Dump peer store to {:?}", path);
+                debug!("Flush peer store");
+            }
+        });
+    }
+
+    /// Save the longest connected outbound peers as anchors, they are the peers most unlikely
+    /// controlled by an attacker.
+    fn save_anchors(&self) {
+        let anchors = self.network_state.with_peer_registry(|registry| {
+            let mut peers: Vec<_> = registry
+                .peers()
+                .values()
+                .filter(|peer| {
+                    peer.is_outbound() && !peer.is_whitelist && !registry.is_feeler(&peer.peer_id)
+                })
+                .collect();
+            peers.sort_by_key(|peer| peer.connected_time);
+            peers
+                .into_iter()
+                .take(MAX_ANCHORS)
+                .filter_map(|peer| peer.connected_addr.extract_ip_addr().ok())
+                .collect::<Vec<_>>()
+        });
+        self.network_state.with_peer_store_mut(|peer_store| {
+            let anchors: Vec<_> = anchors
+                .iter()
+                .filter_map(|key| peer_store.addr_manager().get(key).cloned())
+                .collect();
+            if let Err(err) = peer_store.save_anchors(&anchors) {
+                warn!("Save anchors error: {}", err);
             }
         });
     }
@@ -39,6 +69,7 @@ impl DumpPeerStoreService {
 impl Drop for DumpPeerStoreService {
     fn drop(&mut self) {
         debug!("dump peer store before exit");
+        self.save_anchors();
         self.dump_peer_store();
     }
 }
@@ -1,3 +1,4 @@
+use crate::network_group::{Group, NetworkGroup};
 use crate::peer_store::types::AddrInfo;
 use crate::NetworkState;
 use ckb_logger::{trace, warn};
@@ -5,6 +6,7 @@ use faketime::unix_time_as_millis;
 use futures::{Future, Stream};
 use p2p::service::ServiceControl;
 use std::{
+    collections::HashMap,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
@@ -37,8 +39,35 @@ impl OutboundPeerService {
         }
     }
 
+    /// Count outbound peers by network group, including the ones being dialed, whitelist and
+    /// feeler peers are excluded.
+    fn outbound_groups(&self) -> HashMap<Group, usize> {
+        let asn_map = self.network_state.asn_map();
+        let mut groups = self.network_state.with_peer_registry(|registry| {
+            let mut groups = HashMap::default();
+            for peer in registry.peers().values().filter(|peer| {
+                peer.is_outbound() && !peer.is_whitelist && !registry.is_feeler(&peer.peer_id)
+            }) {
+                *groups.entry(peer.network_group(asn_map)).or_default() += 1;
+            }
+            groups
+        });
+        for addr in self.network_state.dialing_outbound_addrs() {
+            *groups.entry(addr.network_group(asn_map)).or_default() += 1;
+        }
+        groups
+    }
+
     fn dial_peers(&mut self, is_feeler: bool, count: usize) {
         let now_ms = unix_time_as_millis();
+        let max_outbound_per_group =
+            self.network_state.config.max_outbound_peers_per_group() as usize;
+        let mut outbound_groups = if is_feeler {
+            HashMap::default()
+        } else {
+            self.outbound_groups()
+        };
+        let asn_map = self.network_state.asn_map();
         let attempt_peers = self.network_state.with_peer_store_mut(|peer_store| {
             // take extra 5 peers
             // in current implementation fetch peers may return less than count
@@ -46,8 +75,24 @@ impl OutboundPeerService {
             let mut paddrs = if is_feeler {
                 peer_store.fetch_addrs_to_feeler(count + extra_count)
             } else {
-                peer_store.fetch_addrs_to_attempt(count + extra_count)
+                // take more peers since some are filtered out by network group
+                peer_store.fetch_addrs_to_attempt(count * 2 + extra_count)
             };
+            if !is_feeler {
+                // keep outbound peers diverse, peers in the local network are not limited
+                paddrs.retain(|paddr| match paddr.addr.network_group(asn_map) {
+                    Group::LocalNetwork | Group::NoGroup => true,
+                    group => {
+                        let connected = outbound_groups.entry(group).or_default();
+                        if *connected < max_outbound_per_group {
+                            *connected += 1;
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                });
+            }
             paddrs.truncate(count as usize);
             for paddr in &mut paddrs {
                 // mark addr as tried
@@ -78,13 +123,7 @@ impl OutboundPeerService {
     }
 
     fn try_dial_whitelist(&self) {
-        // This will never panic because network start has already been checked
-        for (peer_id, addr) in self
-            .network_state
-            .config
-            .whitelist_peers()
-            .expect("address must be correct")
-        {
+        for (peer_id, addr) in self.network_state.whitelist_peers() {
             if self.network_state.query_session_id(&peer_id).is_none() {
                 self.network_state
                     .dial_identify(&self.p2p_control, &peer_id, addr);
@@ -119,7 +158,7 @@ impl Future for OutboundPeerService {
                             .max_outbound
                             .saturating_sub(status.non_whitelist_outbound)
                             as usize;
-                        if !self.network_state.config.whitelist_only {
+                        if !self.network_state.is_whitelist_only() {
                             if new_outbound > 0 {
                                 // dial peers
                                 self.dial_peers(false, new_outbound);
@@ -15,7 +15,8 @@ proptest! {
     #[test]
     fn test_add_random_addrs(count in RANDOM_REMOVE_ADDRS..MAX_FETCHED_ADDRS) {
         fn new_addr(id: usize) -> AddrInfo{
-            let ip = Ipv4Addr::from(((225 << 24) + id) as u32);
+            // use a different network group for each addr to avoid hitting the bucket limit
+            let ip = Ipv4Addr::from(((1 << 24) + (id << 16) + 1) as u32);
             let addr: Multiaddr = format!("/ip4/{}/tcp/42", ip).parse().unwrap();
             let ip_addr = addr.extract_ip_addr().unwrap();
             AddrInfo::new(
@@ -1,4 +1,6 @@
 mod addr_manager;
+mod network_group;
 mod peer_registry;
 mod peer_store;
 mod peer_store_db;
+mod support_protocols;
@@ -0,0 +1,52 @@
+use crate::multiaddr::Multiaddr;
+use crate::network_group::{AsnMap, Group, NetworkGroup};
+use std::net::IpAddr;
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn test_asn_map_lookup() {
+    let asn_map: AsnMap = "
+        # comment
+        1.0.0.0/8 100
+        1.2.0.0/16 AS200
+
+        1.2.3.0/24 300
+        2001:db8::/32 400
+        "
+    .parse()
+    .unwrap();
+    assert_eq!(asn_map.len(), 4);
+
+    // longest prefix matched
+    assert_eq!(asn_map.lookup(ip("1.2.3.4")), Some(300));
+    assert_eq!(asn_map.lookup(ip("1.2.4.4")), Some(200));
+    assert_eq!(asn_map.lookup(ip("1.3.3.4")), Some(100));
+    assert_eq!(asn_map.lookup(ip("2.2.3.4")), None);
+    assert_eq!(asn_map.lookup(ip("2001:db8::1")), Some(400));
+    assert_eq!(asn_map.lookup(ip("2001:db9::1")), None);
+    // IPv4-mapped IPv6
+    assert_eq!(asn_map.lookup(ip("::ffff:1.2.3.4")), Some(300));
+}
+
+#[test]
+fn test_asn_map_invalid() {
+    assert!("1.2.3.0/24".parse::<AsnMap>().is_err());
+    assert!("1.2.3.0/24 ASX".parse::<AsnMap>().is_err());
+    assert!("1.2.3.0/33 100".parse::<AsnMap>().is_err());
+    assert!("1.2.3.0/24 100 200".parse::<AsnMap>().is_err());
+    assert!("".parse::<AsnMap>().unwrap().is_empty());
+}
+
+#[test]
+fn test_network_group_by_asn_map() {
+    let asn_map: AsnMap = "1.2.0.0/16 200".parse().unwrap();
+    let addr: Multiaddr = "/ip4/1.2.3.4/tcp/8115".parse().unwrap();
+    let other: Multiaddr = "/ip4/1.3.3.4/tcp/8115".parse().unwrap();
+    assert_eq!(addr.network_group(Some(&asn_map)), Group::Asn(200));
+    // not in the map, grouped by IP prefix
+    assert_eq!(other.network_group(Some(&asn_map)), Group::IP4([1, 3]));
+    assert_eq!(addr.network_group(None), Group::IP4([1, 2]));
+}
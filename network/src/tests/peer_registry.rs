@@ -41,6 +41,52 @@ fn test_accept_inbound_peer_in_reserve_only_mode() {
         .expect("accept");
 }
 
+#[test]
+fn test_update_whitelist() {
+    let mut peer_store = PeerStore::default();
+    let peer = PeerId::random();
+    let addr = "/ip4/127.0.0.1/tcp/42".parse::<Multiaddr>().unwrap();
+    let session_id = 1.into();
+
+    let mut peers = PeerRegistry::new(3, 3, false, vec![]);
+    peers
+        .accept_peer(
+            peer.clone(),
+            addr.clone(),
+            session_id,
+            SessionType::Inbound,
+            &mut peer_store,
+        )
+        .expect("accept");
+    assert!(!peers.get_peer(session_id).unwrap().is_whitelist);
+
+    // the connected peer is kept and marked as a whitelist peer
+    assert!(peers.update_whitelist(true, vec![peer]).is_empty());
+    assert!(peers.is_whitelist_only());
+    assert!(peers.get_peer(session_id).unwrap().is_whitelist);
+
+    // the connected peers not in the whitelist are returned to be disconnected in whitelist_only
+    // mode, and only in whitelist_only mode
+    assert!(peers.update_whitelist(false, vec![]).is_empty());
+    assert!(!peers.get_peer(session_id).unwrap().is_whitelist);
+    assert_eq!(peers.update_whitelist(true, vec![]), vec![session_id]);
+
+    // the new whitelist applies to the following connections
+    let err = peers
+        .accept_peer(
+            PeerId::random(),
+            addr,
+            2.into(),
+            SessionType::Inbound,
+            &mut peer_store,
+        )
+        .unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        format!("{}", Error::Peer(PeerError::NonReserved))
+    );
+}
+
 #[test]
 fn test_accept_inbound_peer_until_full() {
     let mut peer_store = PeerStore::default();
@@ -1,8 +1,10 @@
 use crate::{
     multiaddr::{self, Multiaddr},
-    peer_store::{types::MultiaddrExt, PeerStore, Status, ADDR_COUNT_LIMIT},
+    network_group::AsnMap,
+    peer_store::{types::MultiaddrExt, PeerStore, Status, ADDR_COUNT_LIMIT, BUCKET_SIZE},
     Behaviour, PeerId, SessionType,
 };
+use std::sync::Arc;
 
 #[test]
 fn test_add_connected_peer() {
@@ -223,9 +225,16 @@ fn test_eviction() {
     let mut peer_store = PeerStore::default();
     let now = faketime::unix_time_as_millis();
     let tried_ms = now - 61_000;
-    // add addrs
-    for i in 0..(ADDR_COUNT_LIMIT - 2) {
-        let addr: Multiaddr = format!("/ip4/225.0.0.1/tcp/{}", i).parse().unwrap();
+    // add addrs, each from a different network group
+    for i in 0..(ADDR_COUNT_LIMIT - 4) {
+        let addr: Multiaddr = format!("/ip4/{}.{}.0.1/tcp/42", 1 + i / 256, i % 256)
+            .parse()
+            .unwrap();
+        peer_store.add_addr(PeerId::random(), addr).unwrap();
+    }
+    // make 225.0.0.0/16 the largest network group
+    for port in 1..=2 {
+        let addr: Multiaddr = format!("/ip4/225.0.0.1/tcp/{}", port).parse().unwrap();
         peer_store.add_addr(PeerId::random(), addr).unwrap();
     }
     // this peer will be evict from peer store
@@ -277,3 +286,65 @@ fn test_eviction() {
         .get(&evict_addr.extract_ip_addr().unwrap())
         .is_none());
 }
+
+#[test]
+fn test_bucket_limits_addrs_from_same_network_group() {
+    let mut peer_store = PeerStore::default();
+    for i in 0..(BUCKET_SIZE * 2) {
+        let addr: Multiaddr = format!("/ip4/225.0.{}.{}/tcp/42", i / 256, i % 256)
+            .parse()
+            .unwrap();
+        peer_store.add_addr(PeerId::random(), addr).unwrap();
+    }
+    assert_eq!(peer_store.addr_manager().count(), BUCKET_SIZE);
+}
+
+#[test]
+fn test_bucket_limits_addrs_from_same_asn() {
+    let dir = tempfile::tempdir().unwrap();
+    let asn_map: AsnMap = "225.0.0.0/8 100".parse().unwrap();
+    let mut peer_store = PeerStore::load_from_dir_with_asn_map(dir.path(), Some(Arc::new(asn_map)));
+    // the addrs are in different /16 prefixes but announced by the same AS
+    for i in 0..(BUCKET_SIZE * 2) {
+        let addr: Multiaddr = format!("/ip4/225.{}.0.1/tcp/42", i).parse().unwrap();
+        peer_store.add_addr(PeerId::random(), addr).unwrap();
+    }
+    assert_eq!(peer_store.addr_manager().count(), BUCKET_SIZE);
+}
+
+#[test]
+fn test_bucket_evicts_terrible_addrs() {
+    let mut peer_store = PeerStore::default();
+    let tried_ms = faketime::unix_time_as_millis() - 61_000;
+    let terrible_addr: Multiaddr = "/ip4/225.0.0.1/tcp/42".parse().unwrap();
+    peer_store
+        .add_addr(PeerId::random(), terrible_addr.clone())
+        .unwrap();
+    for _ in 0..3 {
+        peer_store
+            .mut_addr_manager()
+            .get_mut(&terrible_addr.extract_ip_addr().unwrap())
+            .unwrap()
+            .mark_tried(tried_ms);
+    }
+    for port in 1..BUCKET_SIZE {
+        let addr: Multiaddr = format!("/ip4/225.0.0.2/tcp/{}", port).parse().unwrap();
+        peer_store.add_addr(PeerId::random(), addr).unwrap();
+    }
+    assert_eq!(peer_store.addr_manager().count(), BUCKET_SIZE);
+
+    // the terrible addr is replaced by the new addr
+    let new_addr: Multiaddr = "/ip4/225.0.0.3/tcp/42".parse().unwrap();
+    peer_store
+        .add_addr(PeerId::random(), new_addr.clone())
+        .unwrap();
+    assert_eq!(peer_store.addr_manager().count(), BUCKET_SIZE);
+    assert!(peer_store
+        .addr_manager()
+        .get(&terrible_addr.extract_ip_addr().unwrap())
+        .is_none());
+    assert!(peer_store
+        .addr_manager()
+        .get(&new_addr.extract_ip_addr().unwrap())
+        .is_some());
+}
@@ -14,7 +14,8 @@ use std::io::Write;
 #[test]
 fn test_peer_store_persistent() {
     let now_ms = faketime::unix_time_as_millis();
-    let mut peer_store = PeerStore::default();
+    let dir = tempfile::tempdir().unwrap();
+    let mut peer_store = PeerStore::load_from_dir_or_default(dir.path());
 
     // add addrs to addr manager
     let addr_manager = peer_store.mut_addr_manager();
@@ -70,10 +71,10 @@ fn test_peer_store_persistent() {
     ban_list.ban(ban2.clone());
     ban_list.ban(ban3.clone());
 
-    // dump and load
-    let dir = tempfile::tempdir().unwrap();
-    peer_store.dump_to_dir(&dir.path()).unwrap();
-    let peer_store2 = PeerStore::load_from_dir_or_default(&dir.path());
+    // flush and load
+    peer_store.flush().unwrap();
+    drop(peer_store);
+    let peer_store2 = PeerStore::load_from_dir_or_default(dir.path());
 
     // check addr manager
     let addr_manager2 = peer_store2.addr_manager();
@@ -102,7 +103,9 @@ fn test_peer_store_persistent() {
 fn test_peer_store_load_from_dir_should_not_panic() {
     // should return an empty store when dir does not exist
     {
-        let peer_store = PeerStore::load_from_dir_or_default("/tmp/a_directory_does_not_exist");
+        let dir = tempfile::tempdir().unwrap();
+        let peer_store =
+            PeerStore::load_from_dir_or_default(dir.path().join("a_directory_does_not_exist"));
         assert_eq!(0, peer_store.addr_manager().count());
         assert_eq!(0, peer_store.ban_list().get_banned_addrs().len());
     }
@@ -144,3 +147,127 @@ fn test_peer_store_load_from_dir_should_not_panic() {
         assert_eq!(0, peer_store.ban_list().get_banned_addrs().len());
     }
 }
+
+#[test]
+fn test_peer_store_flush_changes() {
+    let now_ms = faketime::unix_time_as_millis();
+    let dir = tempfile::tempdir().unwrap();
+    let addr1: Multiaddr = "/ip4/127.0.0.1/tcp/42".parse().unwrap();
+    let addr2: Multiaddr = "/ip4/127.0.0.2/tcp/42".parse().unwrap();
+    let network = multiaddr_to_ip_network(&addr1).unwrap();
+    {
+        let mut peer_store = PeerStore::load_from_dir_or_default(dir.path());
+        peer_store
+            .add_addr(PeerId::random(), addr1.clone())
+            .unwrap();
+        peer_store
+            .add_addr(PeerId::random(), addr2.clone())
+            .unwrap();
+        peer_store.mut_ban_list().ban(BannedAddr {
+            address: network,
+            ban_until: now_ms + 10_000,
+            ban_reason: "test".into(),
+            created_at: now_ms,
+        });
+        peer_store.flush().unwrap();
+
+        // modify, remove and unban after the first flush
+        peer_store
+            .mut_addr_manager()
+            .get_mut(&addr1.extract_ip_addr().unwrap())
+            .unwrap()
+            .mark_tried(now_ms);
+        peer_store
+            .mut_addr_manager()
+            .remove(&addr2.extract_ip_addr().unwrap());
+        peer_store.mut_ban_list().unban_network(&network);
+        peer_store.flush().unwrap();
+    }
+
+    let peer_store = PeerStore::load_from_dir_or_default(dir.path());
+    let addr_manager = peer_store.addr_manager();
+    assert_eq!(addr_manager.count(), 1);
+    assert_eq!(
+        addr_manager
+            .get(&addr1.extract_ip_addr().unwrap())
+            .unwrap()
+            .last_tried_at_ms,
+        now_ms
+    );
+    assert!(peer_store.ban_list().get_banned_addrs().is_empty());
+}
+
+#[test]
+fn test_peer_store_migrate_from_json() {
+    let now_ms = faketime::unix_time_as_millis();
+    let dir = tempfile::tempdir().unwrap();
+
+    // write the JSON files of old versions
+    let addr: Multiaddr = "/ip4/127.0.0.1/tcp/42".parse().unwrap();
+    let banned_addr = BannedAddr {
+        address: multiaddr_to_ip_network(&"/ip4/127.0.0.2/tcp/42".parse().unwrap()).unwrap(),
+        ban_until: now_ms + 10_000,
+        ban_reason: "test".into(),
+        created_at: now_ms,
+    };
+    {
+        let mut peer_store = PeerStore::default();
+        peer_store.add_addr(PeerId::random(), addr.clone()).unwrap();
+        peer_store.mut_ban_list().ban(banned_addr.clone());
+        peer_store
+            .addr_manager()
+            .dump(File::create(dir.path().join("addr_manager.db")).unwrap())
+            .unwrap();
+        peer_store
+            .ban_list()
+            .dump(File::create(dir.path().join("ban_list.db")).unwrap())
+            .unwrap();
+    }
+
+    let check = |peer_store: &PeerStore| {
+        assert!(peer_store
+            .addr_manager()
+            .get(&addr.extract_ip_addr().unwrap())
+            .is_some());
+        assert_eq!(
+            peer_store.ban_list().get_banned_addrs(),
+            vec![banned_addr.clone()]
+        );
+    };
+
+    let peer_store = PeerStore::load_from_dir_or_default(dir.path());
+    check(&peer_store);
+    drop(peer_store);
+    // JSON files are renamed after migration
+    assert!(!dir.path().join("addr_manager.db").exists());
+    assert!(!dir.path().join("ban_list.db").exists());
+    assert!(dir.path().join("addr_manager.db.migrated").exists());
+    assert!(dir.path().join("ban_list.db.migrated").exists());
+
+    // load from the database
+    let peer_store = PeerStore::load_from_dir_or_default(dir.path());
+    check(&peer_store);
+}
+
+#[test]
+fn test_peer_store_anchors() {
+    let dir = tempfile::tempdir().unwrap();
+    let addr: Multiaddr = "/ip4/127.0.0.1/tcp/42".parse().unwrap();
+    let anchor = AddrInfo::new(
+        PeerId::random(),
+        addr.extract_ip_addr().unwrap(),
+        addr,
+        faketime::unix_time_as_millis(),
+        100,
+    );
+    {
+        let mut peer_store = PeerStore::load_from_dir_or_default(dir.path());
+        assert!(peer_store.take_anchors().is_empty());
+        peer_store.save_anchors(&[anchor.clone()]).unwrap();
+    }
+
+    let mut peer_store = PeerStore::load_from_dir_or_default(dir.path());
+    assert_eq!(peer_store.take_anchors(), vec![anchor]);
+    // anchors are only taken once
+    assert!(peer_store.take_anchors().is_empty());
+}
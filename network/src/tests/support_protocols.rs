@@ -0,0 +1,49 @@
+use crate::protocols::support_protocols::{RELAY_MESSAGE_ITEMS, SYNC_MESSAGE_ITEMS};
+use crate::SupportProtocols;
+use ckb_types::{packed, prelude::*};
+
+#[test]
+fn test_message_items_match_unions() {
+    let header = |item_id: usize| (item_id as u32).to_le_bytes();
+
+    assert_eq!(
+        SYNC_MESSAGE_ITEMS.len(),
+        packed::SyncMessageReader::ITEMS_COUNT
+    );
+    for (item_id, name) in SYNC_MESSAGE_ITEMS.iter().enumerate() {
+        let data = header(item_id);
+        let reader = packed::SyncMessageReader::new_unchecked(&data);
+        assert_eq!(reader.to_enum().item_name(), *name);
+    }
+
+    assert_eq!(
+        RELAY_MESSAGE_ITEMS.len(),
+        packed::RelayMessageReader::ITEMS_COUNT
+    );
+    for (item_id, name) in RELAY_MESSAGE_ITEMS.iter().enumerate() {
+        let data = header(item_id);
+        let reader = packed::RelayMessageReader::new_unchecked(&data);
+        assert_eq!(reader.to_enum().item_name(), *name);
+    }
+}
+
+#[test]
+fn test_message_name() {
+    let send_block = packed::SyncMessage::new_builder()
+        .set(packed::SendBlock::default())
+        .build();
+    assert_eq!(
+        SupportProtocols::Sync.message_name(send_block.as_slice()),
+        "SendBlock"
+    );
+    let compact_block = packed::RelayMessage::new_builder()
+        .set(packed::CompactBlock::default())
+        .build();
+    assert_eq!(
+        SupportProtocols::Relay.message_name(compact_block.as_slice()),
+        "CompactBlock"
+    );
+    assert_eq!(SupportProtocols::Relay.message_name(&[0xff; 4]), "unknown");
+    assert_eq!(SupportProtocols::Sync.message_name(&[]), "unknown");
+    assert_eq!(SupportProtocols::Ping.message_name(&[0; 8]), "/ckb/ping");
+}
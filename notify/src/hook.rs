@@ -0,0 +1,298 @@
+use ckb_app_config::{HookConfig, HookEvent, HookTarget};
+use ckb_channel::{bounded, Receiver, Sender, TrySendError};
+use ckb_logger::{debug, error, warn};
+use ckb_metrics::metrics;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_HOOK_QUEUE_SIZE: usize = 1024;
+/// The delay between retries never grows beyond this
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+/// A warning is logged once per this many dropped events
+const DROPPED_EVENTS_LOG_INTERVAL: u64 = 1000;
+
+/// An event hook. The events are queued and delivered by a dedicated thread, so a slow
+/// endpoint never blocks the notify thread; they are dropped when the queue is full.
+pub(crate) struct Hook {
+    config: HookConfig,
+    name: String,
+    sender: Sender<Arc<String>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Hook {
+    pub(crate) fn start(config: HookConfig, queue_size: usize, index: usize) -> Self {
+        let name = match &config.target {
+            HookTarget::Webhook { url, .. } => format!("webhook({})", url),
+            HookTarget::UnixSocket { path } => format!("unix_socket({})", path.display()),
+            HookTarget::File { path } => format!("file({})", path.display()),
+        };
+        let (sender, receiver) = bounded(queue_size);
+        let mut deliverer = Deliverer::new(&config);
+        let max_retries = config.max_retries;
+        let retry_interval = Duration::from_millis(config.retry_interval_ms);
+        let hook_name = name.clone();
+        thread::Builder::new()
+            .name(format!("NotifyHook-{}", index))
+            .spawn(move || {
+                deliver_loop(
+                    &hook_name,
+                    &mut deliverer,
+                    receiver,
+                    max_retries,
+                    retry_interval,
+                )
+            })
+            .expect("Start notify hook failed");
+
+        Hook {
+            config,
+            name,
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub(crate) fn accepts(&self, event: HookEvent) -> bool {
+        self.config.accepts(event)
+    }
+
+    pub(crate) fn send(&self, event: HookEvent, payload: Arc<String>) {
+        match self.sender.try_send(payload) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                metrics!(counter, "ckb-notify.dropped", 1, "event" => event.as_str());
+                if dropped % DROPPED_EVENTS_LOG_INTERVAL == 1 {
+                    warn!(
+                        "notify hook {} is full, {} events dropped in total",
+                        self.name, dropped
+                    );
+                }
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                debug!("notify hook {} is closed", self.name);
+            }
+        }
+    }
+}
+
+fn deliver_loop(
+    name: &str,
+    deliverer: &mut Deliverer,
+    receiver: Receiver<Arc<String>>,
+    max_retries: u32,
+    retry_interval: Duration,
+) {
+    // exits when the hook is dropped with the notify service
+    for payload in receiver {
+        let mut interval = retry_interval;
+        let mut attempt = 0;
+        loop {
+            match deliverer.deliver(&payload) {
+                Ok(()) => break,
+                Err(err) if attempt < max_retries => {
+                    debug!(
+                        "notify hook {} delivery failed, retry in {:?}: {}",
+                        name, interval, err
+                    );
+                    thread::sleep(interval);
+                    interval = (interval * 2).min(MAX_RETRY_INTERVAL);
+                    attempt += 1;
+                }
+                Err(err) => {
+                    error!(
+                        "notify hook {} delivery failed after {} retries: {}",
+                        name, max_retries, err
+                    );
+                    metrics!(counter, "ckb-notify.failed", 1);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+enum Deliverer {
+    Webhook {
+        url: String,
+        client: Option<reqwest::Client>,
+        timeout: Duration,
+    },
+    #[cfg(unix)]
+    UnixSocket {
+        path: PathBuf,
+        stream: Option<std::os::unix::net::UnixStream>,
+    },
+    #[cfg(not(unix))]
+    UnixSocket { path: PathBuf },
+    File {
+        path: PathBuf,
+        file: Option<fs::File>,
+    },
+}
+
+impl Deliverer {
+    fn new(config: &HookConfig) -> Self {
+        match &config.target {
+            HookTarget::Webhook { url, timeout_ms } => Deliverer::Webhook {
+                url: url.to_owned(),
+                client: None,
+                timeout: Duration::from_millis(*timeout_ms),
+            },
+            #[cfg(unix)]
+            HookTarget::UnixSocket { path } => Deliverer::UnixSocket {
+                path: path.to_owned(),
+                stream: None,
+            },
+            #[cfg(not(unix))]
+            HookTarget::UnixSocket { path } => Deliverer::UnixSocket {
+                path: path.to_owned(),
+            },
+            HookTarget::File { path } => Deliverer::File {
+                path: path.to_owned(),
+                file: None,
+            },
+        }
+    }
+
+    // The connections and files are opened lazily and reopened after a failure
+    fn deliver(&mut self, payload: &str) -> Result<(), String> {
+        match self {
+            Deliverer::Webhook {
+                url,
+                client,
+                timeout,
+            } => {
+                if client.is_none() {
+                    *client = Some(
+                        reqwest::Client::builder()
+                            .timeout(*timeout)
+                            .build()
+                            .map_err(|err| err.to_string())?,
+                    );
+                }
+                let response = client
+                    .as_ref()
+                    .expect("checked above")
+                    .post(url.as_str())
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(payload.to_owned())
+                    .send()
+                    .map_err(|err| err.to_string())?;
+                if response.status().is_success() {
+                    Ok(())
+                } else {
+                    Err(format!("response status {}", response.status()))
+                }
+            }
+            #[cfg(unix)]
+            Deliverer::UnixSocket { path, stream } => {
+                if stream.is_none() {
+                    *stream = Some(
+                        std::os::unix::net::UnixStream::connect(&path)
+                            .map_err(|err| err.to_string())?,
+                    );
+                }
+                write_line(stream, payload)
+            }
+            #[cfg(not(unix))]
+            Deliverer::UnixSocket { path } => Err(format!(
+                "unix socket {} is not supported on this platform",
+                path.display()
+            )),
+            Deliverer::File { path, file } => {
+                if file.is_none() {
+                    *file = Some(
+                        fs::OpenOptions::new()
+                            .append(true)
+                            .create(true)
+                            .open(&path)
+                            .map_err(|err| err.to_string())?,
+                    );
+                }
+                write_line(file, payload)
+            }
+        }
+    }
+}
+
+// write the payload as a line, the writer is closed on error so it is reopened on retry
+fn write_line<W: Write>(writer: &mut Option<W>, payload: &str) -> Result<(), String> {
+    let ret = match writer.as_mut() {
+        Some(w) => w
+            .write_all(payload.as_bytes())
+            .and_then(|_| w.write_all(b"\n"))
+            .and_then(|_| w.flush())
+            .map_err(|err| err.to_string()),
+        None => Err("not opened".to_owned()),
+    };
+    if ret.is_err() {
+        *writer = None;
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn file_hook(path: PathBuf, events: Vec<HookEvent>) -> HookConfig {
+        HookConfig {
+            target: HookTarget::File { path },
+            events,
+            max_retries: 0,
+            retry_interval_ms: 0,
+        }
+    }
+
+    fn wait_for_lines(path: &PathBuf, count: usize) -> Vec<String> {
+        let start = Instant::now();
+        loop {
+            let content = fs::read_to_string(path).unwrap_or_default();
+            let lines: Vec<_> = content.lines().map(ToOwned::to_owned).collect();
+            if lines.len() >= count || start.elapsed() > Duration::from_secs(5) {
+                return lines;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_file_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.log");
+        let hook = Hook::start(file_hook(path.clone(), vec![HookEvent::NewBlock]), 16, 0);
+        assert!(hook.accepts(HookEvent::NewBlock));
+        assert!(!hook.accepts(HookEvent::Reorg));
+
+        hook.send(HookEvent::NewBlock, Arc::new("{\"n\":1}".to_owned()));
+        hook.send(HookEvent::NewBlock, Arc::new("{\"n\":2}".to_owned()));
+        assert_eq!(wait_for_lines(&path, 2), vec!["{\"n\":1}", "{\"n\":2}"]);
+    }
+
+    #[test]
+    fn test_hook_drops_events_when_full() {
+        let dir = tempfile::tempdir().unwrap();
+        // the delivery keeps failing since the parent directory does not exist
+        let path = dir.path().join("missing").join("events.log");
+        let mut config = file_hook(path, Vec::new());
+        config.max_retries = 1;
+        config.retry_interval_ms = 10_000;
+        let hook = Hook::start(config, 1, 0);
+
+        for _ in 0..5 {
+            hook.send(HookEvent::Reorg, Arc::new("{}".to_owned()));
+        }
+        // one is being retried, one is queued and the others are dropped
+        assert!(hook.dropped.load(Ordering::Relaxed) >= 3);
+    }
+}
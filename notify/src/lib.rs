@@ -1,13 +1,19 @@
-use ckb_app_config::NotifyConfig;
+mod hook;
+
+use crate::hook::{Hook, DEFAULT_HOOK_QUEUE_SIZE};
+use ckb_app_config::{HookEvent, NotifyConfig};
 use ckb_channel::{bounded, select, Receiver, RecvError, Sender};
+use ckb_jsonrpc_types::{AlertMessage, HeaderView as JsonHeaderView};
 use ckb_logger::{debug, error, trace};
 use ckb_stop_handler::{SignalSender, StopHandler};
 use ckb_types::{
-    core::{service::Request, BlockView},
+    core::{service::Request, BlockView, HeaderView, TransactionView},
     packed::Alert,
 };
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::process::Command;
+use std::sync::Arc;
 use std::thread;
 
 pub const SIGNAL_CHANNEL_SIZE: usize = 1;
@@ -16,6 +22,13 @@ pub const NOTIFY_CHANNEL_SIZE: usize = 128;
 
 pub type NotifyRegister<M> = Sender<Request<String, Receiver<M>>>;
 
+/// The main chain switched to a fork, the blocks are ordered by number
+#[derive(Clone, Debug)]
+pub struct ReorgEvent {
+    pub detached_blocks: Vec<HeaderView>,
+    pub attached_blocks: Vec<HeaderView>,
+}
+
 #[derive(Clone)]
 pub struct NotifyController {
     stop: StopHandler<()>,
@@ -23,6 +36,10 @@ pub struct NotifyController {
     new_block_notifier: Sender<BlockView>,
     network_alert_register: NotifyRegister<Alert>,
     network_alert_notifier: Sender<Alert>,
+    evicted_transaction_register: NotifyRegister<TransactionView>,
+    evicted_transaction_notifier: Sender<TransactionView>,
+    reorg_notifier: Sender<ReorgEvent>,
+    config_updater: Sender<NotifyConfig>,
 }
 
 impl Drop for NotifyController {
@@ -35,6 +52,8 @@ pub struct NotifyService {
     config: NotifyConfig,
     new_block_subscribers: HashMap<String, Sender<BlockView>>,
     network_alert_subscribers: HashMap<String, Sender<Alert>>,
+    evicted_transaction_subscribers: HashMap<String, Sender<TransactionView>>,
+    hooks: Vec<Hook>,
 }
 
 impl NotifyService {
@@ -43,6 +62,8 @@ impl NotifyService {
             config,
             new_block_subscribers: HashMap::default(),
             network_alert_subscribers: HashMap::default(),
+            evicted_transaction_subscribers: HashMap::default(),
+            hooks: Vec::new(),
         }
     }
 
@@ -55,6 +76,14 @@ impl NotifyService {
         let (network_alert_register, network_alert_register_receiver) =
             bounded(REGISTER_CHANNEL_SIZE);
         let (network_alert_sender, network_alert_receiver) = bounded::<Alert>(NOTIFY_CHANNEL_SIZE);
+        let (evicted_transaction_register, evicted_transaction_register_receiver) =
+            bounded(REGISTER_CHANNEL_SIZE);
+        let (evicted_transaction_sender, evicted_transaction_receiver) =
+            bounded::<TransactionView>(NOTIFY_CHANNEL_SIZE);
+        let (reorg_sender, reorg_receiver) = bounded::<ReorgEvent>(NOTIFY_CHANNEL_SIZE);
+        let (config_sender, config_receiver) = bounded::<NotifyConfig>(REGISTER_CHANNEL_SIZE);
+
+        self.start_hooks();
 
         let mut thread_builder = thread::Builder::new();
         if let Some(name) = thread_name {
@@ -70,6 +99,10 @@ impl NotifyService {
                     recv(new_block_receiver) -> msg => self.handle_notify_new_block(msg),
                     recv(network_alert_register_receiver) -> msg => self.handle_register_network_alert(msg),
                     recv(network_alert_receiver) -> msg => self.handle_notify_network_alert(msg),
+                    recv(evicted_transaction_register_receiver) -> msg => self.handle_register_evicted_transaction(msg),
+                    recv(evicted_transaction_receiver) -> msg => self.handle_notify_evicted_transaction(msg),
+                    recv(reorg_receiver) -> msg => self.handle_notify_reorg(msg),
+                    recv(config_receiver) -> msg => self.handle_update_config(msg),
                 }
             })
             .expect("Start notify service failed");
@@ -79,10 +112,45 @@ impl NotifyService {
             new_block_notifier: new_block_sender,
             network_alert_register,
             network_alert_notifier: network_alert_sender,
+            evicted_transaction_register,
+            evicted_transaction_notifier: evicted_transaction_sender,
+            reorg_notifier: reorg_sender,
+            config_updater: config_sender,
             stop: StopHandler::new(SignalSender::Crossbeam(signal_sender), join_handle),
         }
     }
 
+    fn start_hooks(&mut self) {
+        let queue_size = self
+            .config
+            .hook_queue_size
+            .unwrap_or(DEFAULT_HOOK_QUEUE_SIZE);
+        self.hooks = self
+            .config
+            .hooks
+            .iter()
+            .enumerate()
+            .map(|(index, config)| Hook::start(config.clone(), queue_size, index))
+            .collect();
+    }
+
+    // The scripts are replaced at once. The hooks are restarted only if they are changed,
+    // the old hooks exit after delivering their queued events.
+    fn handle_update_config(&mut self, msg: Result<NotifyConfig, RecvError>) {
+        match msg {
+            Ok(config) => {
+                debug!("Update notify config {:?}", config);
+                let hooks_changed = config.hooks != self.config.hooks
+                    || config.hook_queue_size != self.config.hook_queue_size;
+                self.config = config;
+                if hooks_changed {
+                    self.start_hooks();
+                }
+            }
+            _ => debug!("Update config channel is closed"),
+        }
+    }
+
     fn handle_register_new_block(
         &mut self,
         msg: Result<Request<String, Receiver<BlockView>>, RecvError>,
@@ -109,6 +177,11 @@ impl NotifyService {
                 for subscriber in self.new_block_subscribers.values() {
                     let _ = subscriber.send(block.clone());
                 }
+                // notify hooks
+                self.notify_hooks(
+                    HookEvent::NewBlock,
+                    || json!({ "header": JsonHeaderView::from(block.header()) }),
+                );
                 // notify script
                 if let Some(script) = self.config.new_block_notify_script.as_ref() {
                     let args = [format!("{:#x}", block.hash())];
@@ -150,6 +223,11 @@ impl NotifyService {
                 for subscriber in self.network_alert_subscribers.values() {
                     let _ = subscriber.send(alert.clone());
                 }
+                // notify hooks
+                self.notify_hooks(
+                    HookEvent::NetworkAlert,
+                    || json!({ "alert": AlertMessage::from(alert.clone()) }),
+                );
                 // notify script
                 if let Some(script) = self.config.network_alert_notify_script.as_ref() {
                     let args = [alert
@@ -170,6 +248,80 @@ impl NotifyService {
             _ => debug!("network alert channel is closed"),
         }
     }
+
+    fn handle_register_evicted_transaction(
+        &mut self,
+        msg: Result<Request<String, Receiver<TransactionView>>, RecvError>,
+    ) {
+        match msg {
+            Ok(Request {
+                responder,
+                arguments: name,
+            }) => {
+                debug!("Register evicted_transaction {:?}", name);
+                let (sender, receiver) = bounded::<TransactionView>(NOTIFY_CHANNEL_SIZE);
+                self.evicted_transaction_subscribers.insert(name, sender);
+                let _ = responder.send(receiver);
+            }
+            _ => debug!("Register evicted_transaction channel is closed"),
+        }
+    }
+
+    fn handle_notify_evicted_transaction(&mut self, msg: Result<TransactionView, RecvError>) {
+        match msg {
+            Ok(tx) => {
+                trace!("event evicted transaction {:?}", tx);
+                // notify all subscribers
+                for subscriber in self.evicted_transaction_subscribers.values() {
+                    let _ = subscriber.send(tx.clone());
+                }
+            }
+            _ => debug!("evicted transaction channel is closed"),
+        }
+    }
+
+    fn handle_notify_reorg(&mut self, msg: Result<ReorgEvent, RecvError>) {
+        match msg {
+            Ok(reorg) => {
+                trace!("event reorg {:?}", reorg);
+                self.notify_hooks(HookEvent::Reorg, || {
+                    let to_json = |headers: &[HeaderView]| {
+                        headers
+                            .iter()
+                            .cloned()
+                            .map(JsonHeaderView::from)
+                            .collect::<Vec<_>>()
+                    };
+                    json!({
+                        "detached_blocks": to_json(&reorg.detached_blocks),
+                        "attached_blocks": to_json(&reorg.attached_blocks),
+                    })
+                });
+            }
+            _ => debug!("reorg channel is closed"),
+        }
+    }
+
+    // The payload is built only when some hooks accept the event, and it is shared by them
+    fn notify_hooks<F>(&self, event: HookEvent, build_payload: F)
+    where
+        F: FnOnce() -> Value,
+    {
+        let hooks: Vec<_> = self
+            .hooks
+            .iter()
+            .filter(|hook| hook.accepts(event))
+            .collect();
+        if hooks.is_empty() {
+            return;
+        }
+        let mut payload = build_payload();
+        payload["event"] = json!(event.as_str());
+        let payload = Arc::new(payload.to_string());
+        for hook in hooks {
+            hook.send(event, Arc::clone(&payload));
+        }
+    }
 }
 
 impl NotifyController {
@@ -190,4 +342,25 @@ impl NotifyController {
     pub fn notify_network_alert(&self, alert: Alert) {
         let _ = self.network_alert_notifier.send(alert);
     }
+
+    pub fn subscribe_evicted_transaction<S: ToString>(&self, name: S) -> Receiver<TransactionView> {
+        Request::call(&self.evicted_transaction_register, name.to_string())
+            .expect("Subscribe evicted transaction should be OK")
+    }
+
+    /// Apply the reloaded scripts and hooks
+    pub fn update_config(&self, config: NotifyConfig) {
+        let _ = self.config_updater.send(config);
+    }
+
+    pub fn notify_reorg(&self, reorg: ReorgEvent) {
+        let _ = self.reorg_notifier.send(reorg);
+    }
+
+    // called by tx-pool with its lock held, drop the event rather than block it
+    pub fn notify_evicted_transaction(&self, tx: TransactionView) {
+        if let Err(err) = self.evicted_transaction_notifier.try_send(tx) {
+            debug!("notify evicted transaction failed: {}", err);
+        }
+    }
 }
@@ -1,5 +1,15 @@
+use ckb_dao_utils::DaoError;
 use ckb_error::{Error as CKBError, ErrorKind, InternalError, InternalErrorKind};
+use ckb_jsonrpc_types::BlockVerificationError;
+use ckb_script::TransactionScriptError;
 use ckb_tx_pool::error::Reject;
+use ckb_types::core::error::OutPointError;
+use ckb_verification::{
+    BlockError, BlockTransactionsError, BlockVersionError, CellbaseError, CommitError, EpochError,
+    HeaderError, InvalidParentError, NumberError, PowError, TimestampError, TransactionError,
+    UnclesError, UnknownParentError,
+};
+use failure::Fail;
 use jsonrpc_core::{Error, ErrorCode, Value};
 use std::fmt::{Debug, Display};
 
@@ -34,6 +44,7 @@ pub enum RPCError {
     PoolIsFull = -1106,
     PoolRejectedDuplicatedTransaction = -1107,
     PoolRejectedMalformedTransaction = -1108,
+    PoolRejectedInvalidPackage = -1109,
 }
 
 impl RPCError {
@@ -82,6 +93,7 @@ impl RPCError {
             Reject::Full(_, _) => RPCError::PoolIsFull,
             Reject::Duplicated(_) => RPCError::PoolRejectedDuplicatedTransaction,
             Reject::Malformed(_) => RPCError::PoolRejectedMalformedTransaction,
+            Reject::InvalidPackage(_) => RPCError::PoolRejectedInvalidPackage,
         };
         RPCError::custom_with_error(code, reject)
     }
@@ -137,6 +149,15 @@ impl RPCError {
         Self::custom_with_error(RPCError::CKBInternalError, err)
     }
 
+    /// The field `data` tells which check the block fails, see `BlockVerificationError`
+    pub fn from_block_verification_error(err: &CKBError) -> Error {
+        Error {
+            code: ErrorCode::ServerError(RPCError::Invalid as i64),
+            message: format!("{:?}: {}", RPCError::Invalid, err),
+            data: serde_json::to_value(block_verification_error(err)).ok(),
+        }
+    }
+
     pub fn rpc_module_is_disabled(module: &str) -> Error {
         Self::custom(
             RPCError::RPCModuleIsDisabled,
@@ -158,11 +179,201 @@ impl RPCError {
     }
 }
 
+fn block_verification_error(err: &CKBError) -> BlockVerificationError {
+    let mut detail = BlockVerificationError {
+        kind: err.kind().to_string(),
+        check: err.kind().to_string(),
+        message: err.to_string(),
+        ..Default::default()
+    };
+    match err.kind() {
+        ErrorKind::Header => {
+            if let Some(header_err) = err.downcast_ref::<HeaderError>() {
+                detail.check = header_err.kind().to_string();
+                if let Some(cause) = header_err.cause() {
+                    describe_header_error_cause(cause, &mut detail);
+                }
+            }
+        }
+        ErrorKind::Block => {
+            if let Some(block_err) = err.downcast_ref::<BlockError>() {
+                detail.check = block_err.kind().to_string();
+                if let Some(cause) = block_err.cause() {
+                    describe_block_error_cause(cause, &mut detail);
+                }
+            }
+        }
+        ErrorKind::Transaction => {
+            if let Some(tx_err) = err.downcast_ref::<TransactionError>() {
+                detail.check = tx_err.name().to_owned();
+                describe_transaction_error(tx_err, &mut detail);
+            }
+        }
+        _ => {
+            detail.reason = err.cause().and_then(error_name).map(ToOwned::to_owned);
+        }
+    }
+    detail
+}
+
+fn describe_header_error_cause(cause: &dyn Fail, detail: &mut BlockVerificationError) {
+    detail.reason = error_name(cause).map(ToOwned::to_owned);
+    let (expected, actual) = if let Some(err) = cause.downcast_ref::<PowError>() {
+        match err {
+            PowError::Boundary { expected, actual } => (
+                Some(format!("{:#x}", expected)),
+                Some(format!("{:#x}", actual)),
+            ),
+            PowError::InvalidNonce => (None, None),
+        }
+    } else if let Some(err) = cause.downcast_ref::<TimestampError>() {
+        match err {
+            TimestampError::BlockTimeTooOld { min, actual } => (
+                Some(format!(">= {:#x}", min)),
+                Some(format!("{:#x}", actual)),
+            ),
+            TimestampError::BlockTimeTooNew { max, actual } => (
+                Some(format!("<= {:#x}", max)),
+                Some(format!("{:#x}", actual)),
+            ),
+        }
+    } else if let Some(err) = cause.downcast_ref::<NumberError>() {
+        hex_pair(err.expected, err.actual)
+    } else if let Some(err) = cause.downcast_ref::<EpochError>() {
+        match err {
+            EpochError::TargetMismatch { expected, actual } => {
+                hex_pair(u64::from(*expected), u64::from(*actual))
+            }
+            EpochError::NumberMismatch { expected, actual } => hex_pair(*expected, *actual),
+        }
+    } else if let Some(err) = cause.downcast_ref::<BlockVersionError>() {
+        hex_pair(u64::from(err.expected), u64::from(err.actual))
+    } else if let Some(err) = cause.downcast_ref::<InvalidParentError>() {
+        (None, Some(format!("{:#x}", err.parent_hash)))
+    } else {
+        (None, None)
+    };
+    detail.expected = expected;
+    detail.actual = actual;
+}
+
+fn describe_block_error_cause(cause: &dyn Fail, detail: &mut BlockVerificationError) {
+    if let Some(err) = cause.downcast_ref::<BlockTransactionsError>() {
+        detail.tx_index = Some(err.index.into());
+        detail.tx_error = Some(Box::new(block_verification_error(&err.error)));
+        return;
+    }
+
+    detail.reason = error_name(cause).map(ToOwned::to_owned);
+    let (expected, actual) = if let Some(err) = cause.downcast_ref::<UnclesError>() {
+        match err {
+            UnclesError::OverCount { max, actual } => (
+                Some(format!("<= {:#x}", max)),
+                Some(format!("{:#x}", actual)),
+            ),
+            UnclesError::InvalidDepth { max, min, actual } => (
+                Some(format!("{:#x}..={:#x}", min, max)),
+                Some(format!("{:#x}", actual)),
+            ),
+            UnclesError::InvalidHash { expected, actual } => (
+                Some(format!("{:#x}", expected)),
+                Some(format!("{:#x}", actual)),
+            ),
+            UnclesError::Duplicate(hash) | UnclesError::DoubleInclusion(hash) => {
+                (None, Some(format!("{:#x}", hash)))
+            }
+            _ => (None, None),
+        }
+    } else if let Some(err) = cause.downcast_ref::<UnknownParentError>() {
+        (None, Some(format!("{:#x}", err.parent_hash)))
+    } else {
+        (None, None)
+    };
+    detail.expected = expected;
+    detail.actual = actual;
+}
+
+fn describe_transaction_error(err: &TransactionError, detail: &mut BlockVerificationError) {
+    let (expected, actual) = match err {
+        TransactionError::InsufficientCellCapacity {
+            occupied_capacity,
+            capacity,
+            ..
+        } => (
+            Some(format!(">= {:#x}", occupied_capacity)),
+            Some(format!("{:#x}", capacity)),
+        ),
+        TransactionError::OutputsSumOverflow {
+            inputs_sum,
+            outputs_sum,
+        } => (
+            Some(format!("<= {:#x}", inputs_sum)),
+            Some(format!("{:#x}", outputs_sum)),
+        ),
+        TransactionError::OutputsDataLengthMismatch {
+            outputs_len,
+            outputs_data_len,
+        } => hex_pair(*outputs_len as u64, *outputs_data_len as u64),
+        TransactionError::MismatchedVersion { expected, actual } => {
+            hex_pair(u64::from(*expected), u64::from(*actual))
+        }
+        TransactionError::ExceededMaximumBlockBytes { limit, actual } => (
+            Some(format!("< {:#x}", limit)),
+            Some(format!("{:#x}", actual)),
+        ),
+        _ => (None, None),
+    };
+    detail.expected = expected;
+    detail.actual = actual;
+}
+
+fn hex_pair(expected: u64, actual: u64) -> (Option<String>, Option<String>) {
+    (
+        Some(format!("{:#x}", expected)),
+        Some(format!("{:#x}", actual)),
+    )
+}
+
+// The variant name of the error enums, e.g. `NumberMismatch` of `EpochError::NumberMismatch`,
+// or the name of the error structs. `None` if the error is unknown.
+fn error_name(cause: &dyn Fail) -> Option<&'static str> {
+    if let Some(err) = cause.downcast_ref::<PowError>() {
+        Some(err.name())
+    } else if let Some(err) = cause.downcast_ref::<TimestampError>() {
+        Some(err.name())
+    } else if let Some(err) = cause.downcast_ref::<EpochError>() {
+        Some(err.name())
+    } else if let Some(err) = cause.downcast_ref::<UnclesError>() {
+        Some(err.name())
+    } else if let Some(err) = cause.downcast_ref::<CellbaseError>() {
+        Some(err.name())
+    } else if let Some(err) = cause.downcast_ref::<CommitError>() {
+        Some(err.name())
+    } else if let Some(err) = cause.downcast_ref::<OutPointError>() {
+        Some(err.name())
+    } else if let Some(err) = cause.downcast_ref::<DaoError>() {
+        Some(err.name())
+    } else if cause.downcast_ref::<NumberError>().is_some() {
+        Some("NumberError")
+    } else if cause.downcast_ref::<BlockVersionError>().is_some() {
+        Some("BlockVersionError")
+    } else if cause.downcast_ref::<InvalidParentError>().is_some() {
+        Some("InvalidParentError")
+    } else if cause.downcast_ref::<UnknownParentError>().is_some() {
+        Some("UnknownParentError")
+    } else if cause.downcast_ref::<TransactionScriptError>().is_some() {
+        Some("TransactionScriptError")
+    } else if cause.downcast_ref::<InternalError>().is_some() {
+        Some("InternalError")
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ckb_dao_utils::DaoError;
-    use ckb_types::{core::error::OutPointError, packed::Byte32};
+    use ckb_types::packed::Byte32;
 
     #[test]
     fn test_dao_error_from_ckb_error() {
@@ -210,6 +421,15 @@ mod tests {
             )
             .message
         );
+
+        let err: CKBError = Reject::InvalidPackage("empty package".to_owned()).into();
+        assert_eq!(
+            "PoolRejectedInvalidPackage: Invalid transaction package: empty package",
+            RPCError::from_submit_transaction_reject(
+                RPCError::downcast_submit_transaction_reject(&err).unwrap()
+            )
+            .message
+        );
     }
 
     #[test]
@@ -220,4 +440,79 @@ mod tests {
             RPCError::from_ckb_error(err).message
         );
     }
+
+    #[test]
+    fn test_block_verification_error() {
+        let err: CKBError = EpochError::NumberMismatch {
+            expected: 1,
+            actual: 2,
+        }
+        .into();
+        let rpc_err = RPCError::from_block_verification_error(&err);
+        assert_eq!(
+            "Invalid: Header(Epoch(NumberMismatch(expected: 1, actual: 2)))",
+            rpc_err.message
+        );
+        assert_eq!(
+            Some(serde_json::json!({
+                "kind": "Header",
+                "check": "Epoch",
+                "reason": "NumberMismatch",
+                "tx_index": null,
+                "tx_error": null,
+                "expected": "0x1",
+                "actual": "0x2",
+                "message": "Header(Epoch(NumberMismatch(expected: 1, actual: 2)))",
+            })),
+            rpc_err.data
+        );
+
+        let err: CKBError = BlockTransactionsError {
+            index: 3,
+            error: TransactionError::MismatchedVersion {
+                expected: 0,
+                actual: 1,
+            }
+            .into(),
+        }
+        .into();
+        let detail = block_verification_error(&err);
+        assert_eq!(
+            (detail.kind.as_str(), detail.check.as_str()),
+            ("Block", "BlockTransactions")
+        );
+        assert_eq!(detail.tx_index, Some(3u32.into()));
+        let tx_error = detail.tx_error.expect("tx error");
+        assert_eq!(
+            (tx_error.kind.as_str(), tx_error.check.as_str()),
+            ("Transaction", "MismatchedVersion")
+        );
+        assert_eq!(tx_error.expected, Some("0x0".to_owned()));
+        assert_eq!(tx_error.actual, Some("0x1".to_owned()));
+
+        let err: CKBError = UnclesError::OverCount { max: 2, actual: 3 }.into();
+        let detail = block_verification_error(&err);
+        assert_eq!(
+            (detail.check.as_str(), detail.reason.as_deref()),
+            ("Uncles", Some("OverCount"))
+        );
+
+        let err: CKBError = NumberError {
+            expected: 1,
+            actual: 2,
+        }
+        .into();
+        let detail = block_verification_error(&err);
+        assert_eq!(
+            (detail.check.as_str(), detail.reason.as_deref()),
+            ("Number", Some("NumberError"))
+        );
+
+        let err: CKBError = DaoError::InvalidHeader.into();
+        let detail = block_verification_error(&err);
+        assert_eq!(
+            (detail.kind.as_str(), detail.reason.as_deref()),
+            ("Dao", Some("InvalidHeader"))
+        );
+    }
 }
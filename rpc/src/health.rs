@@ -0,0 +1,256 @@
+use ckb_app_config::RpcConfig;
+use ckb_channel::{bounded, RecvTimeoutError, Sender};
+use ckb_logger::error;
+use ckb_network::NetworkController;
+use ckb_sync::SyncShared;
+use ckb_util::Mutex;
+use faketime::unix_time_as_millis;
+use jsonrpc_http_server::hyper::{header, Body, Method, Request, Response, StatusCode};
+use jsonrpc_http_server::RequestMiddlewareAction;
+use serde::Serialize;
+use std::io;
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const LIVE_PATH: &str = "/health/live";
+const READY_PATH: &str = "/health/ready";
+const TX_POOL_PROBE_INTERVAL: Duration = Duration::from_secs(1);
+// The tx-pool is not ready if the last probe returned longer ago, e.g. it is stuck
+const TX_POOL_PROBE_MAX_AGE: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+struct Liveness {
+    live: bool,
+}
+
+#[derive(Serialize)]
+struct Readiness {
+    ready: bool,
+    checks: ReadinessChecks,
+}
+
+#[derive(Serialize)]
+struct ReadinessChecks {
+    initial_block_download: Check,
+    tip_age: Check,
+    outbound_peers: Check,
+    tx_pool: Check,
+}
+
+#[derive(Serialize)]
+struct Check {
+    ok: bool,
+    message: String,
+}
+
+impl Check {
+    fn new(ok: bool, message: String) -> Self {
+        Check { ok, message }
+    }
+}
+
+// The last result of the background tx-pool probe
+struct TxPoolProbe {
+    result: Result<String, String>,
+    probed_at: u64,
+}
+
+// The background thread of the tx-pool probe
+struct TxPoolProbeThread {
+    stop: Sender<()>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl From<Result<String, String>> for Check {
+    fn from(result: Result<String, String>) -> Self {
+        match result {
+            Ok(message) => Check::new(true, message),
+            Err(message) => Check::new(false, message),
+        }
+    }
+}
+
+/// Serves the health endpoints for load balancers on the HTTP RPC server.
+///
+/// * `/health/live` responds 200 as long as the server is running.
+/// * `/health/ready` responds 200 if all the checks pass, otherwise 503.
+///
+/// Both respond a JSON body, the readiness one carries the result of every check. The tx-pool
+/// is probed on a background thread, the requests never wait for it.
+#[derive(Clone)]
+pub struct HealthCheck {
+    sync_shared: Arc<SyncShared>,
+    network_controller: NetworkController,
+    max_tip_age_secs: u64,
+    min_outbound_peers: usize,
+    // Filled by a background thread, the requests only read it. The thread exits once it's
+    // stopped or all the clones are dropped.
+    tx_pool_probe: Arc<Mutex<Option<TxPoolProbe>>>,
+    tx_pool_probe_thread: Arc<TxPoolProbeThread>,
+}
+
+impl HealthCheck {
+    pub fn new(
+        config: &RpcConfig,
+        sync_shared: Arc<SyncShared>,
+        network_controller: NetworkController,
+    ) -> Result<Self, io::Error> {
+        let tx_pool_probe = Arc::new(Mutex::new(None));
+        let tx_pool_probe_thread =
+            spawn_tx_pool_probe(&sync_shared, Arc::downgrade(&tx_pool_probe))?;
+        Ok(HealthCheck {
+            sync_shared,
+            network_controller,
+            max_tip_age_secs: config.health_max_tip_age_secs,
+            min_outbound_peers: config.health_min_outbound_peers,
+            tx_pool_probe,
+            tx_pool_probe_thread: Arc::new(tx_pool_probe_thread),
+        })
+    }
+
+    /// Stop the tx-pool probe and wait for its thread, which holds a `TxPoolController`, so the
+    /// tx-pool is still stopped by the last controller the node drops.
+    pub fn stop(&self) {
+        let thread = &self.tx_pool_probe_thread;
+        if let Some(handle) = thread.handle.lock().take() {
+            // the thread may have exited already
+            let _ = thread.stop.try_send(());
+            if let Err(err) = handle.join() {
+                error!("health check tx-pool probe join error {:?}", err);
+            }
+        }
+    }
+
+    pub(crate) fn on_request(&self, request: Request<Body>) -> RequestMiddlewareAction {
+        if *request.method() != Method::GET {
+            return request.into();
+        }
+        match request.uri().path() {
+            LIVE_PATH => json_response(StatusCode::OK, &Liveness { live: true }).into(),
+            READY_PATH => {
+                let readiness = self.readiness();
+                let status = if readiness.ready {
+                    StatusCode::OK
+                } else {
+                    StatusCode::SERVICE_UNAVAILABLE
+                };
+                json_response(status, &readiness).into()
+            }
+            _ => request.into(),
+        }
+    }
+
+    fn readiness(&self) -> Readiness {
+        let checks = ReadinessChecks {
+            initial_block_download: self.check_initial_block_download(),
+            tip_age: self.check_tip_age(),
+            outbound_peers: self.check_outbound_peers(),
+            tx_pool: self.check_tx_pool(),
+        };
+        let ready = checks.initial_block_download.ok
+            && checks.tip_age.ok
+            && checks.outbound_peers.ok
+            && checks.tx_pool.ok;
+        Readiness { ready, checks }
+    }
+
+    fn check_initial_block_download(&self) -> Check {
+        if self.sync_shared.active_chain().is_initial_block_download() {
+            Check::new(false, "in initial block download".to_owned())
+        } else {
+            Check::new(true, "out of initial block download".to_owned())
+        }
+    }
+
+    fn check_tip_age(&self) -> Check {
+        let tip = self.sync_shared.active_chain().tip_header();
+        let age_secs = unix_time_as_millis().saturating_sub(tip.timestamp()) / 1000;
+        Check::new(
+            age_secs <= self.max_tip_age_secs,
+            format!(
+                "tip #{} is {}s old, max {}s",
+                tip.number(),
+                age_secs,
+                self.max_tip_age_secs
+            ),
+        )
+    }
+
+    fn check_outbound_peers(&self) -> Check {
+        let outbound_peers = self
+            .network_controller
+            .connected_peers()
+            .into_iter()
+            .filter(|(_, peer)| peer.is_outbound() && !peer.is_feeler)
+            .count();
+        Check::new(
+            outbound_peers >= self.min_outbound_peers,
+            format!(
+                "{} outbound peers, min {}",
+                outbound_peers, self.min_outbound_peers
+            ),
+        )
+    }
+
+    fn check_tx_pool(&self) -> Check {
+        match *self.tx_pool_probe.lock() {
+            None => Check::new(false, "not probed yet".to_owned()),
+            Some(ref probe) => {
+                let age = unix_time_as_millis().saturating_sub(probe.probed_at);
+                if age > TX_POOL_PROBE_MAX_AGE.as_millis() as u64 {
+                    Check::new(
+                        false,
+                        format!("the last probe returned {}s ago", age / 1000),
+                    )
+                } else {
+                    probe.result.clone().into()
+                }
+            }
+        }
+    }
+}
+
+// Probe the tx-pool periodically, a stuck tx-pool only blocks this thread
+fn spawn_tx_pool_probe(
+    sync_shared: &SyncShared,
+    cache: Weak<Mutex<Option<TxPoolProbe>>>,
+) -> Result<TxPoolProbeThread, io::Error> {
+    let tx_pool = sync_shared.shared().tx_pool_controller().clone();
+    let (stop, stop_receiver) = bounded(1);
+    let handle = thread::Builder::new()
+        .name("HealthCheckTxPool".to_string())
+        .spawn(move || loop {
+            let result = tx_pool
+                .get_tx_pool_info()
+                .map(|info| format!("responsive, {} pending txs", info.pending_size))
+                .map_err(|err| format!("get_tx_pool_info error: {}", err));
+            match cache.upgrade() {
+                Some(cache) => {
+                    *cache.lock() = Some(TxPoolProbe {
+                        result,
+                        probed_at: unix_time_as_millis(),
+                    })
+                }
+                None => break,
+            }
+            // wake up at once when it's stopped or the sender is dropped with the health check
+            match stop_receiver.recv_timeout(TX_POOL_PROBE_INTERVAL) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+        })?;
+    Ok(TxPoolProbeThread {
+        stop,
+        handle: Mutex::new(Some(handle)),
+    })
+}
+
+fn json_response<T: Serialize>(status: StatusCode, body: &T) -> Response<Body> {
+    let body = serde_json::to_string(body).expect("serialize health check");
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body.into())
+        .expect("build health check response")
+}
@@ -1,4 +1,5 @@
 pub(crate) mod error;
+pub(crate) mod health;
 pub(crate) mod module;
 pub(crate) mod server;
 pub(crate) mod service_builder;
@@ -6,6 +7,7 @@ pub(crate) mod service_builder;
 #[cfg(test)]
 mod test;
 
+pub use crate::health::HealthCheck;
 pub use crate::server::RpcServer;
 pub use crate::service_builder::ServiceBuilder;
 
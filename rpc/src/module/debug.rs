@@ -1,7 +1,15 @@
-use ckb_jsonrpc_types::{ExtraLoggerConfig, MainLoggerConfig};
+use crate::error::RPCError;
+use ckb_config_reloader::ConfigReloader;
+use ckb_indexer::DefaultIndexerStore;
+use ckb_instrument::Backup;
+use ckb_jsonrpc_types::{BackupManifest, ConfigReloadReport, ExtraLoggerConfig, MainLoggerConfig};
 use ckb_logger_service::Logger;
+use ckb_network::NetworkController;
+use ckb_shared::shared::Shared;
 use jsonrpc_core::{Error, ErrorCode::InternalError, Result};
 use jsonrpc_derive::rpc;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time;
 
 #[rpc(server)]
@@ -12,9 +20,18 @@ pub trait DebugRpc {
     fn update_main_logger(&self, config: MainLoggerConfig) -> Result<()>;
     #[rpc(name = "set_extra_logger")]
     fn set_extra_logger(&self, name: String, config_opt: Option<ExtraLoggerConfig>) -> Result<()>;
+    #[rpc(name = "backup_database")]
+    fn backup_database(&self, path: String) -> Result<BackupManifest>;
+    #[rpc(name = "reload_config")]
+    fn reload_config(&self) -> Result<ConfigReloadReport>;
 }
 
-pub(crate) struct DebugRpcImpl {}
+pub(crate) struct DebugRpcImpl {
+    pub shared: Shared,
+    pub network_controller: NetworkController,
+    pub indexer_store: Option<DefaultIndexerStore>,
+    pub config_reloader: Option<Arc<ConfigReloader>>,
+}
 
 impl DebugRpc for DebugRpcImpl {
     fn jemalloc_profiling_dump(&self) -> Result<String> {
@@ -69,4 +86,44 @@ impl DebugRpc for DebugRpcImpl {
             data: None,
         })
     }
+
+    fn backup_database(&self, path: String) -> Result<BackupManifest> {
+        let target = PathBuf::from(path);
+        if target.is_relative() {
+            return Err(RPCError::invalid_params("the backup path must be absolute"));
+        }
+        let mut backup = Backup::new(self.shared.store().clone(), target);
+        if let Some(ref store) = self.indexer_store {
+            backup = backup.indexer_db(store.db().clone());
+        }
+        match self.network_controller.peer_store_db() {
+            Ok(Some(db)) => backup = backup.peer_store_db(db),
+            Ok(None) => {}
+            Err(err) => {
+                return Err(Error {
+                    code: InternalError,
+                    message: format!("failed to flush the peer store: {}", err),
+                    data: None,
+                })
+            }
+        }
+        backup.execute().map_err(|err| Error {
+            code: InternalError,
+            message: err.to_string(),
+            data: None,
+        })
+    }
+
+    fn reload_config(&self) -> Result<ConfigReloadReport> {
+        let reloader = self.config_reloader.as_ref().ok_or_else(|| Error {
+            code: InternalError,
+            message: "the config can not be reloaded".to_owned(),
+            data: None,
+        })?;
+        reloader.reload().map_err(|err| Error {
+            code: InternalError,
+            message: err,
+            data: None,
+        })
+    }
 }
@@ -1,16 +1,29 @@
 use crate::error::RPCError;
-use ckb_chain::chain::ChainController;
-use ckb_jsonrpc_types::{Block, BlockTemplate, Uint64, Version};
+use ckb_chain::{chain::ChainController, switch::Switch};
+use ckb_error::Error as CKBError;
+use ckb_jsonrpc_types::{Block, BlockTemplate, Cycle, Uint64, UncleBlock, UncleBlockView, Version};
 use ckb_logger::{debug, error};
 use ckb_network::{NetworkController, SupportProtocols};
 use ckb_shared::{shared::Shared, Snapshot};
-use ckb_types::{core, packed, prelude::*, H256};
-use ckb_verification::{HeaderResolverWrapper, HeaderVerifier, Verifier};
+use ckb_store::ChainStore;
+use ckb_types::{
+    core::{
+        self,
+        cell::{resolve_transaction, BlockCellProvider, OverlayCellProvider},
+    },
+    packed,
+    prelude::*,
+    H256,
+};
+use ckb_verification::{
+    BlockTransactionsError, BlockVerifier, ContextualBlockVerifier, HeaderResolverWrapper,
+    HeaderVerifier, NonContextualBlockTxsVerifier, NumberVerifier, TimestampVerifier, Verifier,
+    VerifyContext, VersionVerifier,
+};
 use faketime::unix_time_as_millis;
 use jsonrpc_core::{Error, Result};
 use jsonrpc_derive::rpc;
 use std::collections::HashSet;
-use std::fmt::Debug;
 use std::sync::Arc;
 
 #[rpc(server)]
@@ -27,6 +40,18 @@ pub trait MinerRpc {
     // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"submit_block","params": [{"header":{}, "uncles":[], "transactions":[], "proposals":[]}]}' -H 'content-type:application/json' 'http://localhost:8114'
     #[rpc(name = "submit_block")]
     fn submit_block(&self, _work_id: String, _data: Block) -> Result<H256>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"validate_block_proposal","params": [{"header":{}, "uncles":[], "transactions":[], "proposals":[]}]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "validate_block_proposal")]
+    fn validate_block_proposal(&self, _data: Block) -> Result<Cycle>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"get_candidate_uncles","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "get_candidate_uncles")]
+    fn get_candidate_uncles(&self) -> Result<Vec<UncleBlockView>>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"add_candidate_uncle","params": [{"header":{}, "proposals":[]}]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "add_candidate_uncle")]
+    fn add_candidate_uncle(&self, _uncle: UncleBlock) -> Result<bool>;
 }
 
 pub(crate) struct MinerRpcImpl {
@@ -105,9 +130,132 @@ impl MinerRpc for MinerRpcImpl {
 
         Ok(header.hash().unpack())
     }
+
+    fn validate_block_proposal(&self, data: Block) -> Result<Cycle> {
+        let block: packed::Block = data.into();
+        let block = block.into_view();
+        let snapshot: &Snapshot = &self.shared.snapshot();
+        if block.parent_hash() != snapshot.tip_hash() {
+            return Err(RPCError::custom(
+                RPCError::Invalid,
+                format!(
+                    "the block proposal must extend the tip block {:#x}",
+                    snapshot.tip_hash()
+                ),
+            ));
+        }
+
+        self.verify_block_proposal(snapshot, &block)
+            .map(Into::into)
+            .map_err(|err| {
+                debug!("validate_block_proposal error: {}", err);
+                RPCError::from_block_verification_error(&err)
+            })
+    }
+
+    fn get_candidate_uncles(&self) -> Result<Vec<UncleBlockView>> {
+        let tx_pool = self.shared.tx_pool_controller();
+        tx_pool
+            .get_candidate_uncles()
+            .map(|uncles| uncles.into_iter().map(Into::into).collect())
+            .map_err(|err| {
+                error!("send get_candidate_uncles request error {}", err);
+                RPCError::ckb_internal_error(err)
+            })
+    }
+
+    fn add_candidate_uncle(&self, uncle: UncleBlock) -> Result<bool> {
+        let uncle: packed::UncleBlock = uncle.into();
+        let uncle = uncle.into_view();
+        let header = uncle.header();
+
+        if uncle.proposals_hash() != uncle.calc_proposals_hash() {
+            return Err(RPCError::custom(
+                RPCError::Invalid,
+                "the proposals hash of the uncle mismatches its proposals",
+            ));
+        }
+
+        let snapshot: &Snapshot = &self.shared.snapshot();
+        if snapshot.get_block_number(&uncle.hash()).is_some() || snapshot.is_uncle(&uncle.hash()) {
+            return Err(RPCError::custom(
+                RPCError::Invalid,
+                "the uncle is already in the main chain",
+            ));
+        }
+        if uncle.epoch().number() < snapshot.epoch_ext().number() {
+            return Err(RPCError::custom(
+                RPCError::Invalid,
+                "the uncle is from a finished epoch",
+            ));
+        }
+
+        // Verify header
+        let resolver = HeaderResolverWrapper::new(&header, snapshot);
+        HeaderVerifier::new(snapshot, &self.shared.consensus())
+            .verify(&resolver)
+            .map_err(|err| RPCError::custom_with_error(RPCError::Invalid, err))?;
+
+        let tx_pool = self.shared.tx_pool_controller();
+        tx_pool.add_candidate_uncle(uncle).map_err(|err| {
+            error!("send add_candidate_uncle request error {}", err);
+            RPCError::ckb_internal_error(err)
+        })
+    }
+}
+
+impl MinerRpcImpl {
+    // Runs all the verifications of a block extending the tip, without inserting it. The
+    // proposal is not solved yet, so the header is verified without the PoW check.
+    fn verify_block_proposal(
+        &self,
+        snapshot: &Snapshot,
+        block: &core::BlockView,
+    ) -> ::std::result::Result<core::Cycle, CKBError> {
+        let consensus = snapshot.consensus();
+        let header = block.header();
+        VersionVerifier::new(&header, consensus.block_version()).verify()?;
+        NumberVerifier::new(snapshot.tip_header(), &header).verify()?;
+        TimestampVerifier::new(snapshot, &header).verify()?;
+        BlockVerifier::new(consensus).verify(block)?;
+        NonContextualBlockTxsVerifier::new(consensus).verify(block)?;
+
+        let verify_context = VerifyContext::new(snapshot, consensus);
+        let block_cp = BlockCellProvider::new(block)?;
+        let cell_provider = OverlayCellProvider::new(&block_cp, snapshot);
+        let mut seen_inputs = HashSet::new();
+        let resolved = block
+            .transactions()
+            .into_iter()
+            .enumerate()
+            .map(|(index, tx)| {
+                resolve_transaction(tx, &mut seen_inputs, &cell_provider, &verify_context).map_err(
+                    |error| {
+                        BlockTransactionsError {
+                            index: index as u32,
+                            error,
+                        }
+                        .into()
+                    },
+                )
+            })
+            .collect::<::std::result::Result<Vec<_>, CKBError>>()?;
+
+        let tx_pool = self.shared.tx_pool_controller();
+        let contextual_block_verifier = ContextualBlockVerifier::new(&verify_context);
+        contextual_block_verifier
+            .verify(
+                &resolved,
+                block,
+                self.shared.txs_verify_cache(),
+                tx_pool.handle(),
+                Switch::NONE,
+            )
+            .map(|(cycles, _)| cycles)
+    }
 }
 
-fn handle_submit_error<E: std::fmt::Display + Debug>(work_id: &str, err: &E) -> Error {
+fn handle_submit_error(work_id: &str, err: &CKBError) -> Error {
     error!("[{}] submit_block error: {:?}", work_id, err);
-    RPCError::custom_with_error(RPCError::Invalid, err)
+    RPCError::from_block_verification_error(err)
 }
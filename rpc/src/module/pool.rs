@@ -1,17 +1,21 @@
 use crate::error::RPCError;
 use ckb_chain_spec::consensus::Consensus;
-use ckb_fee_estimator::FeeRate;
-use ckb_jsonrpc_types::{OutputsValidator, Transaction, TxPoolInfo};
+use ckb_jsonrpc_types::{
+    Capacity, OutputsValidator, Transaction, TxLifecycle, TxLifecycleStatus, TxPoolInfo,
+};
 use ckb_logger::error;
 use ckb_network::PeerIndex;
 use ckb_script::IllTransactionChecker;
 use ckb_shared::shared::Shared;
+use ckb_store::ChainStore;
 use ckb_sync::SyncShared;
 use ckb_tx_pool::error::Reject;
+use ckb_tx_pool::pool::TxPoolStatus;
 use ckb_types::{core, packed, prelude::*, H256};
 use ckb_verification::{Since, SinceMetric};
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
+use std::cmp;
 use std::convert::TryInto;
 use std::sync::Arc;
 
@@ -25,6 +29,14 @@ pub trait PoolRpc {
         _outputs_validator: Option<OutputsValidator>,
     ) -> Result<H256>;
 
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"send_transaction_package","params": [[{"version":2, "deps":[], "inputs":[], "outputs":[]}]]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "send_transaction_package")]
+    fn send_transaction_package(
+        &self,
+        _txs: Vec<Transaction>,
+        _outputs_validator: Option<OutputsValidator>,
+    ) -> Result<Vec<H256>>;
+
     // curl -d '{"params": [], "method": "tx_pool_info", "jsonrpc": "2.0", "id": 2}' -H 'content-type:application/json' http://localhost:8114
     #[rpc(name = "tx_pool_info")]
     fn tx_pool_info(&self) -> Result<TxPoolInfo>;
@@ -32,12 +44,19 @@ pub trait PoolRpc {
     // curl -d '{"params": [], "method": "clear_tx_pool", "jsonrpc": "2.0", "id": 2}' -H 'content-type:application/json' http://localhost:8114
     #[rpc(name = "clear_tx_pool")]
     fn clear_tx_pool(&self) -> Result<()>;
+
+    // curl -d '{"params": ["0xa0ef4eb5f4ceeb08a4c8524d84c5da95dce2f608e0ca2ec8091191b0f330c6e3", "0x2540be400"], "method": "prioritise_transaction", "jsonrpc": "2.0", "id": 2}' -H 'content-type:application/json' http://localhost:8114
+    #[rpc(name = "prioritise_transaction")]
+    fn prioritise_transaction(&self, _tx_hash: H256, _fee_delta: Capacity) -> Result<()>;
+
+    // curl -d '{"params": ["0xa0ef4eb5f4ceeb08a4c8524d84c5da95dce2f608e0ca2ec8091191b0f330c6e3"], "method": "get_transaction_status", "jsonrpc": "2.0", "id": 2}' -H 'content-type:application/json' http://localhost:8114
+    #[rpc(name = "get_transaction_status")]
+    fn get_transaction_status(&self, _tx_hash: H256) -> Result<TxLifecycle>;
 }
 
 pub(crate) struct PoolRpcImpl {
     sync_shared: Arc<SyncShared>,
     shared: Shared,
-    min_fee_rate: FeeRate,
     reject_ill_transactions: bool,
 }
 
@@ -45,30 +64,25 @@ impl PoolRpcImpl {
     pub fn new(
         shared: Shared,
         sync_shared: Arc<SyncShared>,
-        min_fee_rate: FeeRate,
         reject_ill_transactions: bool,
     ) -> PoolRpcImpl {
         PoolRpcImpl {
             sync_shared,
             shared,
-            min_fee_rate,
             reject_ill_transactions,
         }
     }
 }
 
-impl PoolRpc for PoolRpcImpl {
-    fn send_transaction(
+impl PoolRpcImpl {
+    fn check_transaction(
         &self,
-        tx: Transaction,
+        tx: &core::TransactionView,
         outputs_validator: Option<OutputsValidator>,
-    ) -> Result<H256> {
-        let tx: packed::Transaction = tx.into();
-        let tx: core::TransactionView = tx.into_view();
-
+    ) -> Result<()> {
         if let Err(e) = match outputs_validator {
             Some(OutputsValidator::Default) => {
-                DefaultOutputsValidator::new(self.shared.consensus()).validate(&tx)
+                DefaultOutputsValidator::new(self.shared.consensus()).validate(tx)
             }
             Some(OutputsValidator::Passthrough) | None => Ok(()),
         } {
@@ -84,7 +98,7 @@ impl PoolRpc for PoolRpcImpl {
         }
 
         if self.reject_ill_transactions {
-            if let Err(e) = IllTransactionChecker::new(&tx).check() {
+            if let Err(e) = IllTransactionChecker::new(tx).check() {
                 return Err(RPCError::custom_with_data(
                     RPCError::PoolRejectedTransactionByIllTransactionChecker,
                     "The transaction is rejected by IllTransactionChecker",
@@ -93,6 +107,32 @@ impl PoolRpc for PoolRpcImpl {
             }
         }
 
+        Ok(())
+    }
+
+    fn broadcast(&self, tx_hash: packed::Byte32) {
+        // workaround: we are using `PeerIndex(usize::max)` to indicate that tx hash source is itself.
+        let peer_index = PeerIndex::new(usize::max_value());
+        self.sync_shared
+            .state()
+            .tx_hashes()
+            .entry(peer_index)
+            .or_default()
+            .insert(tx_hash);
+    }
+}
+
+impl PoolRpc for PoolRpcImpl {
+    fn send_transaction(
+        &self,
+        tx: Transaction,
+        outputs_validator: Option<OutputsValidator>,
+    ) -> Result<H256> {
+        let tx: packed::Transaction = tx.into();
+        let tx: core::TransactionView = tx.into_view();
+
+        self.check_transaction(&tx, outputs_validator)?;
+
         let tx_pool = self.shared.tx_pool_controller();
         let submit_txs = tx_pool.submit_txs(vec![tx.clone()]);
 
@@ -101,26 +141,16 @@ impl PoolRpc for PoolRpcImpl {
             return Err(RPCError::ckb_internal_error(e));
         }
 
-        let broadcast = |tx_hash: packed::Byte32| {
-            // workaround: we are using `PeerIndex(usize::max)` to indicate that tx hash source is itself.
-            let peer_index = PeerIndex::new(usize::max_value());
-            self.sync_shared
-                .state()
-                .tx_hashes()
-                .entry(peer_index)
-                .or_default()
-                .insert(tx_hash);
-        };
         let tx_hash = tx.hash();
         match submit_txs.unwrap() {
             Ok(_) => {
-                broadcast(tx_hash.clone());
+                self.broadcast(tx_hash.clone());
                 Ok(tx_hash.unpack())
             }
             Err(e) => match RPCError::downcast_submit_transaction_reject(&e) {
                 Some(reject) => {
                     if let Reject::Duplicated(_) = reject {
-                        broadcast(tx_hash);
+                        self.broadcast(tx_hash);
                     }
                     Err(RPCError::from_submit_transaction_reject(reject))
                 }
@@ -129,6 +159,54 @@ impl PoolRpc for PoolRpcImpl {
         }
     }
 
+    fn send_transaction_package(
+        &self,
+        txs: Vec<Transaction>,
+        outputs_validator: Option<OutputsValidator>,
+    ) -> Result<Vec<H256>> {
+        let txs: Vec<core::TransactionView> = txs
+            .into_iter()
+            .map(|tx| {
+                let tx: packed::Transaction = tx.into();
+                tx.into_view()
+            })
+            .collect();
+        for tx in &txs {
+            self.check_transaction(tx, outputs_validator.clone())?;
+        }
+
+        let tx_pool = self.shared.tx_pool_controller();
+        let submit_package = tx_pool.submit_package(txs.clone());
+
+        if let Err(e) = submit_package {
+            error!("send submit_package request error {}", e);
+            return Err(RPCError::ckb_internal_error(e));
+        }
+
+        let tx_hashes: Vec<packed::Byte32> = txs.iter().map(|tx| tx.hash()).collect();
+        match submit_package.unwrap() {
+            Ok(_) => {
+                // the peers understanding package relay get the txs as a package, the others
+                // get the hashes as usual
+                self.sync_shared
+                    .state()
+                    .tx_packages()
+                    .push((PeerIndex::new(usize::max_value()), tx_hashes.clone()));
+                for tx_hash in &tx_hashes {
+                    self.broadcast(tx_hash.clone());
+                }
+                Ok(tx_hashes
+                    .into_iter()
+                    .map(|tx_hash| tx_hash.unpack())
+                    .collect())
+            }
+            Err(e) => match RPCError::downcast_submit_transaction_reject(&e) {
+                Some(reject) => Err(RPCError::from_submit_transaction_reject(reject)),
+                None => Err(RPCError::from_ckb_error(e)),
+            },
+        }
+    }
+
     fn tx_pool_info(&self) -> Result<TxPoolInfo> {
         let tx_pool = self.shared.tx_pool_controller();
         let get_tx_pool_info = tx_pool.get_tx_pool_info();
@@ -147,7 +225,9 @@ impl PoolRpc for PoolRpcImpl {
             orphan: (tx_pool_info.orphan_size as u64).into(),
             total_tx_size: (tx_pool_info.total_tx_size as u64).into(),
             total_tx_cycles: tx_pool_info.total_tx_cycles.into(),
-            min_fee_rate: self.min_fee_rate.as_u64().into(),
+            min_fee_rate: cmp::max(tx_pool.min_fee_rate(), tx_pool_info.min_fee_rate)
+                .as_u64()
+                .into(),
             last_txs_updated_at: tx_pool_info.last_txs_updated_at.into(),
         })
     }
@@ -161,6 +241,107 @@ impl PoolRpc for PoolRpcImpl {
 
         Ok(())
     }
+
+    fn prioritise_transaction(&self, tx_hash: H256, fee_delta: Capacity) -> Result<()> {
+        let tx_pool = self.shared.tx_pool_controller();
+        tx_pool
+            .prioritise_tx(tx_hash.pack(), fee_delta.into())
+            .map_err(|err| RPCError::custom(RPCError::Invalid, err.to_string()))?;
+
+        Ok(())
+    }
+
+    fn get_transaction_status(&self, tx_hash: H256) -> Result<TxLifecycle> {
+        let tx_hash = tx_hash.pack();
+        let id = packed::ProposalShortId::from_tx_hash(&tx_hash);
+        let snapshot = self.shared.snapshot();
+        let proposal_window = self.shared.consensus().tx_proposal_window();
+
+        // find the earliest main chain block in [from, to] proposing the transaction,
+        // the proposals of its uncles are counted as well
+        let find_proposed_block = |from: core::BlockNumber, to: core::BlockNumber| {
+            (from..=to)
+                .filter_map(|number| snapshot.get_block_hash(number).map(|hash| (number, hash)))
+                .find(|(_, hash)| {
+                    let proposed_in_block = snapshot
+                        .get_block_proposal_txs_ids(hash)
+                        .map(|ids| ids.into_iter().any(|proposal| proposal == id))
+                        .unwrap_or(false);
+                    let proposed_in_uncles = || {
+                        snapshot
+                            .get_block_uncles(hash)
+                            .map(|uncles| {
+                                uncles.into_iter().any(|uncle| {
+                                    uncle
+                                        .data()
+                                        .proposals()
+                                        .into_iter()
+                                        .any(|proposal| proposal == id)
+                                })
+                            })
+                            .unwrap_or(false)
+                    };
+                    proposed_in_block || proposed_in_uncles()
+                })
+        };
+
+        if let Some(tx_info) = snapshot.get_transaction_info(&tx_hash) {
+            let mut lifecycle = TxLifecycle::with_status(TxLifecycleStatus::Committed);
+            lifecycle.committed_block_number = Some(tx_info.block_number.into());
+            lifecycle.committed_block_hash = Some(tx_info.block_hash.unpack());
+            lifecycle.committed_tx_index = Some((tx_info.index as u32).into());
+            if tx_info.block_number >= proposal_window.closest() {
+                if let Some((number, hash)) = find_proposed_block(
+                    tx_info
+                        .block_number
+                        .saturating_sub(proposal_window.farthest()),
+                    tx_info.block_number - proposal_window.closest(),
+                ) {
+                    lifecycle.proposed_block_number = Some(number.into());
+                    lifecycle.proposed_block_hash = Some(hash.unpack());
+                }
+            }
+            return Ok(lifecycle);
+        }
+
+        let tx_pool = self.shared.tx_pool_controller();
+        let status = tx_pool
+            .fetch_tx_status(tx_hash)
+            .map_err(|err| RPCError::custom(RPCError::Invalid, err.to_string()))?;
+        let lifecycle = match status {
+            Some(TxPoolStatus::Pending(received_at)) => {
+                let mut lifecycle = TxLifecycle::with_status(TxLifecycleStatus::Pending);
+                lifecycle.received_at = Some(received_at.into());
+                lifecycle
+            }
+            Some(TxPoolStatus::Orphan) => TxLifecycle::with_status(TxLifecycleStatus::Pending),
+            Some(TxPoolStatus::Proposed(received_at)) => {
+                let mut lifecycle = TxLifecycle::with_status(TxLifecycleStatus::Proposed);
+                lifecycle.received_at = Some(received_at.into());
+                let tip_number = snapshot.tip_number();
+                if let Some((number, hash)) = find_proposed_block(
+                    (tip_number + 1).saturating_sub(proposal_window.farthest()),
+                    tip_number,
+                ) {
+                    lifecycle.proposed_block_number = Some(number.into());
+                    lifecycle.proposed_block_hash = Some(hash.unpack());
+                }
+                lifecycle
+            }
+            Some(TxPoolStatus::Removed(reason)) => {
+                let mut lifecycle = TxLifecycle::with_status(TxLifecycleStatus::Rejected);
+                lifecycle.reason = Some(reason.to_string());
+                lifecycle
+            }
+            Some(TxPoolStatus::Rejected(reason)) => {
+                let mut lifecycle = TxLifecycle::with_status(TxLifecycleStatus::Rejected);
+                lifecycle.reason = Some(reason);
+                lifecycle
+            }
+            None => TxLifecycle::with_status(TxLifecycleStatus::Unknown),
+        };
+        Ok(lifecycle)
+    }
 }
 
 struct DefaultOutputsValidator<'a> {
@@ -45,6 +45,7 @@ impl PubSubMetadata for SubscriptionSession {
 pub enum Topic {
     NewTipHeader,
     NewTipBlock,
+    EvictedTransaction,
 }
 
 #[allow(clippy::needless_return)]
@@ -129,6 +130,8 @@ impl SubscriptionRpcImpl {
     pub fn new<S: ToString>(notify_controller: NotifyController, thread_name: Option<S>) -> Self {
         let new_block_receiver =
             notify_controller.subscribe_new_block(thread_name.as_ref().unwrap().to_string());
+        let evicted_transaction_receiver = notify_controller
+            .subscribe_evicted_transaction(thread_name.as_ref().unwrap().to_string());
 
         let subscription_rpc_impl = SubscriptionRpcImpl::default();
         let subscribers = Arc::clone(&subscription_rpc_impl.subscribers);
@@ -162,6 +165,22 @@ impl SubscriptionRpcImpl {
                             error!("new_block_receiver closed");
                             break;
                         },
+                    },
+                    recv(evicted_transaction_receiver) -> msg => match msg {
+                        Ok(tx) => {
+                            let subscribers = subscribers.read().expect("acquiring subscribers read lock");
+                            if let Some(evicted_transaction_subscribers) = subscribers.get(&Topic::EvictedTransaction) {
+                                let tx: ckb_jsonrpc_types::TransactionView  = tx.into();
+                                let json_string = Ok(serde_json::to_string(&tx).expect("serialization should be ok"));
+                                for sink in evicted_transaction_subscribers.values() {
+                                    let _ = sink.notify(json_string.clone()).wait();
+                                }
+                            }
+                        },
+                        _ => {
+                            error!("evicted_transaction_receiver closed");
+                            break;
+                        },
                     }
                 }
             })
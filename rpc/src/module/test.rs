@@ -109,6 +109,7 @@ impl IntegrationTestRpc for IntegrationTestRpcImpl {
             hash_type: script.hash_type,
             args: script.args,
             message: block_assembler_message.unwrap_or_default(),
+            reward_shares: vec![],
         });
         let block_template = tx_pool
             .get_block_template_with_block_assembler_config(
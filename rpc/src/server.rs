@@ -1,3 +1,4 @@
+use crate::health::HealthCheck;
 use crate::module::{SubscriptionRpc, SubscriptionRpcImpl, SubscriptionSession};
 use crate::IoHandler;
 use ckb_app_config::RpcConfig;
@@ -12,6 +13,7 @@ pub struct RpcServer {
     pub(crate) http: jsonrpc_http_server::Server,
     pub(crate) _tcp: Option<jsonrpc_tcp_server::Server>,
     pub(crate) _ws: Option<jsonrpc_ws_server::Server>,
+    pub(crate) health_check: HealthCheck,
 }
 
 impl RpcServer {
@@ -19,7 +21,9 @@ impl RpcServer {
         config: RpcConfig,
         io_handler: IoHandler,
         notify_controller: &NotifyController,
+        health_check: HealthCheck,
     ) -> RpcServer {
+        let middleware_health_check = health_check.clone();
         let http = jsonrpc_http_server::ServerBuilder::new(io_handler.clone())
             .cors(DomainsValidation::AllowOnly(vec![
                 AccessControlAllowOrigin::Null,
@@ -28,6 +32,7 @@ impl RpcServer {
             .threads(config.threads.unwrap_or_else(num_cpus::get))
             .max_request_body_size(config.max_request_body_size)
             .health_api(("/ping", "ping"))
+            .request_middleware(move |request| middleware_health_check.on_request(request))
             .start_http(
                 &config
                     .listen_address
@@ -96,10 +101,21 @@ impl RpcServer {
             ws_server
         });
 
-        RpcServer { http, _tcp, _ws }
+        RpcServer {
+            http,
+            _tcp,
+            _ws,
+            health_check,
+        }
     }
 
     pub fn http_address(&self) -> &SocketAddr {
         self.http.address()
     }
 }
+
+impl Drop for RpcServer {
+    fn drop(&mut self) {
+        self.health_check.stop();
+    }
+}
@@ -10,14 +10,16 @@ use crate::IoHandler;
 use ckb_app_config::IndexerConfig;
 use ckb_app_config::RpcConfig;
 use ckb_chain::chain::ChainController;
-use ckb_fee_estimator::FeeRate;
 use ckb_indexer::DefaultIndexerStore;
+use ckb_config_reloader::ConfigReloader;
+use ckb_metrics::{metrics, Timer};
 use ckb_network::NetworkController;
 use ckb_network_alert::{notifier::Notifier as AlertNotifier, verifier::Verifier as AlertVerifier};
 use ckb_shared::shared::Shared;
 use ckb_sync::SyncShared;
 use ckb_sync::Synchronizer;
 use ckb_util::Mutex;
+use futures::Future;
 use jsonrpc_core::RemoteProcedure;
 use std::sync::Arc;
 
@@ -26,6 +28,7 @@ const DEPRECATED_RPC_PREFIX: &str = "deprecated.";
 pub struct ServiceBuilder<'a> {
     config: &'a RpcConfig,
     io_handler: IoHandler,
+    indexer_store: Option<DefaultIndexerStore>,
 }
 
 impl<'a> ServiceBuilder<'a> {
@@ -33,6 +36,7 @@ impl<'a> ServiceBuilder<'a> {
         Self {
             config,
             io_handler: IoHandler::default(),
+            indexer_store: None,
         }
     }
     pub fn enable_chain(mut self, shared: Shared) -> Self {
@@ -49,12 +53,10 @@ impl<'a> ServiceBuilder<'a> {
         mut self,
         shared: Shared,
         sync_shared: Arc<SyncShared>,
-        min_fee_rate: FeeRate,
         reject_ill_transactions: bool,
     ) -> Self {
         let rpc_methods =
-            PoolRpcImpl::new(shared, sync_shared, min_fee_rate, reject_ill_transactions)
-                .to_delegate();
+            PoolRpcImpl::new(shared, sync_shared, reject_ill_transactions).to_delegate();
         if self.config.pool_enable() {
             self.add_methods(rpc_methods);
         } else {
@@ -175,6 +177,7 @@ impl<'a> ServiceBuilder<'a> {
         }
         .to_delegate();
         if self.config.indexer_enable() {
+            self.indexer_store = Some(store.clone());
             store.start(Some("IndexerStore"));
             self.add_methods(rpc_methods);
         } else {
@@ -183,9 +186,23 @@ impl<'a> ServiceBuilder<'a> {
         self
     }
 
-    pub fn enable_debug(mut self) -> Self {
+    /// The indexer database is backed up by `backup_database` only if the indexer is enabled
+    /// before. `reload_config` returns an error without a config reloader.
+    pub fn enable_debug(
+        mut self,
+        shared: Shared,
+        network_controller: NetworkController,
+        config_reloader: Option<Arc<ConfigReloader>>,
+    ) -> Self {
         if self.config.debug_enable() {
-            self.io_handler.extend_with(DebugRpcImpl {}.to_delegate());
+            let rpc_methods = DebugRpcImpl {
+                shared,
+                network_controller,
+                indexer_store: self.indexer_store.clone(),
+                config_reloader,
+            }
+            .to_delegate();
+            self.io_handler.extend_with(rpc_methods);
         }
         self
     }
@@ -208,7 +225,9 @@ impl<'a> ServiceBuilder<'a> {
         let enable_deprecated_rpc = self.config.enable_deprecated_rpc;
         self.io_handler
             .extend_with(rpc_methods.into_iter().map(|(name, method)| {
-                if let Some(deprecated_method_name) = name.strip_prefix(DEPRECATED_RPC_PREFIX) {
+                let (name, method) = if let Some(deprecated_method_name) =
+                    name.strip_prefix(DEPRECATED_RPC_PREFIX)
+                {
                     (
                         deprecated_method_name.to_owned(),
                         if enable_deprecated_rpc {
@@ -221,7 +240,9 @@ impl<'a> ServiceBuilder<'a> {
                     )
                 } else {
                     (name, method)
-                }
+                };
+                let method = with_metrics(name.clone(), method);
+                (name, method)
             }));
     }
 
@@ -232,3 +253,26 @@ impl<'a> ServiceBuilder<'a> {
         io_handler
     }
 }
+
+// record the latency and the errors of every call, labeled by the method name
+fn with_metrics(
+    name: String,
+    method: RemoteProcedure<Option<SubscriptionSession>>,
+) -> RemoteProcedure<Option<SubscriptionSession>> {
+    match method {
+        RemoteProcedure::Method(method) => {
+            RemoteProcedure::Method(Arc::new(move |params, meta| {
+                let name = name.clone();
+                let timer = Timer::start();
+                method.call(params, meta).then(move |ret| {
+                    metrics!(timing, "ckb-rpc.latency", timer.stop(), "method" => name.clone());
+                    if ret.is_err() {
+                        metrics!(counter, "ckb-rpc.errors", 1, "method" => name);
+                    }
+                    ret
+                })
+            }))
+        }
+        other => other,
+    }
+}
@@ -1,15 +1,18 @@
-use crate::{RpcServer, ServiceBuilder};
-use ckb_app_config::{IndexerConfig, NetworkAlertConfig, NetworkConfig, RpcConfig, RpcModule};
+use crate::{HealthCheck, RpcServer, ServiceBuilder};
+use ckb_app_config::{
+    BlockAssemblerConfig, IndexerConfig, NetworkAlertConfig, NetworkConfig, RpcConfig, RpcModule,
+    TxPoolConfig,
+};
 use ckb_chain::chain::{ChainController, ChainService};
 use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder};
 use ckb_dao::DaoCalculator;
 use ckb_dao_utils::genesis_dao_data;
-use ckb_fee_estimator::FeeRate;
 use ckb_indexer::{DefaultIndexerStore, IndexerStore};
-use ckb_jsonrpc_types::{Block as JsonBlock, Uint64};
+use ckb_jsonrpc_types::{Block as JsonBlock, BlockTemplate, ScriptHashType, Uint64};
 use ckb_network::{DefaultExitHandler, NetworkService, NetworkState};
 use ckb_network_alert::alert_relayer::AlertRelayer;
 use ckb_notify::NotifyService;
+use ckb_pow::Pow;
 use ckb_shared::{
     shared::{Shared, SharedBuilder},
     Snapshot,
@@ -23,7 +26,9 @@ use ckb_types::{
         EpochNumberWithFraction, HeaderView, TransactionBuilder, TransactionView,
     },
     h256,
-    packed::{AlertBuilder, CellDep, CellInput, CellOutputBuilder, OutPoint, RawAlertBuilder},
+    packed::{
+        self, AlertBuilder, CellDep, CellInput, CellOutputBuilder, OutPoint, RawAlertBuilder,
+    },
     prelude::*,
     H256,
 };
@@ -35,6 +40,8 @@ use std::collections::HashSet;
 use std::fs::File;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 const GENESIS_TIMESTAMP: u64 = 1_557_310_743;
 const GENESIS_TARGET: u32 = 0x2001_0000;
@@ -131,14 +138,29 @@ fn next_block(shared: &Shared, parent: &HeaderView) -> BlockView {
 
 // Setup the running environment
 fn setup_node(height: u64) -> (Shared, ChainController, RpcServer) {
+    setup_node_with_consensus(height, always_success_consensus(), None)
+}
+
+fn setup_node_with_consensus(
+    height: u64,
+    consensus: Consensus,
+    block_assembler_config: Option<BlockAssemblerConfig>,
+) -> (Shared, ChainController, RpcServer) {
+    // the examples in rpc.json are generated without the min fee rate
+    let tx_pool_config = TxPoolConfig {
+        min_fee_rate: Default::default(),
+        ..Default::default()
+    };
     let (shared, table) = SharedBuilder::default()
-        .consensus(always_success_consensus())
+        .consensus(consensus)
+        .tx_pool_config(tx_pool_config)
+        .block_assembler_config(block_assembler_config)
         .build()
         .unwrap();
     let chain_controller = ChainService::new(shared.clone(), table).start::<&str>(None);
 
     // Build chain, insert [1, height) blocks
-    let mut parent = always_success_consensus().genesis_block;
+    let mut parent = shared.consensus().genesis_block().clone();
 
     for _ in 0..height {
         let block = next_block(&shared, &parent.header());
@@ -233,16 +255,19 @@ fn setup_node(height: u64) -> (Shared, ChainController, RpcServer) {
         reject_ill_transactions: true,
         // enable deprecated rpc in unit test
         enable_deprecated_rpc: true,
+        health_max_tip_age_secs: 600,
+        health_min_outbound_peers: 1,
     };
+    let health_check = HealthCheck::new(
+        &rpc_config,
+        Arc::clone(&sync_shared),
+        network_controller.clone(),
+    )
+    .expect("start the health check");
 
     let builder = ServiceBuilder::new(&rpc_config)
         .enable_chain(shared.clone())
-        .enable_pool(
-            shared.clone(),
-            Arc::clone(&sync_shared),
-            FeeRate::zero(),
-            true,
-        )
+        .enable_pool(shared.clone(), Arc::clone(&sync_shared), true)
         .enable_miner(
             shared.clone(),
             network_controller.clone(),
@@ -252,12 +277,21 @@ fn setup_node(height: u64) -> (Shared, ChainController, RpcServer) {
         .enable_net(network_controller.clone(), sync_shared)
         .enable_stats(shared.clone(), synchronizer, Arc::clone(&alert_notifier))
         .enable_experiment(shared.clone())
-        .enable_integration_test(shared.clone(), network_controller, chain_controller.clone())
+        .enable_integration_test(
+            shared.clone(),
+            network_controller.clone(),
+            chain_controller.clone(),
+        )
         .enable_indexer(&indexer_config, shared.clone())
-        .enable_debug();
+        .enable_debug(shared.clone(), network_controller, None);
     let io_handler = builder.build();
 
-    let rpc_server = RpcServer::new(rpc_config, io_handler, shared.notify_controller());
+    let rpc_server = RpcServer::new(
+        rpc_config,
+        io_handler,
+        shared.notify_controller(),
+        health_check,
+    );
 
     (shared, chain_controller, rpc_server)
 }
@@ -392,7 +426,8 @@ fn params_of(shared: &Shared, method: &str) -> Value {
         "remove_node" => vec![json!("QmUsZHPbjjzU627UZFt4k8j6ycEcNvXRnVGxCPKqwbAfQS")],
         "send_transaction" => vec![transaction, json!("passthrough")],
         "dry_run_transaction" | "_compute_transaction_hash" => vec![transaction],
-        "get_transaction" => vec![transaction_hash],
+        "get_transaction" | "get_transaction_status" => vec![transaction_hash],
+        "prioritise_transaction" => vec![transaction_hash, json!("0x2540be400")],
         "index_lock_hash" => vec![json!(always_success_script_hash), json!("0x400")],
         "deindex_lock_hash" | "get_capacity_by_lock_hash" => {
             vec![json!(always_success_script_hash)]
@@ -517,3 +552,93 @@ fn test_rpc() {
         }
     }
 }
+
+#[test]
+fn test_health() {
+    let (_shared, _chain_controller, server) = setup_node(1);
+    let client = reqwest::Client::new();
+    let uri = format!(
+        "http://{}:{}",
+        server.http_address().ip(),
+        server.http_address().port()
+    );
+
+    let mut response = client
+        .get(&format!("{}/health/live", uri))
+        .send()
+        .expect("get /health/live");
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert_eq!(response.json::<Value>().unwrap(), json!({ "live": true }));
+
+    // The test node has no outbound peers and its tip is too old. The tx-pool is probed in
+    // the background, wait for the first probe.
+    let mut readiness = Value::Null;
+    for _ in 0..50 {
+        let mut response = client
+            .get(&format!("{}/health/ready", uri))
+            .send()
+            .expect("get /health/ready");
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        readiness = response.json::<Value>().unwrap();
+        if readiness["checks"]["tx_pool"]["ok"] == json!(true) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert_eq!(readiness["ready"], json!(false));
+    assert_eq!(readiness["checks"]["tip_age"]["ok"], json!(false));
+    assert_eq!(readiness["checks"]["outbound_peers"]["ok"], json!(false));
+    assert_eq!(readiness["checks"]["tx_pool"]["ok"], json!(true));
+
+    // the probe thread exits at once instead of on its next wake-up, the server stops it again
+    // when it's dropped
+    let now = Instant::now();
+    server.health_check.stop();
+    assert!(now.elapsed() < Duration::from_millis(500));
+}
+
+#[test]
+fn test_validate_block_proposal() {
+    // a real PoW engine, so an unsolved template fails the PoW check
+    let mut consensus = always_success_consensus();
+    consensus.pow = Pow::Eaglesong;
+    let engine = consensus.pow_engine();
+    let (_, _, always_success_script) = always_success_cell();
+    let block_assembler_config = BlockAssemblerConfig {
+        code_hash: always_success_script.code_hash().unpack(),
+        hash_type: ScriptHashType::Data,
+        args: Default::default(),
+        message: Default::default(),
+        reward_shares: Vec::new(),
+    };
+    let (_shared, _chain_controller, server) =
+        setup_node_with_consensus(20, consensus, Some(block_assembler_config));
+    let client = reqwest::Client::new();
+    let uri = format!(
+        "http://{}:{}/",
+        server.http_address().ip(),
+        server.http_address().port()
+    );
+
+    let template: BlockTemplate = serde_json::from_value(result_of(
+        &client,
+        &uri,
+        "get_block_template",
+        json!([null, null, null]),
+    ))
+    .expect("parse block template");
+    let block = packed::Block::from(template).into_view();
+    let block = (0u128..)
+        .map(|nonce| block.as_advanced_builder().nonce(nonce.pack()).build())
+        .find(|block| !engine.verify(&block.header().data()))
+        .expect("find an unsolved nonce");
+
+    let json_block: JsonBlock = block.data().into();
+    let cycles = result_of(
+        &client,
+        &uri,
+        "validate_block_proposal",
+        json!([json_block]),
+    );
+    assert!(cycles.is_string());
+}
@@ -60,18 +60,20 @@ impl Shared {
         ));
         let snapshot_mgr = Arc::new(SnapshotMgr::new(Arc::clone(&snapshot)));
 
+        let notify_controller = NotifyService::new(notify_config).start(Some("NotifyService"));
+
         let tx_pool_builder = TxPoolServiceBuilder::new(
             tx_pool_config,
             Arc::clone(&snapshot),
             block_assembler_config,
             Arc::clone(&txs_verify_cache),
             Arc::clone(&snapshot_mgr),
+            notify_controller.clone(),
+            store.clone(),
         );
 
         let tx_pool_controller = tx_pool_builder.start();
 
-        let notify_controller = NotifyService::new(notify_config).start(Some("NotifyService"));
-
         let shared = Shared {
             store,
             consensus,
@@ -227,6 +229,7 @@ impl Default for SharedBuilder {
 }
 
 const INIT_DB_VERSION: &str = "20191127135521";
+const ADD_CANDIDATE_UNCLES_DB_VERSION: &str = "20200812103642";
 
 impl SharedBuilder {
     pub fn with_db_config(config: &DBConfig) -> Self {
@@ -234,6 +237,10 @@ impl SharedBuilder {
         let mut migrations = Migrations::default();
         migrations.add_migration(Box::new(DefaultMigration::new(INIT_DB_VERSION)));
         migrations.add_migration(Box::new(migrations::ChangeMoleculeTableToStruct));
+        // add the candidate uncles column, it starts empty
+        migrations.add_migration(Box::new(DefaultMigration::new(
+            ADD_CANDIDATE_UNCLES_DB_VERSION,
+        )));
 
         SharedBuilder {
             db,
@@ -70,6 +70,8 @@ pub(crate) const MAX_BLOCK_CYCLES: u64 = TWO_IN_TWO_OUT_CYCLES * TWO_IN_TWO_OUT_
 // 1.5 * TWO_IN_TWO_OUT_COUNT
 const MAX_BLOCK_PROPOSALS_LIMIT: u64 = 1_500;
 const PROPOSER_REWARD_RATIO: Ratio = Ratio(4, 10);
+// Maximum number of locks a cellbase witness can split the block reward across
+pub const MAX_REWARD_SHARES: usize = 16;
 
 // Satoshi's pubkey hash in Bitcoin genesis.
 pub(crate) const SATOSHI_PUBKEY_HASH: H160 = h160!("0x62e907b15cbf27d5425399ebf6f0fb50ebb88f18");
@@ -245,6 +247,7 @@ impl ConsensusBuilder {
                 primary_epoch_reward_halving_interval:
                     DEFAULT_PRIMARY_EPOCH_REWARD_HALVING_INTERVAL,
                 permanent_difficulty_in_dummy: false,
+                split_reward_activation: None,
             },
         }
     }
@@ -384,6 +387,12 @@ impl ConsensusBuilder {
         self.inner.permanent_difficulty_in_dummy = permanent;
         self
     }
+
+    #[must_use]
+    pub fn split_reward_activation(mut self, activation: Option<BlockNumber>) -> Self {
+        self.inner.split_reward_activation = activation;
+        self
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -431,6 +440,9 @@ pub struct Consensus {
     pub primary_epoch_reward_halving_interval: EpochNumber,
     // Keep difficulty be permanent if the pow is dummy
     pub permanent_difficulty_in_dummy: bool,
+    // The block number from which the cellbase witness can split the block reward
+    // across multiple locks, disabled if it's None
+    pub split_reward_activation: Option<BlockNumber>,
 }
 
 // genesis difficulty should not be zero
@@ -530,6 +542,12 @@ impl Consensus {
         self.pow.is_dummy() && self.permanent_difficulty_in_dummy
     }
 
+    pub fn is_split_reward_enabled(&self, block_number: BlockNumber) -> bool {
+        self.split_reward_activation
+            .map(|activation| block_number >= activation)
+            .unwrap_or(false)
+    }
+
     pub fn cellbase_maturity(&self) -> EpochNumberWithFraction {
         self.cellbase_maturity
     }
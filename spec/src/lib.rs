@@ -122,6 +122,8 @@ pub struct Params {
     pub genesis_epoch_length: BlockNumber,
     #[serde(default)]
     pub permanent_difficulty_in_dummy: bool,
+    #[serde(default)]
+    pub split_reward_activation: Option<BlockNumber>,
 }
 
 impl Default for Params {
@@ -137,6 +139,7 @@ impl Default for Params {
             epoch_duration_target: default_params::epoch_duration_target(),
             genesis_epoch_length: default_params::genesis_epoch_length(),
             permanent_difficulty_in_dummy: false,
+            split_reward_activation: None,
         }
     }
 }
@@ -291,6 +294,7 @@ impl ChainSpec {
             .initial_primary_epoch_reward(self.params.initial_primary_epoch_reward)
             .epoch_duration_target(self.params.epoch_duration_target)
             .permanent_difficulty_in_dummy(self.params.permanent_difficulty_in_dummy)
+            .split_reward_activation(self.params.split_reward_activation)
             .build();
 
         Ok(consensus)
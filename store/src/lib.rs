@@ -13,7 +13,7 @@ pub use transaction::StoreTransaction;
 
 use ckb_db::Col;
 
-pub const COLUMNS: u32 = 12;
+pub const COLUMNS: u32 = 13;
 pub const COLUMN_INDEX: Col = "0";
 pub const COLUMN_BLOCK_HEADER: Col = "1";
 pub const COLUMN_BLOCK_BODY: Col = "2";
@@ -26,6 +26,7 @@ pub const COLUMN_BLOCK_EPOCH: Col = "8";
 pub const COLUMN_EPOCH: Col = "9";
 pub const COLUMN_CELL_SET: Col = "10";
 pub const COLUMN_UNCLES: Col = "11";
+pub const COLUMN_CANDIDATE_UNCLES: Col = "12";
 
 pub const META_TIP_HEADER_KEY: &[u8] = b"TIP_HEADER";
 pub const META_CURRENT_EPOCH_KEY: &[u8] = b"CURRENT_EPOCH";
@@ -1,9 +1,9 @@
 use crate::cache::StoreCache;
 use crate::{
     COLUMN_BLOCK_BODY, COLUMN_BLOCK_EPOCH, COLUMN_BLOCK_EXT, COLUMN_BLOCK_HEADER,
-    COLUMN_BLOCK_PROPOSAL_IDS, COLUMN_BLOCK_UNCLE, COLUMN_CELL_SET, COLUMN_EPOCH, COLUMN_INDEX,
-    COLUMN_META, COLUMN_TRANSACTION_INFO, COLUMN_UNCLES, META_CURRENT_EPOCH_KEY,
-    META_TIP_HEADER_KEY,
+    COLUMN_BLOCK_PROPOSAL_IDS, COLUMN_BLOCK_UNCLE, COLUMN_CANDIDATE_UNCLES, COLUMN_CELL_SET,
+    COLUMN_EPOCH, COLUMN_INDEX, COLUMN_META, COLUMN_TRANSACTION_INFO, COLUMN_UNCLES,
+    META_CURRENT_EPOCH_KEY, META_TIP_HEADER_KEY,
 };
 use ckb_chain_spec::consensus::Consensus;
 use ckb_db::{
@@ -15,7 +15,7 @@ use ckb_types::{
     core::{
         cell::{CellMeta, CellProvider, CellStatus},
         BlockExt, BlockNumber, BlockView, EpochExt, EpochNumber, HeaderView, TransactionInfo,
-        TransactionMeta, TransactionView, UncleBlockVecView,
+        TransactionMeta, TransactionView, UncleBlockVecView, UncleBlockView,
     },
     packed::{self, OutPoint},
     prelude::*,
@@ -377,6 +377,17 @@ pub trait ChainStore<'a>: Send + Sync + Sized {
         })
     }
 
+    /// Get all the persisted candidate uncles of the block assembler
+    fn get_candidate_uncles(&'a self) -> Vec<UncleBlockView> {
+        self.get_iter(COLUMN_CANDIDATE_UNCLES, IteratorMode::Start)
+            .map(|(_key, value)| {
+                packed::UncleBlockReader::from_slice_should_be_ok(&value.as_ref())
+                    .to_entity()
+                    .into_view()
+            })
+            .collect()
+    }
+
     fn block_exists(&'a self, hash: &packed::Byte32) -> bool {
         if let Some(cache) = self.cache() {
             if cache.headers.lock().get_refresh(hash).is_some() {
@@ -2,9 +2,9 @@ use crate::cache::StoreCache;
 use crate::store::ChainStore;
 use crate::{
     COLUMN_BLOCK_BODY, COLUMN_BLOCK_EPOCH, COLUMN_BLOCK_EXT, COLUMN_BLOCK_HEADER,
-    COLUMN_BLOCK_PROPOSAL_IDS, COLUMN_BLOCK_UNCLE, COLUMN_CELL_SET, COLUMN_EPOCH, COLUMN_INDEX,
-    COLUMN_META, COLUMN_TRANSACTION_INFO, COLUMN_UNCLES, META_CURRENT_EPOCH_KEY,
-    META_TIP_HEADER_KEY,
+    COLUMN_BLOCK_PROPOSAL_IDS, COLUMN_BLOCK_UNCLE, COLUMN_CANDIDATE_UNCLES, COLUMN_CELL_SET,
+    COLUMN_EPOCH, COLUMN_INDEX, COLUMN_META, COLUMN_TRANSACTION_INFO, COLUMN_UNCLES,
+    META_CURRENT_EPOCH_KEY, META_TIP_HEADER_KEY,
 };
 use ckb_db::{
     iter::{DBIter, DBIterator, IteratorMode},
@@ -12,7 +12,7 @@ use ckb_db::{
 };
 use ckb_error::Error;
 use ckb_types::{
-    core::{BlockExt, BlockView, EpochExt, HeaderView},
+    core::{BlockExt, BlockView, EpochExt, HeaderView, UncleBlockView},
     packed,
     prelude::*,
 };
@@ -149,6 +149,10 @@ impl StoreTransaction {
         )
     }
 
+    pub fn delete_block_ext(&self, block_hash: &packed::Byte32) -> Result<(), Error> {
+        self.delete(COLUMN_BLOCK_EXT, block_hash.as_slice())
+    }
+
     pub fn attach_block(&self, block: &BlockView) -> Result<(), Error> {
         let header = block.data().header();
         let block_hash = block.hash();
@@ -200,12 +204,22 @@ impl StoreTransaction {
         )
     }
 
+    pub fn delete_block_epoch_index(&self, block_hash: &packed::Byte32) -> Result<(), Error> {
+        self.delete(COLUMN_BLOCK_EPOCH, block_hash.as_slice())
+    }
+
     pub fn insert_epoch_ext(&self, hash: &packed::Byte32, epoch: &EpochExt) -> Result<(), Error> {
         self.insert_raw(COLUMN_EPOCH, hash.as_slice(), epoch.pack().as_slice())?;
         let epoch_number: packed::Uint64 = epoch.number().pack();
         self.insert_raw(COLUMN_EPOCH, epoch_number.as_slice(), hash.as_slice())
     }
 
+    pub fn delete_epoch_ext(&self, hash: &packed::Byte32, epoch: &EpochExt) -> Result<(), Error> {
+        self.delete(COLUMN_EPOCH, hash.as_slice())?;
+        let epoch_number: packed::Uint64 = epoch.number().pack();
+        self.delete(COLUMN_EPOCH, epoch_number.as_slice())
+    }
+
     pub fn insert_current_epoch_ext(&self, epoch: &EpochExt) -> Result<(), Error> {
         self.insert_raw(COLUMN_META, META_CURRENT_EPOCH_KEY, epoch.pack().as_slice())
     }
@@ -221,4 +235,16 @@ impl StoreTransaction {
     pub fn delete_cell_set(&self, tx_hash: &packed::Byte32) -> Result<(), Error> {
         self.delete(COLUMN_CELL_SET, tx_hash.as_slice())
     }
+
+    pub fn insert_candidate_uncle(&self, uncle: &UncleBlockView) -> Result<(), Error> {
+        self.insert_raw(
+            COLUMN_CANDIDATE_UNCLES,
+            uncle.hash().as_slice(),
+            uncle.data().as_slice(),
+        )
+    }
+
+    pub fn delete_candidate_uncle(&self, hash: &packed::Byte32) -> Result<(), Error> {
+        self.delete(COLUMN_CANDIDATE_UNCLES, hash.as_slice())
+    }
 }
@@ -101,6 +101,7 @@ impl<'a> BlockTransactionsProcess<'a> {
                     &expected_uncle_indexes,
                     &received_uncles,
                 );
+                crate::relayer::metrics_counter_reconstruction(&ret);
 
                 // Request proposal
                 {
@@ -119,6 +120,7 @@ impl<'a> BlockTransactionsProcess<'a> {
                 match ret {
                     ReconstructionResult::Block(block) => {
                         pending.remove();
+                        self.relayer.reconstruction_finished(&block_hash);
                         self.relayer
                             .accept_block(self.nc.as_ref(), self.peer, block);
                         return Status::ok();
@@ -148,6 +150,10 @@ impl<'a> BlockTransactionsProcess<'a> {
                     .write_inflight_blocks()
                     .compact_reconstruct(self.peer, block_hash.clone())
                 {
+                    shared
+                        .state()
+                        .compact_block_relay()
+                        .reconstruct_round_trip(&block_hash);
                     let content = packed::GetBlockTransactions::new_builder()
                         .block_hash(block_hash.clone())
                         .indexes(missing_transactions.pack())
@@ -185,6 +185,11 @@ impl<'a> CompactBlockProcess<'a> {
 
                 // Header has been verified ok, update state
                 shared.insert_valid_header(self.peer, &header);
+
+                // Header and PoW are checked, push to high-bandwidth peers before the block
+                // is verified
+                self.relayer
+                    .relay_compact_block_early(self.nc.as_ref(), self.peer, &compact_block);
             }
 
             // Request proposal
@@ -199,9 +204,14 @@ impl<'a> CompactBlockProcess<'a> {
             }
 
             // Reconstruct block
+            shared
+                .state()
+                .compact_block_relay()
+                .reconstruct_started(block_hash.clone());
             let ret =
                 self.relayer
                     .reconstruct_block(&active_chain, &compact_block, vec![], &[], &[]);
+            crate::relayer::metrics_counter_reconstruction(&ret);
 
             // Accept block
             // `relayer.accept_block` will make sure the validity of block before persisting
@@ -209,6 +219,7 @@ impl<'a> CompactBlockProcess<'a> {
             match ret {
                 ReconstructionResult::Block(block) => {
                     pending_compact_blocks.remove(&block_hash);
+                    self.relayer.reconstruction_finished(&block_hash);
                     self.relayer
                         .accept_block(self.nc.as_ref(), self.peer, block);
                     return Status::ok();
@@ -266,6 +277,10 @@ impl<'a> CompactBlockProcess<'a> {
             metrics!(value, "ckb-net.fresh", missing_uncles.len() as u64, "type" => "uncles", "status" => status.tag());
         }
 
+        shared
+            .state()
+            .compact_block_relay()
+            .reconstruct_round_trip(&block_hash);
         let content = packed::GetBlockTransactions::new_builder()
             .block_hash(block_hash)
             .indexes(missing_transactions.pack())
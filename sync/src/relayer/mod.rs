@@ -7,9 +7,11 @@ mod compact_block_verifier;
 mod get_block_proposal_process;
 mod get_block_transactions_process;
 mod get_transactions_process;
+mod send_compact_block_process;
 #[cfg(test)]
 mod tests;
 mod transaction_hashes_process;
+mod transaction_package_process;
 mod transactions_process;
 
 use self::block_proposal_process::BlockProposalProcess;
@@ -18,7 +20,9 @@ use self::compact_block_process::CompactBlockProcess;
 use self::get_block_proposal_process::GetBlockProposalProcess;
 use self::get_block_transactions_process::GetBlockTransactionsProcess;
 use self::get_transactions_process::GetTransactionsProcess;
+use self::send_compact_block_process::SendCompactBlockProcess;
 use self::transaction_hashes_process::TransactionHashesProcess;
+use self::transaction_package_process::TransactionPackageProcess;
 use self::transactions_process::TransactionsProcess;
 use crate::block_status::BlockStatus;
 use crate::types::{ActiveChain, SyncShared};
@@ -36,7 +40,7 @@ use ckb_types::{
     packed::{self, Byte32, ProposalShortId},
     prelude::*,
 };
-use ckb_util::Mutex;
+use ckb_util::{LinkedHashSet, Mutex};
 use faketime::unix_time_as_millis;
 use ratelimit_meter::KeyedRateLimiter;
 use std::collections::{HashMap, HashSet};
@@ -47,10 +51,17 @@ pub const TX_PROPOSAL_TOKEN: u64 = 0;
 pub const ASK_FOR_TXS_TOKEN: u64 = 1;
 pub const TX_HASHES_TOKEN: u64 = 2;
 pub const SEARCH_ORPHAN_POOL_TOKEN: u64 = 3;
+pub const REBROADCAST_LOCAL_TXS_TOKEN: u64 = 4;
 
 pub const MAX_RELAY_PEERS: usize = 128;
 pub const MAX_RELAY_TXS_NUM_PER_BATCH: usize = 32767;
 pub const MAX_RELAY_TXS_BYTES_PER_BATCH: usize = 1024 * 1024;
+/// The number of peers we ask to push compact blocks in high-bandwidth mode
+pub const MAX_HIGH_BANDWIDTH_PEERS: usize = 3;
+/// The relay protocol version since which `SendCompactBlock` is understood
+pub const HIGH_BANDWIDTH_RELAY_VERSION: &str = "2";
+/// The relay protocol version since which `RelayTransactionPackage` is understood
+pub const PACKAGE_RELAY_VERSION: &str = "3";
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum ReconstructionResult {
@@ -64,7 +75,6 @@ pub enum ReconstructionResult {
 pub struct Relayer {
     chain: ChainController,
     pub(crate) shared: Arc<SyncShared>,
-    pub(crate) min_fee_rate: FeeRate,
     pub(crate) max_tx_verify_cycles: Cycle,
     rate_limiter: Arc<Mutex<KeyedRateLimiter<(PeerIndex, u32)>>>,
 }
@@ -73,7 +83,6 @@ impl Relayer {
     pub fn new(
         chain: ChainController,
         shared: Arc<SyncShared>,
-        min_fee_rate: FeeRate,
         max_tx_verify_cycles: Cycle,
     ) -> Self {
         // setup a rate limiter keyed by peer and message type that lets through 30 requests per second
@@ -84,7 +93,6 @@ impl Relayer {
         Relayer {
             chain,
             shared,
-            min_fee_rate,
             max_tx_verify_cycles,
             rate_limiter,
         }
@@ -94,6 +102,11 @@ impl Relayer {
         &self.shared
     }
 
+    // read from the tx-pool, so it follows the reloaded config
+    pub(crate) fn min_fee_rate(&self) -> FeeRate {
+        self.shared.shared().tx_pool_controller().min_fee_rate()
+    }
+
     fn try_process<'r>(
         &mut self,
         nc: Arc<dyn CKBProtocolContext + Sync>,
@@ -151,6 +164,17 @@ impl Relayer {
             packed::RelayMessageUnionReader::BlockProposal(reader) => {
                 BlockProposalProcess::new(reader, self).execute()
             }
+            packed::RelayMessageUnionReader::SendCompactBlock(reader) => {
+                SendCompactBlockProcess::new(reader, self, peer).execute()
+            }
+            packed::RelayMessageUnionReader::RelayTransactionPackage(reader) => {
+                if reader.check_data() {
+                    TransactionPackageProcess::new(reader, self, nc, peer).execute()
+                } else {
+                    StatusCode::ProtocolMessageIsMalformed
+                        .with_context("RelayTransactionPackage is invalid")
+                }
+            }
         }
     }
 
@@ -275,13 +299,20 @@ impl Relayer {
             );
             let block_hash = boxed.hash();
             self.shared().state().remove_header_view(&block_hash);
+            self.select_high_bandwidth_peer(nc, peer);
+            // High-bandwidth peers have been pushed this block before it was verified
+            let relayed = self
+                .shared()
+                .state()
+                .compact_block_relay()
+                .take_relayed(&block_hash);
             let cb = packed::CompactBlock::build_from_block(&boxed, &HashSet::new());
             let message = packed::RelayMessage::new_builder().set(cb).build();
 
             let selected_peers: Vec<PeerIndex> = nc
                 .connected_peers()
                 .into_iter()
-                .filter(|target_peer| peer != *target_peer)
+                .filter(|target_peer| peer != *target_peer && !relayed.contains(target_peer))
                 .take(MAX_RELAY_PEERS)
                 .collect();
             if let Err(err) =
@@ -296,6 +327,101 @@ impl Relayer {
         }
     }
 
+    // Pushes the compact block to the peers which asked for high-bandwidth mode, before the block
+    // is verified. The caller must have checked the header and PoW. Only the blocks extending the
+    // active tip are pushed, the fork blocks are relayed after they are accepted.
+    pub fn relay_compact_block_early(
+        &self,
+        nc: &dyn CKBProtocolContext,
+        peer: PeerIndex,
+        compact_block: &packed::CompactBlock,
+    ) {
+        let parent_hash = compact_block.header().raw().parent_hash();
+        if parent_hash != self.shared().active_chain().tip_hash() {
+            return;
+        }
+
+        let targets = self
+            .shared()
+            .state()
+            .compact_block_relay()
+            .relay_targets(compact_block.calc_header_hash(), peer);
+        if targets.is_empty() {
+            return;
+        }
+
+        let message = packed::RelayMessage::new_builder()
+            .set(compact_block.clone())
+            .build();
+        for target in targets.iter() {
+            if let Err(err) = nc.send_message_to(*target, message.as_bytes()) {
+                debug_target!(
+                    crate::LOG_TARGET_RELAY,
+                    "relayer send high-bandwidth CompactBlock error: {:?}",
+                    err,
+                );
+            }
+        }
+        metrics!(
+            counter,
+            "ckb-net.compact_block_high_bandwidth",
+            targets.len() as u64
+        );
+    }
+
+    // Asks `peer`, which has just delivered a new block, to push compact blocks in high-bandwidth
+    // mode. The least recent of the selected peers is moved back to low-bandwidth mode when there
+    // are more than `MAX_HIGH_BANDWIDTH_PEERS`.
+    fn select_high_bandwidth_peer(&self, nc: &dyn CKBProtocolContext, peer: PeerIndex) {
+        let (selected, dropped) = {
+            let mut state = self.shared().state().compact_block_relay();
+            if !state.is_supported(peer) {
+                return;
+            }
+            state.select_announcer(peer, MAX_HIGH_BANDWIDTH_PEERS)
+        };
+        if selected {
+            self.send_compact_block_mode(nc, peer, true);
+        }
+        if let Some(dropped) = dropped {
+            self.send_compact_block_mode(nc, dropped, false);
+        }
+    }
+
+    fn send_compact_block_mode(&self, nc: &dyn CKBProtocolContext, peer: PeerIndex, high: bool) {
+        let content = packed::SendCompactBlock::new_builder()
+            .high_bandwidth(high.pack())
+            .build();
+        let message = packed::RelayMessage::new_builder().set(content).build();
+
+        if let Err(err) = nc.send_message_to(peer, message.as_bytes()) {
+            debug_target!(
+                crate::LOG_TARGET_RELAY,
+                "relayer send SendCompactBlock error: {:?}",
+                err,
+            );
+        }
+        crate::relayer::metrics_counter_send(message.to_enum().item_name());
+    }
+
+    // Reports how long the reconstruction of `block_hash` took since the first compact block
+    // arrived, and how many GetBlockTransactions round trips it needed.
+    pub fn reconstruction_finished(&self, block_hash: &Byte32) {
+        let finished = self
+            .shared()
+            .state()
+            .compact_block_relay()
+            .reconstruct_finished(block_hash);
+        if let Some((elapsed, round_trips)) = finished {
+            metrics!(timing, "ckb-net.compact_block_latency", elapsed);
+            metrics!(
+                value,
+                "ckb-net.compact_block_round_trips",
+                u64::from(round_trips)
+            );
+        }
+    }
+
     // nodes should attempt to reconstruct the full block by taking the prefilledtxn transactions
     // from the original CompactBlock message and placing them in the marked positions,
     // then for each short transaction ID from the original compact_block message, in order,
@@ -568,6 +694,126 @@ impl Relayer {
         }
     }
 
+    /// Orphans promoted into the pool after their parents arrived are relayed
+    /// like the other txs from the peer who sent them.
+    pub fn collect_accepted_orphans(&self) {
+        let tx_pool = self.shared.shared().tx_pool_controller();
+        let accepted_orphans = match tx_pool.fetch_accepted_orphans() {
+            Ok(accepted_orphans) => accepted_orphans,
+            Err(err) => {
+                error_target!(
+                    crate::LOG_TARGET_RELAY,
+                    "send fetch_accepted_orphans request error: {:?}",
+                    err,
+                );
+                return;
+            }
+        };
+        if accepted_orphans.is_empty() {
+            return;
+        }
+        let mut cache = self.shared.state().tx_hashes();
+        for (tx_hash, peer_index) in accepted_orphans {
+            cache
+                .entry(PeerIndex::new(peer_index))
+                .or_insert_with(LinkedHashSet::default)
+                .insert(tx_hash);
+        }
+    }
+
+    // Send the packages of dependent txs to the peers understanding package relay
+    pub fn send_bulk_of_tx_packages(&self, nc: &dyn CKBProtocolContext) {
+        let packages = self.shared.state().take_tx_packages();
+        if packages.is_empty() {
+            return;
+        }
+        let target_peers: Vec<PeerIndex> = {
+            let package_relay_peers = self.shared.state().package_relay_peers();
+            nc.connected_peers()
+                .into_iter()
+                .filter(|peer| package_relay_peers.contains(peer))
+                .collect()
+        };
+        if target_peers.is_empty() {
+            return;
+        }
+
+        let tx_pool = self.shared.shared().tx_pool_controller();
+        for (peer_index, tx_hashes) in packages {
+            let short_ids = tx_hashes
+                .iter()
+                .map(ProposalShortId::from_tx_hash)
+                .collect();
+            let fetched: HashMap<_, _> = match tx_pool.fetch_txs_with_cycles(short_ids) {
+                Ok(fetched) => fetched.into_iter().collect(),
+                Err(err) => {
+                    error_target!(
+                        crate::LOG_TARGET_RELAY,
+                        "send fetch_txs_with_cycles request error: {:?}",
+                        err,
+                    );
+                    continue;
+                }
+            };
+            // the package is relayed only when all of its txs are still in the pool
+            let transactions: Option<Vec<packed::RelayTransaction>> = tx_hashes
+                .iter()
+                .map(|tx_hash| {
+                    fetched
+                        .get(&ProposalShortId::from_tx_hash(tx_hash))
+                        .map(|(tx, cycles)| {
+                            packed::RelayTransaction::new_builder()
+                                .cycles(cycles.pack())
+                                .transaction(tx.data())
+                                .build()
+                        })
+                })
+                .collect();
+            let transactions = match transactions {
+                Some(transactions) => transactions,
+                None => continue,
+            };
+
+            let selected_peers: Vec<PeerIndex> = {
+                let mut known_txs = self.shared.state().known_txs();
+                target_peers
+                    .iter()
+                    .filter(|&target_peer| {
+                        // mark every tx as known, the peer is selected if any of them is new
+                        peer_index != *target_peer
+                            && tx_hashes.iter().fold(false, |unknown, tx_hash| {
+                                known_txs.insert(*target_peer, tx_hash.clone()) || unknown
+                            })
+                    })
+                    .take(MAX_RELAY_PEERS)
+                    .cloned()
+                    .collect()
+            };
+            if selected_peers.is_empty() {
+                continue;
+            }
+
+            let content = packed::RelayTransactionPackage::new_builder()
+                .transactions(
+                    packed::RelayTransactionVec::new_builder()
+                        .set(transactions)
+                        .build(),
+                )
+                .build();
+            let message = packed::RelayMessage::new_builder().set(content).build();
+            if let Err(err) =
+                nc.filter_broadcast(TargetSession::Multi(selected_peers), message.as_bytes())
+            {
+                debug_target!(
+                    crate::LOG_TARGET_RELAY,
+                    "relayer send RelayTransactionPackage error: {:?}",
+                    err,
+                );
+            }
+            crate::relayer::metrics_counter_send(message.to_enum().item_name());
+        }
+    }
+
     // Send bulk of tx hashes to selected peers
     pub fn send_bulk_of_tx_hashes(&self, nc: &dyn CKBProtocolContext) {
         let connected_peers = nc.connected_peers();
@@ -615,6 +861,48 @@ impl Relayer {
             }
         }
     }
+
+    // Rebroadcast the hashes of local transactions until they are committed or expired.
+    // Peers may have known them already, so `known_txs` is not consulted here.
+    pub fn rebroadcast_local_txs(&self, nc: &dyn CKBProtocolContext) {
+        let connected_peers = nc.connected_peers();
+        if connected_peers.is_empty() {
+            return;
+        }
+        let tx_pool = self.shared.shared().tx_pool_controller();
+        let tx_hashes = match tx_pool.fetch_local_txs_to_rebroadcast() {
+            Ok(tx_hashes) => tx_hashes,
+            Err(err) => {
+                error_target!(
+                    crate::LOG_TARGET_RELAY,
+                    "send fetch_local_txs_to_rebroadcast request error: {:?}",
+                    err,
+                );
+                return;
+            }
+        };
+        let peers: Vec<_> = connected_peers.into_iter().take(MAX_RELAY_PEERS).collect();
+        for hashes in tx_hashes.chunks(MAX_RELAY_TXS_NUM_PER_BATCH) {
+            debug_target!(
+                crate::LOG_TARGET_RELAY,
+                "rebroadcast {} local txs",
+                hashes.len()
+            );
+            let content = packed::RelayTransactionHashes::new_builder()
+                .tx_hashes(hashes.to_vec().pack())
+                .build();
+            let message = packed::RelayMessage::new_builder().set(content).build();
+            if let Err(err) =
+                nc.filter_broadcast(TargetSession::Multi(peers.clone()), message.as_bytes())
+            {
+                debug_target!(
+                    crate::LOG_TARGET_RELAY,
+                    "relayer rebroadcast TransactionHashes error: {:?}",
+                    err,
+                );
+            }
+        }
+    }
 }
 
 impl CKBProtocolHandler for Relayer {
@@ -628,6 +916,8 @@ impl CKBProtocolHandler for Relayer {
         // todo: remove when the asynchronous verification is completed
         nc.set_notify(Duration::from_secs(5), SEARCH_ORPHAN_POOL_TOKEN)
             .expect("set_notify at init is ok");
+        nc.set_notify(Duration::from_secs(60), REBROADCAST_LOCAL_TXS_TOKEN)
+            .expect("set_notify at init is ok");
     }
 
     fn received(
@@ -694,6 +984,18 @@ impl CKBProtocolHandler for Relayer {
             version,
             peer_index
         );
+        if is_relay_version_since(version, HIGH_BANDWIDTH_RELAY_VERSION) {
+            self.shared()
+                .state()
+                .compact_block_relay()
+                .connected(peer_index);
+        }
+        if is_relay_version_since(version, PACKAGE_RELAY_VERSION) {
+            self.shared()
+                .state()
+                .package_relay_peers()
+                .insert(peer_index);
+        }
     }
 
     fn disconnected(&mut self, _nc: Arc<dyn CKBProtocolContext + Sync>, peer_index: PeerIndex) {
@@ -702,6 +1004,26 @@ impl CKBProtocolHandler for Relayer {
             "RelayProtocol.disconnected peer={}",
             peer_index
         );
+        self.shared()
+            .state()
+            .compact_block_relay()
+            .disconnected(peer_index);
+        self.shared()
+            .state()
+            .package_relay_peers()
+            .remove(&peer_index);
+        if let Err(err) = self
+            .shared()
+            .shared()
+            .tx_pool_controller()
+            .remove_orphans_by_peer(peer_index.value())
+        {
+            error_target!(
+                crate::LOG_TARGET_RELAY,
+                "send remove_orphans_by_peer notify error: {:?}",
+                err,
+            );
+        }
         // remove all rate limiter keys that have been expireable for 1 minutes:
         self.rate_limiter.lock().cleanup(Duration::from_secs(60));
     }
@@ -719,13 +1041,22 @@ impl CKBProtocolHandler for Relayer {
                 tokio::task::block_in_place(|| self.prune_tx_proposal_request(nc.as_ref()))
             }
             ASK_FOR_TXS_TOKEN => self.ask_for_txs(nc.as_ref()),
-            TX_HASHES_TOKEN => self.send_bulk_of_tx_hashes(nc.as_ref()),
+            TX_HASHES_TOKEN => tokio::task::block_in_place(|| {
+                self.collect_accepted_orphans();
+                // the txs sent in packages are marked as known by the receivers,
+                // so their hashes are not announced to them again
+                self.send_bulk_of_tx_packages(nc.as_ref());
+                self.send_bulk_of_tx_hashes(nc.as_ref())
+            }),
             SEARCH_ORPHAN_POOL_TOKEN => tokio::task::block_in_place(|| {
                 self.shared.try_search_orphan_pool(
                     &self.chain,
                     &self.shared.active_chain().tip_header().hash(),
                 )
             }),
+            REBROADCAST_LOCAL_TXS_TOKEN => {
+                tokio::task::block_in_place(|| self.rebroadcast_local_txs(nc.as_ref()))
+            }
             _ => unreachable!(),
         }
         trace_target!(
@@ -737,6 +1068,24 @@ impl CKBProtocolHandler for Relayer {
     }
 }
 
+// The relay protocol versions are increasing numbers
+fn is_relay_version_since(version: &str, since: &str) -> bool {
+    match (version.parse::<u32>(), since.parse::<u32>()) {
+        (Ok(version), Ok(since)) => version >= since,
+        _ => false,
+    }
+}
+
 pub(self) fn metrics_counter_send(item_name: &str) {
     metrics!(counter, "ckb-net.sent", 1, "action" => "relay", "item" => item_name.to_owned());
 }
+
+pub(self) fn metrics_counter_reconstruction(result: &ReconstructionResult) {
+    let result = match result {
+        ReconstructionResult::Block(_) => "block",
+        ReconstructionResult::Missing(..) => "missing",
+        ReconstructionResult::Collided => "collided",
+        ReconstructionResult::Error(_) => "error",
+    };
+    metrics!(counter, "ckb-net.compact_block_reconstruction", 1, "result" => result);
+}
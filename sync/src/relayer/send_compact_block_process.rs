@@ -0,0 +1,43 @@
+use crate::relayer::Relayer;
+use crate::Status;
+use ckb_logger::debug_target;
+use ckb_network::PeerIndex;
+use ckb_types::{packed, prelude::*};
+
+// The peer asks us to push `CompactBlock` as soon as the header is checked (high-bandwidth mode),
+// or to announce it after the block is accepted, as usual (low-bandwidth mode).
+pub struct SendCompactBlockProcess<'a> {
+    message: packed::SendCompactBlockReader<'a>,
+    relayer: &'a Relayer,
+    peer: PeerIndex,
+}
+
+impl<'a> SendCompactBlockProcess<'a> {
+    pub fn new(
+        message: packed::SendCompactBlockReader<'a>,
+        relayer: &'a Relayer,
+        peer: PeerIndex,
+    ) -> Self {
+        SendCompactBlockProcess {
+            message,
+            relayer,
+            peer,
+        }
+    }
+
+    pub fn execute(self) -> Status {
+        let high_bandwidth: bool = self.message.high_bandwidth().unpack();
+        debug_target!(
+            crate::LOG_TARGET_RELAY,
+            "peer {} sets high-bandwidth compact block relay: {}",
+            self.peer,
+            high_bandwidth
+        );
+        self.relayer
+            .shared()
+            .state()
+            .compact_block_relay()
+            .set_announce_to(self.peer, high_bandwidth);
+        Status::ok()
+    }
+}
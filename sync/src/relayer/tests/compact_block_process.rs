@@ -1,6 +1,8 @@
 use crate::block_status::BlockStatus;
 use crate::relayer::compact_block_process::CompactBlockProcess;
+use crate::relayer::send_compact_block_process::SendCompactBlockProcess;
 use crate::relayer::tests::helper::{build_chain, new_header_builder, MockProtocalContext};
+use crate::relayer::MAX_HIGH_BANDWIDTH_PEERS;
 use crate::types::InflightBlocks;
 use crate::{Status, StatusCode};
 use ckb_network::{PeerIndex, SupportProtocols};
@@ -678,3 +680,181 @@ fn test_collision() {
         .borrow()
         .contains(&(peer_index, data)));
 }
+
+#[test]
+fn test_relay_to_high_bandwidth_peers_before_verification() {
+    let (relayer, _) = build_chain(5);
+    let parent = {
+        let active_chain = relayer.shared.active_chain();
+        active_chain.tip_header()
+    };
+
+    let header = new_header_builder(relayer.shared.shared(), &parent).build();
+
+    // Better block including one missing transaction
+    let block = BlockBuilder::default()
+        .header(header)
+        .transaction(TransactionBuilder::default().build())
+        .transaction(
+            TransactionBuilder::default()
+                .output(
+                    CellOutputBuilder::default()
+                        .capacity(Capacity::bytes(1).unwrap().pack())
+                        .build(),
+                )
+                .output_data(Bytes::new().pack())
+                .build(),
+        )
+        .build();
+
+    let mut prefilled_transactions_indexes = HashSet::new();
+    prefilled_transactions_indexes.insert(0);
+    let compact_block = CompactBlock::build_from_block(&block, &prefilled_transactions_indexes);
+
+    let mock_protocal_context = MockProtocalContext::default();
+    let nc = Arc::new(mock_protocal_context);
+    let peer_index: PeerIndex = 100.into();
+    let high_bandwidth_peer: PeerIndex = 101.into();
+    let low_bandwidth_peer: PeerIndex = 102.into();
+
+    let content = packed::SendCompactBlock::new_builder()
+        .high_bandwidth(true.pack())
+        .build();
+    assert_eq!(
+        SendCompactBlockProcess::new(content.as_reader(), &relayer, high_bandwidth_peer).execute(),
+        Status::ok(),
+    );
+
+    let compact_block_process = CompactBlockProcess::new(
+        compact_block.as_reader(),
+        &relayer,
+        Arc::<MockProtocalContext>::clone(&nc),
+        peer_index,
+    );
+    // The block can not be reconstructed, but the header has been checked
+    assert_eq!(
+        compact_block_process.execute(),
+        StatusCode::CompactBlockRequiresFreshTransactions.into()
+    );
+
+    let message = packed::RelayMessage::new_builder()
+        .set(compact_block.clone())
+        .build();
+    let data = message.as_bytes();
+    let sent_messages_to = nc.as_ref().sent_messages_to.borrow();
+    assert!(sent_messages_to.contains(&(high_bandwidth_peer, data.clone())));
+    assert!(!sent_messages_to.contains(&(low_bandwidth_peer, data.clone())));
+    assert!(!sent_messages_to.contains(&(peer_index, data)));
+
+    // Already relayed peers are skipped when the block is accepted
+    let relayed = relayer
+        .shared
+        .state()
+        .compact_block_relay()
+        .take_relayed(&block.hash());
+    assert!(relayed.contains(&high_bandwidth_peer));
+    assert!(relayed.contains(&peer_index));
+}
+
+#[test]
+fn test_do_not_relay_fork_block_before_verification() {
+    let (relayer, _) = build_chain(5);
+    let parent = {
+        let active_chain = relayer.shared.active_chain();
+        let tip = active_chain.tip_header();
+        active_chain
+            .get_block_header(&tip.parent_hash())
+            .expect("parent of the tip")
+    };
+
+    // Fork block which does not extend the active tip
+    let header = new_header_builder(relayer.shared.shared(), &parent).build();
+    let block = BlockBuilder::default()
+        .header(header)
+        .transaction(TransactionBuilder::default().build())
+        .transaction(
+            TransactionBuilder::default()
+                .output(
+                    CellOutputBuilder::default()
+                        .capacity(Capacity::bytes(1).unwrap().pack())
+                        .build(),
+                )
+                .output_data(Bytes::new().pack())
+                .build(),
+        )
+        .build();
+
+    let mut prefilled_transactions_indexes = HashSet::new();
+    prefilled_transactions_indexes.insert(0);
+    let compact_block = CompactBlock::build_from_block(&block, &prefilled_transactions_indexes);
+
+    let mock_protocal_context = MockProtocalContext::default();
+    let nc = Arc::new(mock_protocal_context);
+    let peer_index: PeerIndex = 100.into();
+    let high_bandwidth_peer: PeerIndex = 101.into();
+
+    let content = packed::SendCompactBlock::new_builder()
+        .high_bandwidth(true.pack())
+        .build();
+    assert_eq!(
+        SendCompactBlockProcess::new(content.as_reader(), &relayer, high_bandwidth_peer).execute(),
+        Status::ok(),
+    );
+
+    let compact_block_process = CompactBlockProcess::new(
+        compact_block.as_reader(),
+        &relayer,
+        Arc::<MockProtocalContext>::clone(&nc),
+        peer_index,
+    );
+    assert_eq!(
+        compact_block_process.execute(),
+        StatusCode::CompactBlockRequiresFreshTransactions.into()
+    );
+
+    let message = packed::RelayMessage::new_builder()
+        .set(compact_block)
+        .build();
+    let data = message.as_bytes();
+    assert!(!nc
+        .as_ref()
+        .sent_messages_to
+        .borrow()
+        .contains(&(high_bandwidth_peer, data)));
+}
+
+#[test]
+fn test_select_high_bandwidth_peers() {
+    let (relayer, _) = build_chain(1);
+    let state = relayer.shared.state();
+    let mut compact_block_relay = state.compact_block_relay();
+
+    let peers: Vec<PeerIndex> = (1..=MAX_HIGH_BANDWIDTH_PEERS + 1)
+        .map(PeerIndex::new)
+        .collect();
+    for peer in &peers[..MAX_HIGH_BANDWIDTH_PEERS] {
+        assert_eq!(
+            compact_block_relay.select_announcer(*peer, MAX_HIGH_BANDWIDTH_PEERS),
+            (true, None)
+        );
+    }
+
+    // Delivering a block again makes the peer the most recent one
+    assert_eq!(
+        compact_block_relay.select_announcer(peers[0], MAX_HIGH_BANDWIDTH_PEERS),
+        (false, None)
+    );
+    // The least recent one is dropped for a new peer
+    assert_eq!(
+        compact_block_relay
+            .select_announcer(peers[MAX_HIGH_BANDWIDTH_PEERS], MAX_HIGH_BANDWIDTH_PEERS),
+        (true, Some(peers[1]))
+    );
+    assert_eq!(
+        compact_block_relay.announcers(),
+        vec![peers[2], peers[0], peers[3]]
+    );
+
+    compact_block_relay.disconnected(peers[0]);
+    assert_eq!(compact_block_relay.announcers(), vec![peers[2], peers[3]]);
+}
@@ -1,7 +1,6 @@
 use crate::{Relayer, SyncShared};
 use ckb_chain::{chain::ChainService, switch::Switch};
 use ckb_chain_spec::consensus::ConsensusBuilder;
-use ckb_fee_estimator::FeeRate;
 use ckb_network::{
     bytes::Bytes as P2pBytes, Behaviour, CKBProtocolContext, Error, Peer, PeerIndex, ProtocolId,
     TargetSession,
@@ -153,12 +152,7 @@ pub(crate) fn build_chain(tip: BlockNumber) -> (Relayer, OutPoint) {
 
     let sync_shared = Arc::new(SyncShared::new(shared, Default::default()));
     (
-        Relayer::new(
-            chain_controller,
-            sync_shared,
-            FeeRate::zero(),
-            std::u64::MAX,
-        ),
+        Relayer::new(chain_controller, sync_shared, std::u64::MAX),
         always_success_out_point,
     )
 }
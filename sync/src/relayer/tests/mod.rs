@@ -6,3 +6,4 @@ mod compact_block_process;
 mod compact_block_verifier;
 mod helper;
 mod reconstruct_block;
+mod transaction_package_process;
@@ -0,0 +1,70 @@
+use crate::relayer::tests::helper::{build_chain, new_transaction, MockProtocalContext};
+use crate::relayer::transaction_package_process::TransactionPackageProcess;
+use crate::{Status, StatusCode};
+use ckb_network::PeerIndex;
+use ckb_tx_pool::MAX_PACKAGE_TXS;
+use ckb_types::core::TransactionView;
+use ckb_types::packed::{self, RelayTransactionPackage};
+use ckb_types::prelude::*;
+use std::sync::Arc;
+
+fn build_package(txs: &[TransactionView]) -> RelayTransactionPackage {
+    let relay_txs = txs
+        .iter()
+        .map(|tx| {
+            packed::RelayTransaction::new_builder()
+                .transaction(tx.data())
+                .cycles(0u64.pack())
+                .build()
+        })
+        .collect::<Vec<_>>();
+    RelayTransactionPackage::new_builder()
+        .transactions(
+            packed::RelayTransactionVec::new_builder()
+                .set(relay_txs)
+                .build(),
+        )
+        .build()
+}
+
+#[test]
+fn test_package_too_many_txs() {
+    let (relayer, always_success_out_point) = build_chain(5);
+    let txs: Vec<TransactionView> = (0..=MAX_PACKAGE_TXS)
+        .map(|i| new_transaction(&relayer, i, &always_success_out_point))
+        .collect();
+    let package = build_package(&txs);
+
+    let nc = Arc::new(MockProtocalContext::default());
+    let peer_index: PeerIndex = 1.into();
+    let process = TransactionPackageProcess::new(
+        package.as_reader(),
+        &relayer,
+        Arc::<MockProtocalContext>::clone(&nc),
+        peer_index,
+    );
+
+    assert_eq!(
+        process.execute().code(),
+        StatusCode::ProtocolMessageIsMalformed,
+    );
+}
+
+#[test]
+fn test_package_already_known() {
+    let (relayer, always_success_out_point) = build_chain(5);
+    let tx = new_transaction(&relayer, 0, &always_success_out_point);
+    relayer.shared.state().mark_as_known_txs(vec![tx.hash()]);
+    let package = build_package(&[tx]);
+
+    let nc = Arc::new(MockProtocalContext::default());
+    let peer_index: PeerIndex = 1.into();
+    let process = TransactionPackageProcess::new(
+        package.as_reader(),
+        &relayer,
+        Arc::<MockProtocalContext>::clone(&nc),
+        peer_index,
+    );
+
+    assert_eq!(process.execute(), Status::ok());
+}
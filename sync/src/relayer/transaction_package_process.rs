@@ -0,0 +1,189 @@
+use crate::relayer::transactions_process::{is_malformed, DEFAULT_BAN_TIME};
+use crate::relayer::Relayer;
+use crate::{Status, StatusCode};
+use ckb_error::Error;
+use ckb_logger::debug_target;
+use ckb_network::{CKBProtocolContext, PeerIndex};
+use ckb_tx_pool::MAX_PACKAGE_TXS;
+use ckb_types::{
+    core::{Cycle, TransactionView},
+    packed,
+    prelude::*,
+};
+use ckb_util::LinkedHashSet;
+use ckb_verification::cache::CacheEntry;
+use std::sync::Arc;
+
+pub struct TransactionPackageProcess<'a> {
+    message: packed::RelayTransactionPackageReader<'a>,
+    relayer: &'a Relayer,
+    nc: Arc<dyn CKBProtocolContext + Sync>,
+    peer: PeerIndex,
+}
+
+impl<'a> TransactionPackageProcess<'a> {
+    pub fn new(
+        message: packed::RelayTransactionPackageReader<'a>,
+        relayer: &'a Relayer,
+        nc: Arc<dyn CKBProtocolContext + Sync>,
+        peer: PeerIndex,
+    ) -> Self {
+        TransactionPackageProcess {
+            message,
+            relayer,
+            nc,
+            peer,
+        }
+    }
+
+    pub fn execute(self) -> Status {
+        let transactions = self.message.transactions();
+        if transactions.len() > MAX_PACKAGE_TXS {
+            return StatusCode::ProtocolMessageIsMalformed.with_context(format!(
+                "Package txs count({}) > MAX_PACKAGE_TXS({})",
+                transactions.len(),
+                MAX_PACKAGE_TXS,
+            ));
+        }
+
+        let txs: Vec<(TransactionView, Cycle)> = transactions
+            .iter()
+            .map(|tx| {
+                (
+                    tx.transaction().to_entity().into_view(),
+                    tx.cycles().unpack(),
+                )
+            })
+            .collect();
+        let shared_state = self.relayer.shared().state();
+        if txs
+            .iter()
+            .all(|(tx, _)| shared_state.already_known_tx(&tx.hash()))
+        {
+            return Status::ok();
+        }
+
+        let tx_hashes: Vec<packed::Byte32> = txs.iter().map(|(tx, _)| tx.hash()).collect();
+        shared_state.mark_as_known_txs(tx_hashes.clone());
+        if let Some(peer_state) = shared_state.peers().state.write().get_mut(&self.peer) {
+            for tx_hash in tx_hashes.iter() {
+                peer_state.remove_ask_for_tx(tx_hash);
+            }
+        }
+
+        // skip the package when any tx consumes too much cycles
+        let max_tx_verify_cycles = self.relayer.max_tx_verify_cycles;
+        if let Some((tx, relay_cycles)) = txs
+            .iter()
+            .find(|(_, relay_cycles)| *relay_cycles > max_tx_verify_cycles)
+        {
+            debug_target!(
+                crate::LOG_TARGET_RELAY,
+                "ignore package with tx {} which relay cycles({}) is large than max tx verify cycles {}",
+                tx.hash(),
+                relay_cycles,
+                max_tx_verify_cycles
+            );
+            return Status::ok();
+        }
+
+        let relay_cycles_vec: Vec<Cycle> = txs.iter().map(|(_, cycles)| *cycles).collect();
+        let package_size: usize = txs
+            .iter()
+            .map(|(tx, _)| tx.data().serialized_size_in_block())
+            .sum();
+        let package: Vec<TransactionView> = txs.into_iter().map(|(tx, _)| tx).collect();
+
+        let nc = Arc::clone(&self.nc);
+        let peer_index = self.peer;
+        let shared = Arc::clone(self.relayer.shared());
+        let min_fee_rate = self.relayer.min_fee_rate();
+
+        let callback = Box::new(move |ret: Result<Vec<CacheEntry>, Error>| match ret {
+            Ok(cache_entry_vec) => {
+                for ((tx_hash, relay_cycles), cache_entry) in tx_hashes
+                    .iter()
+                    .zip(relay_cycles_vec.iter())
+                    .zip(cache_entry_vec.iter())
+                {
+                    if *relay_cycles != cache_entry.cycles {
+                        debug_target!(
+                            crate::LOG_TARGET_RELAY,
+                            "peer {} relay package with wrong cycles tx_hash: {} real cycles {} wrong cycles {}",
+                            peer_index,
+                            tx_hash,
+                            cache_entry.cycles,
+                            relay_cycles,
+                        );
+                        nc.ban_peer(
+                            peer_index,
+                            DEFAULT_BAN_TIME,
+                            String::from("send us a transaction with wrong cycles"),
+                        );
+                        return;
+                    }
+                }
+
+                let package_fee: u64 = cache_entry_vec
+                    .iter()
+                    .map(|cache_entry| cache_entry.fee.as_u64())
+                    .sum();
+                if package_fee < min_fee_rate.fee(package_size).as_u64() {
+                    debug_target!(
+                        crate::LOG_TARGET_RELAY,
+                        "peer {} relay package lower than min fee rate {} shannons/KB. \
+                         txs: {:?}  size {} fee {}",
+                        peer_index,
+                        min_fee_rate,
+                        tx_hashes,
+                        package_size,
+                        package_fee,
+                    );
+                    return;
+                }
+
+                // the peers understanding package relay get the package, the others
+                // get the hashes as usual
+                let state = shared.state();
+                state.tx_packages().push((peer_index, tx_hashes.clone()));
+                let mut cache = state.tx_hashes();
+                let entry = cache
+                    .entry(peer_index)
+                    .or_insert_with(LinkedHashSet::default);
+                for tx_hash in tx_hashes {
+                    entry.insert(tx_hash);
+                }
+            }
+            Err(err) => {
+                if is_malformed(&err) {
+                    debug_target!(
+                        crate::LOG_TARGET_RELAY,
+                        "peer {} relay an invalid package, error: {:?}",
+                        peer_index,
+                        err
+                    );
+                    nc.ban_peer(
+                        peer_index,
+                        DEFAULT_BAN_TIME,
+                        String::from("send us an invalid transaction"),
+                    );
+                } else {
+                    debug_target!(
+                        crate::LOG_TARGET_RELAY,
+                        "peer {} relay a package which is rejected, error: {}",
+                        peer_index,
+                        err
+                    );
+                }
+            }
+        });
+
+        let tx_pool = self.relayer.shared.shared().tx_pool_controller();
+        if let Err(err) = tx_pool.notify_package(package, Some(callback)) {
+            return StatusCode::TxPool
+                .with_context(format!("TxPool notify_package error: {:?}", err));
+        }
+
+        Status::ok()
+    }
+}
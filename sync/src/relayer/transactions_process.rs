@@ -3,6 +3,7 @@ use crate::{Status, StatusCode};
 use ckb_error::{Error, ErrorKind, InternalError, InternalErrorKind};
 use ckb_logger::debug_target;
 use ckb_network::{CKBProtocolContext, PeerIndex};
+use ckb_tx_pool::error::missing_inputs;
 use ckb_types::{
     core::{Cycle, TransactionView},
     packed,
@@ -15,7 +16,7 @@ use sentry::{capture_message, with_scope, Level};
 use std::sync::Arc;
 use std::time::Duration;
 
-const DEFAULT_BAN_TIME: Duration = Duration::from_secs(3600 * 24 * 3);
+pub(crate) const DEFAULT_BAN_TIME: Duration = Duration::from_secs(3600 * 24 * 3);
 
 pub struct TransactionsProcess<'a> {
     message: packed::RelayTransactionsReader<'a>,
@@ -104,7 +105,7 @@ impl<'a> TransactionsProcess<'a> {
         let nc = Arc::clone(&self.nc);
         let peer_index = self.peer;
         let shared = Arc::clone(self.relayer.shared());
-        let min_fee_rate = self.relayer.min_fee_rate;
+        let min_fee_rate = self.relayer.min_fee_rate();
 
         let callback = Box::new(move |ret: Result<Vec<CacheEntry>, Error>| match ret {
             Ok(cache_entry_vec) => {
@@ -178,10 +179,35 @@ impl<'a> TransactionsProcess<'a> {
                         DEFAULT_BAN_TIME,
                         String::from("send us an invalid transaction"),
                     );
+                } else if let Some(out_points) = missing_inputs(&err) {
+                    // The orphans are kept in the pool, ask the peer for their parents
+                    let state = shared.state();
+                    let parents: LinkedHashSet<packed::Byte32> = out_points
+                        .iter()
+                        .map(|out_point| out_point.tx_hash())
+                        .filter(|tx_hash| !state.already_known_tx(tx_hash))
+                        .collect();
+                    debug_target!(
+                        crate::LOG_TARGET_RELAY,
+                        "peer {} relay orphan txs, get missing parents {:?} from the peer",
+                        peer_index,
+                        parents,
+                    );
+                    if let Some(peer_state) = state.peers().state.write().get_mut(&peer_index) {
+                        let mut inflight_transactions = state.inflight_transactions();
+                        for tx_hash in parents {
+                            let last_ask_timeout = inflight_transactions.get(&tx_hash).cloned();
+                            if let Some(next_ask_timeout) =
+                                peer_state.add_ask_for_tx(tx_hash.clone(), last_ask_timeout)
+                            {
+                                inflight_transactions.insert(tx_hash, next_ask_timeout);
+                            }
+                        }
+                    }
                 } else {
                     debug_target!(
                         crate::LOG_TARGET_RELAY,
-                        "peer {} relay a conflict or missing input, error: {}",
+                        "peer {} relay a conflict tx, error: {}",
                         peer_index,
                         err
                     );
@@ -190,7 +216,7 @@ impl<'a> TransactionsProcess<'a> {
         });
 
         let tx_pool = self.relayer.shared.shared().tx_pool_controller();
-        if let Err(err) = tx_pool.notify_txs(notify_txs, Some(callback)) {
+        if let Err(err) = tx_pool.notify_txs(notify_txs, Some(peer_index.value()), Some(callback)) {
             return StatusCode::TxPool.with_context(format!("TxPool notify_txs error: {:?}", err));
         }
 
@@ -198,7 +224,7 @@ impl<'a> TransactionsProcess<'a> {
     }
 }
 
-fn is_malformed(error: &Error) -> bool {
+pub(crate) fn is_malformed(error: &Error) -> bool {
     match error.kind() {
         ErrorKind::Transaction => error
             .downcast_ref::<TransactionError>()
@@ -30,7 +30,7 @@ use failure::Error as FailureError;
 use faketime::unix_time_as_millis;
 use lru_cache::LruCache;
 use std::cmp;
-use std::collections::{btree_map::Entry, BTreeMap, HashMap, HashSet};
+use std::collections::{btree_map::Entry, BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::hash::Hash;
 use std::mem;
@@ -56,6 +56,7 @@ const ORPHAN_BLOCK_SIZE: usize = 1024;
 // 2 ** 13 < 6 * 1800 < 2 ** 14
 const ONE_DAY_BLOCK_NUMBER: u64 = 8192;
 const SHRINK_THREHOLD: usize = 300;
+const COMPACT_BLOCK_RELAY_CACHE_SIZE: usize = 128;
 
 // State used to enforce CHAIN_SYNC_TIMEOUT
 // Only in effect for connections that are outbound, non-manual,
@@ -1159,6 +1160,133 @@ fn get_skip_height(height: BlockNumber) -> BlockNumber {
     }
 }
 
+// Peers taking part in high-bandwidth compact block relay.
+//
+// A high-bandwidth announcer pushes `CompactBlock` as soon as the header and PoW are checked,
+// before the block is fully verified. The others still announce after the block is accepted.
+pub struct CompactBlockRelayState {
+    // peers whose relay protocol version understands `SendCompactBlock`
+    supported: HashSet<PeerIndex>,
+    // peers we asked to announce in high-bandwidth mode, the least recent first
+    announce_from: VecDeque<PeerIndex>,
+    // peers which asked us to announce in high-bandwidth mode
+    announce_to: HashSet<PeerIndex>,
+    // blocks pushed before verification, and the peers they were pushed to
+    relayed: LruCache<Byte32, HashSet<PeerIndex>>,
+    // when the first compact block arrived, and the GetBlockTransactions round trips since then
+    reconstructions: LruCache<Byte32, (Instant, u32)>,
+}
+
+impl Default for CompactBlockRelayState {
+    fn default() -> Self {
+        CompactBlockRelayState {
+            supported: HashSet::default(),
+            announce_from: VecDeque::default(),
+            announce_to: HashSet::default(),
+            relayed: LruCache::new(COMPACT_BLOCK_RELAY_CACHE_SIZE),
+            reconstructions: LruCache::new(COMPACT_BLOCK_RELAY_CACHE_SIZE),
+        }
+    }
+}
+
+impl CompactBlockRelayState {
+    pub fn connected(&mut self, peer: PeerIndex) {
+        self.supported.insert(peer);
+    }
+
+    pub fn disconnected(&mut self, peer: PeerIndex) {
+        self.supported.remove(&peer);
+        self.announce_from.retain(|pi| *pi != peer);
+        self.announce_to.remove(&peer);
+    }
+
+    pub fn is_supported(&self, peer: PeerIndex) -> bool {
+        self.supported.contains(&peer)
+    }
+
+    // Marks `peer` as the most recent high-bandwidth announcer.
+    //
+    // Returns whether the peer was not selected before, and the least recent announcer which
+    // has been dropped to keep at most `limit` of them.
+    pub fn select_announcer(&mut self, peer: PeerIndex, limit: usize) -> (bool, Option<PeerIndex>) {
+        let selected = match self.announce_from.iter().position(|pi| *pi == peer) {
+            Some(index) => {
+                self.announce_from.remove(index);
+                false
+            }
+            None => true,
+        };
+        self.announce_from.push_back(peer);
+        let dropped = if self.announce_from.len() > limit {
+            self.announce_from.pop_front()
+        } else {
+            None
+        };
+        (selected, dropped)
+    }
+
+    pub fn announcers(&self) -> Vec<PeerIndex> {
+        self.announce_from.iter().cloned().collect()
+    }
+
+    pub fn set_announce_to(&mut self, peer: PeerIndex, high_bandwidth: bool) {
+        if high_bandwidth {
+            self.announce_to.insert(peer);
+        } else {
+            self.announce_to.remove(&peer);
+        }
+    }
+
+    pub fn announce_to(&self) -> &HashSet<PeerIndex> {
+        &self.announce_to
+    }
+
+    // Records that `peer` knows `block_hash`, and returns the high-bandwidth peers the block has
+    // not been pushed to yet, which are recorded as well.
+    pub fn relay_targets(&mut self, block_hash: Byte32, peer: PeerIndex) -> Vec<PeerIndex> {
+        if self.announce_to.is_empty() {
+            return Vec::new();
+        }
+        if !self.relayed.contains_key(&block_hash) {
+            self.relayed.insert(block_hash.clone(), HashSet::default());
+        }
+        let relayed = self.relayed.get_mut(&block_hash).expect("inserted above");
+        relayed.insert(peer);
+        let targets: Vec<PeerIndex> = self
+            .announce_to
+            .iter()
+            .filter(|target| !relayed.contains(target))
+            .cloned()
+            .collect();
+        relayed.extend(targets.iter().cloned());
+        targets
+    }
+
+    pub fn take_relayed(&mut self, block_hash: &Byte32) -> HashSet<PeerIndex> {
+        self.relayed.remove(block_hash).unwrap_or_default()
+    }
+
+    pub fn reconstruct_started(&mut self, block_hash: Byte32) {
+        if !self.reconstructions.contains_key(&block_hash) {
+            self.reconstructions.insert(block_hash, (Instant::now(), 0));
+        }
+    }
+
+    pub fn reconstruct_round_trip(&mut self, block_hash: &Byte32) {
+        if let Some((_, round_trips)) = self.reconstructions.get_mut(block_hash) {
+            *round_trips += 1;
+        }
+    }
+
+    // Returns the elapsed time since the first compact block arrived, and the round trips
+    // spent on fetching missing transactions and uncles.
+    pub fn reconstruct_finished(&mut self, block_hash: &Byte32) -> Option<(Duration, u32)> {
+        self.reconstructions
+            .remove(block_hash)
+            .map(|(started, round_trips)| (started.elapsed(), round_trips))
+    }
+}
+
 // <CompactBlockHash, (CompactBlock, <PeerIndex, (TransactionsIndex, UnclesIndex)>)>
 type PendingCompactBlockMap = HashMap<
     Byte32,
@@ -1210,12 +1338,15 @@ impl SyncShared {
             known_txs: Mutex::new(KnownFilter::default()),
             pending_get_block_proposals: Mutex::new(HashMap::default()),
             pending_compact_blocks: Mutex::new(HashMap::default()),
+            compact_block_relay: Mutex::new(CompactBlockRelayState::default()),
+            package_relay_peers: Mutex::new(HashSet::default()),
             orphan_block_pool: OrphanBlockPool::with_capacity(ORPHAN_BLOCK_SIZE),
             inflight_proposals: Mutex::new(HashSet::default()),
             inflight_transactions: Mutex::new(LruCache::new(TX_ASKED_SIZE)),
             inflight_blocks: RwLock::new(InflightBlocks::default()),
             pending_get_headers: RwLock::new(LruCache::new(GET_HEADERS_CACHE_SIZE)),
             tx_hashes: Mutex::new(HashMap::default()),
+            tx_packages: Mutex::new(Vec::new()),
         };
 
         SyncShared {
@@ -1446,6 +1577,9 @@ pub struct SyncState {
     pending_get_block_proposals: Mutex<HashMap<packed::ProposalShortId, HashSet<PeerIndex>>>,
     pending_get_headers: RwLock<LruCache<(PeerIndex, Byte32), Instant>>,
     pending_compact_blocks: Mutex<PendingCompactBlockMap>,
+    compact_block_relay: Mutex<CompactBlockRelayState>,
+    // peers whose relay protocol version understands `RelayTransactionPackage`
+    package_relay_peers: Mutex<HashSet<PeerIndex>>,
     orphan_block_pool: OrphanBlockPool,
 
     /* In-flight items for which we request to peers, but not got the responses yet */
@@ -1455,6 +1589,8 @@ pub struct SyncState {
 
     /* cached for sending bulk */
     tx_hashes: Mutex<HashMap<PeerIndex, LinkedHashSet<Byte32>>>,
+    // packages of dependent txs, along with the peers they came from
+    tx_packages: Mutex<Vec<(PeerIndex, Vec<Byte32>)>>,
 }
 
 impl SyncState {
@@ -1488,6 +1624,14 @@ impl SyncState {
         self.pending_compact_blocks.lock()
     }
 
+    pub fn compact_block_relay(&self) -> MutexGuard<CompactBlockRelayState> {
+        self.compact_block_relay.lock()
+    }
+
+    pub fn package_relay_peers(&self) -> MutexGuard<HashSet<PeerIndex>> {
+        self.package_relay_peers.lock()
+    }
+
     pub fn inflight_transactions(&self) -> MutexGuard<LruCache<Byte32, Instant>> {
         self.inflight_transactions.lock()
     }
@@ -1513,6 +1657,15 @@ impl SyncState {
         mem::take(&mut *map)
     }
 
+    pub fn tx_packages(&self) -> MutexGuard<Vec<(PeerIndex, Vec<Byte32>)>> {
+        self.tx_packages.lock()
+    }
+
+    pub fn take_tx_packages(&self) -> Vec<(PeerIndex, Vec<Byte32>)> {
+        let mut packages = self.tx_packages.lock();
+        mem::take(&mut *packages)
+    }
+
     pub fn shared_best_header(&self) -> HeaderView {
         self.shared_best_header.read().to_owned()
     }
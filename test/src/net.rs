@@ -114,6 +114,8 @@ impl Net {
             upnp: false,
             bootnode_mode: false,
             max_send_buffer: None,
+            max_outbound_peers_per_group: None,
+            asmap: None,
             sync: None,
         };
 
@@ -540,6 +540,7 @@ impl Node {
             args: Default::default(),
             hash_type: ScriptHashType::Data.into(),
             message: Default::default(),
+            reward_shares: vec![],
         });
 
         modify_ckb_config(&mut ckb_config);
@@ -69,6 +69,7 @@ impl Spec for BootstrapCellbase {
                 args: JsonBytes::from_bytes(Bytes::from(vec![2, 1])),
                 hash_type: ScriptHashType::Data.into(),
                 message: Default::default(),
+                reward_shares: vec![],
             });
         })
     }
@@ -62,5 +62,6 @@ fn new_block_assembler_config(lock_arg: Bytes, hash_type: ScriptHashType) -> Blo
         hash_type: hash_type.into(),
         args: JsonBytes::from_bytes(lock_arg),
         message: Default::default(),
+        reward_shares: vec![],
     }
 }
@@ -1,5 +1,11 @@
-use ckb_types::{core::BlockNumber, core::UncleBlockView};
-use std::collections::{btree_map::Entry, BTreeMap, HashSet};
+use ckb_logger::error;
+use ckb_store::{ChainDB, ChainStore};
+use ckb_types::{
+    core::{BlockNumber, EpochNumber, UncleBlockView},
+    packed::Byte32,
+};
+use std::cmp::Ordering;
+use std::collections::{btree_map::Entry, BTreeMap};
 
 #[cfg(not(test))]
 const MAX_CANDIDATE_UNCLES: usize = 128;
@@ -11,9 +17,14 @@ const MAX_PER_HEIGHT: usize = 10;
 #[cfg(test)]
 const MAX_PER_HEIGHT: usize = 2;
 
+/// Candidate uncles for the block templates.
+///
+/// The candidates are persisted when a store is attached, so they survive restarts. The ones
+/// at the same height are kept sorted by difficulty, the larger the better.
 pub struct CandidateUncles {
-    pub(crate) map: BTreeMap<BlockNumber, HashSet<UncleBlockView>>,
+    pub(crate) map: BTreeMap<BlockNumber, Vec<UncleBlockView>>,
     count: usize,
+    store: Option<ChainDB>,
 }
 
 impl CandidateUncles {
@@ -21,70 +32,156 @@ impl CandidateUncles {
         CandidateUncles {
             map: BTreeMap::new(),
             count: 0,
+            store: None,
         }
     }
 
+    /// Load the persisted candidates from the store, the later changes are written back to it.
+    pub fn with_store(store: ChainDB) -> CandidateUncles {
+        let persisted = store.get_candidate_uncles();
+        let mut candidate_uncles = CandidateUncles {
+            map: BTreeMap::new(),
+            count: 0,
+            store: Some(store),
+        };
+        for uncle in persisted {
+            if !candidate_uncles.insert_without_persist(uncle.clone()) {
+                candidate_uncles.delete_persisted(&[uncle.hash()]);
+            }
+        }
+        candidate_uncles
+    }
+
     pub fn insert(&mut self, uncle: UncleBlockView) -> bool {
+        let ret = self.insert_without_persist(uncle.clone());
+        if ret {
+            if let Some(ref store) = self.store {
+                let db_txn = store.begin_transaction();
+                if let Err(err) = db_txn
+                    .insert_candidate_uncle(&uncle)
+                    .and_then(|_| db_txn.commit())
+                {
+                    error!("persist candidate uncle {} error: {}", uncle.hash(), err);
+                }
+            }
+        }
+        ret
+    }
+
+    fn insert_without_persist(&mut self, uncle: UncleBlockView) -> bool {
         let number: BlockNumber = uncle.header().number();
         if self.count >= MAX_CANDIDATE_UNCLES {
             let first_key = *self.map.keys().next().expect("length checked");
             if number > first_key {
-                if let Some(set) = self.map.remove(&first_key) {
-                    self.count -= set.len();
+                if let Some(uncles) = self.map.remove(&first_key) {
+                    self.count -= uncles.len();
+                    let hashes: Vec<Byte32> = uncles.iter().map(UncleBlockView::hash).collect();
+                    self.delete_persisted(&hashes);
                 }
             } else {
                 return false;
             }
         }
 
-        let set = self.map.entry(number).or_insert_with(HashSet::new);
-        if set.len() < MAX_PER_HEIGHT {
-            let ret = set.insert(uncle);
-            if ret {
-                self.count += 1;
-            }
-            ret
-        } else {
-            false
+        let uncles = self.map.entry(number).or_insert_with(Vec::new);
+        if uncles.len() >= MAX_PER_HEIGHT || uncles.contains(&uncle) {
+            return false;
         }
+        let index = uncles
+            .binary_search_by(|other| cmp_priority(other, &uncle))
+            .unwrap_or_else(|index| index);
+        uncles.insert(index, uncle);
+        self.count += 1;
+        true
     }
 
-    #[cfg(test)]
     pub fn len(&self) -> usize {
         self.count
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
     #[cfg(test)]
     pub fn clear(&mut self) {
         self.map.clear();
         self.count = 0;
     }
 
+    /// Iterate the candidates by priority: the closer to the tip the better, and among the
+    /// ones at the same height, the larger difficulty the better.
     pub fn values(&self) -> impl Iterator<Item = &UncleBlockView> {
-        self.map.values().flat_map(HashSet::iter)
+        self.map.values().rev().flat_map(|uncles| uncles.iter())
+    }
+
+    /// Iterate the candidates in ascending number order, so a parent is visited before its
+    /// children. The ones at the same height are visited by difficulty as in `values`.
+    pub fn values_by_number(&self) -> impl Iterator<Item = &UncleBlockView> {
+        self.map.values().flat_map(|uncles| uncles.iter())
     }
 
     pub fn remove(&mut self, uncle: &UncleBlockView) -> bool {
         let number: BlockNumber = uncle.header().number();
 
         if let Entry::Occupied(mut entry) = self.map.entry(number) {
-            let set = entry.get_mut();
-            if set.remove(uncle) {
+            let uncles = entry.get_mut();
+            if let Some(index) = uncles.iter().position(|u| u == uncle) {
+                uncles.remove(index);
                 self.count -= 1;
-                if set.is_empty() {
+                if uncles.is_empty() {
                     entry.remove();
                 }
+                self.delete_persisted(&[uncle.hash()]);
                 return true;
             }
         }
         false
     }
+
+    /// Remove the candidates from the epochs before `epoch_number`, an uncle must be in the
+    /// same epoch as the block including it. Returns the count of removed candidates.
+    pub fn prune(&mut self, epoch_number: EpochNumber) -> usize {
+        let stale: Vec<UncleBlockView> = self
+            .map
+            .values()
+            .flat_map(|uncles| uncles.iter())
+            .filter(|uncle| uncle.epoch().number() < epoch_number)
+            .cloned()
+            .collect();
+        for uncle in &stale {
+            self.remove(uncle);
+        }
+        stale.len()
+    }
+
+    fn delete_persisted(&self, hashes: &[Byte32]) {
+        if let Some(ref store) = self.store {
+            let db_txn = store.begin_transaction();
+            let ret = hashes
+                .iter()
+                .try_for_each(|hash| db_txn.delete_candidate_uncle(hash))
+                .and_then(|_| db_txn.commit());
+            if let Err(err) = ret {
+                error!("delete persisted candidate uncles error: {}", err);
+            }
+        }
+    }
+}
+
+// The larger difficulty goes first, the hash breaks the tie
+fn cmp_priority(a: &UncleBlockView, b: &UncleBlockView) -> Ordering {
+    b.difficulty()
+        .cmp(&a.difficulty())
+        .then_with(|| a.hash().cmp(&b.hash()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ckb_types::core::BlockBuilder;
+    use ckb_db::RocksDB;
+    use ckb_store::COLUMNS;
+    use ckb_types::core::{BlockBuilder, EpochNumberWithFraction};
     use ckb_types::prelude::*;
 
     #[test]
@@ -150,4 +247,82 @@ mod tests {
         assert_eq!(candidate_uncles.map.len(), 1);
         assert_eq!(candidate_uncles.len(), MAX_PER_HEIGHT);
     }
+
+    #[test]
+    fn test_candidate_uncles_priority() {
+        let mut candidate_uncles = CandidateUncles::new();
+        let low = BlockBuilder::default()
+            .number(1u64.pack())
+            .build()
+            .as_uncle();
+        let high_easy = BlockBuilder::default()
+            .number(2u64.pack())
+            .compact_target(0x2000_0100u32.pack())
+            .build()
+            .as_uncle();
+        let high_hard = BlockBuilder::default()
+            .number(2u64.pack())
+            .compact_target(0x1e08_3126u32.pack())
+            .build()
+            .as_uncle();
+        assert!(high_hard.difficulty() > high_easy.difficulty());
+
+        for uncle in &[low.clone(), high_easy.clone(), high_hard.clone()] {
+            assert!(candidate_uncles.insert(uncle.clone()));
+        }
+        let ordered: Vec<_> = candidate_uncles.values().cloned().collect();
+        assert_eq!(
+            ordered,
+            vec![high_hard.clone(), high_easy.clone(), low.clone()]
+        );
+        let ordered: Vec<_> = candidate_uncles.values_by_number().cloned().collect();
+        assert_eq!(ordered, vec![low, high_hard, high_easy]);
+    }
+
+    #[test]
+    fn test_candidate_uncles_with_store() {
+        let store = ChainDB::new(RocksDB::open_tmp(COLUMNS), Default::default());
+        let uncles: Vec<_> = (1..=3u64)
+            .map(|number| {
+                BlockBuilder::default()
+                    .number(number.pack())
+                    .build()
+                    .as_uncle()
+            })
+            .collect();
+        let mut candidate_uncles = CandidateUncles::with_store(store.clone());
+        for uncle in &uncles {
+            assert!(candidate_uncles.insert(uncle.clone()));
+        }
+        assert!(candidate_uncles.remove(&uncles[0]));
+
+        // the persisted candidates are reloaded, the removed one is not
+        let reloaded = CandidateUncles::with_store(store);
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(
+            reloaded.values().cloned().collect::<Vec<_>>(),
+            vec![uncles[2].clone(), uncles[1].clone()]
+        );
+    }
+
+    #[test]
+    fn test_candidate_uncles_prune() {
+        let mut candidate_uncles = CandidateUncles::new();
+        for (number, epoch) in &[(1u64, 0u64), (2, 0), (3, 1)] {
+            let block = BlockBuilder::default()
+                .number(number.pack())
+                .epoch(EpochNumberWithFraction::new(*epoch, 0, 10).pack())
+                .build()
+                .as_uncle();
+            assert!(candidate_uncles.insert(block));
+        }
+
+        assert_eq!(candidate_uncles.prune(1), 2);
+        assert_eq!(candidate_uncles.len(), 1);
+        assert_eq!(
+            candidate_uncles.map.keys().cloned().collect::<Vec<_>>(),
+            vec![3]
+        );
+        assert_eq!(candidate_uncles.prune(1), 0);
+    }
 }
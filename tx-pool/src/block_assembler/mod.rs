@@ -12,12 +12,13 @@ use ckb_store::ChainStore;
 use ckb_types::{
     bytes::Bytes,
     core::{
-        BlockNumber, Capacity, Cycle, EpochExt, HeaderView, TransactionBuilder, TransactionView,
+        BlockNumber, Cycle, EpochExt, HeaderView, TransactionBuilder, TransactionView,
         UncleBlockView, Version,
     },
-    packed::{self, Byte32, CellInput, CellOutput, CellbaseWitness, ProposalShortId, Transaction},
+    packed::{self, Byte32, CellInput, ProposalShortId, Transaction},
     prelude::*,
 };
+use ckb_util::RwLock;
 use failure::Error as FailureError;
 use lru_cache::LruCache;
 use std::collections::HashSet;
@@ -49,7 +50,8 @@ pub type BlockTemplateCacheKey = (Byte32, Cycle, u64, Version);
 
 #[derive(Clone)]
 pub struct BlockAssembler {
-    pub(crate) config: Arc<BlockAssemblerConfig>,
+    // replaced when the config is reloaded
+    pub(crate) config: Arc<RwLock<Arc<BlockAssemblerConfig>>>,
     pub(crate) work_id: Arc<AtomicU64>,
     pub(crate) last_uncles_updated_at: Arc<AtomicU64>,
     pub(crate) template_caches: Arc<Mutex<LruCache<BlockTemplateCacheKey, TemplateCache>>>,
@@ -57,16 +59,30 @@ pub struct BlockAssembler {
 }
 
 impl BlockAssembler {
-    pub fn new(config: BlockAssemblerConfig) -> Self {
+    pub fn new(
+        config: BlockAssemblerConfig,
+        candidate_uncles: Arc<Mutex<CandidateUncles>>,
+    ) -> Self {
         Self {
-            config: Arc::new(config),
+            config: Arc::new(RwLock::new(Arc::new(config))),
             work_id: Arc::new(AtomicU64::new(0)),
             last_uncles_updated_at: Arc::new(AtomicU64::new(0)),
             template_caches: Arc::new(Mutex::new(LruCache::new(TEMPLATE_CACHE_SIZE))),
-            candidate_uncles: Arc::new(Mutex::new(CandidateUncles::new())),
+            candidate_uncles,
         }
     }
 
+    pub(crate) fn config(&self) -> Arc<BlockAssemblerConfig> {
+        Arc::clone(&self.config.read())
+    }
+
+    /// Replace the config, the cached templates are dropped since their cellbases are built
+    /// with the old one
+    pub(crate) async fn update_config(&self, config: BlockAssemblerConfig) {
+        *self.config.write() = Arc::new(config);
+        self.template_caches.lock().await.clear();
+    }
+
     pub(crate) fn transform_params(
         consensus: &Consensus,
         bytes_limit: Option<u64>,
@@ -155,35 +171,33 @@ impl BlockAssembler {
 
     /// Miner mined block H(c), the block reward will be finalized at H(c + w_far + 1).
     /// Miner specify own lock in cellbase witness.
-    /// The cellbase have only one output,
+    /// The cellbase have only one output, or one output per share once the split reward is enabled,
     /// miner should collect the block reward for finalize target H(max(0, c - w_far - 1))
     pub(crate) fn build_cellbase(
         snapshot: &Snapshot,
         tip: &HeaderView,
-        cellbase_witness: CellbaseWitness,
+        cellbase_witness: packed::Bytes,
     ) -> Result<TransactionView, FailureError> {
         let candidate_number = tip.number() + 1;
 
         let tx = {
-            let (target_lock, block_reward) = RewardCalculator::new(snapshot.consensus(), snapshot)
-                .block_reward_to_finalize(tip)?;
+            let (target, block_reward) = RewardCalculator::new(snapshot.consensus(), snapshot)
+                .block_reward_target_to_finalize(tip)?;
             let input = CellInput::new_cellbase_input(candidate_number);
-            let output = CellOutput::new_builder()
-                .capacity(block_reward.total.pack())
-                .lock(target_lock)
-                .build();
-
-            let witness = cellbase_witness.as_bytes().pack();
             let no_finalization_target =
                 candidate_number <= snapshot.consensus().finalization_delay_length();
-            let tx_builder = TransactionBuilder::default().input(input).witness(witness);
-            let insufficient_reward_to_create_cell = output.is_lack_of_capacity(Capacity::zero())?;
-            if no_finalization_target || insufficient_reward_to_create_cell {
+            let tx_builder = TransactionBuilder::default()
+                .input(input)
+                .witness(cellbase_witness);
+            if no_finalization_target {
                 tx_builder.build()
             } else {
+                // empty outputs if the reward is insufficient to create a cell
+                let outputs = target.outputs(block_reward.total)?;
+                let outputs_data = vec![Bytes::default().pack(); outputs.len()];
                 tx_builder
-                    .output(output)
-                    .output_data(Bytes::default().pack())
+                    .outputs(outputs)
+                    .outputs_data(outputs_data)
                     .build()
             }
         };
@@ -207,22 +221,30 @@ impl BlockAssembler {
         let mut uncles: Vec<UncleBlockView> = Vec::with_capacity(max_uncles_num);
         let mut removed = Vec::new();
 
-        for uncle in candidate_uncles.values() {
+        let candidate_hashes: HashSet<Byte32> = candidate_uncles
+            .values()
+            .map(UncleBlockView::hash)
+            .collect();
+        // the candidates are visited in ascending number order, so a child can be included
+        // right after its parent, the one whose parent is still a candidate is kept for the
+        // later templates
+        for uncle in candidate_uncles.values_by_number() {
             if uncles.len() == max_uncles_num {
                 break;
             }
             let parent_hash = uncle.header().parent_hash();
+            let parent_included = uncles.iter().any(|u| u.hash() == parent_hash)
+                || snapshot.get_block_number(&parent_hash).is_some()
+                || snapshot.is_uncle(&parent_hash);
             if uncle.compact_target() != current_epoch_ext.compact_target()
                 || uncle.epoch().number() != epoch_number
                 || snapshot.get_block_number(&uncle.hash()).is_some()
                 || snapshot.is_uncle(&uncle.hash())
-                || !(uncles.iter().any(|u| u.hash() == parent_hash)
-                    || snapshot.get_block_number(&parent_hash).is_some()
-                    || snapshot.is_uncle(&parent_hash))
+                || !(parent_included || candidate_hashes.contains(&parent_hash))
                 || uncle.number() >= candidate_number
             {
                 removed.push(uncle.clone());
-            } else {
+            } else if parent_included {
                 uncles.push(uncle.clone());
             }
         }
@@ -3,9 +3,12 @@ use crate::component::{
     proposed::ProposedPool,
 };
 use ckb_fee_estimator::FeeRate;
-use ckb_types::{core::Cycle, packed::ProposalShortId};
+use ckb_types::{
+    core::{Capacity, Cycle},
+    packed::ProposalShortId,
+};
 use std::cmp::max;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 /// node will give up to package more txs after MAX_CONSECUTIVE_FAILED
 const MAX_CONSECUTIVE_FAILED: usize = 500;
@@ -13,6 +16,8 @@ const MAX_CONSECUTIVE_FAILED: usize = 500;
 /// find txs to package into commitment
 pub struct CommitTxsScanner<'a> {
     proposed_pool: &'a ProposedPool,
+    // fee deltas set by `prioritise_transaction`
+    fee_deltas: &'a HashMap<ProposalShortId, Capacity>,
     entries: Vec<TxEntry>,
     // modified entries, after put a tx into block,
     // the scores of descendants txs should be updated,
@@ -25,9 +30,13 @@ pub struct CommitTxsScanner<'a> {
 }
 
 impl<'a> CommitTxsScanner<'a> {
-    pub fn new(proposed_pool: &'a ProposedPool) -> CommitTxsScanner<'a> {
+    pub fn new(
+        proposed_pool: &'a ProposedPool,
+        fee_deltas: &'a HashMap<ProposalShortId, Capacity>,
+    ) -> CommitTxsScanner<'a> {
         CommitTxsScanner {
             proposed_pool,
+            fee_deltas,
             entries: Vec::new(),
             modified_entries: TxModifiedEntries::default(),
             fetched_txs: HashSet::default(),
@@ -42,6 +51,7 @@ impl<'a> CommitTxsScanner<'a> {
     ) -> (Vec<TxEntry>, usize, Cycle) {
         let mut size: usize = 0;
         let mut cycles: Cycle = 0;
+        self.apply_fee_deltas();
         self.proposed_pool.with_sorted_by_score_iter(|iter| {
             let mut candidate_pool_tx = None;
             let mut candidate_modified_tx = None;
@@ -144,6 +154,34 @@ impl<'a> CommitTxsScanner<'a> {
         (self.entries, size, cycles)
     }
 
+    /// put prioritised txs and their descendants into modified entries,
+    /// with the fee deltas added to their fees and ancestors fees
+    fn apply_fee_deltas(&mut self) {
+        let fee_deltas = self.fee_deltas;
+        for (id, fee_delta) in fee_deltas {
+            if !self.proposed_pool.contains_key(id) {
+                continue;
+            }
+            let mut tx = self.take_modified_entry(id);
+            tx.add_fee_delta(*fee_delta);
+            self.modified_entries.insert(tx);
+            for descendant in self.proposed_pool.get_descendants(id) {
+                let mut tx = self.take_modified_entry(&descendant);
+                tx.add_ancestor_fee_delta(*fee_delta);
+                self.modified_entries.insert(tx);
+            }
+        }
+    }
+
+    fn take_modified_entry(&mut self, id: &ProposalShortId) -> TxEntry {
+        self.modified_entries.remove(id).unwrap_or_else(|| {
+            self.proposed_pool
+                .get(id)
+                .map(ToOwned::to_owned)
+                .expect("pool consistent")
+        })
+    }
+
     /// update weight for all descendants of packaged txs
     fn update_modified_entries(&mut self, new_fetched_txs: &HashSet<TxEntry>) {
         for ptx in new_fetched_txs {
@@ -153,12 +191,7 @@ impl<'a> CommitTxsScanner<'a> {
             }
             let descendants = self.proposed_pool.get_descendants(&ptx_id);
             for id in descendants {
-                let mut tx = self.modified_entries.remove(&id).unwrap_or_else(|| {
-                    self.proposed_pool
-                        .get(&id)
-                        .map(ToOwned::to_owned)
-                        .expect("pool consistent")
-                });
+                let mut tx = self.take_modified_entry(&id);
                 tx.sub_entry_weight(&ptx);
                 self.modified_entries.insert(tx);
             }
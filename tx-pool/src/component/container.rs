@@ -2,7 +2,10 @@
 //! and its top-level members.
 
 use crate::{component::entry::TxEntry, error::Reject};
-use ckb_types::{core::Capacity, packed::ProposalShortId};
+use ckb_types::{
+    core::{Capacity, Cycle},
+    packed::ProposalShortId,
+};
 use std::cmp::Ordering;
 use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 
@@ -58,6 +61,58 @@ impl Ord for AncestorsScoreSortKey {
     }
 }
 
+/// A struct to use as a sorted key of descendants score, the lowest one is evicted first
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct DescendantsScoreSortKey {
+    pub fee: Capacity,
+    pub vbytes: u64,
+    pub id: ProposalShortId,
+    pub descendants_fee: Capacity,
+    pub descendants_vbytes: u64,
+}
+
+impl DescendantsScoreSortKey {
+    /// compare tx fee rate with descendants fee rate and return the max one
+    pub(crate) fn max_fee_and_vbytes(&self) -> (Capacity, u64) {
+        // avoid division a_fee/a_vbytes > b_fee/b_vbytes
+        let tx_weight = u128::from(self.fee.as_u64()) * u128::from(self.descendants_vbytes);
+        let descendants_weight =
+            u128::from(self.descendants_fee.as_u64()) * u128::from(self.vbytes);
+
+        if tx_weight > descendants_weight {
+            (self.fee, self.vbytes)
+        } else {
+            (self.descendants_fee, self.descendants_vbytes)
+        }
+    }
+}
+
+impl PartialOrd for DescendantsScoreSortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DescendantsScoreSortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // avoid division a_fee/a_vbytes > b_fee/b_vbytes
+        let (fee, vbytes) = self.max_fee_and_vbytes();
+        let (other_fee, other_vbytes) = other.max_fee_and_vbytes();
+        let self_weight = u128::from(fee.as_u64()) * u128::from(other_vbytes);
+        let other_weight = u128::from(other_fee.as_u64()) * u128::from(vbytes);
+        if self_weight == other_weight {
+            // if fee rate weight is same, the larger package goes first
+            if self.descendants_vbytes == other.descendants_vbytes {
+                self.id.raw_data().cmp(&other.id.raw_data())
+            } else {
+                other.descendants_vbytes.cmp(&self.descendants_vbytes)
+            }
+        } else {
+            self_weight.cmp(&other_weight)
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct TxLink {
     pub parents: HashSet<ProposalShortId>,
@@ -130,9 +185,14 @@ impl TxLink {
 pub(crate) struct SortedTxMap {
     entries: HashMap<ProposalShortId, TxEntry>,
     sorted_index: BTreeSet<AncestorsScoreSortKey>,
+    /// Entries sorted by descendants score, used to find the package to evict
+    descendants_index: BTreeSet<DescendantsScoreSortKey>,
     /// A map track transaction ancestors and descendants
     links: HashMap<ProposalShortId, TxLink>,
     max_ancestors_count: usize,
+    /// The total size and cycles of the entries, kept up to date on insert and remove
+    total_size: usize,
+    total_cycles: Cycle,
 }
 
 impl SortedTxMap {
@@ -140,8 +200,11 @@ impl SortedTxMap {
         SortedTxMap {
             entries: Default::default(),
             sorted_index: Default::default(),
+            descendants_index: Default::default(),
             links: Default::default(),
             max_ancestors_count,
+            total_size: 0,
+            total_cycles: 0,
         }
     }
 
@@ -149,6 +212,24 @@ impl SortedTxMap {
         self.entries.len()
     }
 
+    pub fn total_size(&self) -> usize {
+        self.total_size
+    }
+
+    pub fn total_cycles(&self) -> Cycle {
+        self.total_cycles
+    }
+
+    fn update_totals_for_insert(&mut self, entry: &TxEntry) {
+        self.total_size += entry.size;
+        self.total_cycles += entry.cycles;
+    }
+
+    fn update_totals_for_remove(&mut self, entry: &TxEntry) {
+        self.total_size = self.total_size.saturating_sub(entry.size);
+        self.total_cycles = self.total_cycles.saturating_sub(entry.cycles);
+    }
+
     /// update entry ancestor prefix fields
     fn update_ancestors_stat_for_entry(
         &self,
@@ -161,6 +242,30 @@ impl SortedTxMap {
         }
     }
 
+    /// update descendant prefix fields of the given ancestors
+    fn update_descendants_stat_for_ancestors(
+        &mut self,
+        ancestors: &HashSet<ProposalShortId>,
+        entry: &TxEntry,
+        add: bool,
+    ) {
+        for id in ancestors {
+            if let Some(ancestor) = self.entries.get_mut(id) {
+                let deleted = self
+                    .descendants_index
+                    .remove(&ancestor.as_descendants_sorted_key());
+                debug_assert!(deleted, "pending pool inconsistent");
+                if add {
+                    ancestor.add_descendant_weight(entry);
+                } else {
+                    ancestor.sub_descendant_weight(entry);
+                }
+                self.descendants_index
+                    .insert(ancestor.as_descendants_sorted_key());
+            }
+        }
+    }
+
     pub fn add_entry(&mut self, mut entry: TxEntry) -> Result<Option<TxEntry>, Reject> {
         let short_id = entry.transaction.proposal_short_id();
 
@@ -183,6 +288,8 @@ impl SortedTxMap {
         }
         // update ancestor_fields
         self.update_ancestors_stat_for_entry(&mut entry, &parents);
+        // descendants add their weight back when they are inserted after it
+        entry.reset_descendants_weight();
 
         if entry.ancestors_count > self.max_ancestors_count {
             return Err(Reject::ExceededMaximumAncestorsCount);
@@ -211,8 +318,13 @@ impl SortedTxMap {
                 children: Default::default(),
             },
         );
+        let ancestors = self.get_ancestors(&short_id);
+        self.update_descendants_stat_for_ancestors(&ancestors, &entry, true);
         self.sorted_index
             .insert(AncestorsScoreSortKey::from(&entry));
+        self.descendants_index
+            .insert(DescendantsScoreSortKey::from(&entry));
+        self.update_totals_for_insert(&entry);
         self.entries.insert(short_id, entry);
         Ok(removed_entry)
     }
@@ -225,10 +337,30 @@ impl SortedTxMap {
         self.entries.get(id)
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = (&ProposalShortId, &TxEntry)> {
+        self.entries.iter()
+    }
+
     pub fn remove_entry_and_descendants(&mut self, id: &ProposalShortId) -> Vec<TxEntry> {
+        if !self.entries.contains_key(id) {
+            return Vec::new();
+        }
+        let mut removed_ids = self.get_descendants(id);
+        removed_ids.insert(id.clone());
+        // update descendants stat of the ancestors which are not removed
+        for removed_id in &removed_ids {
+            let ancestors = self
+                .get_ancestors(removed_id)
+                .difference(&removed_ids)
+                .cloned()
+                .collect();
+            if let Some(entry) = self.entries.get(removed_id).cloned() {
+                self.update_descendants_stat_for_ancestors(&ancestors, &entry, false);
+            }
+        }
+
         let mut queue = VecDeque::new();
         let mut removed = Vec::new();
-        let tx_link = self.links.get(&id).map(ToOwned::to_owned);
         queue.push_back(id.clone());
         while let Some(id) = queue.pop_front() {
             if let Some(entry) = self.entries.remove(&id) {
@@ -236,20 +368,23 @@ impl SortedTxMap {
                     .sorted_index
                     .remove(&AncestorsScoreSortKey::from(&entry));
                 debug_assert!(deleted, "pending pool inconsistent");
+                let deleted = self
+                    .descendants_index
+                    .remove(&DescendantsScoreSortKey::from(&entry));
+                debug_assert!(deleted, "pending pool inconsistent");
                 if let Some(link) = self.links.remove(&id) {
+                    // update parents links
+                    for p_id in &link.parents {
+                        self.links
+                            .get_mut(p_id)
+                            .map(|link| link.children.remove(&id));
+                    }
                     queue.extend(link.children);
                 }
+                self.update_totals_for_remove(&entry);
                 removed.push(entry);
             }
         }
-        // update parents links
-        if let Some(link) = tx_link {
-            for p_id in link.parents {
-                self.links
-                    .get_mut(&p_id)
-                    .map(|link| link.children.remove(&id));
-            }
-        }
         removed
     }
 
@@ -259,6 +394,14 @@ impl SortedTxMap {
                 .sorted_index
                 .remove(&AncestorsScoreSortKey::from(&entry));
             debug_assert!(deleted, "pending pool inconsistent");
+            let deleted = self
+                .descendants_index
+                .remove(&DescendantsScoreSortKey::from(&entry));
+            debug_assert!(deleted, "pending pool inconsistent");
+            self.update_totals_for_remove(&entry);
+            // update ancestors entries
+            let ancestors = self.get_ancestors(&id);
+            self.update_descendants_stat_for_ancestors(&ancestors, &entry, false);
             // update descendants entries
             for desc_id in self.get_descendants(&id) {
                 if let Some(key) = self
@@ -312,6 +455,13 @@ impl SortedTxMap {
         self.sorted_index.iter().rev()
     }
 
+    /// return keys sorted by descendants score in ascending order
+    pub fn keys_sorted_by_descendants_score(
+        &self,
+    ) -> impl Iterator<Item = &DescendantsScoreSortKey> {
+        self.descendants_index.iter()
+    }
+
     /// return keys sorted by tx fee rate and transaction relation
     pub fn keys_sorted_by_fee_and_relation(&self) -> Vec<&AncestorsScoreSortKey> {
         let mut keys: Vec<_> = self.keys_sorted_by_fee().collect();
@@ -521,14 +671,98 @@ mod tests {
         map.add_entry(tx1).unwrap();
         map.add_entry(tx2).unwrap();
         map.add_entry(tx3).unwrap();
+        assert_eq!((map.total_size(), map.total_cycles()), (500, 500));
         let descendants_map = map.get_descendants(&tx1_id);
         assert!(descendants_map.contains(&tx2_id));
         assert!(descendants_map.contains(&tx3_id));
         map.remove_entry_and_descendants(&tx2_id);
         assert!(!map.contains_key(&tx2_id));
         assert!(!map.contains_key(&tx3_id));
+        assert_eq!((map.total_size(), map.total_cycles()), (100, 100));
         let descendants_map = map.get_descendants(&tx1_id);
         assert!(!descendants_map.contains(&tx2_id));
         assert!(!descendants_map.contains(&tx3_id));
+        map.remove_entry(&tx1_id);
+        assert_eq!((map.total_size(), map.total_cycles()), (0, 0));
+    }
+
+    #[test]
+    fn test_descendants_stat() {
+        let mut map = SortedTxMap::new(DEFAULT_MAX_ANCESTORS_SIZE);
+        let tx1 = TxEntry::new(
+            TransactionBuilder::default().build(),
+            100,
+            Capacity::shannons(100),
+            100,
+            Default::default(),
+        );
+        let tx2 = TxEntry::new(
+            TransactionBuilder::default()
+                .input(
+                    CellInput::new_builder()
+                        .previous_output(
+                            OutPoint::new_builder()
+                                .tx_hash(tx1.transaction.hash())
+                                .index(0u32.pack())
+                                .build(),
+                        )
+                        .build(),
+                )
+                .witness(Bytes::new().pack())
+                .build(),
+            200,
+            Capacity::shannons(200),
+            200,
+            Default::default(),
+        );
+        let tx3 = TxEntry::new(
+            TransactionBuilder::default()
+                .input(
+                    CellInput::new_builder()
+                        .previous_output(
+                            OutPoint::new_builder()
+                                .tx_hash(tx2.transaction.hash())
+                                .index(0u32.pack())
+                                .build(),
+                        )
+                        .build(),
+                )
+                .witness(Bytes::new().pack())
+                .build(),
+            300,
+            Capacity::shannons(300),
+            300,
+            Default::default(),
+        );
+        let tx1_id = tx1.transaction.proposal_short_id();
+        let tx2_id = tx2.transaction.proposal_short_id();
+        let tx3_id = tx3.transaction.proposal_short_id();
+        map.add_entry(tx1).unwrap();
+        map.add_entry(tx2).unwrap();
+        map.add_entry(tx3).unwrap();
+
+        let entry1 = map.get(&tx1_id).unwrap();
+        assert_eq!(entry1.descendants_count, 3);
+        assert_eq!(entry1.descendants_size, 600);
+        assert_eq!(entry1.descendants_fee, Capacity::shannons(600));
+        let entry2 = map.get(&tx2_id).unwrap();
+        assert_eq!(entry2.descendants_count, 2);
+        assert_eq!(entry2.descendants_fee, Capacity::shannons(500));
+
+        // all txs pay the same fee rate, the largest package goes first
+        let lowest = map.keys_sorted_by_descendants_score().next().unwrap();
+        assert_eq!(lowest.id, tx1_id);
+
+        map.remove_entry(&tx3_id);
+        let entry1 = map.get(&tx1_id).unwrap();
+        assert_eq!(entry1.descendants_count, 2);
+        assert_eq!(entry1.descendants_fee, Capacity::shannons(300));
+
+        map.remove_entry_and_descendants(&tx2_id);
+        let entry1 = map.get(&tx1_id).unwrap();
+        assert_eq!(entry1.descendants_count, 1);
+        assert_eq!(entry1.descendants_size, 100);
+        assert_eq!(entry1.descendants_fee, Capacity::shannons(100));
+        assert_eq!(map.keys_sorted_by_descendants_score().count(), 1);
     }
 }
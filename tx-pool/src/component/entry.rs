@@ -1,4 +1,4 @@
-use crate::component::container::AncestorsScoreSortKey;
+use crate::component::container::{AncestorsScoreSortKey, DescendantsScoreSortKey};
 use crate::component::get_transaction_virtual_bytes;
 use ckb_types::{
     core::{Capacity, Cycle, TransactionView},
@@ -61,8 +61,18 @@ pub struct TxEntry {
     pub ancestors_cycles: Cycle,
     /// ancestors txs count
     pub ancestors_count: usize,
+    /// descendants txs size
+    pub descendants_size: usize,
+    /// descendants txs fee
+    pub descendants_fee: Capacity,
+    /// descendants txs cycles
+    pub descendants_cycles: Cycle,
+    /// descendants txs count
+    pub descendants_count: usize,
     /// related out points (cell deps includes cell group itself)
     pub related_out_points: Vec<OutPoint>,
+    /// the time when the tx entered the pool, in milliseconds
+    pub timestamp: u64,
 }
 
 impl TxEntry {
@@ -83,7 +93,12 @@ impl TxEntry {
             ancestors_fee: fee,
             ancestors_cycles: cycles,
             ancestors_count: 1,
+            descendants_size: size,
+            descendants_fee: fee,
+            descendants_cycles: cycles,
+            descendants_count: 1,
             related_out_points,
+            timestamp: faketime::unix_time_as_millis(),
         }
     }
 
@@ -91,6 +106,10 @@ impl TxEntry {
         AncestorsScoreSortKey::from(self)
     }
 
+    pub fn as_descendants_sorted_key(&self) -> DescendantsScoreSortKey {
+        DescendantsScoreSortKey::from(self)
+    }
+
     pub fn add_entry_weight(&mut self, entry: &TxEntry) {
         self.ancestors_count = self.ancestors_count.saturating_add(1);
         self.ancestors_size = self.ancestors_size.saturating_add(entry.size);
@@ -132,6 +151,48 @@ impl TxEntry {
                 .saturating_sub(entry.ancestors_fee.as_u64()),
         );
     }
+
+    pub fn add_descendant_weight(&mut self, entry: &TxEntry) {
+        self.descendants_count = self.descendants_count.saturating_add(1);
+        self.descendants_size = self.descendants_size.saturating_add(entry.size);
+        self.descendants_cycles = self.descendants_cycles.saturating_add(entry.cycles);
+        self.descendants_fee = Capacity::shannons(
+            self.descendants_fee
+                .as_u64()
+                .saturating_add(entry.fee.as_u64()),
+        );
+    }
+    pub fn sub_descendant_weight(&mut self, entry: &TxEntry) {
+        self.descendants_count = self.descendants_count.saturating_sub(1);
+        self.descendants_size = self.descendants_size.saturating_sub(entry.size);
+        self.descendants_cycles = self.descendants_cycles.saturating_sub(entry.cycles);
+        self.descendants_fee = Capacity::shannons(
+            self.descendants_fee
+                .as_u64()
+                .saturating_sub(entry.fee.as_u64()),
+        );
+    }
+
+    /// Raise the fee by the delta set by `prioritise_transaction`
+    pub fn add_fee_delta(&mut self, fee_delta: Capacity) {
+        self.fee = Capacity::shannons(self.fee.as_u64().saturating_add(fee_delta.as_u64()));
+        self.add_ancestor_fee_delta(fee_delta);
+    }
+    /// Raise the ancestors fee by the delta set on one of the ancestors
+    pub fn add_ancestor_fee_delta(&mut self, fee_delta: Capacity) {
+        self.ancestors_fee = Capacity::shannons(
+            self.ancestors_fee
+                .as_u64()
+                .saturating_add(fee_delta.as_u64()),
+        );
+    }
+
+    pub fn reset_descendants_weight(&mut self) {
+        self.descendants_count = 1;
+        self.descendants_size = self.size;
+        self.descendants_cycles = self.cycles;
+        self.descendants_fee = self.fee;
+    }
 }
 
 impl From<&TxEntry> for AncestorsScoreSortKey {
@@ -150,6 +211,21 @@ impl From<&TxEntry> for AncestorsScoreSortKey {
     }
 }
 
+impl From<&TxEntry> for DescendantsScoreSortKey {
+    fn from(entry: &TxEntry) -> Self {
+        let vbytes = get_transaction_virtual_bytes(entry.size, entry.cycles);
+        let descendants_vbytes =
+            get_transaction_virtual_bytes(entry.descendants_size, entry.descendants_cycles);
+        DescendantsScoreSortKey {
+            fee: entry.fee,
+            vbytes,
+            id: entry.transaction.proposal_short_id(),
+            descendants_fee: entry.descendants_fee,
+            descendants_vbytes,
+        }
+    }
+}
+
 impl Hash for TxEntry {
     fn hash<H: Hasher>(&self, state: &mut H) {
         Hash::hash(&self.transaction, state);
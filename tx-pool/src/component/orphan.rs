@@ -1,22 +1,28 @@
 use crate::component::entry::DefectEntry;
+use crate::PeerIndex;
 use ckb_types::{
     core::TransactionView,
     packed::{OutPoint, ProposalShortId},
 };
 use ckb_verification::cache::CacheEntry;
 use std::collections::VecDeque;
-use std::collections::{hash_map, HashMap};
+use std::collections::{hash_map, HashMap, HashSet};
 use std::iter::ExactSizeIterator;
 
 pub(crate) const TTL: u64 = 4 * 60 * 60;
 pub(crate) const PRUNE_THRESHOLD: usize = 1500;
+pub(crate) const MAX_ORPHANS_PER_PEER: usize = 100;
 
 ///not verified, may contain conflict transactions
 #[derive(Default, Debug, Clone)]
 pub(crate) struct OrphanPool {
     pub(crate) vertices: HashMap<ProposalShortId, DefectEntry>,
     pub(crate) edges: HashMap<OutPoint, Vec<ProposalShortId>>,
+    // the peer which relayed the orphan, orphans from reorg have no source peer
+    pub(crate) sources: HashMap<ProposalShortId, PeerIndex>,
+    pub(crate) peers: HashMap<PeerIndex, HashSet<ProposalShortId>>,
     pub(crate) prune_threshold: usize,
+    pub(crate) max_orphans_per_peer: usize,
 }
 
 impl OrphanPool {
@@ -27,6 +33,7 @@ impl OrphanPool {
     pub(crate) fn raw_new(prune_threshold: usize) -> Self {
         OrphanPool {
             prune_threshold,
+            max_orphans_per_peer: MAX_ORPHANS_PER_PEER,
             ..Default::default()
         }
     }
@@ -52,6 +59,14 @@ impl OrphanPool {
         self.vertices.contains_key(id)
     }
 
+    pub(crate) fn get_source(&self, id: &ProposalShortId) -> Option<PeerIndex> {
+        self.sources.get(id).cloned()
+    }
+
+    pub(crate) fn peer_size(&self, peer: PeerIndex) -> usize {
+        self.peers.get(&peer).map(HashSet::len).unwrap_or(0)
+    }
+
     /// add orphan transaction
     pub(crate) fn add_tx(
         &mut self,
@@ -70,6 +85,69 @@ impl OrphanPool {
         self.vertices.insert(short_id, entry)
     }
 
+    /// add orphan transaction relayed by `peer`,
+    /// returns the orphans evicted to keep the peer within its quota
+    pub(crate) fn add_peer_tx(
+        &mut self,
+        peer: PeerIndex,
+        cache_entry: Option<CacheEntry>,
+        size: usize,
+        tx: TransactionView,
+        unknown: impl ExactSizeIterator<Item = OutPoint>,
+    ) -> Vec<DefectEntry> {
+        let short_id = tx.proposal_short_id();
+        let mut evicted = Vec::new();
+        if !self.contains_key(&short_id) {
+            while self.peer_size(peer) >= self.max_orphans_per_peer {
+                let oldest = self.peers.get(&peer).and_then(|ids| {
+                    ids.iter()
+                        .min_by_key(|id| self.vertices.get(id).map(|entry| entry.timestamp))
+                        .cloned()
+                });
+                match oldest {
+                    Some(id) => evicted.extend(self.recursion_remove(&id)),
+                    None => break,
+                }
+            }
+        }
+        if let Some(replaced) = self.add_tx(cache_entry, size, tx, unknown) {
+            evicted.push(replaced);
+        }
+        self.untag(&short_id);
+        self.sources.insert(short_id.clone(), peer);
+        self.peers
+            .entry(peer)
+            .or_insert_with(HashSet::new)
+            .insert(short_id);
+        evicted
+    }
+
+    /// remove all the orphans relayed by `peer` and their descendants
+    pub(crate) fn remove_by_peer(&mut self, peer: PeerIndex) -> Vec<DefectEntry> {
+        let ids = self.peers.remove(&peer).unwrap_or_default();
+        let mut removed = Vec::new();
+        for id in ids {
+            removed.extend(self.recursion_remove(&id));
+        }
+        removed
+    }
+
+    fn untag(&mut self, id: &ProposalShortId) {
+        if let Some(peer) = self.sources.remove(id) {
+            if let hash_map::Entry::Occupied(mut ids) = self.peers.entry(peer) {
+                ids.get_mut().remove(id);
+                if ids.get().is_empty() {
+                    ids.remove();
+                }
+            }
+        }
+    }
+
+    fn remove_vertex(&mut self, id: &ProposalShortId) -> Option<DefectEntry> {
+        self.untag(id);
+        self.vertices.remove(id)
+    }
+
     fn prune(&mut self) {
         if self.size() < self.prune_threshold {
             return;
@@ -94,21 +172,36 @@ impl OrphanPool {
         }
     }
 
-    pub(crate) fn recursion_remove(&mut self, id: &ProposalShortId) {
+    pub(crate) fn recursion_remove(&mut self, id: &ProposalShortId) -> Vec<DefectEntry> {
+        let mut removed = Vec::new();
         let mut queue: VecDeque<ProposalShortId> = VecDeque::new();
         queue.push_back(id.clone());
         while let Some(id) = queue.pop_front() {
-            if let Some(entry) = self.vertices.remove(&id) {
+            if let Some(entry) = self.remove_vertex(&id) {
                 for outpoint in entry.transaction.output_pts() {
                     if let Some(ids) = self.edges.remove(&outpoint) {
                         queue.extend(ids);
                     }
                 }
+                removed.push(entry);
             }
         }
+        removed
     }
 
+    #[cfg(test)]
     pub(crate) fn remove_by_ancestor(&mut self, tx: &TransactionView) -> Vec<DefectEntry> {
+        self.remove_by_ancestor_with_sources(tx)
+            .into_iter()
+            .map(|(entry, _)| entry)
+            .collect()
+    }
+
+    /// Same as `remove_by_ancestor`, along with the peers which relayed the orphans
+    pub(crate) fn remove_by_ancestor_with_sources(
+        &mut self,
+        tx: &TransactionView,
+    ) -> Vec<(DefectEntry, Option<PeerIndex>)> {
         let mut txs = Vec::new();
         let mut queue = VecDeque::new();
 
@@ -128,8 +221,10 @@ impl OrphanPool {
 
                             if refs_count == 0 {
                                 let tx = o.remove();
+                                let source = self.get_source(&tx.transaction.proposal_short_id());
+                                self.untag(&tx.transaction.proposal_short_id());
                                 queue.push_back(tx.transaction.output_pts());
-                                txs.push(tx);
+                                txs.push((tx, source));
                             }
                         }
                     }
@@ -155,6 +250,7 @@ impl OrphanPool {
 #[cfg(test)]
 mod tests {
     use super::{OrphanPool, TTL};
+    use crate::PeerIndex;
     use ckb_types::{
         bytes::Bytes,
         core::{Capacity, TransactionBuilder, TransactionView},
@@ -322,4 +418,110 @@ mod tests {
         assert!(!pool.contains(&tx2));
         assert!(pool.contains(&tx3));
     }
+
+    #[test]
+    fn test_orphan_per_peer_limit() {
+        let faketime_file = faketime::millis_tempfile(0).expect("create faketime file");
+        faketime::enable(&faketime_file);
+
+        let mut pool = OrphanPool::new();
+        pool.max_orphans_per_peer = 2;
+        let peer1: PeerIndex = 1;
+        let peer2: PeerIndex = 2;
+
+        let tx1 = build_tx(vec![(&Byte32::zero(), 0)], 1);
+        let tx2 = build_tx(vec![(&Byte32::zero(), 1)], 1);
+        let tx3 = build_tx(vec![(&Byte32::zero(), 2)], 1);
+        let tx4 = build_tx(vec![(&Byte32::zero(), 3)], 1);
+
+        let unknown = |tx: &TransactionView| {
+            tx.inputs()
+                .into_iter()
+                .map(|input| input.previous_output())
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+
+        assert!(pool
+            .add_peer_tx(peer1, None, MOCK_SIZE, tx1.clone(), unknown(&tx1))
+            .is_empty());
+        faketime::write_millis(&faketime_file, 1000).expect("write millis");
+        assert!(pool
+            .add_peer_tx(peer1, None, MOCK_SIZE, tx2.clone(), unknown(&tx2))
+            .is_empty());
+        assert!(pool
+            .add_peer_tx(peer2, None, MOCK_SIZE, tx3.clone(), unknown(&tx3))
+            .is_empty());
+
+        // peer1 exceeds its quota, the oldest orphan of peer1 is evicted
+        faketime::write_millis(&faketime_file, 2000).expect("write millis");
+        let evicted: Vec<_> = pool
+            .add_peer_tx(peer1, None, MOCK_SIZE, tx4.clone(), unknown(&tx4))
+            .into_iter()
+            .map(|e| e.transaction)
+            .collect();
+        assert_eq!(evicted, vec![tx1.clone()]);
+        assert!(!pool.contains(&tx1));
+        assert!(pool.contains(&tx2));
+        assert!(pool.contains(&tx3));
+        assert!(pool.contains(&tx4));
+        assert_eq!(pool.peer_size(peer1), 2);
+        assert_eq!(pool.peer_size(peer2), 1);
+        assert_eq!(pool.get_source(&tx4.proposal_short_id()), Some(peer1));
+    }
+
+    #[test]
+    fn test_orphan_remove_by_peer() {
+        let mut pool = OrphanPool::new();
+        let peer1: PeerIndex = 1;
+        let peer2: PeerIndex = 2;
+
+        let tx1 = build_tx(vec![(&Byte32::zero(), 0)], 1);
+        let tx1_hash = tx1.hash();
+
+        let tx2 = build_tx(vec![(&tx1_hash, 0)], 1);
+        let tx2_hash = tx2.hash();
+
+        // tx3 is relayed by peer2, but depends on the orphan tx2 relayed by peer1
+        let tx3 = build_tx(vec![(&tx2_hash, 0)], 1);
+        let tx4 = build_tx(vec![(&Byte32::zero(), 1)], 1);
+
+        pool.add_peer_tx(
+            peer1,
+            None,
+            MOCK_SIZE,
+            tx2.clone(),
+            tx1.output_pts().into_iter(),
+        );
+        pool.add_peer_tx(
+            peer2,
+            None,
+            MOCK_SIZE,
+            tx3.clone(),
+            tx2.output_pts().into_iter(),
+        );
+        pool.add_peer_tx(
+            peer2,
+            None,
+            MOCK_SIZE,
+            tx4.clone(),
+            tx1.inputs()
+                .into_iter()
+                .map(|x| x.previous_output())
+                .collect::<Vec<_>>()
+                .into_iter(),
+        );
+
+        let removed: Vec<_> = pool
+            .remove_by_peer(peer1)
+            .into_iter()
+            .map(|e| e.transaction)
+            .collect();
+        assert_eq!(removed, vec![tx2.clone(), tx3.clone()]);
+        assert!(!pool.contains(&tx2));
+        assert!(!pool.contains(&tx3));
+        assert!(pool.contains(&tx4));
+        assert_eq!(pool.peer_size(peer1), 0);
+        assert_eq!(pool.peer_size(peer2), 1);
+    }
 }
@@ -1,16 +1,16 @@
-use crate::component::container::{AncestorsScoreSortKey, SortedTxMap};
+use crate::component::container::{AncestorsScoreSortKey, DescendantsScoreSortKey, SortedTxMap};
 use crate::component::entry::TxEntry;
 use crate::error::Reject;
 use ckb_fee_estimator::FeeRate;
 use ckb_types::{
     core::{
         cell::{CellMetaBuilder, CellProvider, CellStatus},
-        TransactionView,
+        Capacity, Cycle, TransactionView,
     },
     packed::{OutPoint, ProposalShortId},
     prelude::*,
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone)]
 pub(crate) struct PendingQueue {
@@ -28,6 +28,14 @@ impl PendingQueue {
         self.inner.size()
     }
 
+    pub(crate) fn total_size(&self) -> usize {
+        self.inner.total_size()
+    }
+
+    pub(crate) fn total_cycles(&self) -> Cycle {
+        self.inner.total_cycles()
+    }
+
     pub(crate) fn add_entry(&mut self, entry: TxEntry) -> Result<Option<TxEntry>, Reject> {
         self.inner.add_entry(entry)
     }
@@ -44,6 +52,10 @@ impl PendingQueue {
         self.inner.get(id).map(|x| &x.transaction)
     }
 
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&ProposalShortId, &TxEntry)> {
+        self.inner.iter()
+    }
+
     pub(crate) fn remove_entry_and_descendants(&mut self, id: &ProposalShortId) -> Vec<TxEntry> {
         self.inner.remove_entry_and_descendants(id)
     }
@@ -61,19 +73,110 @@ impl PendingQueue {
         self.inner.keys_sorted_by_fee()
     }
 
+    pub(crate) fn keys_sorted_by_descendants_score(
+        &self,
+    ) -> impl Iterator<Item = &DescendantsScoreSortKey> {
+        self.inner.keys_sorted_by_descendants_score()
+    }
+
+    /// return keys sorted by descendants score, with the fee deltas applied to the prioritised
+    /// txs and their ancestors
+    pub(crate) fn keys_sorted_by_modified_descendants_score(
+        &self,
+        fee_deltas: &HashMap<ProposalShortId, Capacity>,
+    ) -> Vec<DescendantsScoreSortKey> {
+        let add = |fee: Capacity, fee_delta: Capacity| {
+            Capacity::shannons(fee.as_u64().saturating_add(fee_delta.as_u64()))
+        };
+        let mut modified: HashMap<ProposalShortId, DescendantsScoreSortKey> = HashMap::new();
+        for (id, fee_delta) in fee_deltas {
+            if let Some(entry) = self.get(id) {
+                let key = modified
+                    .entry(id.clone())
+                    .or_insert_with(|| entry.as_descendants_sorted_key());
+                key.fee = add(key.fee, *fee_delta);
+                key.descendants_fee = add(key.descendants_fee, *fee_delta);
+                for ancestor in self.get_ancestors(id) {
+                    let entry = self.get(&ancestor).expect("pool consistent");
+                    let key = modified
+                        .entry(ancestor)
+                        .or_insert_with(|| entry.as_descendants_sorted_key());
+                    key.descendants_fee = add(key.descendants_fee, *fee_delta);
+                }
+            }
+        }
+        let mut keys: Vec<_> = self
+            .keys_sorted_by_descendants_score()
+            .filter(|key| !modified.contains_key(&key.id))
+            .cloned()
+            .chain(modified.into_iter().map(|(_, key)| key))
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    /// find all descendants from pool
+    pub(crate) fn get_descendants(
+        &self,
+        tx_short_id: &ProposalShortId,
+    ) -> HashSet<ProposalShortId> {
+        self.inner.get_descendants(tx_short_id)
+    }
+
     pub(crate) fn keys_sorted_by_fee_and_relation(&self) -> Vec<&AncestorsScoreSortKey> {
         self.inner.keys_sorted_by_fee_and_relation()
     }
 
+    /// return keys sorted by tx fee rate, with the fee deltas applied to the prioritised txs
+    /// and their descendants, or None if there is no prioritised tx in the queue
+    fn keys_sorted_by_modified_fee(
+        &self,
+        fee_deltas: &HashMap<ProposalShortId, Capacity>,
+    ) -> Option<Vec<AncestorsScoreSortKey>> {
+        let mut modified: HashMap<ProposalShortId, TxEntry> = HashMap::new();
+        for (id, fee_delta) in fee_deltas {
+            if let Some(entry) = self.get(id) {
+                modified
+                    .entry(id.clone())
+                    .or_insert_with(|| entry.clone())
+                    .add_fee_delta(*fee_delta);
+                for descendant in self.get_descendants(id) {
+                    let entry = self.get(&descendant).expect("pool consistent");
+                    modified
+                        .entry(descendant)
+                        .or_insert_with(|| entry.clone())
+                        .add_ancestor_fee_delta(*fee_delta);
+                }
+            }
+        }
+        if modified.is_empty() {
+            return None;
+        }
+        let mut keys: Vec<_> = self
+            .keys_sorted_by_fee()
+            .filter(|key| !modified.contains_key(&key.id))
+            .cloned()
+            .chain(modified.values().map(TxEntry::as_sorted_key))
+            .collect();
+        keys.sort_unstable_by(|a, b| b.cmp(a));
+        Some(keys)
+    }
+
     // fill proposal txs
     pub fn fill_proposals(
         &self,
         limit: usize,
         min_fee_rate: FeeRate,
+        fee_deltas: &HashMap<ProposalShortId, Capacity>,
         exclusion: &HashSet<ProposalShortId>,
         proposals: &mut HashSet<ProposalShortId>,
     ) {
-        for key in self.keys_sorted_by_fee() {
+        let modified_keys = self.keys_sorted_by_modified_fee(fee_deltas);
+        let keys: Box<dyn Iterator<Item = &AncestorsScoreSortKey>> = match modified_keys {
+            Some(ref keys) => Box::new(keys.iter()),
+            None => Box::new(self.keys_sorted_by_fee()),
+        };
+        for key in keys {
             if proposals.len() == limit {
                 break;
             } else if proposals.contains(&key.id)
@@ -350,4 +453,106 @@ mod tests {
         ];
         assert_eq!(keys_sorted_by_fee_and_relation, expect_result);
     }
+
+    #[test]
+    fn test_sorted_by_descendants_score() {
+        let tx1 = build_tx(vec![(&Byte32::zero(), 1)], 1);
+        let tx1_hash = tx1.hash();
+        let tx2 = build_tx(vec![(&tx1_hash, 0)], 1);
+        let tx3 = build_tx(vec![(&Byte32::zero(), 2)], 1);
+
+        let mut pool = PendingQueue::new(DEFAULT_MAX_ANCESTORS_SIZE);
+
+        // tx1 pays a low fee, but its child tx2 pays for it
+        for &(tx, fee) in &[(&tx1, 100), (&tx2, 500), (&tx3, 200)] {
+            pool.add_entry(TxEntry::new(
+                tx.clone(),
+                MOCK_CYCLES,
+                Capacity::shannons(fee),
+                MOCK_SIZE,
+                vec![],
+            ))
+            .unwrap();
+        }
+
+        let keys_sorted_by_descendants_score = pool
+            .keys_sorted_by_descendants_score()
+            .map(|key| key.id.clone())
+            .collect::<Vec<_>>();
+        let expect_result = vec![
+            tx3.proposal_short_id(),
+            tx1.proposal_short_id(),
+            tx2.proposal_short_id(),
+        ];
+        assert_eq!(keys_sorted_by_descendants_score, expect_result);
+
+        // evicting tx1 evicts its descendants as well
+        let evicted = pool.remove_entry_and_descendants(&tx1.proposal_short_id());
+        assert_eq!(evicted.len(), 2);
+        let keys_sorted_by_descendants_score = pool
+            .keys_sorted_by_descendants_score()
+            .map(|key| key.id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys_sorted_by_descendants_score,
+            vec![tx3.proposal_short_id()]
+        );
+    }
+
+    #[test]
+    fn test_fill_proposals_with_fee_deltas() {
+        let tx1 = build_tx(vec![(&Byte32::zero(), 1)], 1);
+        let tx2 = build_tx(vec![(&Byte32::zero(), 2)], 1);
+        let tx3 = build_tx(vec![(&tx1.hash(), 0)], 1);
+
+        let mut pool = PendingQueue::new(DEFAULT_MAX_ANCESTORS_SIZE);
+        for &(tx, fee) in &[(&tx1, 100), (&tx2, 300), (&tx3, 200)] {
+            pool.add_entry(TxEntry::new(
+                tx.clone(),
+                MOCK_CYCLES,
+                Capacity::shannons(fee),
+                MOCK_SIZE,
+                vec![],
+            ))
+            .unwrap();
+        }
+
+        let fill = |fee_deltas: &HashMap<ProposalShortId, Capacity>, limit: usize| {
+            let mut proposals = HashSet::new();
+            pool.fill_proposals(
+                limit,
+                FeeRate::zero(),
+                fee_deltas,
+                &HashSet::new(),
+                &mut proposals,
+            );
+            proposals
+        };
+
+        let mut fee_deltas = HashMap::new();
+        let expect_result: HashSet<_> = vec![tx2.proposal_short_id()].into_iter().collect();
+        assert_eq!(fill(&fee_deltas, 1), expect_result);
+
+        // the prioritised tx1 goes first regardless of its fee
+        fee_deltas.insert(tx1.proposal_short_id(), Capacity::shannons(1000));
+        let expect_result: HashSet<_> = vec![tx1.proposal_short_id()].into_iter().collect();
+        assert_eq!(fill(&fee_deltas, 1), expect_result);
+
+        // the delta of tx1 is counted in the ancestors fee of tx3
+        let keys = pool.keys_sorted_by_modified_fee(&fee_deltas).unwrap();
+        let tx3_key = keys
+            .iter()
+            .find(|key| key.id == tx3.proposal_short_id())
+            .unwrap();
+        assert_eq!(tx3_key.fee, Capacity::shannons(200));
+        assert_eq!(tx3_key.ancestors_fee, Capacity::shannons(1300));
+
+        // fee deltas of the txs not in the queue are ignored
+        let mut fee_deltas = HashMap::new();
+        fee_deltas.insert(
+            build_tx(vec![(&Byte32::zero(), 3)], 1).proposal_short_id(),
+            Capacity::shannons(1000),
+        );
+        assert!(pool.keys_sorted_by_modified_fee(&fee_deltas).is_none());
+    }
 }
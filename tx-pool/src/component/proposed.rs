@@ -5,7 +5,7 @@ use ckb_types::{
     bytes::Bytes,
     core::{
         cell::{CellMetaBuilder, CellProvider, CellStatus},
-        TransactionView,
+        Cycle, TransactionView,
     },
     packed::{CellOutput, OutPoint, ProposalShortId},
     prelude::*,
@@ -134,6 +134,18 @@ impl ProposedPool {
         self.inner.size()
     }
 
+    pub(crate) fn total_size(&self) -> usize {
+        self.inner.total_size()
+    }
+
+    pub(crate) fn total_cycles(&self) -> Cycle {
+        self.inner.total_cycles()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&ProposalShortId, &TxEntry)> {
+        self.inner.iter()
+    }
+
     pub(crate) fn get_output_with_data(&self, out_point: &OutPoint) -> Option<(CellOutput, Bytes)> {
         self.inner
             .get(&ProposalShortId::from_tx_hash(&out_point.tx_hash()))
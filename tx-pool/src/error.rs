@@ -1,5 +1,8 @@
 use ckb_error::{Error, ErrorKind};
-use ckb_types::packed::Byte32;
+use ckb_types::{
+    core::error::OutPointError,
+    packed::{Byte32, OutPoint},
+};
 use failure::Fail;
 use tokio::sync::mpsc::error::TrySendError as TokioTrySendError;
 
@@ -26,6 +29,23 @@ pub enum Reject {
 
     #[fail(display = "Malformed {} transaction", _0)]
     Malformed(String),
+
+    #[fail(display = "Invalid transaction package: {}", _0)]
+    InvalidPackage(String),
+}
+
+impl Reject {
+    /// The variant name, used as the label of the reject metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Reject::LowFeeRate(..) => "LowFeeRate",
+            Reject::ExceededMaximumAncestorsCount => "ExceededMaximumAncestorsCount",
+            Reject::Full(..) => "Full",
+            Reject::Duplicated(..) => "Duplicated",
+            Reject::Malformed(..) => "Malformed",
+            Reject::InvalidPackage(..) => "InvalidPackage",
+        }
+    }
 }
 
 impl From<Reject> for Error {
@@ -34,6 +54,17 @@ impl From<Reject> for Error {
     }
 }
 
+/// The unknown cells spent by the rejected tx, the tx is kept as an orphan if it's relayed
+pub fn missing_inputs(err: &Error) -> Option<&[OutPoint]> {
+    match err.kind() {
+        ErrorKind::OutPoint => match err.downcast_ref::<OutPointError>() {
+            Some(OutPointError::Unknown(out_points)) => Some(out_points),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Eq, Fail)]
 pub enum BlockAssemblerError {
     #[fail(display = "InvalidInput")]
@@ -6,6 +6,9 @@ mod process;
 pub mod service;
 
 pub use component::entry::TxEntry;
-pub use process::PlugTarget;
+pub use process::{PlugTarget, MAX_PACKAGE_TXS};
 pub use service::{TxPoolController, TxPoolServiceBuilder};
 pub use tokio::sync::RwLock as TokioRwLock;
+
+/// The index of the peer which relayed the txs, it's the value of the network `PeerIndex`
+pub type PeerIndex = usize;
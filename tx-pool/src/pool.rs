@@ -1,13 +1,19 @@
 //! Top-level Pool type, methods, and tests
 use super::component::{DefectEntry, TxEntry};
+use crate::component::container::DescendantsScoreSortKey;
+use crate::component::get_transaction_virtual_bytes;
 use crate::component::orphan::OrphanPool;
 use crate::component::pending::PendingQueue;
 use crate::component::proposed::ProposedPool;
 use crate::error::Reject;
+use crate::process::TxStatus;
+use crate::PeerIndex;
 use ckb_app_config::TxPoolConfig;
 use ckb_dao::DaoCalculator;
 use ckb_error::{Error, ErrorKind};
+use ckb_fee_estimator::FeeRate;
 use ckb_logger::{debug, error, trace};
+use ckb_metrics::metrics;
 use ckb_snapshot::Snapshot;
 use ckb_store::ChainStore;
 use ckb_types::core::BlockNumber;
@@ -23,13 +29,62 @@ use ckb_verification::cache::CacheEntry;
 use ckb_verification::{TimeRelativeTransactionVerifier, TransactionVerifier};
 use faketime::unix_time_as_millis;
 use lru_cache::LruCache;
+use std::cmp;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt;
+use std::slice;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
     Arc,
 };
 
+/// The min fee rate raised by eviction halves every 12 hours
+const ROLLING_MIN_FEE_RATE_HALF_LIFE: u64 = 12 * 60 * 60 * 1000;
+/// Local transactions are rebroadcast every 10 minutes until they are committed or expired
+const LOCAL_TX_REBROADCAST_INTERVAL: u64 = 10 * 60 * 1000;
+/// The number of recently removed transactions whose reasons are kept
+const REMOVED_TXS_CACHE_SIZE: usize = 10_000;
+
+/// Why a transaction is removed from the pool before it is committed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemovedReason {
+    /// Stayed in pending or gap longer than `expiry_hours`
+    Expired,
+    /// Evicted to make room for the transactions paying more
+    Evicted,
+}
+
+impl RemovedReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RemovedReason::Expired => "expired",
+            RemovedReason::Evicted => "evicted",
+        }
+    }
+}
+
+impl fmt::Display for RemovedReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Where a transaction is in the pool, or why it was removed from the pool
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TxPoolStatus {
+    /// In pending, with the time it was received
+    Pending(u64),
+    /// In gap or proposed, with the time it was received
+    Proposed(u64),
+    /// In orphan, waiting for its parents
+    Orphan,
+    /// Removed before it was committed
+    Removed(RemovedReason),
+    /// Rejected when it was submitted, with the reject reason
+    Rejected(String),
+}
+
 #[derive(Clone)]
 pub struct TxPool {
     pub(crate) config: TxPoolConfig,
@@ -45,12 +100,28 @@ pub struct TxPool {
     pub(crate) conflict: LruCache<ProposalShortId, DefectEntry>,
     /// cache for committed transactions hash
     pub(crate) committed_txs_hash_cache: LruCache<ProposalShortId, Byte32>,
+    /// reasons of the recently removed transactions
+    pub(crate) removed_txs: LruCache<Byte32, RemovedReason>,
+    /// locally submitted transactions and the time to rebroadcast them
+    pub(crate) local_txs: HashMap<ProposalShortId, u64>,
+    /// fee deltas set by `prioritise_transaction`, kept until they are cleared explicitly
+    pub(crate) fee_deltas: HashMap<ProposalShortId, Capacity>,
+    /// relayed orphans which have been accepted since their parents arrived,
+    /// along with the peers which relayed them
+    pub(crate) accepted_orphans: Vec<(Byte32, PeerIndex)>,
+    /// relayed orphans whose parents have arrived, they are submitted again to be checked
+    /// against the pool policy
+    pub(crate) orphans_to_resubmit: Vec<(TransactionView, PeerIndex)>,
     /// last txs updated timestamp, used by getblocktemplate
     pub(crate) last_txs_updated_at: Arc<AtomicU64>,
     // sum of all tx_pool tx's virtual sizes.
     pub(crate) total_tx_size: usize,
     // sum of all tx_pool tx's cycles.
     pub(crate) total_tx_cycles: Cycle,
+    // min fee rate raised by eviction, it decays over time.
+    pub(crate) rolling_min_fee_rate: FeeRate,
+    // timestamp when rolling_min_fee_rate was raised.
+    pub(crate) rolling_min_fee_rate_updated_at: u64,
     pub snapshot: Arc<Snapshot>,
 }
 
@@ -63,6 +134,7 @@ pub struct TxPoolInfo {
     pub orphan_size: usize,
     pub total_tx_size: usize,
     pub total_tx_cycles: Cycle,
+    pub min_fee_rate: FeeRate,
     pub last_txs_updated_at: u64,
 }
 
@@ -83,9 +155,16 @@ impl TxPool {
             orphan: OrphanPool::new(),
             conflict: LruCache::new(conflict_cache_size),
             committed_txs_hash_cache: LruCache::new(committed_txs_hash_cache_size),
+            removed_txs: LruCache::new(REMOVED_TXS_CACHE_SIZE),
+            local_txs: HashMap::default(),
+            fee_deltas: HashMap::default(),
+            accepted_orphans: Vec::new(),
+            orphans_to_resubmit: Vec::new(),
             last_txs_updated_at,
             total_tx_size: 0,
             total_tx_cycles: 0,
+            rolling_min_fee_rate: FeeRate::zero(),
+            rolling_min_fee_rate_updated_at: 0,
             snapshot,
         }
     }
@@ -108,10 +187,17 @@ impl TxPool {
             orphan_size: self.orphan.size(),
             total_tx_size: self.total_tx_size,
             total_tx_cycles: self.total_tx_cycles,
+            min_fee_rate: self.min_fee_rate(),
             last_txs_updated_at: self.get_last_txs_updated_at(),
         }
     }
 
+    /// Apply the limits in the reloaded config, see `TxPoolConfig::update_limits`. The new
+    /// limits only apply to the transactions submitted afterwards.
+    pub fn update_config(&mut self, config: TxPoolConfig) {
+        self.config.update_limits(&config);
+    }
+
     pub fn reach_size_limit(&self, tx_size: usize) -> bool {
         (self.total_tx_size + tx_size) > self.config.max_mem_size
     }
@@ -120,6 +206,377 @@ impl TxPool {
         (self.total_tx_cycles + cycles) > self.config.max_cycles
     }
 
+    /// The min fee rate a new transaction must pay. It is raised when the pool evicts
+    /// transactions to make room, and decays back to `config.min_fee_rate` over time.
+    pub fn min_fee_rate(&self) -> FeeRate {
+        let elapsed = unix_time_as_millis().saturating_sub(self.rolling_min_fee_rate_updated_at);
+        let halvings = elapsed as f64 / ROLLING_MIN_FEE_RATE_HALF_LIFE as f64;
+        let rolling = self.rolling_min_fee_rate.as_u64() as f64 / 2f64.powf(halvings);
+        cmp::max(self.config.min_fee_rate, FeeRate::from_u64(rolling as u64))
+    }
+
+    // the new min fee rate is a bit higher than the evicted one,
+    // so the evicted transactions can not come back at once.
+    fn raise_min_fee_rate(&mut self, evicted_fee_rate: FeeRate) {
+        let fee_rate = FeeRate::from_u64(
+            evicted_fee_rate
+                .as_u64()
+                .saturating_add(self.config.min_fee_rate.as_u64()),
+        );
+        self.rolling_min_fee_rate = cmp::max(self.min_fee_rate(), fee_rate);
+        self.rolling_min_fee_rate_updated_at = unix_time_as_millis();
+    }
+
+    fn full_reject(&self, tx_size: usize) -> Reject {
+        if self.reach_size_limit(tx_size) {
+            Reject::Full("size".to_owned(), self.config.max_mem_size as u64)
+        } else {
+            Reject::Full("cycles".to_owned(), self.config.max_cycles)
+        }
+    }
+
+    /// find the in-pool ancestors of transactions from pending and gap
+    fn get_pending_ancestors(&self, txs: &[TransactionView]) -> HashSet<ProposalShortId> {
+        let mut ancestors = HashSet::new();
+        let parents = txs.iter().flat_map(|tx| {
+            tx.inputs()
+                .into_iter()
+                .map(|input| input.previous_output().tx_hash())
+                .chain(
+                    tx.cell_deps()
+                        .into_iter()
+                        .map(|cell_dep| cell_dep.out_point().tx_hash()),
+                )
+        });
+        for parent_hash in parents {
+            let id = ProposalShortId::from_tx_hash(&parent_hash);
+            for queue in &[&self.pending, &self.gap] {
+                if queue.contains_key(&id) {
+                    ancestors.extend(queue.get_ancestors(&id));
+                    ancestors.insert(id.clone());
+                }
+            }
+        }
+        ancestors
+    }
+
+    /// Pick the packages with the lowest descendants score from pending and gap, until there is
+    /// room for a transaction of `tx_size` and `cycles`. Every picked package must pay a lower
+    /// fee rate than the transaction. Its in-pool ancestors and local transactions are never
+    /// picked. The scores are computed with the fee deltas set by `prioritise_tx`.
+    pub(crate) fn plan_eviction(
+        &self,
+        tx: &TransactionView,
+        tx_size: usize,
+        cycles: Cycle,
+        fee: Capacity,
+    ) -> Result<Vec<DescendantsScoreSortKey>, Reject> {
+        self.plan_package_eviction(slice::from_ref(tx), tx_size, cycles, fee)
+    }
+
+    /// Same as `plan_eviction`, for a package of transactions admitted as a unit, with their
+    /// total size, cycles and fee.
+    pub(crate) fn plan_package_eviction(
+        &self,
+        txs: &[TransactionView],
+        tx_size: usize,
+        cycles: Cycle,
+        fee: Capacity,
+    ) -> Result<Vec<DescendantsScoreSortKey>, Reject> {
+        let mut plan = Vec::new();
+        if !self.reach_size_limit(tx_size) && !self.reach_cycles_limit(cycles) {
+            return Ok(plan);
+        }
+
+        let vbytes = get_transaction_virtual_bytes(tx_size, cycles);
+        let protected = self.get_pending_ancestors(txs);
+        let mut planned = HashSet::new();
+        let mut freed_size = 0;
+        let mut freed_cycles = 0;
+        let mut pending_keys = self
+            .pending
+            .keys_sorted_by_modified_descendants_score(&self.fee_deltas)
+            .into_iter()
+            .peekable();
+        let mut gap_keys = self
+            .gap
+            .keys_sorted_by_modified_descendants_score(&self.fee_deltas)
+            .into_iter()
+            .peekable();
+        loop {
+            if self.total_tx_size.saturating_sub(freed_size) + tx_size <= self.config.max_mem_size
+                && self.total_tx_cycles.saturating_sub(freed_cycles) + cycles
+                    <= self.config.max_cycles
+            {
+                return Ok(plan);
+            }
+
+            let take_gap = match (pending_keys.peek(), gap_keys.peek()) {
+                (Some(pending_key), Some(gap_key)) => gap_key < pending_key,
+                (None, Some(_)) => true,
+                (_, None) => false,
+            };
+            let (queue, key) = if take_gap {
+                (&self.gap, gap_keys.next())
+            } else {
+                (&self.pending, pending_keys.next())
+            };
+            let key = match key {
+                Some(key) => key,
+                None => return Err(self.full_reject(tx_size)),
+            };
+            if protected.contains(&key.id) || planned.contains(&key.id) {
+                continue;
+            }
+            let mut package = queue.get_descendants(&key.id);
+            package.insert(key.id.clone());
+            // local transactions are never evicted
+            if package.iter().any(|id| self.is_local_tx(id)) {
+                continue;
+            }
+
+            // avoid division package_fee/package_vbytes >= fee/vbytes
+            let (package_fee, package_vbytes) = key.max_fee_and_vbytes();
+            if u128::from(package_fee.as_u64()) * u128::from(vbytes)
+                >= u128::from(fee.as_u64()) * u128::from(package_vbytes)
+            {
+                return Err(self.full_reject(tx_size));
+            }
+
+            for id in package {
+                if let Some(entry) = queue.get(&id) {
+                    if planned.insert(id) {
+                        freed_size += entry.size;
+                        freed_cycles += entry.cycles;
+                    }
+                }
+            }
+            plan.push(key);
+        }
+    }
+
+    /// Insert the entries of a transaction, or of a package admitted as a unit. The packages
+    /// planned by `plan_eviction` or `plan_package_eviction` are evicted only after the entries
+    /// are inserted, so nothing is evicted for a rejected transaction. Return the ids of the
+    /// inserted entries and the evicted entries.
+    pub(crate) fn add_entries_and_evict(
+        &mut self,
+        entries: Vec<(TxEntry, TxStatus)>,
+        plan: &[DescendantsScoreSortKey],
+    ) -> Result<(Vec<ProposalShortId>, Vec<TxEntry>), Reject> {
+        let mut inserted = Vec::with_capacity(entries.len());
+        for (entry, status) in entries {
+            let id = entry.transaction.proposal_short_id();
+            let (size, cycles) = (entry.size, entry.cycles);
+            let ret = match status {
+                TxStatus::Fresh => self.add_pending(entry),
+                TxStatus::Gap => self.add_gap(entry),
+                TxStatus::Proposed => self.add_proposed(entry),
+            };
+            match ret {
+                Ok(true) => {
+                    self.update_statics_for_add_tx(size, cycles);
+                    inserted.push(id);
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    self.remove_package(&inserted);
+                    return Err(err);
+                }
+            }
+        }
+        let evicted = if inserted.is_empty() {
+            Vec::new()
+        } else {
+            self.evict(plan)
+        };
+        Ok((inserted, evicted))
+    }
+
+    /// Evict the planned packages and raise the min fee rate, return the evicted entries.
+    fn evict(&mut self, plan: &[DescendantsScoreSortKey]) -> Vec<TxEntry> {
+        let mut evicted = Vec::new();
+        for key in plan {
+            let removed = self.remove_entry_and_descendants_in_all_pools(&key.id);
+            for entry in &removed {
+                self.update_statics_for_remove_tx(entry.size, entry.cycles);
+                self.record_removed_tx(entry.transaction.hash(), RemovedReason::Evicted);
+            }
+            evicted.extend(removed);
+        }
+        // the plan is sorted by descendants score, the last one pays the highest fee rate
+        if let Some(key) = plan.last() {
+            let (package_fee, package_vbytes) = key.max_fee_and_vbytes();
+            self.raise_min_fee_rate(FeeRate::calculate(package_fee, package_vbytes as usize));
+            self.touch_last_txs_updated_at();
+        }
+        evicted
+    }
+
+    /// Remove the pending and gap transactions which stay in the pool longer than
+    /// `expiry_hours`, with their descendants. Return the removed entries. Nothing expires if
+    /// `expiry_hours` is 0.
+    ///
+    /// It runs on every new tip, and on the relayer's timer which rebroadcasts the local
+    /// transactions, so the transactions expire on a node which sees no new tips too.
+    pub(crate) fn expire_txs(&mut self, now: u64) -> Vec<TxEntry> {
+        if self.config.expiry_hours() == 0 {
+            return Vec::new();
+        }
+        let expiry = self.config.expiry_hours().saturating_mul(60 * 60 * 1000);
+        let expired_before = now.saturating_sub(expiry);
+        let is_expired = |(id, entry): (&ProposalShortId, &TxEntry)| {
+            if entry.timestamp < expired_before {
+                Some(id.clone())
+            } else {
+                None
+            }
+        };
+        let expired_pending: Vec<_> = self.pending.iter().filter_map(is_expired).collect();
+        let expired_gap: Vec<_> = self.gap.iter().filter_map(is_expired).collect();
+
+        let mut expired = Vec::new();
+        for id in expired_pending.into_iter().chain(expired_gap) {
+            expired.extend(self.remove_entry_and_descendants_in_all_pools(&id));
+        }
+        for entry in &expired {
+            debug!("tx {} expired", entry.transaction.hash());
+            self.update_statics_for_remove_tx(entry.size, entry.cycles);
+            self.record_removed_tx(entry.transaction.hash(), RemovedReason::Expired);
+        }
+        if !expired.is_empty() {
+            self.touch_last_txs_updated_at();
+        }
+        expired
+    }
+
+    /// Remove the entry and its descendants from pending, gap and proposed. A descendant may
+    /// stay in another sub-pool than its parent, which the sub-pools do not link, so the
+    /// sub-pools are searched for the children of the removed entries until none is left.
+    fn remove_entry_and_descendants_in_all_pools(&mut self, id: &ProposalShortId) -> Vec<TxEntry> {
+        let mut removed = Vec::new();
+        let mut ids = vec![id.clone()];
+        while !ids.is_empty() {
+            let mut entries = Vec::new();
+            for id in &ids {
+                entries.extend(self.pending.remove_entry_and_descendants(id));
+                entries.extend(self.gap.remove_entry_and_descendants(id));
+                entries.extend(self.proposed.remove_entry_and_descendants(id));
+            }
+            let removed_hashes: HashSet<Byte32> = entries
+                .iter()
+                .map(|entry| entry.transaction.hash())
+                .collect();
+            let is_child = |entry: &TxEntry| {
+                let tx = &entry.transaction;
+                tx.input_pts_iter()
+                    .chain(tx.cell_deps_iter().map(|cell_dep| cell_dep.out_point()))
+                    .any(|out_point| removed_hashes.contains(&out_point.tx_hash()))
+            };
+            ids = self
+                .pending
+                .iter()
+                .chain(self.gap.iter())
+                .chain(self.proposed.iter())
+                .filter(|(_, entry)| is_child(entry))
+                .map(|(id, _)| id.clone())
+                .collect();
+            removed.extend(entries);
+        }
+        removed
+    }
+
+    fn record_removed_tx(&mut self, tx_hash: Byte32, reason: RemovedReason) {
+        metrics!(counter, "ckb-tx-pool.removed", 1, "reason" => reason.as_str());
+        self.removed_txs.insert(tx_hash, reason);
+    }
+
+    /// The reason why the transaction was removed, if it was removed recently
+    pub fn get_removed_reason(&self, tx_hash: &Byte32) -> Option<RemovedReason> {
+        self.removed_txs.get(tx_hash).cloned()
+    }
+
+    /// Find the transaction in the pool, or the reason why it was removed recently
+    pub fn get_tx_status(&self, tx_hash: &Byte32) -> Option<TxPoolStatus> {
+        let id = ProposalShortId::from_tx_hash(tx_hash);
+        let is_same_tx = |entry: &&TxEntry| &entry.transaction.hash() == tx_hash;
+        if let Some(entry) = self.pending.get(&id).filter(is_same_tx) {
+            Some(TxPoolStatus::Pending(entry.timestamp))
+        } else if let Some(entry) = self
+            .gap
+            .get(&id)
+            .or_else(|| self.proposed.get(&id))
+            .filter(is_same_tx)
+        {
+            Some(TxPoolStatus::Proposed(entry.timestamp))
+        } else if self
+            .orphan
+            .get(&id)
+            .map(|entry| &entry.transaction.hash() == tx_hash)
+            .unwrap_or(false)
+        {
+            Some(TxPoolStatus::Orphan)
+        } else {
+            self.get_removed_reason(tx_hash).map(TxPoolStatus::Removed)
+        }
+    }
+
+    /// Track a locally submitted transaction, it's rebroadcast later and never evicted
+    pub(crate) fn track_local_tx(&mut self, id: ProposalShortId) {
+        self.local_txs
+            .insert(id, unix_time_as_millis() + LOCAL_TX_REBROADCAST_INTERVAL);
+    }
+
+    pub fn is_local_tx(&self, id: &ProposalShortId) -> bool {
+        self.local_txs.contains_key(id)
+    }
+
+    /// Return the hashes of the local transactions due to rebroadcast.
+    /// The local transactions which have left the pool are untracked here.
+    pub(crate) fn take_local_txs_to_rebroadcast(&mut self, now: u64) -> Vec<Byte32> {
+        let pending = &self.pending;
+        let gap = &self.gap;
+        let proposed = &self.proposed;
+        let mut tx_hashes = Vec::new();
+        self.local_txs.retain(|id, rebroadcast_at| {
+            let tx = pending
+                .get_tx(id)
+                .or_else(|| gap.get_tx(id))
+                .or_else(|| proposed.get_tx(id));
+            match tx {
+                Some(tx) => {
+                    if *rebroadcast_at <= now {
+                        tx_hashes.push(tx.hash());
+                        *rebroadcast_at = now + LOCAL_TX_REBROADCAST_INTERVAL;
+                    }
+                    true
+                }
+                None => false,
+            }
+        });
+        tx_hashes
+    }
+
+    /// Set the fee delta of a transaction, which is added to its fee when selecting transactions
+    /// for the block template and when picking the packages to evict. The delta is kept even if
+    /// the transaction is not in the pool yet, or leaves the pool. Setting it to zero clears it.
+    pub fn prioritise_tx(&mut self, id: ProposalShortId, fee_delta: Capacity) {
+        if fee_delta == Capacity::zero() {
+            self.fee_deltas.remove(&id);
+        } else {
+            self.fee_deltas.insert(id, fee_delta);
+        }
+        self.touch_last_txs_updated_at();
+    }
+
+    /// The fee with the delta set by `prioritise_transaction` applied
+    pub(crate) fn modified_fee(&self, id: &ProposalShortId, fee: Capacity) -> Capacity {
+        match self.fee_deltas.get(id) {
+            Some(fee_delta) => Capacity::shannons(fee.as_u64().saturating_add(fee_delta.as_u64())),
+            None => fee,
+        }
+    }
+
     pub fn update_statics_for_add_tx(&mut self, tx_size: usize, cycles: Cycle) {
         self.total_tx_size += tx_size;
         self.total_tx_cycles += cycles;
@@ -145,6 +602,35 @@ impl TxPool {
         self.total_tx_cycles = total_tx_cycles;
     }
 
+    /// Report the number, size and cycles of the txs in each pool state
+    pub(crate) fn report_metrics(&self) {
+        report_state_metrics(
+            "pending",
+            self.pending.size(),
+            self.pending.total_size(),
+            self.pending.total_cycles(),
+        );
+        report_state_metrics(
+            "gap",
+            self.gap.size(),
+            self.gap.total_size(),
+            self.gap.total_cycles(),
+        );
+        report_state_metrics(
+            "proposed",
+            self.proposed.size(),
+            self.proposed.total_size(),
+            self.proposed.total_cycles(),
+        );
+        metrics!(gauge, "ckb-tx-pool.txs", self.orphan.size() as i64, "state" => "orphan");
+        metrics!(gauge, "ckb-tx-pool.total_size", self.total_tx_size as i64);
+        metrics!(
+            gauge,
+            "ckb-tx-pool.total_cycles",
+            self.total_tx_cycles as i64
+        );
+    }
+
     // If did have this value present, false is returned.
     pub fn add_pending(&mut self, entry: TxEntry) -> Result<bool, Reject> {
         if self
@@ -154,19 +640,34 @@ impl TxPool {
             return Ok(false);
         }
         trace!("add_pending {}", entry.transaction.hash());
-        self.pending.add_entry(entry).map(|entry| entry.is_none())
+        let fee_rate = entry_fee_rate(&entry);
+        let inserted = self.pending.add_entry(entry)?.is_none();
+        if inserted {
+            metrics!(value, "ckb-tx-pool.fee_rate", fee_rate, "state" => "pending");
+        }
+        Ok(inserted)
     }
 
     // add_gap inserts proposed but still uncommittable transaction.
     pub fn add_gap(&mut self, entry: TxEntry) -> Result<bool, Reject> {
         trace!("add_gap {}", entry.transaction.hash());
-        self.gap.add_entry(entry).map(|entry| entry.is_none())
+        let fee_rate = entry_fee_rate(&entry);
+        let inserted = self.gap.add_entry(entry)?.is_none();
+        if inserted {
+            metrics!(value, "ckb-tx-pool.fee_rate", fee_rate, "state" => "gap");
+        }
+        Ok(inserted)
     }
 
     pub fn add_proposed(&mut self, entry: TxEntry) -> Result<bool, Reject> {
         trace!("add_proposed {}", entry.transaction.hash());
         self.touch_last_txs_updated_at();
-        self.proposed.add_entry(entry).map(|entry| entry.is_none())
+        let fee_rate = entry_fee_rate(&entry);
+        let inserted = self.proposed.add_entry(entry)?.is_none();
+        if inserted {
+            metrics!(value, "ckb-tx-pool.fee_rate", fee_rate, "state" => "proposed");
+        }
+        Ok(inserted)
     }
 
     pub(crate) fn add_orphan(
@@ -181,6 +682,66 @@ impl TxPool {
             .add_tx(cache_entry, size, tx, unknowns.into_iter())
     }
 
+    /// add orphan transaction relayed by `peer`, the oldest orphans of the peer are evicted
+    /// if it exceeds the quota
+    pub(crate) fn add_peer_orphan(
+        &mut self,
+        peer: PeerIndex,
+        size: usize,
+        tx: TransactionView,
+        unknowns: Vec<OutPoint>,
+    ) {
+        trace!("add_orphan {} from peer {}", &tx.hash(), peer);
+        let evicted = self
+            .orphan
+            .add_peer_tx(peer, None, size, tx, unknowns.into_iter());
+        self.update_statics_for_add_tx(size, 0);
+        for entry in evicted {
+            self.update_statics_for_remove_tx(
+                entry.size,
+                entry.cache_entry.map(|c| c.cycles).unwrap_or(0),
+            );
+        }
+    }
+
+    /// remove the orphans relayed by the disconnected peer
+    pub(crate) fn remove_orphans_by_peer(&mut self, peer: PeerIndex) {
+        let removed = self.orphan.remove_by_peer(peer);
+        if !removed.is_empty() {
+            debug!("remove {} orphans relayed by peer {}", removed.len(), peer);
+        }
+        for entry in removed {
+            self.update_statics_for_remove_tx(
+                entry.size,
+                entry.cache_entry.map(|c| c.cycles).unwrap_or(0),
+            );
+        }
+    }
+
+    /// remove the entries of a package which failed to be admitted as a unit
+    pub(crate) fn remove_package(&mut self, ids: &[ProposalShortId]) {
+        for id in ids {
+            let mut removed = self.pending.remove_entry_and_descendants(id);
+            if removed.is_empty() {
+                removed = self.gap.remove_entry_and_descendants(id);
+            }
+            if removed.is_empty() {
+                removed = self.proposed.remove_entry_and_descendants(id);
+            }
+            for entry in removed {
+                self.update_statics_for_remove_tx(entry.size, entry.cycles);
+            }
+        }
+    }
+
+    pub(crate) fn take_accepted_orphans(&mut self) -> Vec<(Byte32, PeerIndex)> {
+        std::mem::take(&mut self.accepted_orphans)
+    }
+
+    pub(crate) fn take_orphans_to_resubmit(&mut self) -> Vec<(TransactionView, PeerIndex)> {
+        std::mem::take(&mut self.orphans_to_resubmit)
+    }
+
     pub(crate) fn touch_last_txs_updated_at(&self) {
         self.last_txs_updated_at
             .store(unix_time_as_millis(), Ordering::SeqCst);
@@ -377,9 +938,19 @@ impl TxPool {
 
     // remove resolved tx from orphan pool
     pub(crate) fn try_proposed_orphan_by_ancestor(&mut self, tx: &TransactionView) {
-        let entries = self.orphan.remove_by_ancestor(tx);
-        for entry in entries {
+        let entries = self.orphan.remove_by_ancestor_with_sources(tx);
+        for (entry, source) in entries {
             let tx_hash = entry.transaction.hash();
+            // the relayed orphans have not been checked against the pool policy, they are
+            // submitted again once the pool lock is released
+            if let Some(peer) = source {
+                self.update_statics_for_remove_tx(
+                    entry.size,
+                    entry.cache_entry.map(|c| c.cycles).unwrap_or(0),
+                );
+                self.orphans_to_resubmit.push((entry.transaction, peer));
+                continue;
+            }
             if self.contains_proposed(&entry.transaction.proposal_short_id()) {
                 let ret = self.proposed_tx(entry.cache_entry, entry.size, entry.transaction);
                 if ret.is_err() {
@@ -675,10 +1246,20 @@ impl TxPool {
     ) -> HashSet<ProposalShortId> {
         let min_fee_rate = self.config.min_fee_rate;
         let mut proposals = HashSet::with_capacity(limit);
-        self.pending
-            .fill_proposals(limit, min_fee_rate, exclusion, &mut proposals);
-        self.gap
-            .fill_proposals(limit, min_fee_rate, exclusion, &mut proposals);
+        self.pending.fill_proposals(
+            limit,
+            min_fee_rate,
+            &self.fee_deltas,
+            exclusion,
+            &mut proposals,
+        );
+        self.gap.fill_proposals(
+            limit,
+            min_fee_rate,
+            &self.fee_deltas,
+            exclusion,
+            &mut proposals,
+        );
         proposals
     }
 
@@ -694,3 +1275,387 @@ impl TxPool {
             })
     }
 }
+
+fn entry_fee_rate(entry: &TxEntry) -> u64 {
+    let vbytes = get_transaction_virtual_bytes(entry.size, entry.cycles);
+    FeeRate::calculate(entry.fee, vbytes as usize).as_u64()
+}
+
+fn report_state_metrics(state: &'static str, count: usize, size: usize, cycles: Cycle) {
+    metrics!(gauge, "ckb-tx-pool.txs", count as i64, "state" => state);
+    metrics!(gauge, "ckb-tx-pool.size", size as i64, "state" => state);
+    metrics!(gauge, "ckb-tx-pool.cycles", cycles as i64, "state" => state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::commit_txs_scanner::CommitTxsScanner;
+    use ckb_chain_spec::consensus::Consensus;
+    use ckb_db::RocksDB;
+    use ckb_proposal_table::ProposalView;
+    use ckb_store::{ChainDB, COLUMNS};
+    use ckb_types::{
+        bytes::Bytes,
+        core::TransactionBuilder,
+        packed::{CellInput, CellOutputBuilder},
+        prelude::*,
+        U256,
+    };
+
+    const MOCK_SIZE: usize = 200;
+
+    fn build_tx(inputs: Vec<(&Byte32, u32)>, outputs_len: usize) -> TransactionView {
+        TransactionBuilder::default()
+            .inputs(
+                inputs
+                    .into_iter()
+                    .map(|(txid, index)| CellInput::new(OutPoint::new(txid.to_owned(), index), 0)),
+            )
+            .outputs((0..outputs_len).map(|i| {
+                CellOutputBuilder::default()
+                    .capacity(Capacity::bytes(i + 1).unwrap().pack())
+                    .build()
+            }))
+            .outputs_data((0..outputs_len).map(|_| Bytes::new().pack()))
+            .build()
+    }
+
+    fn new_pool(config: TxPoolConfig) -> TxPool {
+        let consensus = Consensus::default();
+        let store = ChainDB::new(RocksDB::open_tmp(COLUMNS), Default::default());
+        let snapshot = Snapshot::new(
+            consensus.genesis_block().header(),
+            U256::zero(),
+            consensus.genesis_epoch_ext().to_owned(),
+            store.get_snapshot(),
+            ProposalView::default(),
+            Arc::new(consensus),
+        );
+        TxPool::new(config, Arc::new(snapshot), Arc::new(AtomicU64::new(0)))
+    }
+
+    fn mock_entry(tx: &TransactionView, fee: u64) -> TxEntry {
+        TxEntry::new(tx.clone(), 0, Capacity::shannons(fee), MOCK_SIZE, vec![])
+    }
+
+    fn add_entry(pool: &mut TxPool, tx: &TransactionView, fee: u64, status: TxStatus) {
+        let (inserted, evicted) = pool
+            .add_entries_and_evict(vec![(mock_entry(tx, fee), status)], &[])
+            .expect("add entry");
+        assert_eq!(inserted, vec![tx.proposal_short_id()]);
+        assert!(evicted.is_empty());
+    }
+
+    #[test]
+    fn test_evict_after_insert() {
+        // room for 3 txs, and a tx can not have in-pool parents
+        let mut pool = new_pool(TxPoolConfig {
+            max_mem_size: MOCK_SIZE * 3,
+            max_ancestors_count: 1,
+            ..Default::default()
+        });
+        let parent = build_tx(vec![(&Byte32::zero(), 0)], 1);
+        let cheap1 = build_tx(vec![(&Byte32::zero(), 1)], 1);
+        let cheap2 = build_tx(vec![(&Byte32::zero(), 2)], 1);
+        add_entry(&mut pool, &parent, 1000, TxStatus::Fresh);
+        add_entry(&mut pool, &cheap1, 100, TxStatus::Fresh);
+        add_entry(&mut pool, &cheap2, 200, TxStatus::Fresh);
+
+        // the child fails to insert after the eviction is planned
+        let child = build_tx(vec![(&parent.hash(), 0)], 1);
+        let plan = pool
+            .plan_eviction(&child, MOCK_SIZE, 0, Capacity::shannons(1000))
+            .unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].id, cheap1.proposal_short_id());
+        let ret =
+            pool.add_entries_and_evict(vec![(mock_entry(&child, 1000), TxStatus::Fresh)], &plan);
+        assert_eq!(ret.unwrap_err(), Reject::ExceededMaximumAncestorsCount);
+        assert!(pool.contains_tx(&cheap1.proposal_short_id()));
+        assert!(!pool.contains_tx(&child.proposal_short_id()));
+        assert_eq!(pool.total_tx_size, MOCK_SIZE * 3);
+        assert_eq!(pool.get_removed_reason(&cheap1.hash()), None);
+        assert_eq!(pool.min_fee_rate(), pool.config.min_fee_rate);
+
+        // the planned package is evicted once the tx is inserted
+        let tx = build_tx(vec![(&Byte32::zero(), 3)], 1);
+        let plan = pool
+            .plan_eviction(&tx, MOCK_SIZE, 0, Capacity::shannons(1000))
+            .unwrap();
+        let (inserted, evicted) = pool
+            .add_entries_and_evict(vec![(mock_entry(&tx, 1000), TxStatus::Fresh)], &plan)
+            .unwrap();
+        assert_eq!(inserted, vec![tx.proposal_short_id()]);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].transaction, cheap1);
+        assert!(!pool.contains_tx(&cheap1.proposal_short_id()));
+        assert_eq!(pool.total_tx_size, MOCK_SIZE * 3);
+        assert_eq!(
+            pool.get_removed_reason(&cheap1.hash()),
+            Some(RemovedReason::Evicted)
+        );
+    }
+
+    #[test]
+    fn test_evict_after_package_insert() {
+        // room for 3 txs, and a tx can not have in-pool parents
+        let mut pool = new_pool(TxPoolConfig {
+            max_mem_size: MOCK_SIZE * 3,
+            max_ancestors_count: 1,
+            ..Default::default()
+        });
+        let cheap1 = build_tx(vec![(&Byte32::zero(), 1)], 1);
+        let cheap2 = build_tx(vec![(&Byte32::zero(), 2)], 1);
+        add_entry(&mut pool, &cheap1, 100, TxStatus::Fresh);
+        add_entry(&mut pool, &cheap2, 200, TxStatus::Fresh);
+
+        // the second member fails to insert after the first one is inserted
+        let parent = build_tx(vec![(&Byte32::zero(), 3)], 1);
+        let child = build_tx(vec![(&parent.hash(), 0)], 1);
+        let package = vec![parent.clone(), child.clone()];
+        let plan = pool
+            .plan_package_eviction(&package, MOCK_SIZE * 2, 0, Capacity::shannons(2000))
+            .unwrap();
+        assert_eq!(plan.len(), 1);
+        let entries = vec![
+            (mock_entry(&parent, 1000), TxStatus::Fresh),
+            (mock_entry(&child, 1000), TxStatus::Fresh),
+        ];
+        let ret = pool.add_entries_and_evict(entries, &plan);
+        assert_eq!(ret.unwrap_err(), Reject::ExceededMaximumAncestorsCount);
+        assert!(!pool.contains_tx(&parent.proposal_short_id()));
+        assert!(!pool.contains_tx(&child.proposal_short_id()));
+        assert!(pool.contains_tx(&cheap1.proposal_short_id()));
+        assert!(pool.contains_tx(&cheap2.proposal_short_id()));
+        assert_eq!(pool.total_tx_size, MOCK_SIZE * 2);
+        assert_eq!(pool.get_removed_reason(&cheap1.hash()), None);
+        assert_eq!(pool.get_removed_reason(&cheap2.hash()), None);
+    }
+
+    #[test]
+    fn test_evict_descendants_in_other_pools() {
+        // room for 3 txs
+        let mut pool = new_pool(TxPoolConfig {
+            max_mem_size: MOCK_SIZE * 3,
+            ..Default::default()
+        });
+        let parent = build_tx(vec![(&Byte32::zero(), 0)], 2);
+        let gap_child = build_tx(vec![(&parent.hash(), 0)], 1);
+        let proposed_child = build_tx(vec![(&parent.hash(), 1)], 1);
+        add_entry(&mut pool, &parent, 100, TxStatus::Fresh);
+        add_entry(&mut pool, &gap_child, 2000, TxStatus::Gap);
+        add_entry(&mut pool, &proposed_child, 2000, TxStatus::Proposed);
+
+        let tx = build_tx(vec![(&Byte32::zero(), 1)], 1);
+        let plan = pool
+            .plan_eviction(&tx, MOCK_SIZE, 0, Capacity::shannons(1000))
+            .unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].id, parent.proposal_short_id());
+        let (_, evicted) = pool
+            .add_entries_and_evict(vec![(mock_entry(&tx, 1000), TxStatus::Fresh)], &plan)
+            .unwrap();
+
+        // the children in gap and proposed leave with their parent
+        assert_eq!(evicted.len(), 3);
+        for evicted_tx in &[&parent, &gap_child, &proposed_child] {
+            assert!(!pool.contains_tx(&evicted_tx.proposal_short_id()));
+            assert_eq!(
+                pool.get_removed_reason(&evicted_tx.hash()),
+                Some(RemovedReason::Evicted)
+            );
+        }
+        assert!(pool.contains_tx(&tx.proposal_short_id()));
+        assert_eq!(pool.total_tx_size, MOCK_SIZE);
+    }
+
+    #[test]
+    fn test_relayed_orphans_are_resubmitted() {
+        let mut pool = new_pool(TxPoolConfig::default());
+        let peer: PeerIndex = 1;
+        let parent = build_tx(vec![(&Byte32::zero(), 0)], 1);
+        let child = build_tx(vec![(&parent.hash(), 0)], 1);
+        pool.add_peer_orphan(
+            peer,
+            MOCK_SIZE,
+            child.clone(),
+            vec![OutPoint::new(parent.hash(), 0)],
+        );
+        assert_eq!(pool.total_tx_size, MOCK_SIZE);
+
+        // the relayed orphan is not promoted under the pool lock
+        add_entry(&mut pool, &parent, 1000, TxStatus::Fresh);
+        pool.try_proposed_orphan_by_ancestor(&parent);
+        assert!(!pool.contains_tx(&child.proposal_short_id()));
+        assert!(pool.take_accepted_orphans().is_empty());
+        assert_eq!(pool.total_tx_size, MOCK_SIZE);
+        assert_eq!(pool.take_orphans_to_resubmit(), vec![(child, peer)]);
+    }
+
+    #[test]
+    fn test_expire_txs() {
+        let mut pool = new_pool(TxPoolConfig {
+            expiry_hours: Some(1),
+            ..Default::default()
+        });
+        let hour = 60 * 60 * 1000;
+        let now = unix_time_as_millis();
+        let add_entry_at = |pool: &mut TxPool, tx: &TransactionView, status, timestamp| {
+            let mut entry = mock_entry(tx, 1000);
+            entry.timestamp = timestamp;
+            pool.add_entries_and_evict(vec![(entry, status)], &[])
+                .unwrap();
+        };
+
+        // an expired pending tx and an expired gap tx, both with a fresh descendant
+        let pending_parent = build_tx(vec![(&Byte32::zero(), 0)], 1);
+        let pending_child = build_tx(vec![(&pending_parent.hash(), 0)], 1);
+        let gap_parent = build_tx(vec![(&Byte32::zero(), 1)], 1);
+        let gap_child = build_tx(vec![(&gap_parent.hash(), 0)], 1);
+        let fresh = build_tx(vec![(&Byte32::zero(), 2)], 1);
+        add_entry_at(&mut pool, &pending_parent, TxStatus::Fresh, now - 2 * hour);
+        add_entry_at(&mut pool, &pending_child, TxStatus::Fresh, now);
+        add_entry_at(&mut pool, &gap_parent, TxStatus::Gap, now - 2 * hour);
+        add_entry_at(&mut pool, &gap_child, TxStatus::Gap, now);
+        add_entry_at(&mut pool, &fresh, TxStatus::Fresh, now - hour / 2);
+        assert_eq!(pool.total_tx_size, MOCK_SIZE * 5);
+
+        let expired = pool.expire_txs(now);
+        assert_eq!(expired.len(), 4);
+        for tx in &[&pending_parent, &pending_child, &gap_parent, &gap_child] {
+            assert!(!pool.contains_tx(&tx.proposal_short_id()));
+            assert_eq!(
+                pool.get_tx_status(&tx.hash()),
+                Some(TxPoolStatus::Removed(RemovedReason::Expired))
+            );
+        }
+        assert!(pool.contains_tx(&fresh.proposal_short_id()));
+        assert_eq!(pool.total_tx_size, MOCK_SIZE);
+        assert_eq!(pool.total_tx_cycles, 0);
+        assert!(pool.expire_txs(now).is_empty());
+
+        // nothing expires with zero expiry hours
+        let mut pool = new_pool(TxPoolConfig {
+            expiry_hours: Some(0),
+            ..Default::default()
+        });
+        add_entry_at(&mut pool, &pending_parent, TxStatus::Fresh, now - 2 * hour);
+        assert!(pool.expire_txs(now).is_empty());
+        assert!(pool.contains_tx(&pending_parent.proposal_short_id()));
+    }
+
+    #[test]
+    fn test_local_txs_are_not_evicted() {
+        // room for 3 txs
+        let mut pool = new_pool(TxPoolConfig {
+            max_mem_size: MOCK_SIZE * 3,
+            ..Default::default()
+        });
+        let local = build_tx(vec![(&Byte32::zero(), 0)], 1);
+        let cheap = build_tx(vec![(&Byte32::zero(), 1)], 1);
+        let other = build_tx(vec![(&Byte32::zero(), 2)], 1);
+        pool.track_local_tx(local.proposal_short_id());
+        add_entry(&mut pool, &local, 100, TxStatus::Fresh);
+        add_entry(&mut pool, &cheap, 200, TxStatus::Fresh);
+        add_entry(&mut pool, &other, 2000, TxStatus::Fresh);
+
+        // the cheapest package is local, the next one is evicted instead
+        let tx = build_tx(vec![(&Byte32::zero(), 3)], 1);
+        let plan = pool
+            .plan_eviction(&tx, MOCK_SIZE, 0, Capacity::shannons(1000))
+            .unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].id, cheap.proposal_short_id());
+        let (_, evicted) = pool
+            .add_entries_and_evict(vec![(mock_entry(&tx, 1000), TxStatus::Fresh)], &plan)
+            .unwrap();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].transaction, cheap);
+        assert!(pool.contains_tx(&local.proposal_short_id()));
+
+        // the next package pays as much as the tx, the local one is still kept
+        let tx = build_tx(vec![(&Byte32::zero(), 4)], 1);
+        let ret = pool.plan_eviction(&tx, MOCK_SIZE, 0, Capacity::shannons(1000));
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_fee_deltas_change_scores() {
+        // room for 2 txs
+        let mut pool = new_pool(TxPoolConfig {
+            max_mem_size: MOCK_SIZE * 2,
+            ..Default::default()
+        });
+        let cheap = build_tx(vec![(&Byte32::zero(), 0)], 1);
+        let other = build_tx(vec![(&Byte32::zero(), 1)], 1);
+        add_entry(&mut pool, &cheap, 100, TxStatus::Fresh);
+        add_entry(&mut pool, &other, 200, TxStatus::Fresh);
+
+        let tx = build_tx(vec![(&Byte32::zero(), 2)], 1);
+        let plan = pool
+            .plan_eviction(&tx, MOCK_SIZE, 0, Capacity::shannons(1000))
+            .unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].id, cheap.proposal_short_id());
+
+        // the prioritised tx is scored with its modified fee, the other one goes first
+        pool.prioritise_tx(cheap.proposal_short_id(), Capacity::shannons(200));
+        let plan = pool
+            .plan_eviction(&tx, MOCK_SIZE, 0, Capacity::shannons(1000))
+            .unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].id, other.proposal_short_id());
+
+        // but it is not protected from eviction
+        let plan = pool
+            .plan_eviction(&tx, MOCK_SIZE * 2, 0, Capacity::shannons(10_000))
+            .unwrap();
+        let ids: Vec<_> = plan.into_iter().map(|key| key.id).collect();
+        assert_eq!(
+            ids,
+            vec![other.proposal_short_id(), cheap.proposal_short_id()]
+        );
+
+        // the block template picks the prioritised tx first as well
+        let mut pool = new_pool(TxPoolConfig::default());
+        add_entry(&mut pool, &cheap, 100, TxStatus::Proposed);
+        add_entry(&mut pool, &other, 200, TxStatus::Proposed);
+        let commit_first = |pool: &TxPool| {
+            let (entries, _, _) = CommitTxsScanner::new(pool.proposed(), &pool.fee_deltas)
+                .txs_to_commit(MOCK_SIZE, Cycle::max_value(), FeeRate::zero());
+            entries
+                .into_iter()
+                .map(|entry| entry.transaction)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(commit_first(&pool), vec![other.clone()]);
+        pool.prioritise_tx(cheap.proposal_short_id(), Capacity::shannons(200));
+        assert_eq!(commit_first(&pool), vec![cheap]);
+    }
+
+    #[test]
+    fn test_untrack_local_txs() {
+        let mut pool = new_pool(TxPoolConfig::default());
+        let local = build_tx(vec![(&Byte32::zero(), 0)], 1);
+        pool.track_local_tx(local.proposal_short_id());
+        add_entry(&mut pool, &local, 1000, TxStatus::Fresh);
+
+        // not due yet
+        assert!(pool.take_local_txs_to_rebroadcast(0).is_empty());
+        assert!(pool.is_local_tx(&local.proposal_short_id()));
+        let later = unix_time_as_millis() + LOCAL_TX_REBROADCAST_INTERVAL;
+        assert_eq!(
+            pool.take_local_txs_to_rebroadcast(later),
+            vec![local.hash()]
+        );
+        assert!(pool.take_local_txs_to_rebroadcast(later).is_empty());
+
+        // untracked once it leaves the pool
+        pool.remove_package(&[local.proposal_short_id()]);
+        assert!(pool
+            .take_local_txs_to_rebroadcast(later + LOCAL_TX_REBROADCAST_INTERVAL)
+            .is_empty());
+        assert!(!pool.is_local_tx(&local.proposal_short_id()));
+    }
+}
@@ -1,14 +1,16 @@
 use crate::block_assembler::{BlockAssembler, BlockTemplateCacheKey, TemplateCache};
 use crate::component::commit_txs_scanner::CommitTxsScanner;
 use crate::component::entry::TxEntry;
-use crate::error::{BlockAssemblerError, Reject};
+use crate::error::{missing_inputs, BlockAssemblerError, Reject};
 use crate::pool::TxPool;
 use crate::service::TxPoolService;
+use crate::PeerIndex;
 use ckb_app_config::BlockAssemblerConfig;
 use ckb_dao::DaoCalculator;
 use ckb_error::{Error, InternalErrorKind};
 use ckb_jsonrpc_types::BlockTemplate;
 use ckb_logger::{debug, info};
+use ckb_metrics::metrics;
 use ckb_snapshot::Snapshot;
 use ckb_store::ChainStore;
 use ckb_types::{
@@ -17,10 +19,13 @@ use ckb_types::{
             get_related_dep_out_points, resolve_transaction, OverlayCellProvider,
             ResolvedTransaction, TransactionsProvider,
         },
-        BlockView, Capacity, Cycle, EpochExt, ScriptHashType, TransactionView, UncleBlockView,
-        Version,
+        BlockView, Capacity, Cycle, EpochExt, EpochNumber, ScriptHashType, TransactionView,
+        UncleBlockView, Version,
+    },
+    packed::{
+        Byte32, CellbaseWitness, OutPoint, ProposalShortId, RewardShare, RewardShareVec, Script,
+        SplitCellbaseWitness,
     },
-    packed::{Byte32, CellbaseWitness, OutPoint, ProposalShortId, Script},
     prelude::*,
 };
 use ckb_util::LinkedHashSet;
@@ -37,6 +42,9 @@ use std::sync::{atomic::AtomicU64, Arc};
 use std::{cmp, iter};
 use tokio::task::block_in_place;
 
+/// The maximum number of transactions in a package admitted as a unit
+pub const MAX_PACKAGE_TXS: usize = 25;
+
 pub enum PlugTarget {
     Pending,
     Proposed,
@@ -99,12 +107,43 @@ impl TxPoolService {
             .code_hash(config.code_hash.pack())
             .hash_type(hash_type.into())
             .build();
-        let cellbase_witness = CellbaseWitness::new_builder()
-            .lock(cellbase_lock)
-            .message(config.message.as_bytes().pack())
-            .build();
+        let candidate_number = snapshot.tip_header().number() + 1;
+        let cellbase_witness = if !config.reward_shares.is_empty()
+            && snapshot
+                .consensus()
+                .is_split_reward_enabled(candidate_number)
+        {
+            let shares = config
+                .reward_shares
+                .iter()
+                .map(|share| {
+                    let hash_type: ScriptHashType = share.hash_type.clone().into();
+                    let lock = Script::new_builder()
+                        .args(share.args.as_bytes().pack())
+                        .code_hash(share.code_hash.pack())
+                        .hash_type(hash_type.into())
+                        .build();
+                    RewardShare::new_builder()
+                        .lock(lock)
+                        .weight(share.weight.pack())
+                        .build()
+                })
+                .collect();
+            SplitCellbaseWitness::new_builder()
+                .lock(cellbase_lock)
+                .message(config.message.as_bytes().pack())
+                .shares(RewardShareVec::new_builder().set(shares).build())
+                .build()
+                .as_bytes()
+        } else {
+            CellbaseWitness::new_builder()
+                .lock(cellbase_lock)
+                .message(config.message.as_bytes().pack())
+                .build()
+                .as_bytes()
+        };
 
-        BlockAssembler::build_cellbase(snapshot, snapshot.tip_header(), cellbase_witness)
+        BlockAssembler::build_cellbase(snapshot, snapshot.tip_header(), cellbase_witness.pack())
     }
 
     async fn prepare_block_template_uncles(
@@ -150,11 +189,8 @@ impl TxPoolService {
             &proposals,
         )?;
 
-        let (entries, size, cycles) = CommitTxsScanner::new(guard.proposed()).txs_to_commit(
-            txs_size_limit,
-            max_block_cycles,
-            guard.config.min_fee_rate,
-        );
+        let (entries, size, cycles) = CommitTxsScanner::new(guard.proposed(), &guard.fee_deltas)
+            .txs_to_commit(txs_size_limit, max_block_cycles, guard.config.min_fee_rate);
         if !entries.is_empty() {
             info!(
                 "[get_block_template] candidate txs count: {}, size: {}/{}, cycles:{}/{}",
@@ -269,7 +305,7 @@ impl TxPoolService {
                 .into())
         } else {
             let block_assembler = block_assembler_config
-                .map(BlockAssembler::new)
+                .map(|config| BlockAssembler::new(config, Arc::clone(&self.candidate_uncles)))
                 .unwrap_or_else(|| self.block_assembler.clone().unwrap());
             let snapshot = self.snapshot();
             let consensus = snapshot.consensus();
@@ -295,7 +331,7 @@ impl TxPoolService {
             }
 
             let cellbase = block_in_place(|| {
-                self.build_block_template_cellbase(&snapshot, &block_assembler.config)
+                self.build_block_template_cellbase(&snapshot, &block_assembler.config())
             })?;
 
             let (uncles, current_epoch, uncles_updated_at) = self
@@ -341,7 +377,7 @@ impl TxPoolService {
         }
     }
 
-    async fn pre_resolve_txs(&self, txs: &[TransactionView]) -> Result<PreResolvedTxs, Error> {
+    async fn pre_resolve_txs(&self, txs: &[TransactionView]) -> Result<PreResolvedTxs, RejectedTx> {
         let tx_pool = self.tx_pool.read().await;
 
         debug_assert!(!txs.is_empty(), "txs should not be empty!");
@@ -354,12 +390,50 @@ impl TxPoolService {
         let resolved = txs
             .iter()
             .map(|tx| {
-                let ret = resolve_tx(&tx_pool, &snapshot, &txs_provider, tx.clone());
+                let ret = resolve_tx(&tx_pool, &snapshot, &txs_provider, tx.clone())
+                    .map_err(|err| (tx.hash(), err));
+                txs_provider.insert(tx);
+                ret
+            })
+            .collect::<Result<Vec<(ResolvedTransaction, usize, Capacity, TxStatus)>, _>>()?;
+
+        let (rtxs, status) = resolved
+            .into_iter()
+            .map(|(rtx, tx_size, fee, status)| (rtx, (tx_size, fee, status)))
+            .unzip();
+
+        Ok((tip_hash, snapshot, rtxs, status))
+    }
+
+    async fn pre_resolve_package(&self, txs: &[TransactionView]) -> Result<PreResolvedTxs, Error> {
+        let tx_pool = self.tx_pool.read().await;
+
+        let snapshot = tx_pool.cloned_snapshot();
+        let tip_hash = snapshot.tip_hash();
+
+        check_transaction_hash_collision(&tx_pool, txs).map_err(|(_, err)| err)?;
+
+        let mut txs_provider = TransactionsProvider::default();
+        let resolved = txs
+            .iter()
+            .map(|tx| {
+                let ret = resolve_tx_with_fee(&tx_pool, &snapshot, &txs_provider, tx.clone());
                 txs_provider.insert(tx);
                 ret
             })
             .collect::<Result<Vec<(ResolvedTransaction, usize, Capacity, TxStatus)>, _>>()?;
 
+        // a full pool only accepts the package when there are cheaper packages to evict,
+        // check it with the total fee before the expensive verification
+        let mut total_size = 0;
+        let mut total_fee = Capacity::zero();
+        for (rtx, tx_size, fee, _) in &resolved {
+            total_size += tx_size;
+            total_fee = total_fee
+                .safe_add(tx_pool.modified_fee(&rtx.transaction.proposal_short_id(), *fee))?;
+        }
+        tx_pool.plan_package_eviction(txs, total_size, 0, total_fee)?;
+
         let (rtxs, status) = resolved
             .into_iter()
             .map(|(rtx, tx_size, fee, status)| (rtx, (tx_size, fee, status)))
@@ -385,7 +459,8 @@ impl TxPoolService {
         txs: Vec<(ResolvedTransaction, CacheEntry)>,
         pre_resolve_tip: Byte32,
         status: Vec<(usize, Capacity, TxStatus)>,
-    ) -> Result<(), Error> {
+        local: bool,
+    ) -> Result<(), RejectedTx> {
         let mut tx_pool = self.tx_pool.write().await;
         let snapshot = tx_pool.snapshot();
 
@@ -393,24 +468,33 @@ impl TxPoolService {
             let mut txs_provider = TransactionsProvider::default();
 
             for (tx, _) in &txs {
-                resolve_tx(&tx_pool, snapshot, &txs_provider, tx.transaction.clone())?;
+                resolve_tx(&tx_pool, snapshot, &txs_provider, tx.transaction.clone())
+                    .map_err(|err| (tx.transaction.hash(), err))?;
                 txs_provider.insert(&tx.transaction);
             }
         }
 
         for ((rtx, cache_entry), (tx_size, fee, status)) in txs.into_iter().zip(status.into_iter())
         {
-            if tx_pool.reach_cycles_limit(cache_entry.cycles) {
-                return Err(Reject::Full("cycles".to_owned(), tx_pool.config.max_cycles).into());
-            }
-
-            let min_fee = tx_pool.config.min_fee_rate.fee(tx_size);
+            let tx_hash = rtx.transaction.hash();
+            // the prioritised txs are checked with their fee deltas applied
+            let modified_fee = tx_pool.modified_fee(&rtx.transaction.proposal_short_id(), fee);
+            let min_fee = tx_pool.min_fee_rate().fee(tx_size);
             // reject txs which fee lower than min fee rate
-            if fee < min_fee {
-                return Err(Reject::LowFeeRate(min_fee.as_u64(), fee.as_u64()).into());
+            if modified_fee < min_fee {
+                let reject = Reject::LowFeeRate(min_fee.as_u64(), modified_fee.as_u64());
+                return Err((tx_hash, reject.into()));
             }
 
+            // plan to evict the packages paying less when the pool is full, they are evicted
+            // only after the tx is inserted
+            let plan = tx_pool
+                .plan_eviction(&rtx.transaction, tx_size, cache_entry.cycles, modified_fee)
+                .map_err(|reject| (tx_hash.clone(), reject.into()))?;
+
+            let short_id = rtx.transaction.proposal_short_id();
             let related_dep_out_points = rtx.related_dep_out_points();
+            let tx = rtx.transaction.clone();
             let entry = TxEntry::new(
                 rtx.transaction,
                 cache_entry.cycles,
@@ -418,25 +502,115 @@ impl TxPoolService {
                 tx_size,
                 related_dep_out_points,
             );
-            let inserted = match status {
-                TxStatus::Fresh => tx_pool.add_pending(entry)?,
-                TxStatus::Gap => tx_pool.add_gap(entry)?,
-                TxStatus::Proposed => tx_pool.add_proposed(entry)?,
-            };
-            if inserted {
-                tx_pool.update_statics_for_add_tx(tx_size, cache_entry.cycles);
+            let (inserted, evicted) = tx_pool
+                .add_entries_and_evict(vec![(entry, status)], &plan)
+                .map_err(|reject| (tx_hash, reject.into()))?;
+            self.notify_evicted_txs(&tx, evicted);
+            if !inserted.is_empty() {
+                // the orphans waiting for this tx may be accepted now
+                tx_pool.try_proposed_orphan_by_ancestor(&tx);
+            }
+            if local {
+                tx_pool.track_local_tx(short_id);
             }
         }
         Ok(())
     }
 
-    fn non_contextual_verify(&self, txs: &[TransactionView]) -> Result<(), Error> {
+    async fn submit_package(
+        &self,
+        txs: Vec<(ResolvedTransaction, CacheEntry)>,
+        pre_resolve_tip: Byte32,
+        status: Vec<(usize, Capacity, TxStatus)>,
+        local: bool,
+    ) -> Result<(), Error> {
+        let mut tx_pool = self.tx_pool.write().await;
+        let snapshot = tx_pool.snapshot();
+
+        if pre_resolve_tip != snapshot.tip_hash() {
+            let mut txs_provider = TransactionsProvider::default();
+
+            for (tx, _) in &txs {
+                resolve_tx_with_fee(&tx_pool, snapshot, &txs_provider, tx.transaction.clone())?;
+                txs_provider.insert(&tx.transaction);
+            }
+        }
+
+        // the package is checked against the min fee rate as a unit, a parent paying less
+        // can be carried by its children
+        let mut total_size = 0;
+        let mut total_cycles = 0;
+        let mut total_fee = Capacity::zero();
+        for ((rtx, cache_entry), (tx_size, fee, _)) in txs.iter().zip(status.iter()) {
+            total_size += tx_size;
+            total_cycles += cache_entry.cycles;
+            total_fee = total_fee
+                .safe_add(tx_pool.modified_fee(&rtx.transaction.proposal_short_id(), *fee))?;
+        }
+        let min_fee = tx_pool.min_fee_rate().fee(total_size);
+        if total_fee < min_fee {
+            return Err(Reject::LowFeeRate(min_fee.as_u64(), total_fee.as_u64()).into());
+        }
+
+        // the packages paying less are evicted only after every member is inserted
+        let package: Vec<_> = txs.iter().map(|(rtx, _)| rtx.transaction.clone()).collect();
+        let plan = tx_pool.plan_package_eviction(&package, total_size, total_cycles, total_fee)?;
+
+        let entries = txs
+            .into_iter()
+            .zip(status.into_iter())
+            .map(|((rtx, cache_entry), (tx_size, fee, status))| {
+                let related_dep_out_points = rtx.related_dep_out_points();
+                let entry = TxEntry::new(
+                    rtx.transaction,
+                    cache_entry.cycles,
+                    fee,
+                    tx_size,
+                    related_dep_out_points,
+                );
+                (entry, status)
+            })
+            .collect();
+        // the package is rejected as a unit
+        let (_, evicted) = tx_pool.add_entries_and_evict(entries, &plan)?;
+        if let Some(tx) = package.last() {
+            self.notify_evicted_txs(tx, evicted);
+        }
+
+        for tx in &package {
+            // the orphans waiting for the package may be accepted now
+            tx_pool.try_proposed_orphan_by_ancestor(tx);
+            if local {
+                tx_pool.track_local_tx(tx.proposal_short_id());
+            }
+        }
+        Ok(())
+    }
+
+    fn notify_evicted_txs(&self, tx: &TransactionView, evicted: Vec<TxEntry>) {
+        for entry in evicted {
+            debug!(
+                "evict tx {} to make room for tx {}",
+                entry.transaction.hash(),
+                tx.hash()
+            );
+            self.notify_controller
+                .notify_evicted_transaction(entry.transaction);
+        }
+    }
+
+    fn non_contextual_verify(&self, txs: &[TransactionView]) -> Result<(), RejectedTx> {
         for tx in txs {
-            NonContextualTransactionVerifier::new(tx, &self.consensus).verify()?;
+            NonContextualTransactionVerifier::new(tx, &self.consensus)
+                .verify()
+                .map_err(|err| (tx.hash(), err))?;
 
             // cellbase is only valid in a block, not as a loose transaction
             if tx.is_cellbase() {
-                return Err(Reject::Malformed("cellbase like".to_owned()).into());
+                return Err((
+                    tx.hash(),
+                    Reject::Malformed("cellbase like".to_owned()).into(),
+                ));
             }
         }
         Ok(())
@@ -445,7 +619,127 @@ impl TxPoolService {
     pub(crate) async fn process_txs(
         &self,
         txs: Vec<TransactionView>,
+        local: bool,
+        peer: Option<PeerIndex>,
+    ) -> Result<Vec<CacheEntry>, Error> {
+        let orphan_candidates = peer.map(|peer| (peer, txs.clone()));
+        let ret = self.verify_and_submit_txs(txs, local).await;
+        if let Err((tx_hash, err)) = &ret {
+            match orphan_candidates {
+                // the orphans are not rejected, they are submitted again once their parents
+                // arrive
+                Some((peer, txs)) if missing_inputs(err).is_some() => {
+                    self.add_peer_orphans(&txs, peer).await;
+                }
+                // the txs after it in the batch were not checked
+                _ => self.record_rejected_txs(vec![tx_hash.clone()], err).await,
+            }
+        }
+        self.resubmit_orphans().await;
+        self.tx_pool.read().await.report_metrics();
+        ret.map_err(|(_, err)| err)
+    }
+
+    /// Submit a package of dependent txs, admitted or rejected as a unit. The package is
+    /// checked against the min fee rate, the pool limits and the block cycles limit with its
+    /// total size, cycles and fee.
+    pub(crate) async fn process_package(
+        &self,
+        txs: Vec<TransactionView>,
+        local: bool,
     ) -> Result<Vec<CacheEntry>, Error> {
+        let tx_hashes: Vec<_> = txs.iter().map(|tx| tx.hash()).collect();
+        let ret = self.verify_and_submit_package(txs, local).await;
+        if let Err(err) = &ret {
+            // the package is rejected as a unit, so is every member
+            self.record_rejected_txs(tx_hashes, err).await;
+        }
+        self.resubmit_orphans().await;
+        self.tx_pool.read().await.report_metrics();
+        ret
+    }
+
+    // Submit the relayed orphans whose parents have arrived, they are checked against the
+    // fee, cycles and size limits like the other relayed txs. The orphans waiting for them
+    // are queued again once they are accepted, so it loops until the queue is empty.
+    async fn resubmit_orphans(&self) {
+        loop {
+            let orphans = self.tx_pool.write().await.take_orphans_to_resubmit();
+            if orphans.is_empty() {
+                return;
+            }
+            for (tx, peer) in orphans {
+                let tx_hash = tx.hash();
+                match self.verify_and_submit_txs(vec![tx.clone()], false).await {
+                    Ok(_) => {
+                        let mut tx_pool = self.tx_pool.write().await;
+                        tx_pool.accepted_orphans.push((tx_hash, peer));
+                    }
+                    Err((_, err)) => {
+                        if missing_inputs(&err).is_some() {
+                            self.add_peer_orphans(&[tx], peer).await;
+                        } else {
+                            self.record_rejected_txs(vec![tx_hash], &err).await;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // keep the relayed txs spending unknown cells in the orphan pool, tagged with the peer,
+    // they are accepted once their parents arrive
+    async fn add_peer_orphans(&self, txs: &[TransactionView], peer: PeerIndex) {
+        let mut tx_pool = self.tx_pool.write().await;
+        let snapshot = tx_pool.cloned_snapshot();
+        let mut txs_provider = TransactionsProvider::default();
+        for tx in txs {
+            if !tx_pool.contains_tx(&tx.proposal_short_id()) {
+                let ret = resolve_tx_from_pending_and_proposed(
+                    &tx_pool,
+                    &snapshot,
+                    &txs_provider,
+                    tx.clone(),
+                );
+                if let Err(err) = ret {
+                    if let Some(out_points) = missing_inputs(&err) {
+                        let tx_size = tx.data().serialized_size_in_block();
+                        tx_pool.add_peer_orphan(peer, tx_size, tx.clone(), out_points.to_owned());
+                    }
+                }
+            }
+            txs_provider.insert(tx);
+        }
+    }
+
+    pub(crate) async fn remove_orphans_by_peer(&self, peer: PeerIndex) {
+        let mut tx_pool = self.tx_pool.write().await;
+        tx_pool.remove_orphans_by_peer(peer);
+    }
+
+    // remember why the txs were rejected, the txs already accepted in the batch are skipped
+    async fn record_rejected_txs(&self, tx_hashes: Vec<Byte32>, err: &Error) {
+        let reason = match err.downcast_ref::<Reject>() {
+            Some(reject) => reject.kind().to_owned(),
+            None => err.kind().to_string(),
+        };
+        metrics!(counter, "ckb-tx-pool.rejected", 1, "reason" => reason);
+        let tx_pool = self.tx_pool.read().await;
+        let mut recent_rejects = self.recent_rejects.write().await;
+        for tx_hash in tx_hashes {
+            let id = ProposalShortId::from_tx_hash(&tx_hash);
+            if tx_pool.get_tx_without_conflict(&id).is_none() {
+                debug!("tx {} rejected: {}", tx_hash, err);
+                recent_rejects.insert(tx_hash, err.to_string());
+            }
+        }
+    }
+
+    async fn verify_and_submit_txs(
+        &self,
+        txs: Vec<TransactionView>,
+        local: bool,
+    ) -> Result<Vec<CacheEntry>, RejectedTx> {
         // non contextual verify first
         self.non_contextual_verify(&txs)?;
 
@@ -462,8 +756,53 @@ impl TxPoolService {
             .collect::<Vec<_>>();
         let cycles_vec = verified.iter().map(|(_, cycles)| *cycles).collect();
 
-        self.submit_txs(verified, tip_hash, status).await?;
+        self.submit_txs(verified, tip_hash, status, local).await?;
+
+        self.update_txs_verify_cache(updated_cache);
+        Ok(cycles_vec)
+    }
+
+    async fn verify_and_submit_package(
+        &self,
+        txs: Vec<TransactionView>,
+        local: bool,
+    ) -> Result<Vec<CacheEntry>, Error> {
+        check_package(&txs, self.consensus.max_block_bytes())?;
+        self.non_contextual_verify(&txs).map_err(|(_, err)| err)?;
 
+        let max_tx_verify_cycles = self.tx_pool_config.max_tx_verify_cycles;
+        let (tip_hash, snapshot, rtxs, status) = self.pre_resolve_package(&txs).await?;
+        let fetched_cache = self.fetch_txs_verify_cache(txs.iter()).await;
+
+        let verified =
+            block_in_place(|| verify_rtxs(&snapshot, rtxs, &fetched_cache, max_tx_verify_cycles))
+                .map_err(|(_, err)| err)?;
+
+        // the package must fit in a block to be committed as a unit
+        let total_cycles: Cycle = verified.iter().map(|(_, cycles)| cycles.cycles).sum();
+        let max_block_cycles = self.consensus.max_block_cycles();
+        if total_cycles > max_block_cycles {
+            return Err(Reject::InvalidPackage(format!(
+                "cycles {} exceeds the block cycles limit {}",
+                total_cycles, max_block_cycles
+            ))
+            .into());
+        }
+
+        let updated_cache = verified
+            .iter()
+            .map(|(tx, cycles)| (tx.transaction.hash(), *cycles))
+            .collect::<Vec<_>>();
+        let cycles_vec = verified.iter().map(|(_, cycles)| *cycles).collect();
+
+        self.submit_package(verified, tip_hash, status, local)
+            .await?;
+
+        self.update_txs_verify_cache(updated_cache);
+        Ok(cycles_vec)
+    }
+
+    fn update_txs_verify_cache(&self, updated_cache: Vec<(Byte32, CacheEntry)>) {
         let txs_verify_cache = Arc::clone(&self.txs_verify_cache);
         tokio::spawn(async move {
             let mut guard = txs_verify_cache.write().await;
@@ -471,7 +810,6 @@ impl TxPoolService {
                 guard.insert(k, v);
             }
         });
-        Ok(cycles_vec)
     }
 
     pub(crate) async fn update_tx_pool_for_reorg(
@@ -481,6 +819,7 @@ impl TxPoolService {
         detached_proposal_id: HashSet<ProposalShortId>,
         snapshot: Arc<Snapshot>,
     ) {
+        let epoch_number = snapshot.epoch_ext().number();
         let mut detached_txs = HashSet::new();
         let mut attached_txs = HashSet::new();
         for blk in &detached_blocks {
@@ -494,15 +833,20 @@ impl TxPoolService {
             .await;
         let mut tx_pool = self.tx_pool.write().await;
         let updated_cache = block_in_place(|| {
-            _update_tx_pool_for_reorg(
+            let updated_cache = _update_tx_pool_for_reorg(
                 &mut tx_pool,
                 &fetched_cache,
                 detached_blocks,
                 attached_blocks,
                 detached_proposal_id,
                 snapshot,
-            )
+            );
+            tx_pool.expire_txs(unix_time_as_millis());
+            tx_pool.report_metrics();
+            updated_cache
         });
+        drop(tx_pool);
+        self.resubmit_orphans().await;
 
         let txs_verify_cache = Arc::clone(&self.txs_verify_cache);
         tokio::spawn(async move {
@@ -511,13 +855,57 @@ impl TxPoolService {
                 guard.insert(k, v);
             }
         });
+
+        self.prune_candidate_uncles(epoch_number).await;
+    }
+
+    /// Add an uncle to the candidates of the block templates, returns whether it is a new one
+    pub(crate) async fn add_candidate_uncle(&self, uncle: UncleBlockView) -> bool {
+        let inserted = self.candidate_uncles.lock().await.insert(uncle);
+        if inserted {
+            self.touch_uncles_updated_at();
+        }
+        inserted
+    }
+
+    pub(crate) async fn get_candidate_uncles(&self) -> Vec<UncleBlockView> {
+        self.candidate_uncles
+            .lock()
+            .await
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    // the candidates from the finished epochs can never be included
+    async fn prune_candidate_uncles(&self, epoch_number: EpochNumber) {
+        let pruned = self.candidate_uncles.lock().await.prune(epoch_number);
+        if pruned > 0 {
+            debug!(
+                "pruned {} candidate uncles before epoch {}",
+                pruned, epoch_number
+            );
+            self.touch_uncles_updated_at();
+        }
+    }
+
+    fn touch_uncles_updated_at(&self) {
+        if let Some(ref block_assembler) = self.block_assembler {
+            block_assembler
+                .last_uncles_updated_at
+                .store(unix_time_as_millis(), Ordering::SeqCst);
+        }
     }
 
     pub(crate) async fn clear_pool(&self, new_snapshot: Arc<Snapshot>) {
         let mut tx_pool = self.tx_pool.write().await;
         let config = tx_pool.config;
         let last_txs_updated_at = Arc::new(AtomicU64::new(0));
+        // fee deltas are kept until they are cleared by `prioritise_transaction`
+        let fee_deltas = std::mem::take(&mut tx_pool.fee_deltas);
         *tx_pool = TxPool::new(config, new_snapshot, last_txs_updated_at);
+        tx_pool.fee_deltas = fee_deltas;
+        tx_pool.report_metrics();
     }
 }
 
@@ -530,14 +918,80 @@ type PreResolvedTxs = (
 
 type ResolveResult = Result<(ResolvedTransaction, usize, Capacity, TxStatus), Error>;
 
+// The error of a batch of txs, with the hash of the tx which caused it
+type RejectedTx = (Byte32, Error);
+
+// A package is a child with its parents: it is sorted topologically, and every tx other than
+// the last one is an ancestor of the last one within the package.
+fn check_package(txs: &[TransactionView], max_block_bytes: u64) -> Result<(), Error> {
+    let invalid =
+        |reason: String| -> Result<(), Error> { Err(Reject::InvalidPackage(reason).into()) };
+    if txs.is_empty() {
+        return invalid("empty package".to_owned());
+    }
+    if txs.len() > MAX_PACKAGE_TXS {
+        return invalid(format!(
+            "{} txs exceeds the limit {}",
+            txs.len(),
+            MAX_PACKAGE_TXS
+        ));
+    }
+    let total_size: usize = txs
+        .iter()
+        .map(|tx| tx.data().serialized_size_in_block())
+        .sum();
+    if total_size as u64 > max_block_bytes {
+        return invalid(format!(
+            "size {} exceeds the block bytes limit {}",
+            total_size, max_block_bytes
+        ));
+    }
+
+    let positions: HashMap<Byte32, usize> = txs
+        .iter()
+        .enumerate()
+        .map(|(index, tx)| (tx.hash(), index))
+        .collect();
+    if positions.len() != txs.len() {
+        return invalid("duplicated txs".to_owned());
+    }
+    let parents_in_package = |tx: &TransactionView| -> Vec<usize> {
+        tx.input_pts_iter()
+            .map(|out_point| out_point.tx_hash())
+            .chain(
+                tx.cell_deps_iter()
+                    .map(|cell_dep| cell_dep.out_point().tx_hash()),
+            )
+            .filter_map(|tx_hash| positions.get(&tx_hash).cloned())
+            .collect()
+    };
+
+    let mut ancestors = HashSet::new();
+    let mut queue = vec![txs.len() - 1];
+    while let Some(index) = queue.pop() {
+        for parent in parents_in_package(&txs[index]) {
+            if parent >= index {
+                return invalid("txs are not sorted topologically".to_owned());
+            }
+            if ancestors.insert(parent) {
+                queue.push(parent);
+            }
+        }
+    }
+    if ancestors.len() != txs.len() - 1 {
+        return invalid("txs are not the ancestors of the last tx".to_owned());
+    }
+    Ok(())
+}
+
 fn check_transaction_hash_collision(
     tx_pool: &TxPool,
     txs: &[TransactionView],
-) -> Result<(), Error> {
+) -> Result<(), RejectedTx> {
     for tx in txs {
         let short_id = tx.proposal_short_id();
         if tx_pool.contains_proposal_id(&short_id) {
-            return Err(Reject::Duplicated(tx.hash()).into());
+            return Err((tx.hash(), Reject::Duplicated(tx.hash()).into()));
         }
     }
     Ok(())
@@ -548,17 +1002,29 @@ fn resolve_tx<'a>(
     snapshot: &Snapshot,
     txs_provider: &'a TransactionsProvider<'a>,
     tx: TransactionView,
+) -> ResolveResult {
+    let (rtx, tx_size, fee, status) = resolve_tx_with_fee(tx_pool, snapshot, txs_provider, tx)?;
+
+    // a full pool only accepts the tx when there are cheaper packages to evict,
+    // check it before the expensive verification
+    let modified_fee = tx_pool.modified_fee(&rtx.transaction.proposal_short_id(), fee);
+    tx_pool.plan_eviction(&rtx.transaction, tx_size, 0, modified_fee)?;
+    Ok((rtx, tx_size, fee, status))
+}
+
+fn resolve_tx_with_fee<'a>(
+    tx_pool: &TxPool,
+    snapshot: &Snapshot,
+    txs_provider: &'a TransactionsProvider<'a>,
+    tx: TransactionView,
 ) -> ResolveResult {
     let tx_size = tx.data().serialized_size_in_block();
-    if tx_pool.reach_size_limit(tx_size) {
-        return Err(Reject::Full("size".to_owned(), tx_pool.config.max_mem_size as u64).into());
-    }
 
     let short_id = tx.proposal_short_id();
-    if snapshot.proposals().contains_proposed(&short_id) {
+    let (rtx, fee, status) = if snapshot.proposals().contains_proposed(&short_id) {
         resolve_tx_from_proposed(tx_pool, snapshot, txs_provider, tx).and_then(|rtx| {
             let fee = tx_pool.calculate_transaction_fee(snapshot, &rtx);
-            fee.map(|fee| (rtx, tx_size, fee, TxStatus::Proposed))
+            fee.map(|fee| (rtx, fee, TxStatus::Proposed))
         })
     } else {
         resolve_tx_from_pending_and_proposed(tx_pool, snapshot, txs_provider, tx).and_then(|rtx| {
@@ -568,9 +1034,11 @@ fn resolve_tx<'a>(
                 TxStatus::Fresh
             };
             let fee = tx_pool.calculate_transaction_fee(snapshot, &rtx);
-            fee.map(|fee| (rtx, tx_size, fee, status))
+            fee.map(|fee| (rtx, fee, status))
         })
-    }
+    }?;
+
+    Ok((rtx, tx_size, fee, status))
 }
 
 fn resolve_tx_from_proposed<'a>(
@@ -603,7 +1071,7 @@ fn verify_rtxs(
     txs: Vec<ResolvedTransaction>,
     txs_verify_cache: &HashMap<Byte32, CacheEntry>,
     max_tx_verify_cycles: Cycle,
-) -> Result<Vec<(ResolvedTransaction, CacheEntry)>, Error> {
+) -> Result<Vec<(ResolvedTransaction, CacheEntry)>, RejectedTx> {
     let tip_header = snapshot.tip_header();
     let tip_number = tip_header.number();
     let epoch = tip_header.epoch();
@@ -612,7 +1080,7 @@ fn verify_rtxs(
     txs.into_iter()
         .map(|tx| {
             let tx_hash = tx.transaction.hash();
-            if let Some(cache_entry) = txs_verify_cache.get(&tx_hash) {
+            let ret = if let Some(cache_entry) = txs_verify_cache.get(&tx_hash) {
                 TimeRelativeTransactionVerifier::new(
                     &tx,
                     snapshot,
@@ -635,7 +1103,8 @@ fn verify_rtxs(
                 )
                 .verify(max_tx_verify_cycles)
                 .map(|cycles| (tx, cycles))
-            }
+            };
+            ret.map_err(|err| (tx_hash, err))
         })
         .collect::<Result<Vec<_>, _>>()
 }
@@ -762,3 +1231,88 @@ fn _update_tx_pool_for_reorg(
 
     to_update_cache
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{check_package, MAX_PACKAGE_TXS};
+    use crate::error::Reject;
+    use ckb_types::{
+        bytes::Bytes,
+        core::{Capacity, TransactionBuilder, TransactionView},
+        packed::{Byte32, CellInput, CellOutputBuilder, OutPoint},
+        prelude::*,
+    };
+
+    const MAX_BLOCK_BYTES: u64 = 597_000;
+
+    fn build_tx(inputs: Vec<(&Byte32, u32)>, outputs_len: usize) -> TransactionView {
+        TransactionBuilder::default()
+            .inputs(
+                inputs
+                    .into_iter()
+                    .map(|(txid, index)| CellInput::new(OutPoint::new(txid.to_owned(), index), 0)),
+            )
+            .outputs((0..outputs_len).map(|i| {
+                CellOutputBuilder::default()
+                    .capacity(Capacity::bytes(i + 1).unwrap().pack())
+                    .build()
+            }))
+            .outputs_data((0..outputs_len).map(|_| Bytes::new().pack()))
+            .build()
+    }
+
+    fn is_invalid_package(txs: &[TransactionView]) -> bool {
+        check_package(txs, MAX_BLOCK_BYTES)
+            .err()
+            .and_then(|err| err.downcast_ref::<Reject>().cloned())
+            .map(|reject| match reject {
+                Reject::InvalidPackage(_) => true,
+                _ => false,
+            })
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn test_check_package() {
+        let parent1 = build_tx(vec![(&Byte32::zero(), 0)], 2);
+        let parent2 = build_tx(vec![(&Byte32::zero(), 1)], 1);
+        let child = build_tx(vec![(&parent1.hash(), 0), (&parent2.hash(), 0)], 1);
+        let unrelated = build_tx(vec![(&Byte32::zero(), 2)], 1);
+
+        assert!(check_package(&[child.clone()], MAX_BLOCK_BYTES).is_ok());
+        assert!(check_package(
+            &[parent1.clone(), parent2.clone(), child.clone()],
+            MAX_BLOCK_BYTES
+        )
+        .is_ok());
+
+        assert!(is_invalid_package(&[]));
+        // the child must be the last one
+        assert!(is_invalid_package(&[
+            child.clone(),
+            parent1.clone(),
+            parent2.clone()
+        ]));
+        // every tx must be an ancestor of the child
+        assert!(is_invalid_package(&[
+            unrelated,
+            parent1.clone(),
+            parent2.clone(),
+            child.clone()
+        ]));
+        assert!(is_invalid_package(&[
+            parent1.clone(),
+            parent1.clone(),
+            child
+        ]));
+
+        // a chain of MAX_PACKAGE_TXS + 1 txs
+        let mut chain = vec![parent1];
+        for _ in 0..MAX_PACKAGE_TXS {
+            let tx = build_tx(vec![(&chain.last().unwrap().hash(), 0)], 1);
+            chain.push(tx);
+        }
+        assert!(check_package(&chain[1..], MAX_BLOCK_BYTES).is_ok());
+        assert!(is_invalid_package(&chain));
+    }
+}
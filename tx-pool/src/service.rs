@@ -1,29 +1,38 @@
-use crate::block_assembler::BlockAssembler;
+use crate::block_assembler::{BlockAssembler, CandidateUncles};
 use crate::component::entry::TxEntry;
 use crate::error::handle_try_send_error;
-use crate::pool::{TxPool, TxPoolInfo};
+use crate::pool::{TxPool, TxPoolInfo, TxPoolStatus};
 use crate::process::PlugTarget;
+use crate::PeerIndex;
 use ckb_app_config::{BlockAssemblerConfig, TxPoolConfig};
 use ckb_async_runtime::{new_runtime, Handle};
 use ckb_chain_spec::consensus::Consensus;
 use ckb_error::Error;
+use ckb_fee_estimator::FeeRate;
 use ckb_jsonrpc_types::BlockTemplate;
 use ckb_logger::error;
+use ckb_notify::NotifyController;
 use ckb_snapshot::{Snapshot, SnapshotMgr};
 use ckb_stop_handler::{SignalSender, StopHandler};
+use ckb_store::ChainDB;
 use ckb_types::{
-    core::{BlockView, Cycle, TransactionView, UncleBlockView, Version},
-    packed::ProposalShortId,
+    core::{BlockView, Capacity, Cycle, TransactionView, UncleBlockView, Version},
+    packed::{Byte32, ProposalShortId},
 };
 use ckb_verification::cache::{CacheEntry, TxVerifyCache};
 use failure::Error as FailureError;
 use faketime::unix_time_as_millis;
+use lru_cache::LruCache;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::atomic::Ordering;
-use std::sync::{atomic::AtomicU64, Arc};
-use tokio::sync::{mpsc, oneshot, RwLock};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
 
 pub const DEFAULT_CHANNEL_SIZE: usize = 512;
+/// The number of recently rejected transactions whose reasons are kept
+const RECENT_REJECTS_CACHE_SIZE: usize = 10_000;
 
 pub struct Request<A, R> {
     pub responder: oneshot::Sender<R>,
@@ -74,7 +83,9 @@ pub type ChainReorgArgs = (
 pub enum Message {
     BlockTemplate(Request<BlockTemplateArgs, BlockTemplateResult>),
     SubmitTxs(Request<Vec<TransactionView>, SubmitTxsResult>),
-    NotifyTxs(Notify<(Vec<TransactionView>, NotifyTxsCallback)>),
+    SubmitPackage(Request<Vec<TransactionView>, SubmitTxsResult>),
+    NotifyTxs(Notify<(Vec<TransactionView>, Option<PeerIndex>, NotifyTxsCallback)>),
+    NotifyPackage(Notify<(Vec<TransactionView>, NotifyTxsCallback)>),
     ChainReorg(Notify<ChainReorgArgs>),
     FreshProposalsFilter(Request<Vec<ProposalShortId>, Vec<ProposalShortId>>),
     FetchTxs(Request<Vec<ProposalShortId>, HashMap<ProposalShortId, TransactionView>>),
@@ -82,8 +93,17 @@ pub enum Message {
     GetTxPoolInfo(Request<(), TxPoolInfo>),
     FetchTxRPC(Request<ProposalShortId, Option<(bool, TransactionView)>>),
     NewUncle(Notify<UncleBlockView>),
+    AddCandidateUncle(Request<UncleBlockView, bool>),
+    GetCandidateUncles(Request<(), Vec<UncleBlockView>>),
     PlugEntry(Request<(Vec<TxEntry>, PlugTarget), ()>),
     ClearPool(Request<Arc<Snapshot>, ()>),
+    FetchLocalTxsToRebroadcast(Request<(), Vec<Byte32>>),
+    PrioritiseTx(Request<(Byte32, Capacity), ()>),
+    FetchTxStatus(Request<Byte32, Option<TxPoolStatus>>),
+    RemoveOrphansByPeer(Notify<PeerIndex>),
+    FetchAcceptedOrphans(Request<(), Vec<(Byte32, PeerIndex)>>),
+    UpdateConfig(Request<TxPoolConfig, ()>),
+    UpdateBlockAssemblerConfig(Request<BlockAssemblerConfig, bool>),
 }
 
 #[derive(Clone)]
@@ -91,6 +111,8 @@ pub struct TxPoolController {
     sender: mpsc::Sender<Message>,
     handle: Handle,
     stop: StopHandler<()>,
+    // the configured min fee rate, shared with the relayer and the RPC without a round trip
+    min_fee_rate: Arc<AtomicU64>,
 }
 
 impl Drop for TxPoolController {
@@ -104,6 +126,11 @@ impl TxPoolController {
         &self.handle
     }
 
+    /// The min fee rate in the config, which is the lower bound of `TxPool::min_fee_rate`
+    pub fn min_fee_rate(&self) -> FeeRate {
+        FeeRate::from_u64(self.min_fee_rate.load(Ordering::SeqCst))
+    }
+
     pub fn get_block_template(
         &self,
         bytes_limit: Option<u64>,
@@ -155,6 +182,32 @@ impl TxPoolController {
         })
     }
 
+    pub fn add_candidate_uncle(&self, uncle: UncleBlockView) -> Result<bool, FailureError> {
+        let mut sender = self.sender.clone();
+        let (responder, response) = oneshot::channel();
+        let request = Request::call(uncle, responder);
+        sender
+            .try_send(Message::AddCandidateUncle(request))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e
+            })?;
+        self.handle.block_on(response).map_err(Into::into)
+    }
+
+    pub fn get_candidate_uncles(&self) -> Result<Vec<UncleBlockView>, FailureError> {
+        let mut sender = self.sender.clone();
+        let (responder, response) = oneshot::channel();
+        let request = Request::call((), responder);
+        sender
+            .try_send(Message::GetCandidateUncles(request))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e
+            })?;
+        self.handle.block_on(response).map_err(Into::into)
+    }
+
     pub fn update_tx_pool_for_reorg(
         &self,
         detached_blocks: VecDeque<BlockView>,
@@ -186,6 +239,23 @@ impl TxPoolController {
         self.handle.block_on(response).map_err(Into::into)
     }
 
+    /// Submit a package of dependent txs, which is admitted or rejected as a unit
+    pub fn submit_package(
+        &self,
+        txs: Vec<TransactionView>,
+    ) -> Result<SubmitTxsResult, FailureError> {
+        let mut sender = self.sender.clone();
+        let (responder, response) = oneshot::channel();
+        let request = Request::call(txs, responder);
+        sender
+            .try_send(Message::SubmitPackage(request))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e
+            })?;
+        self.handle.block_on(response).map_err(Into::into)
+    }
+
     pub fn plug_entry(
         &self,
         entries: Vec<TxEntry>,
@@ -201,19 +271,63 @@ impl TxPoolController {
         self.handle.block_on(response).map_err(Into::into)
     }
 
+    /// Submit the txs relayed by `peer`, the ones spending unknown cells are kept as orphans
     pub fn notify_txs(
         &self,
         txs: Vec<TransactionView>,
+        peer: Option<PeerIndex>,
         callback: NotifyTxsCallback,
     ) -> Result<(), FailureError> {
         let mut sender = self.sender.clone();
-        let notify = Notify::notify((txs, callback));
+        let notify = Notify::notify((txs, peer, callback));
         sender.try_send(Message::NotifyTxs(notify)).map_err(|e| {
             let (_m, e) = handle_try_send_error(e);
             e.into()
         })
     }
 
+    /// Submit a relayed package of dependent txs, which is admitted or rejected as a unit
+    pub fn notify_package(
+        &self,
+        txs: Vec<TransactionView>,
+        callback: NotifyTxsCallback,
+    ) -> Result<(), FailureError> {
+        let mut sender = self.sender.clone();
+        let notify = Notify::notify((txs, callback));
+        sender
+            .try_send(Message::NotifyPackage(notify))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e.into()
+            })
+    }
+
+    /// Evict the orphans relayed by the disconnected peer
+    pub fn remove_orphans_by_peer(&self, peer: PeerIndex) -> Result<(), FailureError> {
+        let mut sender = self.sender.clone();
+        let notify = Notify::notify(peer);
+        sender
+            .try_send(Message::RemoveOrphansByPeer(notify))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e.into()
+            })
+    }
+
+    /// Take the orphans accepted since their parents arrived, along with the peers relayed them
+    pub fn fetch_accepted_orphans(&self) -> Result<Vec<(Byte32, PeerIndex)>, FailureError> {
+        let mut sender = self.sender.clone();
+        let (responder, response) = oneshot::channel();
+        let request = Request::call((), responder);
+        sender
+            .try_send(Message::FetchAcceptedOrphans(request))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e
+            })?;
+        self.handle.block_on(response).map_err(Into::into)
+    }
+
     pub fn get_tx_pool_info(&self) -> Result<TxPoolInfo, FailureError> {
         let mut sender = self.sender.clone();
         let (responder, response) = oneshot::channel();
@@ -284,6 +398,82 @@ impl TxPoolController {
         self.handle.block_on(response).map_err(Into::into)
     }
 
+    /// Return the hashes of the locally submitted transactions which are due to rebroadcast
+    pub fn fetch_local_txs_to_rebroadcast(&self) -> Result<Vec<Byte32>, FailureError> {
+        let mut sender = self.sender.clone();
+        let (responder, response) = oneshot::channel();
+        let request = Request::call((), responder);
+        sender
+            .try_send(Message::FetchLocalTxsToRebroadcast(request))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e
+            })?;
+        self.handle.block_on(response).map_err(Into::into)
+    }
+
+    pub fn prioritise_tx(&self, tx_hash: Byte32, fee_delta: Capacity) -> Result<(), FailureError> {
+        let mut sender = self.sender.clone();
+        let (responder, response) = oneshot::channel();
+        let request = Request::call((tx_hash, fee_delta), responder);
+        sender
+            .try_send(Message::PrioritiseTx(request))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e
+            })?;
+        self.handle.block_on(response).map_err(Into::into)
+    }
+
+    pub fn fetch_tx_status(&self, tx_hash: Byte32) -> Result<Option<TxPoolStatus>, FailureError> {
+        let mut sender = self.sender.clone();
+        let (responder, response) = oneshot::channel();
+        let request = Request::call(tx_hash, responder);
+        sender
+            .try_send(Message::FetchTxStatus(request))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e
+            })?;
+        self.handle.block_on(response).map_err(Into::into)
+    }
+
+    /// Apply the reloaded config, only the limits which can be changed on a running pool
+    /// take effect, see `TxPool::update_config`
+    pub fn update_config(&self, config: TxPoolConfig) -> Result<(), FailureError> {
+        let mut sender = self.sender.clone();
+        let (responder, response) = oneshot::channel();
+        let request = Request::call(config, responder);
+        sender
+            .try_send(Message::UpdateConfig(request))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e
+            })?;
+        self.handle.block_on(response)?;
+        self.min_fee_rate
+            .store(config.min_fee_rate.as_u64(), Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Replace the config of the block assembler, return false if the block assembler is
+    /// disabled
+    pub fn update_block_assembler_config(
+        &self,
+        config: BlockAssemblerConfig,
+    ) -> Result<bool, FailureError> {
+        let mut sender = self.sender.clone();
+        let (responder, response) = oneshot::channel();
+        let request = Request::call(config, responder);
+        sender
+            .try_send(Message::UpdateBlockAssemblerConfig(request))
+            .map_err(|e| {
+                let (_m, e) = handle_try_send_error(e);
+                e
+            })?;
+        self.handle.block_on(response).map_err(Into::into)
+    }
+
     pub fn clear_pool(&self, new_snapshot: Arc<Snapshot>) -> Result<(), FailureError> {
         let mut sender = self.sender.clone();
         let (responder, response) = oneshot::channel();
@@ -307,20 +497,26 @@ impl TxPoolServiceBuilder {
         block_assembler_config: Option<BlockAssemblerConfig>,
         txs_verify_cache: Arc<RwLock<TxVerifyCache>>,
         snapshot_mgr: Arc<SnapshotMgr>,
+        notify_controller: NotifyController,
+        store: ChainDB,
     ) -> TxPoolServiceBuilder {
         let last_txs_updated_at = Arc::new(AtomicU64::new(0));
         let consensus = snapshot.cloned_consensus();
         let tx_pool = TxPool::new(tx_pool_config, snapshot, Arc::clone(&last_txs_updated_at));
-        let block_assembler = block_assembler_config.map(BlockAssembler::new);
+        let candidate_uncles = Arc::new(Mutex::new(CandidateUncles::with_store(store)));
+        let block_assembler = block_assembler_config
+            .map(|config| BlockAssembler::new(config, Arc::clone(&candidate_uncles)));
 
         TxPoolServiceBuilder {
             service: Some(TxPoolService::new(
                 tx_pool,
                 consensus,
                 block_assembler,
+                candidate_uncles,
                 txs_verify_cache,
                 last_txs_updated_at,
                 snapshot_mgr,
+                notify_controller,
             )),
         }
     }
@@ -330,6 +526,7 @@ impl TxPoolServiceBuilder {
         let (signal_sender, mut signal_receiver) = oneshot::channel();
 
         let service = self.service.take().expect("tx pool service start once");
+        let min_fee_rate = Arc::new(AtomicU64::new(service.tx_pool_config.min_fee_rate.as_u64()));
         let server = move |handle: Handle| async move {
             loop {
                 tokio::select! {
@@ -348,6 +545,7 @@ impl TxPoolServiceBuilder {
             sender,
             handle,
             stop,
+            min_fee_rate,
         }
     }
 }
@@ -358,19 +556,27 @@ pub struct TxPoolService {
     pub(crate) consensus: Arc<Consensus>,
     pub(crate) tx_pool_config: Arc<TxPoolConfig>,
     pub(crate) block_assembler: Option<BlockAssembler>,
+    // shared with the block assemblers, kept even if the block assembler is disabled
+    pub(crate) candidate_uncles: Arc<Mutex<CandidateUncles>>,
     pub(crate) txs_verify_cache: Arc<RwLock<TxVerifyCache>>,
     pub(crate) last_txs_updated_at: Arc<AtomicU64>,
+    pub(crate) notify_controller: NotifyController,
+    // reasons of the recently rejected transactions
+    pub(crate) recent_rejects: Arc<RwLock<LruCache<Byte32, String>>>,
     snapshot_mgr: Arc<SnapshotMgr>,
 }
 
 impl TxPoolService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         tx_pool: TxPool,
         consensus: Arc<Consensus>,
         block_assembler: Option<BlockAssembler>,
+        candidate_uncles: Arc<Mutex<CandidateUncles>>,
         txs_verify_cache: Arc<RwLock<TxVerifyCache>>,
         last_txs_updated_at: Arc<AtomicU64>,
         snapshot_mgr: Arc<SnapshotMgr>,
+        notify_controller: NotifyController,
     ) -> Self {
         let tx_pool_config = Arc::new(tx_pool.config);
         Self {
@@ -378,8 +584,11 @@ impl TxPoolService {
             consensus,
             tx_pool_config,
             block_assembler,
+            candidate_uncles,
             txs_verify_cache,
             last_txs_updated_at,
+            notify_controller,
+            recent_rejects: Arc::new(RwLock::new(LruCache::new(RECENT_REJECTS_CACHE_SIZE))),
             snapshot_mgr,
         }
     }
@@ -418,15 +627,32 @@ async fn process(service: TxPoolService, message: Message) {
             responder,
             arguments: txs,
         }) => {
-            let submit_txs_result = service.process_txs(txs).await;
+            let submit_txs_result = service.process_txs(txs, true, None).await;
             if let Err(e) = responder.send(submit_txs_result) {
                 error!("responder send submit_txs_result failed {:?}", e);
             };
         }
-        Message::NotifyTxs(Notify {
+        Message::SubmitPackage(Request {
+            responder,
+            arguments: txs,
+        }) => {
+            let submit_package_result = service.process_package(txs, true).await;
+            if let Err(e) = responder.send(submit_package_result) {
+                error!("responder send submit_package_result failed {:?}", e);
+            };
+        }
+        Message::NotifyPackage(Notify {
             arguments: (txs, callback),
         }) => {
-            let submit_txs_result = service.process_txs(txs).await;
+            let submit_package_result = service.process_package(txs, false).await;
+            if let Some(call) = callback {
+                call(submit_package_result)
+            };
+        }
+        Message::NotifyTxs(Notify {
+            arguments: (txs, peer, callback),
+        }) => {
+            let submit_txs_result = service.process_txs(txs, false, peer).await;
             if let Some(call) = callback {
                 call(submit_txs_result)
             };
@@ -505,13 +731,24 @@ async fn process(service: TxPoolService, message: Message) {
         }
         Message::NewUncle(Notify { arguments: uncle }) => {
             if service.block_assembler.is_some() {
-                let block_assembler = service.block_assembler.clone().unwrap();
-                block_assembler.candidate_uncles.lock().await.insert(uncle);
-                block_assembler
-                    .last_uncles_updated_at
-                    .store(unix_time_as_millis(), Ordering::SeqCst);
+                service.add_candidate_uncle(uncle).await;
             }
         }
+        Message::AddCandidateUncle(Request {
+            responder,
+            arguments: uncle,
+        }) => {
+            let inserted = service.add_candidate_uncle(uncle).await;
+            if let Err(e) = responder.send(inserted) {
+                error!("responder send add_candidate_uncle failed {:?}", e);
+            };
+        }
+        Message::GetCandidateUncles(Request { responder, .. }) => {
+            let uncles = service.get_candidate_uncles().await;
+            if let Err(e) = responder.send(uncles) {
+                error!("responder send get_candidate_uncles failed {:?}", e);
+            };
+        }
         Message::PlugEntry(Request {
             responder,
             arguments: (entries, target),
@@ -546,5 +783,89 @@ async fn process(service: TxPoolService, message: Message) {
                 error!("responder send clear_pool failed {:?}", e)
             };
         }
+        Message::RemoveOrphansByPeer(Notify { arguments: peer }) => {
+            service.remove_orphans_by_peer(peer).await;
+        }
+        Message::FetchAcceptedOrphans(Request { responder, .. }) => {
+            let accepted = service.tx_pool.write().await.take_accepted_orphans();
+            if let Err(e) = responder.send(accepted) {
+                error!("responder send fetch_accepted_orphans failed {:?}", e)
+            };
+        }
+        Message::FetchLocalTxsToRebroadcast(Request { responder, .. }) => {
+            let now = unix_time_as_millis();
+            let mut tx_pool = service.tx_pool.write().await;
+            // the relayer fetches the local txs on a timer, the txs expire on it as well
+            if !tx_pool.expire_txs(now).is_empty() {
+                tx_pool.report_metrics();
+            }
+            let tx_hashes = tx_pool.take_local_txs_to_rebroadcast(now);
+            drop(tx_pool);
+            if let Err(e) = responder.send(tx_hashes) {
+                error!(
+                    "responder send fetch_local_txs_to_rebroadcast failed {:?}",
+                    e
+                )
+            };
+        }
+        Message::PrioritiseTx(Request {
+            responder,
+            arguments: (tx_hash, fee_delta),
+        }) => {
+            service
+                .tx_pool
+                .write()
+                .await
+                .prioritise_tx(ProposalShortId::from_tx_hash(&tx_hash), fee_delta);
+            if let Err(e) = responder.send(()) {
+                error!("responder send prioritise_tx failed {:?}", e)
+            };
+        }
+        Message::FetchTxStatus(Request {
+            responder,
+            arguments: tx_hash,
+        }) => {
+            let status = service.tx_pool.read().await.get_tx_status(&tx_hash);
+            let status = match status {
+                Some(status) => Some(status),
+                None => service
+                    .recent_rejects
+                    .read()
+                    .await
+                    .get(&tx_hash)
+                    .cloned()
+                    .map(TxPoolStatus::Rejected),
+            };
+            if let Err(e) = responder.send(status) {
+                error!("responder send fetch_tx_status failed {:?}", e)
+            };
+        }
+        Message::UpdateConfig(Request {
+            responder,
+            arguments: config,
+        }) => {
+            service.tx_pool.write().await.update_config(config);
+            if let Err(e) = responder.send(()) {
+                error!("responder send update_config failed {:?}", e)
+            };
+        }
+        Message::UpdateBlockAssemblerConfig(Request {
+            responder,
+            arguments: config,
+        }) => {
+            let updated = match service.block_assembler {
+                Some(ref block_assembler) => {
+                    block_assembler.update_config(config).await;
+                    true
+                }
+                None => false,
+            };
+            if let Err(e) = responder.send(updated) {
+                error!(
+                    "responder send update_block_assembler_config failed {:?}",
+                    e
+                )
+            };
+        }
     }
 }
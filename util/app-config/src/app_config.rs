@@ -6,6 +6,7 @@
 
 use path_clean::PathClean;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
@@ -166,14 +167,29 @@ impl AppConfig {
 }
 
 impl CKBAppConfig {
-    fn derive_options(mut self, root_dir: &Path, subcommand_name: &str) -> Result<Self, ExitCode> {
-        self.data_dir = canonicalize_data_dir(self.data_dir, root_dir)?;
+    /// Load `ckb.toml` again for the running node, the options are derived in the same way as
+    /// `ckb run`.
+    pub fn reload<P: AsRef<Path>>(root_dir: P) -> Result<CKBAppConfig, String> {
+        let content = Resource::ckb_config(root_dir.as_ref())
+            .get()
+            .map_err(|err| format!("failed to read ckb.toml: {}", err))?;
+        let config: CKBAppConfig =
+            toml::from_slice(&content).map_err(|err| format!("invalid ckb.toml: {}", err))?;
+        config
+            .derive_options(root_dir.as_ref(), cli::CMD_RUN)
+            .map_err(|err| format!("failed to derive the options of ckb.toml: {}", err))
+    }
+
+    fn derive_options(mut self, root_dir: &Path, subcommand_name: &str) -> io::Result<Self> {
+        self.data_dir = canonicalize_data_dir(self.data_dir, root_dir);
 
         self.db.adjust(root_dir, &self.data_dir, "db");
         self.indexer
             .db
             .adjust(root_dir, &self.data_dir, "indexer_db");
         self.network.path = self.data_dir.join("network");
+        self.network.asmap = self.network.asmap.map(|path| root_dir.join(path));
+        self.notify.absolutize(root_dir);
         if self.tmp_dir.is_none() {
             self.tmp_dir = Some(self.data_dir.join("tmp"));
         }
@@ -206,7 +222,7 @@ impl CKBAppConfig {
 
 impl MinerAppConfig {
     fn derive_options(mut self, root_dir: &Path) -> Result<Self, ExitCode> {
-        self.data_dir = mkdir(canonicalize_data_dir(self.data_dir, root_dir)?)?;
+        self.data_dir = mkdir(canonicalize_data_dir(self.data_dir, root_dir))?;
         self.logger.log_dir = self.data_dir.join("logs");
         self.logger.file = self.logger.log_dir.join("miner.log");
         if self.logger.log_to_file {
@@ -219,23 +235,21 @@ impl MinerAppConfig {
     }
 }
 
-fn canonicalize_data_dir(data_dir: PathBuf, root_dir: &Path) -> Result<PathBuf, ExitCode> {
-    let path = if data_dir.is_absolute() {
+fn canonicalize_data_dir(data_dir: PathBuf, root_dir: &Path) -> PathBuf {
+    if data_dir.is_absolute() {
         data_dir
     } else {
         root_dir.join(data_dir)
-    };
-
-    Ok(path)
+    }
 }
 
-fn mkdir(dir: PathBuf) -> Result<PathBuf, ExitCode> {
+fn mkdir(dir: PathBuf) -> io::Result<PathBuf> {
     fs::create_dir_all(&dir.clean())?;
     // std::fs::canonicalize will bring windows compatibility problems
     Ok(dir)
 }
 
-fn touch(path: PathBuf) -> Result<PathBuf, ExitCode> {
+fn touch(path: PathBuf) -> io::Result<PathBuf> {
     fs::OpenOptions::new()
         .create(true)
         .append(true)
@@ -4,23 +4,49 @@ use ckb_jsonrpc_types::ScriptHashType;
 use ckb_pow::PowEngine;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub struct ExportArgs {
     pub config: Box<CKBAppConfig>,
     pub consensus: Consensus,
     pub target: PathBuf,
+    pub format: String,
+    pub compression: String,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+pub struct BackupArgs {
+    pub config: Box<CKBAppConfig>,
+    pub target: PathBuf,
+}
+
+pub struct DBCheckArgs {
+    pub config: Box<CKBAppConfig>,
+    pub consensus: Consensus,
+    pub repair: bool,
+}
+
+pub struct RollbackArgs {
+    pub config: Box<CKBAppConfig>,
+    pub consensus: Consensus,
+    /// the block number or the block hash to roll back to
+    pub to: String,
 }
 
 pub struct ImportArgs {
     pub config: Box<CKBAppConfig>,
     pub consensus: Consensus,
     pub source: PathBuf,
+    pub switch: String,
 }
 
 pub struct RunArgs {
     pub config: Box<CKBAppConfig>,
     pub consensus: Consensus,
     pub block_assembler_advanced: bool,
+    /// the directory of `ckb.toml`, which is loaded again when the config is reloaded
+    pub root_dir: PathBuf,
 }
 
 pub type ProfileArgs = Option<(Option<u64>, Option<u64>)>;
@@ -38,6 +64,7 @@ pub struct MinerArgs {
     pub pow_engine: Arc<dyn PowEngine>,
     pub memory_tracker: MemoryTrackerConfig,
     pub limit: u128,
+    pub benchmark: Option<Duration>,
 }
 
 pub struct StatsArgs {
@@ -45,6 +72,8 @@ pub struct StatsArgs {
     pub consensus: Consensus,
     pub from: Option<u64>,
     pub to: Option<u64>,
+    pub format: String,
+    pub cycles: bool,
 }
 
 pub struct InitArgs {
@@ -11,9 +11,15 @@ pub const CMD_REPLAY: &str = "replay";
 pub const CMD_STATS: &str = "stats";
 pub const CMD_LIST_HASHES: &str = "list-hashes";
 pub const CMD_RESET_DATA: &str = "reset-data";
+pub const CMD_BACKUP: &str = "backup";
+pub const CMD_DB_CHECK: &str = "db-check";
+pub const CMD_ROLLBACK: &str = "rollback";
 pub const CMD_PEERID: &str = "peer-id";
 pub const CMD_GEN_SECRET: &str = "gen";
 pub const CMD_FROM_SECRET: &str = "from-secret";
+pub const CMD_CONFIG: &str = "config";
+pub const CMD_CHECK: &str = "check";
+pub const CMD_MIGRATE: &str = "migrate";
 
 pub const ARG_CONFIG_DIR: &str = "config-dir";
 pub const ARG_FORMAT: &str = "format";
@@ -38,6 +44,8 @@ pub const ARG_FROM: &str = "from";
 pub const ARG_TO: &str = "to";
 pub const ARG_ALL: &str = "all";
 pub const ARG_LIMIT: &str = "limit";
+pub const ARG_BENCHMARK: &str = "benchmark";
+pub const ARG_DURATION: &str = "duration";
 pub const ARG_DATABASE: &str = "database";
 pub const ARG_INDEXER: &str = "indexer";
 pub const ARG_NETWORK: &str = "network";
@@ -49,6 +57,10 @@ pub const ARG_SECRET_PATH: &str = "secret-path";
 pub const ARG_PROFILE: &str = "profile";
 pub const ARG_SANITY_CHECK: &str = "sanity-check";
 pub const ARG_FULL_VERFICATION: &str = "full-verfication";
+pub const ARG_COMPRESSION: &str = "compression";
+pub const ARG_SWITCH: &str = "switch";
+pub const ARG_CYCLES: &str = "cycles";
+pub const ARG_REPAIR: &str = "repair";
 
 const GROUP_BA: &str = "ba";
 
@@ -76,7 +88,11 @@ fn basic_app<'b>() -> App<'static, 'b> {
         .subcommand(replay())
         .subcommand(stats())
         .subcommand(reset_data())
+        .subcommand(backup())
+        .subcommand(db_check())
+        .subcommand(rollback())
         .subcommand(peer_id())
+        .subcommand(config())
 }
 
 pub fn get_matches(version: &Version) -> ArgMatches<'static> {
@@ -107,6 +123,17 @@ fn miner() -> App<'static, 'static> {
             0 means the miner will never exit. [default: 0]",
                 ),
         )
+        .arg(Arg::with_name(ARG_BENCHMARK).long(ARG_BENCHMARK).help(
+            "Runs the configured workers against a synthetic work \
+                     and prints the hash rate of Eaglesong and EaglesongBlake2b",
+        ))
+        .arg(
+            Arg::with_name(ARG_DURATION)
+                .long(ARG_DURATION)
+                .takes_value(true)
+                .requires(ARG_BENCHMARK)
+                .help("Benchmark duration in seconds for each hash function [default: 10]"),
+        )
 }
 
 fn reset_data() -> App<'static, 'static> {
@@ -159,6 +186,56 @@ fn reset_data() -> App<'static, 'static> {
         )
 }
 
+fn backup() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_BACKUP)
+        .about(
+            "Backs up the chain database, the indexer database and the peer store of a stopped \
+             node, use the `backup_database` RPC to back up a running node\n\
+             Example:\n\
+             ckb -C <dir> backup <path>",
+        )
+        .arg(
+            Arg::with_name(ARG_TARGET)
+                .short("t")
+                .long(ARG_TARGET)
+                .value_name("path")
+                .required(true)
+                .index(1)
+                .help("Specifies the backup directory, it must not exist or be empty."),
+        )
+}
+
+fn db_check() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_DB_CHECK)
+        .about(
+            "Checks that the indexes, transaction infos, block exts, epochs and live cells \
+             in the database agree with the main chain blocks\n\
+             Example:\n\
+             ckb -C <dir> db-check --repair",
+        )
+        .arg(
+            Arg::with_name(ARG_REPAIR)
+                .long(ARG_REPAIR)
+                .help("Rebuilds the inconsistent columns from the blocks without a resync."),
+        )
+}
+
+fn rollback() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_ROLLBACK)
+        .about(
+            "Rolls back the chain to a block on the main chain, the node must be stopped\n\
+             Example:\n\
+             ckb -C <dir> rollback --to 1000",
+        )
+        .arg(
+            Arg::with_name(ARG_TO)
+                .long(ARG_TO)
+                .value_name("number|hash")
+                .required(true)
+                .help("Specifies the block number or the 0x-prefixed block hash to roll back to."),
+        )
+}
+
 pub(crate) fn stats() -> App<'static, 'static> {
     SubCommand::with_name(CMD_STATS)
         .about(
@@ -178,6 +255,18 @@ pub(crate) fn stats() -> App<'static, 'static> {
                 .takes_value(true)
                 .help("Specifies to block number."),
         )
+        .arg(
+            Arg::with_name(ARG_FORMAT)
+                .long(ARG_FORMAT)
+                .possible_values(&["text", "csv", "json"])
+                .default_value("text")
+                .help("Specifies the output format."),
+        )
+        .arg(
+            Arg::with_name(ARG_CYCLES)
+                .long(ARG_CYCLES)
+                .help("Re-runs scripts to report cycle usage, which is slow."),
+        )
 }
 
 fn replay() -> App<'static, 'static> {
@@ -226,6 +315,32 @@ fn export() -> App<'static, 'static> {
                 .index(1)
                 .help("Specifies the export target path."),
         )
+        .arg(
+            Arg::with_name(ARG_FORMAT)
+                .long(ARG_FORMAT)
+                .possible_values(&["json", "bin"])
+                .default_value("json")
+                .help("Specifies the export data format."),
+        )
+        .arg(
+            Arg::with_name(ARG_COMPRESSION)
+                .long(ARG_COMPRESSION)
+                .possible_values(&["none", "zstd", "snappy"])
+                .default_value("none")
+                .help("Specifies the compression of the binary format."),
+        )
+        .arg(
+            Arg::with_name(ARG_FROM)
+                .long(ARG_FROM)
+                .takes_value(true)
+                .help("Specifies from block number."),
+        )
+        .arg(
+            Arg::with_name(ARG_TO)
+                .long(ARG_TO)
+                .takes_value(true)
+                .help("Specifies to block number."),
+        )
 }
 
 fn import() -> App<'static, 'static> {
@@ -238,7 +353,22 @@ fn import() -> App<'static, 'static> {
                 .value_name("path")
                 .required(true)
                 .index(1)
-                .help("Specifies the exported data path."),
+                .help(
+                    "Specifies the exported data path. Both JSON and binary format are accepted, \
+                     blocks already in the chain are skipped.",
+                ),
+        )
+        .arg(
+            Arg::with_name(ARG_SWITCH)
+                .long(ARG_SWITCH)
+                .takes_value(true)
+                .default_value("none")
+                .help(
+                    "Skips verifications for trusted data, a comma separated list of \
+                     disable-epoch, disable-uncles, disable-two-phase-commit, disable-daoheader, \
+                     disable-reward, disable-non-contextual and disable-all. \
+                     Scripts are only skipped with disable-all.",
+                ),
         )
 }
 
@@ -395,6 +525,23 @@ fn peer_id() -> App<'static, 'static> {
         )
 }
 
+fn config() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_CONFIG)
+        .about("Checks and migrates ckb.toml and ckb-miner.toml")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name(CMD_CHECK).about(
+            "Reports the unknown and deprecated keys in the config files\n\
+             Example:\n\
+             ckb -C <dir> config check",
+        ))
+        .subcommand(SubCommand::with_name(CMD_MIGRATE).about(
+            "Rewrites the deprecated keys in the config files, keeping the other lines \
+             untouched, the original files are saved with the suffix `.bak`\n\
+             Example:\n\
+             ckb -C <dir> config migrate",
+        ))
+}
+
 fn is_hex(hex: String) -> Result<(), String> {
     let tmp = hex.as_bytes();
     if tmp.len() < 2 {
@@ -491,4 +638,27 @@ mod tests {
 
         assert_eq!(1, sub_matches.occurrences_of(ARG_BA_ADVANCED));
     }
+
+    #[test]
+    fn export_format() {
+        let ok_matches = basic_app().get_matches_from_safe(&[
+            "ckb",
+            "export",
+            "data",
+            "--format",
+            "bin",
+            "--compression",
+            "zstd",
+            "--from",
+            "10",
+        ]);
+        assert!(
+            ok_matches.is_ok(),
+            "--format bin is OK with --compression, but gets error: {:?}",
+            ok_matches.err()
+        );
+
+        let err = basic_app().get_matches_from_safe(&["ckb", "export", "data", "--format", "xml"]);
+        assert_eq!(clap::ErrorKind::InvalidValue, err.err().unwrap().kind);
+    }
 }
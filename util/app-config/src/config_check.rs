@@ -0,0 +1,508 @@
+//! # Config Check
+//!
+//! Validates `ckb.toml` and `ckb-miner.toml` against the current config structs, and migrates
+//! the files written by older versions.
+//!
+//! The unknown keys are collected while deserializing, because serde silently ignores them,
+//! and the known keys for suggestions come from the bundled config files and the parsed config.
+//! The migration edits the file line by line, so the user values and comments are kept.
+
+use crate::{CKBAppConfig, MinerAppConfig};
+use ckb_resource::{Resource, CKB_CONFIG_FILE_NAME, MINER_CONFIG_FILE_NAME};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFile {
+    CKB,
+    Miner,
+}
+
+/// A key which is renamed or no longer used.
+struct Deprecation {
+    key: &'static str,
+    /// The new key, the deprecated key is commented out by the migration if it is `None`
+    replacement: Option<&'static str>,
+    reason: &'static str,
+}
+
+const LOGGER_FILE: Deprecation = Deprecation {
+    key: "logger.file",
+    replacement: None,
+    reason: "the log file is always in `<data_dir>/logs`",
+};
+
+const LOGGER_LOG_DIR: Deprecation = Deprecation {
+    key: "logger.log_dir",
+    replacement: None,
+    reason: "the log directory is always `<data_dir>/logs`",
+};
+
+const CKB_DEPRECATIONS: &[Deprecation] = &[
+    Deprecation {
+        key: "notifier",
+        replacement: Some("notify"),
+        reason: "the section is renamed",
+    },
+    Deprecation {
+        key: "network.path",
+        replacement: None,
+        reason: "the network directory is always `<data_dir>/network`",
+    },
+    LOGGER_FILE,
+    LOGGER_LOG_DIR,
+];
+
+const MINER_DEPRECATIONS: &[Deprecation] = &[LOGGER_FILE, LOGGER_LOG_DIR];
+
+impl ConfigFile {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ConfigFile::CKB => CKB_CONFIG_FILE_NAME,
+            ConfigFile::Miner => MINER_CONFIG_FILE_NAME,
+        }
+    }
+
+    fn bundled(self) -> Resource {
+        match self {
+            ConfigFile::CKB => Resource::bundled_ckb_config(),
+            ConfigFile::Miner => Resource::bundled_miner_config(),
+        }
+    }
+
+    fn deprecations(self) -> &'static [Deprecation] {
+        match self {
+            ConfigFile::CKB => CKB_DEPRECATIONS,
+            ConfigFile::Miner => MINER_DEPRECATIONS,
+        }
+    }
+
+    fn deprecation(self, key: &str) -> Option<&'static Deprecation> {
+        self.deprecations().iter().find(|deprecation| {
+            key == deprecation.key
+                || (key.starts_with(deprecation.key)
+                    && key[deprecation.key.len()..].starts_with('.'))
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigIssue {
+    /// The key is ignored by the current version
+    Unknown {
+        key: String,
+        suggestion: Option<String>,
+    },
+    /// The key is renamed or no longer used, it can be fixed by `ckb config migrate`
+    Deprecated {
+        key: String,
+        replacement: Option<String>,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigIssue::Unknown { key, suggestion } => {
+                write!(f, "unknown key `{}`", key)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", did you mean `{}`?", suggestion)?;
+                }
+                Ok(())
+            }
+            ConfigIssue::Deprecated {
+                key,
+                replacement,
+                reason,
+            } => {
+                write!(f, "deprecated key `{}`, {}", key, reason)?;
+                match replacement {
+                    Some(replacement) => write!(f, ", use `{}` instead", replacement),
+                    None => write!(f, ", it is ignored"),
+                }
+            }
+        }
+    }
+}
+
+/// The result of `migrate_config`.
+pub struct Migration {
+    /// The migrated file content
+    pub content: String,
+    /// The deprecated keys rewritten by the migration
+    pub changes: Vec<ConfigIssue>,
+    /// The issues left in the migrated file, which must be fixed by hand
+    pub remaining: Vec<ConfigIssue>,
+}
+
+impl Migration {
+    pub fn is_changed(&self) -> bool {
+        !self.changes.is_empty()
+    }
+}
+
+/// Checks the config file content, returns the unknown and deprecated keys.
+///
+/// Returns an error if the content can't be parsed as the config file.
+pub fn check_config(file: ConfigFile, content: &str) -> Result<Vec<ConfigIssue>, toml::de::Error> {
+    match file {
+        ConfigFile::CKB => check::<CKBAppConfig>(file, content),
+        ConfigFile::Miner => check::<MinerAppConfig>(file, content),
+    }
+}
+
+/// Rewrites the deprecated keys in the config file content.
+///
+/// Renamed keys and sections are renamed in place, and the keys no longer used are commented
+/// out, other lines are kept untouched.
+pub fn migrate_config(file: ConfigFile, content: &str) -> Result<Migration, String> {
+    let (changes, remaining): (Vec<_>, Vec<_>) = check_config(file, content)
+        .map_err(|err| format!("invalid {}: {}", file.file_name(), err))?
+        .into_iter()
+        .partition(|issue| matches!(issue, ConfigIssue::Deprecated { .. }));
+    if changes.is_empty() {
+        return Ok(Migration {
+            content: content.to_owned(),
+            changes,
+            remaining,
+        });
+    }
+
+    let content = rewrite(file, content);
+    let remaining = check_config(file, &content)
+        .map_err(|err| format!("the migrated {} is invalid: {}", file.file_name(), err))?;
+    Ok(Migration {
+        content,
+        changes,
+        remaining,
+    })
+}
+
+#[derive(Clone, Debug)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+fn check<T: DeserializeOwned + Serialize>(
+    file: ConfigFile,
+    content: &str,
+) -> Result<Vec<ConfigIssue>, toml::de::Error> {
+    let value: toml::Value = toml::from_str(content)?;
+    let mut ignored = Vec::new();
+    let config: T = serde_ignored::deserialize(&mut toml::Deserializer::new(content), |path| {
+        let mut segments = Vec::new();
+        collect_segments(&path, &mut segments);
+        ignored.push(segments);
+    })?;
+
+    let mut references = Vec::new();
+    if let Ok(bundled) = file.bundled().get() {
+        references.extend(toml::from_slice::<toml::Value>(&bundled).ok());
+    }
+    references.extend(toml::Value::try_from(&config).ok());
+
+    let mut issues: Vec<_> = file
+        .deprecations()
+        .iter()
+        .filter(|deprecation| lookup(&value, deprecation.key).is_some())
+        .map(|deprecation| ConfigIssue::Deprecated {
+            key: deprecation.key.to_owned(),
+            replacement: deprecation.replacement.map(ToOwned::to_owned),
+            reason: deprecation.reason.to_owned(),
+        })
+        .collect();
+    for segments in ignored {
+        let key = join_segments(&segments);
+        if file.deprecation(&key).is_some() {
+            continue;
+        }
+        let suggestion = match segments.split_last() {
+            Some((Segment::Key(name), parent)) => {
+                let mut candidates = BTreeSet::new();
+                for reference in references.iter() {
+                    collect_candidates(reference, parent, &mut candidates);
+                }
+                suggest(name, &candidates)
+            }
+            _ => None,
+        };
+        issues.push(ConfigIssue::Unknown { key, suggestion });
+    }
+    Ok(issues)
+}
+
+fn collect_segments(path: &serde_ignored::Path, segments: &mut Vec<Segment>) {
+    match path {
+        serde_ignored::Path::Root => {}
+        serde_ignored::Path::Seq { parent, index } => {
+            collect_segments(parent, segments);
+            segments.push(Segment::Index(*index));
+        }
+        serde_ignored::Path::Map { parent, key } => {
+            collect_segments(parent, segments);
+            segments.push(Segment::Key(key.to_owned()));
+        }
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => collect_segments(parent, segments),
+    }
+}
+
+fn join_segments(segments: &[Segment]) -> String {
+    let mut key = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Key(name) => {
+                if !key.is_empty() {
+                    key.push('.');
+                }
+                key.push_str(name);
+            }
+            Segment::Index(index) => key.push_str(&format!("[{}]", index)),
+        }
+    }
+    key
+}
+
+fn lookup<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.')
+        .try_fold(value, |value, name| value.get(name))
+}
+
+// The keys of the tables at `path`, every element is visited for an array.
+fn collect_candidates(value: &toml::Value, path: &[Segment], candidates: &mut BTreeSet<String>) {
+    match path.split_first() {
+        None => {
+            if let Some(table) = value.as_table() {
+                candidates.extend(table.keys().cloned());
+            }
+        }
+        Some((Segment::Key(name), rest)) => {
+            if let Some(value) = value.get(name) {
+                collect_candidates(value, rest, candidates);
+            }
+        }
+        Some((Segment::Index(_), rest)) => {
+            if let Some(array) = value.as_array() {
+                for value in array {
+                    collect_candidates(value, rest, candidates);
+                }
+            }
+        }
+    }
+}
+
+fn suggest(name: &str, candidates: &BTreeSet<String>) -> Option<String> {
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance * 3 <= name.len().max(3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_owned())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == *cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+fn rewrite(file: ConfigFile, content: &str) -> String {
+    let mut output = Vec::new();
+    let mut table = String::new();
+    // The deprecated section which is being commented out
+    let mut commented_table = false;
+    // The unclosed brackets of a multi-line value which is being commented out
+    let mut commented_brackets = 0;
+
+    for line in content.lines() {
+        if commented_brackets > 0 {
+            commented_brackets += bracket_balance(line);
+            output.push(format!("# {}", line));
+            continue;
+        }
+
+        let trimmed = line.trim_start();
+        if let Some(name) = table_header(trimmed) {
+            commented_table = false;
+            table = name.to_owned();
+            if let Some(deprecation) = file.deprecation(name) {
+                match deprecation.replacement {
+                    Some(replacement) => {
+                        table = format!("{}{}", replacement, &name[deprecation.key.len()..]);
+                        output.push(line.replacen(name, &table, 1));
+                    }
+                    None => {
+                        commented_table = true;
+                        output.push(format!("# Deprecated, {}", deprecation.reason));
+                        output.push(format!("# {}", line));
+                    }
+                }
+                continue;
+            }
+        } else if commented_table {
+            output.push(format!("# {}", line));
+            continue;
+        } else if let Some((name, value)) = key_value(trimmed) {
+            let key = if table.is_empty() {
+                name.to_owned()
+            } else {
+                format!("{}.{}", table, name)
+            };
+            if let Some(deprecation) = file.deprecation(&key) {
+                let renamed = deprecation.replacement.and_then(|replacement| {
+                    let (parent, new_name) = match replacement.rfind('.') {
+                        Some(pos) => (&replacement[..pos], &replacement[pos + 1..]),
+                        None => ("", replacement),
+                    };
+                    // only renames in the same table can be done in place
+                    if parent == table {
+                        Some(line.replacen(name, new_name, 1))
+                    } else {
+                        None
+                    }
+                });
+                match renamed {
+                    Some(renamed) => output.push(renamed),
+                    None => {
+                        output.push(format!("# Deprecated, {}", deprecation.reason));
+                        output.push(format!("# {}", line));
+                        commented_brackets = bracket_balance(value);
+                    }
+                }
+                continue;
+            }
+        }
+        output.push(line.to_owned());
+    }
+
+    let mut content = output.join("\n");
+    content.push('\n');
+    content
+}
+
+// Returns the table name of `[name]` or `[[name]]`
+fn table_header(line: &str) -> Option<&str> {
+    if !line.starts_with('[') {
+        return None;
+    }
+    let end = line.find(']')?;
+    Some(line[..end].trim_start_matches('[').trim())
+}
+
+// Returns the key and the value of `key = value`
+fn key_value(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with('#') {
+        return None;
+    }
+    let pos = line.find('=')?;
+    let key = line[..pos].trim();
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return None;
+    }
+    Some((key, &line[pos + 1..]))
+}
+
+// The count of `[` minus the count of `]` outside strings and comments
+fn bracket_balance(line: &str) -> i32 {
+    let mut balance = 0;
+    let mut quote = None;
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' => balance += 1,
+                ']' => balance -= 1,
+                '#' => break,
+                _ => {}
+            },
+        }
+    }
+    balance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundled(file: ConfigFile) -> String {
+        String::from_utf8(file.bundled().get().unwrap().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_bundled_config_files_have_no_issues() {
+        for file in &[ConfigFile::CKB, ConfigFile::Miner] {
+            assert_eq!(check_config(*file, &bundled(*file)).unwrap(), vec![]);
+        }
+    }
+
+    #[test]
+    fn test_unknown_keys_with_suggestions() {
+        let content = bundled(ConfigFile::CKB)
+            .replace("expiry_hours = 12", "expiry_hour = 12")
+            .replace("bootnode_mode = false", "bootnode_mode = false\nfoo = 1");
+        let issues = check_config(ConfigFile::CKB, &content).unwrap();
+        assert_eq!(
+            issues,
+            vec![
+                ConfigIssue::Unknown {
+                    key: "network.foo".to_owned(),
+                    suggestion: None,
+                },
+                ConfigIssue::Unknown {
+                    key: "tx_pool.expiry_hour".to_owned(),
+                    suggestion: Some("expiry_hours".to_owned()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_migrate_deprecated_keys() {
+        let content = format!(
+            "{}\n[notifier]\n# the script\nnew_block_notify_script = \"notify.sh\"\n",
+            bundled(ConfigFile::CKB).replace(
+                "color = true",
+                "color = true\nfile = \"ckb.log\"\nlog_dir = [\n  \"logs\",\n]"
+            )
+        );
+        let issues = check_config(ConfigFile::CKB, &content).unwrap();
+        assert_eq!(issues.len(), 3);
+
+        let migration = migrate_config(ConfigFile::CKB, &content).unwrap();
+        assert_eq!(migration.changes, issues);
+        assert!(migration.remaining.is_empty());
+        assert!(migration.content.contains("[notify]\n# the script\n"));
+        assert!(migration.content.contains("# file = \"ckb.log\""));
+        assert!(migration
+            .content
+            .contains("# log_dir = [\n#   \"logs\",\n# ]"));
+
+        let config: CKBAppConfig = toml::from_str(&migration.content).unwrap();
+        assert_eq!(
+            config.notify.new_block_notify_script,
+            Some("notify.sh".to_owned())
+        );
+
+        let migration = migrate_config(ConfigFile::CKB, &migration.content).unwrap();
+        assert!(!migration.is_changed());
+    }
+}
@@ -18,9 +18,9 @@ pub use miner::{
 };
 pub use network::{Config as NetworkConfig, HeaderMapConfig, SyncConfig};
 pub use network_alert::Config as NetworkAlertConfig;
-pub use notify::Config as NotifyConfig;
+pub use notify::{Config as NotifyConfig, HookConfig, HookEvent, HookTarget};
 pub use rpc::{Config as RpcConfig, Module as RpcModule};
 pub use store::Config as StoreConfig;
-pub use tx_pool::{BlockAssemblerConfig, TxPoolConfig};
+pub use tx_pool::{BlockAssemblerConfig, RewardShareConfig, TxPoolConfig};
 
 pub(crate) use network::{generate_random_key, read_secret_key, write_secret_to_file};
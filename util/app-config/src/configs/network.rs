@@ -8,6 +8,8 @@ use std::path::PathBuf;
 
 // Max data size in send buffer: 24MB (a little larger than max frame length)
 const DEFAULT_SEND_BUFFER: usize = 24 * 1024 * 1024;
+// Max outbound peers in the same network group
+const DEFAULT_MAX_OUTBOUND_PEERS_PER_GROUP: u32 = 2;
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct Config {
@@ -37,6 +39,10 @@ pub struct Config {
     pub bootnode_mode: bool,
     // Max send buffer size
     pub max_send_buffer: Option<usize>,
+    // Max outbound peers in the same network group
+    pub max_outbound_peers_per_group: Option<u32>,
+    // ASN map file used to group peers by autonomous system
+    pub asmap: Option<PathBuf>,
     pub sync: Option<SyncConfig>,
 }
 
@@ -162,6 +168,11 @@ impl Config {
         self.max_send_buffer.unwrap_or(DEFAULT_SEND_BUFFER)
     }
 
+    pub fn max_outbound_peers_per_group(&self) -> u32 {
+        self.max_outbound_peers_per_group
+            .unwrap_or(DEFAULT_MAX_OUTBOUND_PEERS_PER_GROUP)
+    }
+
     fn read_secret_key(&self) -> Result<Option<secio::SecioKeyPair>, Error> {
         let path = self.secret_key_path();
         read_secret_key(path)
@@ -1,6 +1,96 @@
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
 pub struct Config {
     pub new_block_notify_script: Option<String>,
     pub network_alert_notify_script: Option<String>,
+    /// The event hooks delivered off the notify thread
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
+    /// The capacity of the queue of each hook, the events are dropped when it is full
+    pub hook_queue_size: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HookConfig {
+    #[serde(flatten)]
+    pub target: HookTarget,
+    /// The events delivered to this hook, all the events when it is empty
+    #[serde(default)]
+    pub events: Vec<HookEvent>,
+    /// How many times a failed delivery is retried before the event is dropped
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// The delay before the first retry, it is doubled on each following retry
+    #[serde(default = "default_retry_interval_ms")]
+    pub retry_interval_ms: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "type")]
+pub enum HookTarget {
+    /// POST each event as a JSON object to the url
+    Webhook {
+        url: String,
+        #[serde(default = "default_webhook_timeout_ms")]
+        timeout_ms: u64,
+    },
+    /// Write each event as a line of JSON to the Unix domain socket
+    UnixSocket { path: PathBuf },
+    /// Append each event as a line of JSON to the file
+    File { path: PathBuf },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    NewBlock,
+    Reorg,
+    NetworkAlert,
+}
+
+const fn default_max_retries() -> u32 {
+    3
+}
+
+const fn default_retry_interval_ms() -> u64 {
+    1000
+}
+
+const fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
+impl Config {
+    // The relative paths of the socket and file hooks are relative to `ckb.toml`
+    pub fn absolutize(&mut self, root_dir: &Path) {
+        for hook in self.hooks.iter_mut() {
+            match &mut hook.target {
+                HookTarget::UnixSocket { path } | HookTarget::File { path } => {
+                    if path.is_relative() {
+                        *path = root_dir.join(&path);
+                    }
+                }
+                HookTarget::Webhook { .. } => {}
+            }
+        }
+    }
+}
+
+impl HookConfig {
+    pub fn accepts(&self, event: HookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+impl HookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::NewBlock => "new_block",
+            HookEvent::Reorg => "reorg",
+            HookEvent::NetworkAlert => "network_alert",
+        }
+    }
 }
@@ -30,6 +30,21 @@ pub struct Config {
     pub reject_ill_transactions: bool,
     #[serde(default)]
     pub enable_deprecated_rpc: bool,
+    // `/health/ready` fails if the tip block is older than this
+    #[serde(default = "default_health_max_tip_age_secs")]
+    pub health_max_tip_age_secs: u64,
+    // `/health/ready` fails if there are fewer outbound peers than this
+    #[serde(default = "default_health_min_outbound_peers")]
+    pub health_min_outbound_peers: usize,
+}
+
+const fn default_health_max_tip_age_secs() -> u64 {
+    // 10 minutes
+    600
+}
+
+const fn default_health_min_outbound_peers() -> usize {
+    1
 }
 
 impl Config {
@@ -1,4 +1,4 @@
-use ckb_chain_spec::consensus::TWO_IN_TWO_OUT_CYCLES;
+use ckb_chain_spec::consensus::{MAX_REWARD_SHARES, TWO_IN_TWO_OUT_CYCLES};
 use ckb_fee_estimator::FeeRate;
 use ckb_jsonrpc_types::{JsonBytes, ScriptHashType};
 use ckb_types::core::Cycle;
@@ -11,6 +11,8 @@ const DEFAULT_MIN_FEE_RATE: FeeRate = FeeRate::from_u64(1000);
 const DEFAULT_MAX_TX_VERIFY_CYCLES: Cycle = TWO_IN_TWO_OUT_CYCLES * 20;
 // default max ancestors count
 const DEFAULT_MAX_ANCESTORS_COUNT: usize = 25;
+// default expiry hours of pending and gap txs
+const DEFAULT_EXPIRY_HOURS: u64 = 12;
 
 /// Transaction pool configuration
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
@@ -31,6 +33,9 @@ pub struct TxPoolConfig {
     pub max_tx_verify_cycles: Cycle,
     // max ancestors size limit for a single tx
     pub max_ancestors_count: usize,
+    // pending and gap txs are removed after staying in the pool for <expiry_hours> hours,
+    // 0 means they never expire
+    pub expiry_hours: Option<u64>,
 }
 
 impl Default for TxPoolConfig {
@@ -44,14 +49,59 @@ impl Default for TxPoolConfig {
             min_fee_rate: DEFAULT_MIN_FEE_RATE,
             max_tx_verify_cycles: DEFAULT_MAX_TX_VERIFY_CYCLES,
             max_ancestors_count: DEFAULT_MAX_ANCESTORS_COUNT,
+            expiry_hours: None,
         }
     }
 }
 
+impl TxPoolConfig {
+    pub fn expiry_hours(&self) -> u64 {
+        self.expiry_hours.unwrap_or(DEFAULT_EXPIRY_HOURS)
+    }
+
+    /// Copy the limits which can be changed on a running tx-pool, the others are kept
+    pub fn update_limits(&mut self, config: &TxPoolConfig) {
+        self.max_mem_size = config.max_mem_size;
+        self.max_cycles = config.max_cycles;
+        self.min_fee_rate = config.min_fee_rate;
+        self.expiry_hours = config.expiry_hours;
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct BlockAssemblerConfig {
     pub code_hash: H256,
     pub hash_type: ScriptHashType,
     pub args: JsonBytes,
     pub message: JsonBytes,
+    // Split the block reward across these locks by weight, it only takes effect after
+    // the split reward switch is activated in the chain spec
+    #[serde(default)]
+    pub reward_shares: Vec<RewardShareConfig>,
+}
+
+impl BlockAssemblerConfig {
+    /// Check the reward shares, the cellbase verifier rejects the split cellbase witness which
+    /// has more than `MAX_REWARD_SHARES` shares or a share with zero weight
+    pub fn check_reward_shares(&self) -> Result<(), String> {
+        if self.reward_shares.len() > MAX_REWARD_SHARES {
+            return Err(format!(
+                "block_assembler.reward_shares has {} shares, the limit is {}",
+                self.reward_shares.len(),
+                MAX_REWARD_SHARES
+            ));
+        }
+        if self.reward_shares.iter().any(|share| share.weight == 0) {
+            return Err("block_assembler.reward_shares has a share with zero weight".to_owned());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RewardShareConfig {
+    pub code_hash: H256,
+    pub hash_type: ScriptHashType,
+    pub args: JsonBytes,
+    pub weight: u32,
 }
@@ -1,15 +1,17 @@
 mod app_config;
 mod args;
 pub mod cli;
+mod config_check;
 mod configs;
 mod exit_code;
 mod sentry_config;
 
 pub use app_config::{AppConfig, CKBAppConfig, MinerAppConfig};
 pub use args::{
-    ExportArgs, ImportArgs, InitArgs, MinerArgs, PeerIDArgs, ReplayArgs, ResetDataArgs, RunArgs,
-    StatsArgs,
+    BackupArgs, DBCheckArgs, ExportArgs, ImportArgs, InitArgs, MinerArgs, PeerIDArgs, ReplayArgs,
+    ResetDataArgs, RollbackArgs, RunArgs, StatsArgs,
 };
+pub use config_check::{check_config, migrate_config, ConfigFile, ConfigIssue, Migration};
 pub use configs::*;
 pub use exit_code::ExitCode;
 
@@ -17,6 +19,9 @@ use ckb_chain_spec::{consensus::Consensus, ChainSpec};
 use ckb_jsonrpc_types::ScriptHashType;
 use clap::{value_t, ArgMatches, ErrorKind};
 use std::path::PathBuf;
+use std::time::Duration;
+
+const DEFAULT_BENCHMARK_SECS: u64 = 10;
 
 pub struct Setup {
     pub subcommand_name: String,
@@ -48,11 +53,13 @@ impl Setup {
     pub fn run<'m>(self, matches: &ArgMatches<'m>) -> Result<RunArgs, ExitCode> {
         let consensus = self.consensus()?;
         let config = self.config.into_ckb()?;
+        let root_dir = Self::root_dir_from_matches(matches)?;
 
         Ok(RunArgs {
             config,
             consensus,
             block_assembler_advanced: matches.is_present(cli::ARG_BA_ADVANCED),
+            root_dir,
         })
     }
 
@@ -69,11 +76,25 @@ impl Setup {
             }
         };
 
+        let benchmark = if matches.is_present(cli::ARG_BENCHMARK) {
+            let secs = match value_t!(matches, cli::ARG_DURATION, u64) {
+                Ok(secs) => secs,
+                Err(ref e) if e.kind == ErrorKind::ArgumentNotFound => DEFAULT_BENCHMARK_SECS,
+                Err(e) => {
+                    return Err(e.into());
+                }
+            };
+            Some(Duration::from_secs(secs))
+        } else {
+            None
+        };
+
         Ok(MinerArgs {
             pow_engine,
             config: config.miner,
             memory_tracker,
             limit,
+            benchmark,
         })
     }
 
@@ -106,12 +127,16 @@ impl Setup {
 
         let from = option_value_t!(matches, cli::ARG_FROM, u64)?;
         let to = option_value_t!(matches, cli::ARG_TO, u64)?;
+        let format = matches.value_of(cli::ARG_FORMAT).unwrap().to_string();
+        let cycles = matches.is_present(cli::ARG_CYCLES);
 
         Ok(StatsArgs {
             config,
             consensus,
             from,
             to,
+            format,
+            cycles,
         })
     }
 
@@ -119,11 +144,13 @@ impl Setup {
         let consensus = self.consensus()?;
         let config = self.config.into_ckb()?;
         let source = value_t!(matches.value_of(cli::ARG_SOURCE), PathBuf)?;
+        let switch = matches.value_of(cli::ARG_SWITCH).unwrap().to_string();
 
         Ok(ImportArgs {
             config,
             consensus,
             source,
+            switch,
         })
     }
 
@@ -131,11 +158,50 @@ impl Setup {
         let consensus = self.consensus()?;
         let config = self.config.into_ckb()?;
         let target = value_t!(matches.value_of(cli::ARG_TARGET), PathBuf)?;
+        let format = matches.value_of(cli::ARG_FORMAT).unwrap().to_string();
+        let compression = matches.value_of(cli::ARG_COMPRESSION).unwrap().to_string();
+        let from = option_value_t!(matches, cli::ARG_FROM, u64)?;
+        let to = option_value_t!(matches, cli::ARG_TO, u64)?;
 
         Ok(ExportArgs {
             config,
             consensus,
             target,
+            format,
+            compression,
+            from,
+            to,
+        })
+    }
+
+    pub fn backup<'m>(self, matches: &ArgMatches<'m>) -> Result<BackupArgs, ExitCode> {
+        let config = self.config.into_ckb()?;
+        let target = value_t!(matches.value_of(cli::ARG_TARGET), PathBuf)?;
+
+        Ok(BackupArgs { config, target })
+    }
+
+    pub fn db_check<'m>(self, matches: &ArgMatches<'m>) -> Result<DBCheckArgs, ExitCode> {
+        let consensus = self.consensus()?;
+        let config = self.config.into_ckb()?;
+        let repair = matches.is_present(cli::ARG_REPAIR);
+
+        Ok(DBCheckArgs {
+            config,
+            consensus,
+            repair,
+        })
+    }
+
+    pub fn rollback<'m>(self, matches: &ArgMatches<'m>) -> Result<RollbackArgs, ExitCode> {
+        let consensus = self.consensus()?;
+        let config = self.config.into_ckb()?;
+        let to = value_t!(matches.value_of(cli::ARG_TO), String)?;
+
+        Ok(RollbackArgs {
+            config,
+            consensus,
+            to,
         })
     }
 
@@ -357,5 +423,15 @@ mod tests {
             .clone()
             .get_matches_from_safe(vec!["", CMD_STATS, "--from", "10", "--to", "100"]);
         assert!(stats.is_ok());
+
+        let stats = app
+            .clone()
+            .get_matches_from_safe(vec!["", CMD_STATS, "--format", "csv", "--cycles"]);
+        assert!(stats.is_ok());
+
+        let stats = app
+            .clone()
+            .get_matches_from_safe(vec!["", CMD_STATS, "--format", "xml"]);
+        assert!(stats.is_err());
     }
 }
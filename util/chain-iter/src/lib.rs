@@ -10,8 +10,20 @@ pub struct ChainIterator<'a, S: ChainStore<'a>> {
 
 impl<'a, S: ChainStore<'a>> ChainIterator<'a, S> {
     pub fn new(store: &'a S) -> Self {
-        let current = store.get_block_hash(0).and_then(|h| store.get_block(&h));
-        let tip = store.get_tip_header().expect("store inited").number();
+        Self::with_range(store, None, None)
+    }
+
+    /// Iterates over main chain blocks in range `[from, to]`,
+    /// `from` defaults to genesis and `to` defaults to tip.
+    pub fn with_range(store: &'a S, from: Option<BlockNumber>, to: Option<BlockNumber>) -> Self {
+        let from = from.unwrap_or(0);
+        let tip_number = store.get_tip_header().expect("store inited").number();
+        let tip = to.map(|to| to.min(tip_number)).unwrap_or(tip_number);
+        let current = if from <= tip {
+            store.get_block_hash(from).and_then(|h| store.get_block(&h))
+        } else {
+            None
+        };
         ChainIterator {
             store,
             current,
@@ -20,13 +32,14 @@ impl<'a, S: ChainStore<'a>> ChainIterator<'a, S> {
     }
 
     pub fn len(&self) -> u64 {
-        self.tip + 1
+        match self.current {
+            Some(ref b) => self.tip - b.header().number() + 1,
+            None => 0,
+        }
     }
 
-    // we always have genesis, this function may be meaningless
-    // but for convention, mute len-without-is-empty lint
     pub fn is_empty(&self) -> bool {
-        false
+        self.current.is_none()
     }
 }
 
@@ -37,6 +50,7 @@ impl<'a, S: ChainStore<'a>> Iterator for ChainIterator<'a, S> {
         let current = self.current.take();
 
         self.current = match current {
+            Some(ref b) if b.header().number() >= self.tip => None,
             Some(ref b) => {
                 if let Some(block_hash) = self.store.get_block_hash(b.header().number() + 1) {
                     self.store.get_block(&block_hash)
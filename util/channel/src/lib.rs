@@ -1,3 +1,3 @@
 pub use crossbeam_channel::{
-    bounded, select, unbounded, Receiver, RecvError, RecvTimeoutError, Sender,
+    bounded, select, unbounded, Receiver, RecvError, RecvTimeoutError, Sender, TrySendError,
 };
@@ -0,0 +1,431 @@
+//! # The Config Reloader
+//!
+//! [ConfigReloader](ConfigReloader) applies the changes of `ckb.toml` to a running node, it's
+//! triggered by `SIGHUP` and the `reload_config` RPC.
+
+use ckb_app_config::CKBAppConfig;
+use ckb_jsonrpc_types::ConfigReloadReport;
+use ckb_logger::info;
+use ckb_logger_config::Config as LogConfig;
+use ckb_logger_service::Logger;
+use ckb_network::NetworkController;
+use ckb_shared::shared::Shared;
+use ckb_util::Mutex;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// The options which can be applied to the running node, an option matches its sub-options
+/// too. The changes of all the other options take effect only after restarting the node.
+const RELOADABLE_OPTIONS: &[&str] = &[
+    "logger.filter",
+    "logger.extra",
+    "tx_pool.max_mem_size",
+    "tx_pool.max_cycles",
+    "tx_pool.min_fee_rate",
+    "tx_pool.expiry_hours",
+    "network.whitelist_peers",
+    "network.whitelist_only",
+    "network.bootnodes",
+    "block_assembler",
+    "notify",
+];
+
+/// Reloads `ckb.toml` and applies the changed options to the running node.
+///
+/// The changes are detected against the config applied last time, so an option which
+/// requires a restart keeps being reported until the node is restarted.
+pub struct ConfigReloader {
+    /// the directory of `ckb.toml`
+    pub root_dir: PathBuf,
+    pub shared: Shared,
+    pub network_controller: NetworkController,
+    /// allow the block assembler to use lock scripts other than the recommended one
+    pub block_assembler_advanced: bool,
+    // the config applied to the running node
+    current: Mutex<CKBAppConfig>,
+}
+
+impl ConfigReloader {
+    pub fn new(
+        root_dir: PathBuf,
+        config: CKBAppConfig,
+        shared: Shared,
+        network_controller: NetworkController,
+    ) -> Self {
+        ConfigReloader {
+            root_dir,
+            shared,
+            network_controller,
+            block_assembler_advanced: false,
+            current: Mutex::new(config),
+        }
+    }
+
+    pub fn block_assembler_advanced(mut self, advanced: bool) -> Self {
+        self.block_assembler_advanced = advanced;
+        self
+    }
+
+    /// Nothing is applied if `ckb.toml` can not be loaded.
+    pub fn reload(&self) -> Result<ConfigReloadReport, String> {
+        let config = CKBAppConfig::reload(&self.root_dir)?;
+        let mut current = self.current.lock();
+        let changed = changed_options(&to_value(&*current)?, &to_value(&config)?);
+
+        let mut report = ConfigReloadReport::default();
+        let mut reloadable = Vec::new();
+        for option in changed {
+            if is_reloadable(&option) {
+                reloadable.push(option);
+            } else {
+                report.restart_required.push(option);
+            }
+        }
+        let take = |section: &str| -> Vec<String> {
+            reloadable
+                .iter()
+                .filter(|option| is_option_of(option, section))
+                .cloned()
+                .collect()
+        };
+
+        let options = take("logger.filter");
+        if !options.is_empty() && apply(&mut report, options, reload_main_logger(&config.logger)) {
+            current.logger.filter = config.logger.filter.clone();
+        }
+
+        let options = take("logger.extra");
+        if !options.is_empty()
+            && apply(
+                &mut report,
+                options,
+                reload_extra_loggers(&current.logger, &config.logger),
+            )
+        {
+            current.logger.extra = config.logger.extra.clone();
+        }
+
+        let options = take("tx_pool");
+        if !options.is_empty() {
+            let result = self
+                .shared
+                .tx_pool_controller()
+                .update_config(config.tx_pool)
+                .map_err(|err| err.to_string());
+            if apply(&mut report, options, result) {
+                current.tx_pool.update_limits(&config.tx_pool);
+            }
+        }
+
+        let options = take("network");
+        if !options.is_empty() {
+            let result = self
+                .network_controller
+                .update_whitelist_and_bootnodes(&config.network)
+                .map_err(|err| err.to_string());
+            if apply(&mut report, options, result) {
+                current.network.whitelist_peers = config.network.whitelist_peers.clone();
+                current.network.whitelist_only = config.network.whitelist_only;
+                current.network.bootnodes = config.network.bootnodes.clone();
+            }
+        }
+
+        let options = take("block_assembler");
+        if !options.is_empty() {
+            match (&current.block_assembler, &config.block_assembler) {
+                (Some(old), Some(new)) => {
+                    // the lock script of the running block assembler has passed the checks of
+                    // `ckb run`, a new lock script is only checked against it
+                    let result = if let Err(err) = new.check_reward_shares() {
+                        Err(err)
+                    } else if !self.block_assembler_advanced
+                        && (new.code_hash != old.code_hash
+                            || new.hash_type != old.hash_type
+                            || new.args.len() != old.args.len())
+                    {
+                        Err("the block assembler is not a recommended lock format, \
+                             use `ckb run --ba-advanced` to use other lock scripts"
+                            .to_owned())
+                    } else {
+                        self.shared
+                            .tx_pool_controller()
+                            .update_block_assembler_config(new.clone())
+                            .map_err(|err| err.to_string())
+                    };
+                    match result {
+                        Ok(true) => {
+                            report.applied.extend(options);
+                            current.block_assembler = config.block_assembler.clone();
+                        }
+                        // the block assembler is disabled when the node starts
+                        Ok(false) => report.restart_required.extend(options),
+                        Err(err) => report.failed.extend(
+                            options
+                                .into_iter()
+                                .map(|option| format!("{}: {}", option, err)),
+                        ),
+                    }
+                }
+                // enabling or disabling the block assembler changes the miner RPC module
+                _ => report.restart_required.extend(options),
+            }
+        }
+
+        let options = take("notify");
+        if !options.is_empty() {
+            self.shared
+                .notify_controller()
+                .update_config(config.notify.clone());
+            report.applied.extend(options);
+            current.notify = config.notify.clone();
+        }
+
+        info!(
+            "reload config, applied: {:?}, restart required: {:?}, failed: {:?}",
+            report.applied, report.restart_required, report.failed
+        );
+        Ok(report)
+    }
+}
+
+fn to_value(config: &CKBAppConfig) -> Result<Value, String> {
+    serde_json::to_value(config).map_err(|err| format!("failed to compare ckb.toml: {}", err))
+}
+
+// The changed options are named as `section.option`, or `section` if the section is not a
+// table on either side.
+fn changed_options(old: &Value, new: &Value) -> Vec<String> {
+    let mut changed = Vec::new();
+    for section in keys(old, new) {
+        let (old, new) = (&old[&section], &new[&section]);
+        if old == new {
+            continue;
+        }
+        if old.is_object() && new.is_object() {
+            changed.extend(
+                keys(old, new)
+                    .into_iter()
+                    .filter(|option| old[option] != new[option])
+                    .map(|option| format!("{}.{}", section, option)),
+            );
+        } else {
+            changed.push(section);
+        }
+    }
+    changed
+}
+
+fn keys(old: &Value, new: &Value) -> BTreeSet<String> {
+    old.as_object()
+        .into_iter()
+        .chain(new.as_object())
+        .flat_map(|object| object.keys().cloned())
+        .collect()
+}
+
+fn is_option_of(option: &str, section: &str) -> bool {
+    option == section || option.starts_with(&format!("{}.", section))
+}
+
+fn is_reloadable(option: &str) -> bool {
+    RELOADABLE_OPTIONS
+        .iter()
+        .any(|reloadable| is_option_of(option, reloadable))
+}
+
+// Return whether the options are applied
+fn apply(
+    report: &mut ConfigReloadReport,
+    options: Vec<String>,
+    result: Result<(), String>,
+) -> bool {
+    match result {
+        Ok(()) => {
+            report.applied.extend(options);
+            true
+        }
+        Err(err) => {
+            report.failed.extend(
+                options
+                    .into_iter()
+                    .map(|option| format!("{}: {}", option, err)),
+            );
+            false
+        }
+    }
+}
+
+fn reload_main_logger(config: &LogConfig) -> Result<(), String> {
+    // same as the logger service, the environment variable takes precedence over ckb.toml
+    if std::env::var("CKB_LOG").is_ok() {
+        return Err("the filter is overridden by the environment variable CKB_LOG".to_owned());
+    }
+    Logger::update_main_logger(
+        Some(config.filter.clone().unwrap_or_default()),
+        None,
+        None,
+        None,
+    )
+}
+
+fn reload_extra_loggers(old: &LogConfig, new: &LogConfig) -> Result<(), String> {
+    for name in new.extra.keys() {
+        Logger::check_extra_logger_name(name)?;
+    }
+    for name in old.extra.keys() {
+        if !new.extra.contains_key(name) {
+            Logger::remove_extra_logger(name.to_owned())?;
+        }
+    }
+    for (name, extra) in new.extra.iter() {
+        if old.extra.get(name).map(|old| &old.filter) != Some(&extra.filter) {
+            Logger::update_extra_logger(name.to_owned(), extra.filter.clone())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_app_config::NetworkConfig;
+    use ckb_network::{DefaultExitHandler, NetworkService, NetworkState};
+    use ckb_resource::{Resource, TemplateContext};
+    use ckb_shared::shared::SharedBuilder;
+    use serde_json::json;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    fn setup_reloader(root_dir: &Path) -> ConfigReloader {
+        let context = TemplateContext::new(
+            "dev",
+            vec![
+                ("rpc_port", "7000"),
+                ("p2p_port", "8000"),
+                ("log_to_file", "false"),
+                ("log_to_stdout", "true"),
+                ("block_assembler", ""),
+                ("spec_source", "bundled"),
+            ],
+        );
+        Resource::bundled_ckb_config()
+            .export(&context, root_dir)
+            .expect("export config files");
+        let config = CKBAppConfig::reload(root_dir).unwrap();
+
+        let (shared, _) = SharedBuilder::default()
+            .tx_pool_config(config.tx_pool)
+            .build()
+            .unwrap();
+        let network_controller = {
+            let mut network_config = NetworkConfig::default();
+            network_config.path = config.network.path.clone();
+            let network_state = Arc::new(
+                NetworkState::from_config(network_config).expect("Init network state failed"),
+            );
+            NetworkService::new(
+                network_state,
+                Vec::new(),
+                Vec::new(),
+                shared.consensus().identify_name(),
+                "0.1.0".to_string(),
+                DefaultExitHandler::default(),
+            )
+            .start(Some("reload-test-network"))
+            .expect("Start network service failed")
+        };
+        ConfigReloader::new(root_dir.to_path_buf(), config, shared, network_controller)
+    }
+
+    fn update_ckb_toml(root_dir: &Path, from: &str, to: &str) {
+        let path = root_dir.join("ckb.toml");
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains(from));
+        fs::write(&path, content.replace(from, to)).unwrap();
+    }
+
+    #[test]
+    fn test_reload() {
+        let dir = tempfile::Builder::new()
+            .prefix("test_reload")
+            .tempdir()
+            .unwrap();
+        let reloader = setup_reloader(dir.path());
+
+        let report = reloader.reload().unwrap();
+        assert!(report.applied.is_empty());
+        assert!(report.restart_required.is_empty());
+        assert!(report.failed.is_empty());
+
+        update_ckb_toml(dir.path(), "min_fee_rate = 1_000", "min_fee_rate = 2_000");
+        update_ckb_toml(dir.path(), "127.0.0.1:7000", "127.0.0.1:7001");
+        let report = reloader.reload().unwrap();
+        assert_eq!(report.applied, vec!["tx_pool.min_fee_rate".to_owned()]);
+        assert_eq!(
+            report.restart_required,
+            vec!["rpc.listen_address".to_owned()]
+        );
+        assert!(report.failed.is_empty());
+        assert_eq!(
+            reloader.shared.tx_pool_controller().min_fee_rate().as_u64(),
+            2_000
+        );
+
+        // the applied changes are not reported again, unlike the ones requiring a restart
+        let report = reloader.reload().unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(
+            report.restart_required,
+            vec!["rpc.listen_address".to_owned()]
+        );
+
+        // nothing is applied if ckb.toml is invalid
+        update_ckb_toml(
+            dir.path(),
+            "min_fee_rate = 2_000",
+            "min_fee_rate = \"3_000\"",
+        );
+        assert!(reloader.reload().is_err());
+        assert_eq!(
+            reloader.shared.tx_pool_controller().min_fee_rate().as_u64(),
+            2_000
+        );
+    }
+
+    #[test]
+    fn test_changed_options() {
+        let old = json!({
+            "tx_pool": { "min_fee_rate": 1000, "max_mem_size": 20 },
+            "block_assembler": null,
+            "notify": { "hooks": [] },
+            "data_dir": "data",
+        });
+        let new = json!({
+            "tx_pool": { "min_fee_rate": 2000, "max_mem_size": 20 },
+            "block_assembler": { "args": "0x" },
+            "notify": { "hooks": [], "new_block_notify_script": "notify.sh" },
+            "data_dir": "data",
+        });
+        assert_eq!(
+            changed_options(&old, &new),
+            vec![
+                "block_assembler".to_owned(),
+                "notify.new_block_notify_script".to_owned(),
+                "tx_pool.min_fee_rate".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_reloadable() {
+        assert!(is_reloadable("tx_pool.min_fee_rate"));
+        assert!(is_reloadable("block_assembler"));
+        assert!(is_reloadable("block_assembler.args"));
+        assert!(is_reloadable("notify.hooks"));
+        assert!(!is_reloadable("tx_pool.max_tx_verify_cycles"));
+        assert!(!is_reloadable("rpc.modules"));
+        assert!(!is_reloadable("logger.filter_extra"));
+    }
+}
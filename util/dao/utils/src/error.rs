@@ -11,6 +11,19 @@ pub enum DaoError {
     ZeroC,
 }
 
+impl DaoError {
+    /// The variant name
+    pub fn name(&self) -> &'static str {
+        match self {
+            DaoError::InvalidHeader => "InvalidHeader",
+            DaoError::InvalidOutPoint => "InvalidOutPoint",
+            DaoError::InvalidDaoFormat => "InvalidDaoFormat",
+            DaoError::Overflow => "Overflow",
+            DaoError::ZeroC => "ZeroC",
+        }
+    }
+}
+
 impl From<DaoError> for Error {
     fn from(error: DaoError) -> Self {
         error.context(ErrorKind::Dao).into()
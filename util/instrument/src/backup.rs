@@ -0,0 +1,290 @@
+use ckb_app_config::{DBConfig, StoreConfig};
+use ckb_db::RocksDB;
+use ckb_jsonrpc_types::BackupManifest;
+use ckb_logger::info;
+use ckb_store::{ChainDB, ChainStore, COLUMNS};
+use ckb_types::{
+    core::{BlockNumber, HeaderView},
+    prelude::*,
+};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The manifest file in the backup directory, it is written after all the databases, so a
+/// directory with the manifest is a complete backup.
+pub const BACKUP_MANIFEST: &str = "backup.json";
+/// The manifest is renamed to this once `ckb run` has verified the restored directory.
+pub const VERIFIED_BACKUP_MANIFEST: &str = "backup.verified.json";
+// The backup directory mirrors the layout of the data directory, so it can be restored by
+// replacing the data directory with it.
+const CHAIN_DB: &str = "db";
+const INDEXER_DB: &str = "indexer_db";
+const PEER_STORE_DB: &str = "network/peer_store/db";
+
+/// Backup the databases into a directory while the node is running, using RocksDB
+/// checkpoints.
+pub struct Backup {
+    /// backup target path, it must not exist or be empty
+    pub target: PathBuf,
+    /// the chain database, it doesn't need a running `Shared`
+    pub store: ChainDB,
+    /// the database of the indexer, skipped if `None`
+    pub indexer_db: Option<RocksDB>,
+    /// the database of the peer store, skipped if `None`
+    pub peer_store_db: Option<RocksDB>,
+}
+
+impl Backup {
+    pub fn new(store: ChainDB, target: PathBuf) -> Self {
+        Backup {
+            store,
+            target,
+            indexer_db: None,
+            peer_store_db: None,
+        }
+    }
+
+    pub fn indexer_db(mut self, db: RocksDB) -> Self {
+        self.indexer_db = Some(db);
+        self
+    }
+
+    pub fn peer_store_db(mut self, db: RocksDB) -> Self {
+        self.peer_store_db = Some(db);
+        self
+    }
+
+    pub fn execute(self) -> Result<BackupManifest, Box<dyn Error>> {
+        if self.target.exists() && fs::read_dir(&self.target)?.next().is_some() {
+            return Err(format!("backup target {} is not empty", self.target.display()).into());
+        }
+        fs::create_dir_all(&self.target)?;
+
+        let mut databases = Vec::new();
+        // The indexer is checkpointed before the chain, so it is never ahead of the chain
+        // database it is restored with.
+        if let Some(ref db) = self.indexer_db {
+            self.checkpoint(db, INDEXER_DB, &mut databases)?;
+        }
+        self.checkpoint(self.store.db(), CHAIN_DB, &mut databases)?;
+        if let Some(ref db) = self.peer_store_db {
+            self.checkpoint(db, PEER_STORE_DB, &mut databases)?;
+        }
+
+        // The chain may have moved on since the checkpoint is created, so the tip is read
+        // from the checkpoint itself.
+        let tip = read_tip(&self.target.join(CHAIN_DB))?;
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        let manifest = BackupManifest {
+            tip_hash: tip.hash().unpack(),
+            tip_number: tip.number().into(),
+            created_at: created_at.into(),
+            databases,
+        };
+        let tmp_path = self.target.join(format!("{}.tmp", BACKUP_MANIFEST));
+        fs::write(&tmp_path, serde_json::to_string_pretty(&manifest)?)?;
+        fs::rename(&tmp_path, self.target.join(BACKUP_MANIFEST))?;
+        info!(
+            "Backup #{} {:#x} into {}",
+            tip.number(),
+            tip.hash(),
+            self.target.display()
+        );
+        Ok(manifest)
+    }
+
+    fn checkpoint(
+        &self,
+        db: &RocksDB,
+        name: &str,
+        databases: &mut Vec<String>,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = self.target.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        db.create_checkpoint(&path)
+            .map_err(|err| format!("create checkpoint of {} failed: {}", name, err))?;
+        databases.push(name.to_owned());
+        Ok(())
+    }
+}
+
+/// Verify the data directory restored from a backup against the manifest in it.
+///
+/// Returns `None` if the directory isn't restored from a backup or has been verified.
+pub fn verify_restored_backup(
+    store: &ChainDB,
+    data_dir: &Path,
+) -> Result<Option<BackupManifest>, Box<dyn Error>> {
+    let path = data_dir.join(BACKUP_MANIFEST);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let manifest: BackupManifest = serde_json::from_slice(&fs::read(&path)?)?;
+    let tip_number: BlockNumber = manifest.tip_number.into();
+    let tip_hash = manifest.tip_hash.pack();
+    if store.get_block_hash(tip_number).as_ref() != Some(&tip_hash) {
+        return Err(format!(
+            "the chain database doesn't contain the backup tip #{} {:#x}",
+            tip_number, tip_hash
+        )
+        .into());
+    }
+    fs::rename(&path, data_dir.join(VERIFIED_BACKUP_MANIFEST))?;
+    Ok(Some(manifest))
+}
+
+fn read_tip(path: &Path) -> Result<HeaderView, Box<dyn Error>> {
+    let config = DBConfig {
+        path: path.to_path_buf(),
+        ..Default::default()
+    };
+    let db = RocksDB::open_with_check(&config, COLUMNS).map_err(|err| err.to_string())?;
+    let store = ChainDB::new(db, StoreConfig::default());
+    store
+        .get_tip_header()
+        .ok_or_else(|| "the tip is not found in the chain database".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_chain::{chain::ChainService, switch::Switch};
+    use ckb_shared::shared::{Shared, SharedBuilder};
+    use ckb_test_chain_utils::{always_success_cellbase, always_success_consensus};
+    use ckb_types::core::{BlockBuilder, Capacity};
+    use std::sync::Arc;
+
+    const HEIGHT: BlockNumber = 5;
+
+    // A chain of blocks with only the cellbase
+    fn setup_chain() -> Shared {
+        let (shared, table) = SharedBuilder::default()
+            .consensus(always_success_consensus())
+            .build()
+            .unwrap();
+        let chain = ChainService::new(shared.clone(), table).start::<&str>(None);
+        let mut parent = shared.consensus().genesis_block().header();
+        for number in 1..=HEIGHT {
+            let cellbase =
+                always_success_cellbase(number, Capacity::bytes(1000).unwrap(), shared.consensus());
+            let block = BlockBuilder::default()
+                .transaction(cellbase)
+                .parent_hash(parent.hash())
+                .number(number.pack())
+                .epoch(
+                    shared
+                        .consensus()
+                        .genesis_epoch_ext()
+                        .number_with_fraction(number)
+                        .pack(),
+                )
+                .timestamp((parent.timestamp() + 1).pack())
+                .compact_target(parent.compact_target().pack())
+                .build();
+            chain
+                .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+                .unwrap();
+            parent = block.header();
+        }
+        shared
+    }
+
+    fn open_chain_db(path: &Path) -> ChainDB {
+        let config = DBConfig {
+            path: path.to_path_buf(),
+            ..Default::default()
+        };
+        ChainDB::new(
+            RocksDB::open_with_check(&config, COLUMNS).unwrap(),
+            StoreConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_execute() {
+        let shared = setup_chain();
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("test_backup_execute")
+            .tempdir()
+            .unwrap();
+        let target = tmp_dir.path().join("backup");
+
+        let manifest = Backup::new(shared.store().clone(), target.clone())
+            .indexer_db(RocksDB::open_tmp(1))
+            .execute()
+            .unwrap();
+        let tip = shared.store().get_tip_header().unwrap();
+        assert_eq!(manifest.tip_number.value(), HEIGHT);
+        assert_eq!(manifest.tip_hash, tip.hash().unpack());
+        assert_eq!(manifest.databases, vec![INDEXER_DB, CHAIN_DB]);
+
+        let saved: BackupManifest =
+            serde_json::from_slice(&fs::read(target.join(BACKUP_MANIFEST)).unwrap()).unwrap();
+        assert_eq!(saved, manifest);
+        assert!(!target.join(format!("{}.tmp", BACKUP_MANIFEST)).exists());
+        let restored = open_chain_db(&target.join(CHAIN_DB));
+        assert_eq!(restored.get_tip_header(), Some(tip));
+
+        // the target isn't empty now
+        assert!(Backup::new(shared.store().clone(), target)
+            .execute()
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_restored_backup() {
+        let shared = setup_chain();
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("test_verify_restored_backup")
+            .tempdir()
+            .unwrap();
+        let data_dir = tmp_dir.path().join("data");
+
+        // the data directory isn't restored from a backup
+        fs::create_dir_all(&data_dir).unwrap();
+        assert!(verify_restored_backup(shared.store(), &data_dir)
+            .unwrap()
+            .is_none());
+        fs::remove_dir(&data_dir).unwrap();
+
+        let manifest = Backup::new(shared.store().clone(), data_dir.clone())
+            .execute()
+            .unwrap();
+        let restored = open_chain_db(&data_dir.join(CHAIN_DB));
+        assert_eq!(
+            verify_restored_backup(&restored, &data_dir).unwrap(),
+            Some(manifest)
+        );
+        assert!(!data_dir.join(BACKUP_MANIFEST).exists());
+        assert!(data_dir.join(VERIFIED_BACKUP_MANIFEST).exists());
+        // verified only once
+        assert!(verify_restored_backup(&restored, &data_dir)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_verify_restored_backup_with_other_chain() {
+        let shared = setup_chain();
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("test_verify_restored_backup_with_other_chain")
+            .tempdir()
+            .unwrap();
+        let data_dir = tmp_dir.path().join("data");
+        Backup::new(shared.store().clone(), data_dir.clone())
+            .execute()
+            .unwrap();
+
+        // a chain database which contains only the genesis block
+        let (other, _) = SharedBuilder::default()
+            .consensus(always_success_consensus())
+            .build()
+            .unwrap();
+        assert!(verify_restored_backup(other.store(), &data_dir).is_err());
+        assert!(data_dir.join(BACKUP_MANIFEST).exists());
+    }
+}
@@ -0,0 +1,677 @@
+use ckb_db::{Col, IteratorMode};
+use ckb_error::Error as CKBError;
+use ckb_shared::shared::Shared;
+use ckb_store::{
+    ChainDB, ChainStore, StoreTransaction, COLUMN_CELL_SET, COLUMN_EPOCH, COLUMN_INDEX,
+    COLUMN_TRANSACTION_INFO, COLUMN_UNCLES,
+};
+use ckb_types::{
+    core::{BlockExt, BlockNumber, TransactionMeta},
+    packed,
+    prelude::*,
+    U256,
+};
+#[cfg(feature = "progress_bar")]
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// The pending repairs are committed once they reach this size
+const REPAIR_BATCH_SIZE: usize = 10_000;
+
+/// Checks that the columns derived from the main chain blocks agree with the blocks.
+///
+/// The main chain is walked back from the tip header through the parent hashes, the headers,
+/// bodies and epochs are taken as the source of truth. The block index, transaction infos,
+/// uncles, block exts, epoch indexes and the live cell set are rebuilt from them on repair.
+pub struct DBCheck {
+    pub shared: Shared,
+    /// rebuild the inconsistent derived columns
+    pub repair: bool,
+}
+
+/// A disagreement between the columns found by `DBCheck`
+#[derive(Debug)]
+pub struct Inconsistency {
+    pub column: &'static str,
+    pub message: String,
+    /// whether it can be rebuilt from the other columns
+    pub repairable: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct DBCheckReport {
+    pub checked_blocks: u64,
+    pub inconsistencies: Vec<Inconsistency>,
+    /// whether the repairable inconsistencies are repaired
+    pub repaired: bool,
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {}", self.column, self.message)?;
+        if !self.repairable {
+            write!(f, " (unrepairable)")?;
+        }
+        Ok(())
+    }
+}
+
+impl DBCheck {
+    pub fn new(shared: Shared) -> Self {
+        DBCheck {
+            shared,
+            repair: false,
+        }
+    }
+
+    pub fn repair(mut self, repair: bool) -> Self {
+        self.repair = repair;
+        self
+    }
+
+    pub fn execute(self) -> Result<DBCheckReport, Box<dyn Error>> {
+        let store = self.shared.store();
+        let consensus = self.shared.consensus();
+        let mut checker = Checker {
+            store,
+            genesis_epoch_hash: consensus
+                .genesis_epoch_ext()
+                .last_block_hash_in_previous_epoch(),
+            report: DBCheckReport::default(),
+            repair: if self.repair {
+                Some(Repair::new(store))
+            } else {
+                None
+            },
+        };
+        let main_chain = checker.main_chain(consensus.genesis_hash())?;
+        checker.check_blocks(&main_chain)?;
+        checker.check_stale_entries(&main_chain)?;
+        if let Some(mut repair) = checker.repair.take() {
+            repair.commit().map_err(|err| err.to_string())?;
+            checker.report.repaired = true;
+        }
+        Ok(checker.report)
+    }
+}
+
+struct Checker<'a> {
+    store: &'a ChainDB,
+    genesis_epoch_hash: packed::Byte32,
+    report: DBCheckReport,
+    repair: Option<Repair<'a>>,
+}
+
+impl<'a> Checker<'a> {
+    fn inconsistent(&mut self, column: &'static str, message: String, repairable: bool) {
+        self.report.inconsistencies.push(Inconsistency {
+            column,
+            message,
+            repairable,
+        });
+    }
+
+    fn put(&mut self, col: Col, key: &[u8], value: &[u8]) -> Result<(), String> {
+        self.write(|txn| txn.insert_raw(col, key, value))
+    }
+
+    fn delete(&mut self, col: Col, key: &[u8]) -> Result<(), String> {
+        self.write(|txn| txn.delete(col, key))
+    }
+
+    // Only writes when repairing
+    fn write<F>(&mut self, f: F) -> Result<(), String>
+    where
+        F: FnOnce(&StoreTransaction) -> Result<(), CKBError>,
+    {
+        match self.repair.as_mut() {
+            Some(repair) => repair.write(f).map_err(|err| err.to_string()),
+            None => Ok(()),
+        }
+    }
+
+    // The main chain hashes indexed by the block number
+    fn main_chain(
+        &mut self,
+        genesis_hash: packed::Byte32,
+    ) -> Result<Vec<packed::Byte32>, Box<dyn Error>> {
+        let tip = self
+            .store
+            .get_tip_header()
+            .ok_or("the tip header is not found")?;
+        let mut main_chain = vec![packed::Byte32::zero(); tip.number() as usize + 1];
+        let mut header = tip;
+        loop {
+            let number = header.number();
+            main_chain[number as usize] = header.hash();
+            if number == 0 {
+                break;
+            }
+            let parent_hash = header.parent_hash();
+            header = self.store.get_block_header(&parent_hash).ok_or_else(|| {
+                format!(
+                    "the header of #{} {:#x} is not found, the main chain is broken",
+                    number - 1,
+                    parent_hash
+                )
+            })?;
+        }
+        if main_chain[0] != genesis_hash {
+            return Err(format!(
+                "the genesis {:#x} doesn't match the chain spec {:#x}",
+                main_chain[0], genesis_hash
+            )
+            .into());
+        }
+        Ok(main_chain)
+    }
+
+    fn check_blocks(&mut self, main_chain: &[packed::Byte32]) -> Result<(), Box<dyn Error>> {
+        let progress = Progress::new(main_chain.len() as u64);
+        let mut cell_set: Option<HashMap<packed::Byte32, TransactionMeta>> = Some(HashMap::new());
+        // the totals of the block exts can't be recomputed after a missing block
+        let mut totals = Some((U256::zero(), 0));
+
+        for (number, hash) in main_chain.iter().enumerate() {
+            let number = number as BlockNumber;
+            self.report.checked_blocks += 1;
+            progress.inc();
+
+            let block = match self.store.get_block(hash) {
+                Some(block) => block,
+                None => {
+                    let message = format!("the block #{} {:#x} is not found", number, hash);
+                    self.inconsistent("block", message, false);
+                    cell_set = None;
+                    totals = None;
+                    continue;
+                }
+            };
+            let packed_number: packed::Uint64 = number.pack();
+            let epoch = block.epoch();
+
+            // block index
+            if self.store.get_block_hash(number).as_ref() != Some(hash) {
+                let message = format!("#{} is not indexed to {:#x}", number, hash);
+                self.inconsistent("index", message, true);
+                self.put(COLUMN_INDEX, packed_number.as_slice(), hash.as_slice())?;
+            }
+            if self.store.get_block_number(hash) != Some(number) {
+                let message = format!("{:#x} is not indexed to #{}", hash, number);
+                self.inconsistent("index", message, true);
+                self.put(COLUMN_INDEX, hash.as_slice(), packed_number.as_slice())?;
+            }
+
+            // transaction infos
+            for (index, tx_hash) in block.tx_hashes().iter().enumerate() {
+                let key = packed::TransactionKey::new_builder()
+                    .block_hash(hash.clone())
+                    .index(index.pack())
+                    .build();
+                let info = packed::TransactionInfo::new_builder()
+                    .key(key)
+                    .block_number(packed_number.clone())
+                    .block_epoch(block.data().header().raw().epoch())
+                    .build();
+                let stored = self.store.get_transaction_info_packed(tx_hash);
+                if stored.as_ref().map(|stored| stored.as_slice()) != Some(info.as_slice()) {
+                    let message = format!(
+                        "the info of transaction {:#x} in #{} is {}",
+                        tx_hash,
+                        number,
+                        if stored.is_some() { "wrong" } else { "missing" },
+                    );
+                    self.inconsistent("transaction_info", message, true);
+                    self.put(COLUMN_TRANSACTION_INFO, tx_hash.as_slice(), info.as_slice())?;
+                }
+            }
+
+            // uncles
+            for uncle in block.uncles().into_iter() {
+                if !self.store.is_uncle(&uncle.hash()) {
+                    let message =
+                        format!("the uncle {:#x} of #{} is missing", uncle.hash(), number);
+                    self.inconsistent("uncles", message, true);
+                    self.put(
+                        COLUMN_UNCLES,
+                        uncle.hash().as_slice(),
+                        uncle.header().pack().as_slice(),
+                    )?;
+                }
+            }
+
+            // block ext
+            if let Some((ref mut total_difficulty, ref mut total_uncles_count)) = totals {
+                *total_difficulty = total_difficulty.clone() + block.header().difficulty();
+                *total_uncles_count += block.data().uncles().len() as u64;
+            }
+            match (self.store.get_block_ext(hash), totals.clone()) {
+                (Some(ext), Some((total_difficulty, total_uncles_count))) => {
+                    if ext.total_difficulty != total_difficulty
+                        || ext.total_uncles_count != total_uncles_count
+                    {
+                        let message = format!(
+                            "the total difficulty or uncles count of #{} {:#x} is wrong",
+                            number, hash
+                        );
+                        self.inconsistent("block_ext", message, true);
+                        let ext = BlockExt {
+                            total_difficulty,
+                            total_uncles_count,
+                            ..ext
+                        };
+                        self.write(|txn| txn.insert_block_ext(hash, &ext))?;
+                    }
+                }
+                (Some(_), None) => {}
+                (None, _) => {
+                    // the fees of the transactions can't be recovered without verifying them
+                    let message = format!("the ext of #{} {:#x} is missing", number, hash);
+                    self.inconsistent("block_ext", message, false);
+                }
+            }
+
+            // epoch, indexed by the hash of the last block in the previous epoch
+            let epoch_hash = if epoch.number() == 0 {
+                self.genesis_epoch_hash.clone()
+            } else {
+                main_chain[(number - epoch.index() - 1) as usize].clone()
+            };
+            match self.store.get_epoch_ext(&epoch_hash) {
+                Some(ext) if ext.number() == epoch.number() => {
+                    if self.store.get_block_epoch_index(hash).as_ref() != Some(&epoch_hash) {
+                        let message =
+                            format!("the epoch index of #{} {:#x} is wrong", number, hash);
+                        self.inconsistent("block_epoch", message, true);
+                        self.write(|txn| txn.insert_block_epoch_index(hash, &epoch_hash))?;
+                    }
+                    if epoch.index() == 0
+                        && self.store.get_epoch_index(epoch.number()).as_ref() != Some(&epoch_hash)
+                    {
+                        let message = format!("the epoch {} is not indexed", epoch.number());
+                        self.inconsistent("epoch", message, true);
+                        let packed_epoch_number: packed::Uint64 = epoch.number().pack();
+                        self.put(
+                            COLUMN_EPOCH,
+                            packed_epoch_number.as_slice(),
+                            epoch_hash.as_slice(),
+                        )?;
+                    }
+                    if number as usize == main_chain.len() - 1
+                        && self.store.get_current_epoch_ext().as_ref() != Some(&ext)
+                    {
+                        let message = "the current epoch is not the epoch of the tip".to_owned();
+                        self.inconsistent("meta", message, true);
+                        self.write(|txn| txn.insert_current_epoch_ext(&ext))?;
+                    }
+                }
+                _ => {
+                    let message = format!(
+                        "the epoch {} of #{} {:#x} is not found",
+                        epoch.number(),
+                        number,
+                        hash
+                    );
+                    self.inconsistent("epoch", message, false);
+                }
+            }
+
+            // live cells
+            if let Some(ref mut cell_set) = cell_set {
+                for tx in block.transactions() {
+                    for out_point in tx.input_pts_iter() {
+                        let tx_hash = out_point.tx_hash();
+                        if let Some(meta) = cell_set.get_mut(&tx_hash) {
+                            meta.set_dead(out_point.index().unpack());
+                            if meta.all_dead() {
+                                cell_set.remove(&tx_hash);
+                            }
+                        }
+                    }
+                    let outputs_len = tx.outputs().len();
+                    let meta = if tx.is_cellbase() {
+                        TransactionMeta::new_cellbase(
+                            number,
+                            epoch.number(),
+                            hash.clone(),
+                            outputs_len,
+                            false,
+                        )
+                    } else {
+                        TransactionMeta::new(
+                            number,
+                            epoch.number(),
+                            hash.clone(),
+                            outputs_len,
+                            false,
+                        )
+                    };
+                    cell_set.insert(tx.hash(), meta);
+                }
+            }
+        }
+        progress.finish();
+
+        match cell_set {
+            Some(cell_set) => self.check_cell_set(cell_set),
+            None => {
+                let message = "the live cells can't be recomputed".to_owned();
+                self.inconsistent("cell_set", message, false);
+                Ok(())
+            }
+        }
+    }
+
+    fn check_cell_set(
+        &mut self,
+        mut cell_set: HashMap<packed::Byte32, TransactionMeta>,
+    ) -> Result<(), Box<dyn Error>> {
+        let store = self.store;
+        for (key, value) in store.get_iter(COLUMN_CELL_SET, IteratorMode::Start) {
+            let tx_hash = packed::Byte32Reader::from_slice_should_be_ok(&key).to_entity();
+            match cell_set.remove(&tx_hash) {
+                Some(meta) => {
+                    let meta = meta.pack();
+                    if meta.as_slice() != &value[..] {
+                        let message = format!("the live cells of {:#x} are wrong", tx_hash);
+                        self.inconsistent("cell_set", message, true);
+                        self.put(COLUMN_CELL_SET, &key, meta.as_slice())?;
+                    }
+                }
+                None => {
+                    let message = format!("{:#x} has no live cells", tx_hash);
+                    self.inconsistent("cell_set", message, true);
+                    self.delete(COLUMN_CELL_SET, &key)?;
+                }
+            }
+        }
+        for (tx_hash, meta) in cell_set {
+            let message = format!("the live cells of {:#x} are missing", tx_hash);
+            self.inconsistent("cell_set", message, true);
+            self.put(COLUMN_CELL_SET, tx_hash.as_slice(), meta.pack().as_slice())?;
+        }
+        Ok(())
+    }
+
+    // The entries left by the blocks which are no longer in the main chain
+    fn check_stale_entries(&mut self, main_chain: &[packed::Byte32]) -> Result<(), Box<dyn Error>> {
+        let tip_number = main_chain.len() as BlockNumber - 1;
+        let main_chain_set: HashSet<_> = main_chain.iter().cloned().collect();
+
+        let store = self.store;
+        for (key, value) in store.get_iter(COLUMN_INDEX, IteratorMode::Start) {
+            let stale = if key.len() == 8 {
+                let number: BlockNumber =
+                    packed::Uint64Reader::from_slice_should_be_ok(&key).unpack();
+                if number > tip_number {
+                    Some(format!("#{} is indexed but beyond the tip", number))
+                } else {
+                    None
+                }
+            } else {
+                let hash = packed::Byte32Reader::from_slice_should_be_ok(&key).to_entity();
+                if main_chain_set.contains(&hash) {
+                    None
+                } else {
+                    let number: BlockNumber =
+                        packed::Uint64Reader::from_slice_should_be_ok(&value).unpack();
+                    Some(format!(
+                        "{:#x} is indexed to #{} but not in the main chain",
+                        hash, number
+                    ))
+                }
+            };
+            if let Some(message) = stale {
+                self.inconsistent("index", message, true);
+                self.delete(COLUMN_INDEX, &key)?;
+            }
+        }
+
+        for (key, value) in store.get_iter(COLUMN_TRANSACTION_INFO, IteratorMode::Start) {
+            let info = packed::TransactionInfoReader::from_slice_should_be_ok(&value);
+            let block_hash = info.key().block_hash().to_entity();
+            if !main_chain_set.contains(&block_hash) {
+                let message = format!(
+                    "the transaction {:#x} is committed in {:#x} which is not in the main chain",
+                    packed::Byte32Reader::from_slice_should_be_ok(&key).to_entity(),
+                    block_hash
+                );
+                self.inconsistent("transaction_info", message, true);
+                self.delete(COLUMN_TRANSACTION_INFO, &key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Writes the repairs in batches
+struct Repair<'a> {
+    store: &'a ChainDB,
+    txn: Option<StoreTransaction>,
+    pending: usize,
+}
+
+impl<'a> Repair<'a> {
+    fn new(store: &'a ChainDB) -> Self {
+        Repair {
+            store,
+            txn: None,
+            pending: 0,
+        }
+    }
+
+    fn write<F>(&mut self, f: F) -> Result<(), CKBError>
+    where
+        F: FnOnce(&StoreTransaction) -> Result<(), CKBError>,
+    {
+        let store = self.store;
+        f(self.txn.get_or_insert_with(|| store.begin_transaction()))?;
+        self.pending += 1;
+        if self.pending >= REPAIR_BATCH_SIZE {
+            self.commit()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn commit(&mut self) -> Result<(), CKBError> {
+        if let Some(txn) = self.txn.take() {
+            txn.commit()?;
+        }
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "progress_bar")]
+struct Progress(ProgressBar);
+
+#[cfg(feature = "progress_bar")]
+impl Progress {
+    fn new(len: u64) -> Self {
+        let progress_bar = ProgressBar::new(len);
+        progress_bar.set_style(
+            ProgressStyle::default_bar()
+                .template("[{elapsed_precise}] {bar:50.cyan/blue} {pos:>6}/{len:6} {msg}")
+                .progress_chars("##-"),
+        );
+        Progress(progress_bar)
+    }
+
+    fn inc(&self) {
+        self.0.inc(1);
+    }
+
+    fn finish(&self) {
+        self.0.finish_with_message("done!");
+    }
+}
+
+#[cfg(not(feature = "progress_bar"))]
+struct Progress;
+
+#[cfg(not(feature = "progress_bar"))]
+impl Progress {
+    fn new(_len: u64) -> Self {
+        Progress
+    }
+
+    fn inc(&self) {}
+
+    fn finish(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_chain::{chain::ChainService, switch::Switch};
+    use ckb_shared::shared::SharedBuilder;
+    use ckb_store::COLUMN_BLOCK_EXT;
+    use ckb_test_chain_utils::{always_success_cellbase, always_success_consensus};
+    use ckb_types::core::{BlockBuilder, Capacity};
+    use std::sync::Arc;
+
+    const HEIGHT: BlockNumber = 10;
+
+    // A chain of blocks with only the cellbase
+    fn setup_chain() -> Shared {
+        let (shared, table) = SharedBuilder::default()
+            .consensus(always_success_consensus())
+            .build()
+            .unwrap();
+        let chain = ChainService::new(shared.clone(), table).start::<&str>(None);
+        let mut parent = shared.consensus().genesis_block().header();
+        for number in 1..=HEIGHT {
+            let cellbase =
+                always_success_cellbase(number, Capacity::bytes(1000).unwrap(), shared.consensus());
+            let block = BlockBuilder::default()
+                .transaction(cellbase)
+                .parent_hash(parent.hash())
+                .number(number.pack())
+                .epoch(
+                    shared
+                        .consensus()
+                        .genesis_epoch_ext()
+                        .number_with_fraction(number)
+                        .pack(),
+                )
+                .timestamp((parent.timestamp() + 1).pack())
+                .compact_target(parent.compact_target().pack())
+                .build();
+            chain
+                .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+                .unwrap();
+            parent = block.header();
+        }
+        shared
+    }
+
+    fn get_raw(shared: &Shared, col: Col, key: &[u8]) -> Option<Vec<u8>> {
+        shared.store().get(col, key).map(|value| value.to_vec())
+    }
+
+    fn columns(report: &DBCheckReport) -> Vec<&'static str> {
+        let mut columns: Vec<_> = report
+            .inconsistencies
+            .iter()
+            .map(|inconsistency| inconsistency.column)
+            .collect();
+        columns.sort();
+        columns
+    }
+
+    #[test]
+    fn test_consistent_chain() {
+        let shared = setup_chain();
+        let report = DBCheck::new(shared).execute().unwrap();
+        assert_eq!(report.checked_blocks, HEIGHT + 1);
+        assert!(report.inconsistencies.is_empty());
+        assert!(!report.repaired);
+    }
+
+    #[test]
+    fn test_repair() {
+        let shared = setup_chain();
+        let store = shared.store();
+        let cellbase_hash = |number: BlockNumber| {
+            let hash = store.get_block_hash(number).unwrap();
+            store.get_block_body(&hash)[0].hash()
+        };
+
+        let index_key: packed::Uint64 = 5u64.pack();
+        let block_ext_key = store.get_block_hash(6).unwrap();
+        let tx_info_key = cellbase_hash(7);
+        let cell_set_key = cellbase_hash(8);
+        let stale_key = packed::Byte32::new([1u8; 32]);
+        let corrupted = vec![
+            (COLUMN_INDEX, index_key.as_slice().to_vec()),
+            (COLUMN_BLOCK_EXT, block_ext_key.as_slice().to_vec()),
+            (COLUMN_TRANSACTION_INFO, tx_info_key.as_slice().to_vec()),
+            (COLUMN_CELL_SET, cell_set_key.as_slice().to_vec()),
+            (COLUMN_INDEX, stale_key.as_slice().to_vec()),
+        ];
+        let originals: Vec<_> = corrupted
+            .iter()
+            .map(|(col, key)| get_raw(&shared, *col, key))
+            .collect();
+
+        let txn = store.begin_transaction();
+        txn.delete(COLUMN_INDEX, index_key.as_slice()).unwrap();
+        let mut block_ext = store.get_block_ext(&block_ext_key).unwrap();
+        block_ext.total_uncles_count += 1;
+        txn.insert_block_ext(&block_ext_key, &block_ext).unwrap();
+        txn.delete(COLUMN_TRANSACTION_INFO, tx_info_key.as_slice())
+            .unwrap();
+        txn.delete(COLUMN_CELL_SET, cell_set_key.as_slice())
+            .unwrap();
+        let stale_number: packed::Uint64 = 3u64.pack();
+        txn.insert_raw(COLUMN_INDEX, stale_key.as_slice(), stale_number.as_slice())
+            .unwrap();
+        txn.commit().unwrap();
+
+        // the check only reports
+        let report = DBCheck::new(shared.clone()).execute().unwrap();
+        assert_eq!(
+            columns(&report),
+            vec![
+                "block_ext",
+                "cell_set",
+                "index",
+                "index",
+                "transaction_info"
+            ]
+        );
+        assert!(report.inconsistencies.iter().all(|i| i.repairable));
+        assert!(!report.repaired);
+        assert_eq!(get_raw(&shared, COLUMN_INDEX, index_key.as_slice()), None);
+
+        // the repair restores the original values
+        let report = DBCheck::new(shared.clone()).repair(true).execute().unwrap();
+        assert_eq!(report.inconsistencies.len(), 5);
+        assert!(report.repaired);
+        for ((col, key), original) in corrupted.iter().zip(originals.iter()) {
+            assert_eq!(&get_raw(&shared, *col, key), original);
+        }
+
+        let report = DBCheck::new(shared).execute().unwrap();
+        assert!(report.inconsistencies.is_empty());
+    }
+
+    #[test]
+    fn test_missing_block_ext() {
+        let shared = setup_chain();
+        let store = shared.store();
+        let hash = store.get_block_hash(6).unwrap();
+        let txn = store.begin_transaction();
+        txn.delete_block_ext(&hash).unwrap();
+        txn.commit().unwrap();
+
+        // the fees and the received time can't be recomputed, it is reported but not repaired
+        let report = DBCheck::new(shared.clone()).repair(true).execute().unwrap();
+        assert_eq!(columns(&report), vec!["block_ext"]);
+        assert!(!report.inconsistencies[0].repairable);
+        assert!(store.get_block_ext(&hash).is_none());
+    }
+}
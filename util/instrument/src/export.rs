@@ -1,6 +1,8 @@
+use crate::format::{BinaryWriter, Compression, Format};
 use ckb_chain_iter::ChainIterator;
 use ckb_jsonrpc_types::BlockView as JsonBlock;
 use ckb_shared::shared::Shared;
+use ckb_types::core::{BlockNumber, BlockView};
 #[cfg(feature = "progress_bar")]
 use indicatif::{ProgressBar, ProgressStyle};
 use std::error::Error;
@@ -14,53 +16,119 @@ pub struct Export {
     /// export target path
     pub target: PathBuf,
     pub shared: Shared,
+    /// exported data format
+    pub format: Format,
+    /// compression of the binary format
+    pub compression: Compression,
+    /// first exported block number, defaults to genesis
+    pub from: Option<BlockNumber>,
+    /// last exported block number, defaults to tip
+    pub to: Option<BlockNumber>,
 }
 
 impl Export {
     pub fn new(shared: Shared, target: PathBuf) -> Self {
-        Export { shared, target }
+        Export {
+            shared,
+            target,
+            format: Format::default(),
+            compression: Compression::default(),
+            from: None,
+            to: None,
+        }
+    }
+
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn range(mut self, from: Option<BlockNumber>, to: Option<BlockNumber>) -> Self {
+        self.from = from;
+        self.to = to;
+        self
     }
 
     /// export file name
     fn file_name(&self) -> String {
-        format!("{}.{}", self.shared.consensus().id, "json")
+        let id = &self.shared.consensus().id;
+        if self.from.is_none() && self.to.is_none() {
+            format!("{}.{}", id, self.format.extension())
+        } else {
+            let from = self.from.unwrap_or(0);
+            let to = self
+                .to
+                .map(|to| to.to_string())
+                .unwrap_or_else(|| "tip".to_string());
+            format!("{}.{}-{}.{}", id, from, to, self.format.extension())
+        }
     }
 
     pub fn execute(self) -> Result<(), Box<dyn Error>> {
         fs::create_dir_all(&self.target)?;
-        self.write_to_json()
+        match self.format {
+            Format::Json => self.write_to_json(),
+            Format::Binary => self.write_to_binary(),
+        }
     }
 
-    #[cfg(not(feature = "progress_bar"))]
-    pub fn write_to_json(self) -> Result<(), Box<dyn Error>> {
+    fn create_file(&self) -> Result<fs::File, Box<dyn Error>> {
         let f = fs::OpenOptions::new()
             .create_new(true)
             .read(true)
             .write(true)
             .open(&self.target.join(self.file_name()))?;
-        let mut writer = io::BufWriter::new(f);
-        let snapshot = self.shared.snapshot();
+        Ok(f)
+    }
 
-        for block in ChainIterator::new(snapshot.as_ref()) {
+    pub fn write_to_json(self) -> Result<(), Box<dyn Error>> {
+        let mut writer = io::BufWriter::new(self.create_file()?);
+        self.for_each_block(|block| {
             let block: JsonBlock = block.into();
             let encoded = serde_json::to_vec(&block)?;
             writer.write_all(&encoded)?;
             writer.write_all(b"\n")?;
+            Ok(())
+        })?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn write_to_binary(self) -> Result<(), Box<dyn Error>> {
+        let mut writer =
+            BinaryWriter::new(io::BufWriter::new(self.create_file()?), self.compression)?;
+        self.for_each_block(|block| {
+            writer.write_block(block)?;
+            Ok(())
+        })?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "progress_bar"))]
+    fn for_each_block<F>(&self, mut f: F) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(BlockView) -> Result<(), Box<dyn Error>>,
+    {
+        let snapshot = self.shared.snapshot();
+        for block in ChainIterator::with_range(snapshot.as_ref(), self.from, self.to) {
+            f(block)?;
         }
         Ok(())
     }
 
     #[cfg(feature = "progress_bar")]
-    pub fn write_to_json(self) -> Result<(), Box<dyn Error>> {
-        let f = fs::OpenOptions::new()
-            .create_new(true)
-            .read(true)
-            .write(true)
-            .open(&self.target.join(self.file_name()))?;
-
-        let mut writer = io::BufWriter::new(f);
+    fn for_each_block<F>(&self, mut f: F) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(BlockView) -> Result<(), Box<dyn Error>>,
+    {
         let snapshot = self.shared.snapshot();
-        let blocks_iter = ChainIterator::new(snapshot.as_ref());
+        let blocks_iter = ChainIterator::with_range(snapshot.as_ref(), self.from, self.to);
         let progress_bar = ProgressBar::new(blocks_iter.len());
         progress_bar.set_style(
             ProgressStyle::default_bar()
@@ -68,10 +136,7 @@ impl Export {
                 .progress_chars("##-"),
         );
         for block in blocks_iter {
-            let block: JsonBlock = block.into();
-            let encoded = serde_json::to_vec(&block)?;
-            writer.write_all(&encoded)?;
-            writer.write_all(b"\n")?;
+            f(block)?;
             progress_bar.inc(1);
         }
         progress_bar.finish_with_message("done!");
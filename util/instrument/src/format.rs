@@ -0,0 +1,384 @@
+use ckb_types::{core::BlockView, packed, prelude::*};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+/// Magic bytes at the beginning of a binary export file.
+pub const BINARY_MAGIC: &[u8; 8] = b"CKBBLOCK";
+/// Binary export format version.
+pub const BINARY_VERSION: u8 = 1;
+/// Number of blocks packed into one binary chunk.
+pub const CHUNK_SIZE: usize = 1000;
+
+// magic + version + compression
+const BINARY_HEADER_SIZE: usize = 10;
+// first number + block count + payload length + payload checksum
+const CHUNK_HEADER_SIZE: usize = 20;
+
+/// Data format of the exported file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// One JSON encoded block per line
+    Json,
+    /// Molecule encoded blocks grouped in checksummed chunks
+    Binary,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Binary => "bin",
+        }
+    }
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::Json
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "bin" => Ok(Format::Binary),
+            _ => Err(format!("unknown export format {}", s)),
+        }
+    }
+}
+
+/// Compression algorithm applied to the chunks of the binary format.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Zstd,
+    Snappy,
+}
+
+impl Compression {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Zstd),
+            2 => Some(Compression::Snappy),
+            _ => None,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Zstd => 1,
+            Compression::Snappy => 2,
+        }
+    }
+
+    fn compress(self, data: Vec<u8>) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data),
+            Compression::Zstd => zstd::stream::encode_all(&data[..], 0),
+            Compression::Snappy => snap::raw::Encoder::new()
+                .compress_vec(&data)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err)),
+        }
+    }
+
+    fn decompress(self, data: Vec<u8>) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data),
+            Compression::Zstd => zstd::stream::decode_all(&data[..]),
+            Compression::Snappy => snap::raw::Decoder::new()
+                .decompress_vec(&data)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        }
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::None
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Compression::None),
+            "zstd" => Ok(Compression::Zstd),
+            "snappy" => Ok(Compression::Snappy),
+            _ => Err(format!("unknown compression {}", s)),
+        }
+    }
+}
+
+/// Error raised when the binary data is corrupted.
+#[derive(Debug)]
+pub struct MalformedData(String);
+
+impl fmt::Display for MalformedData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "malformed export data: {}", self.0)
+    }
+}
+
+impl Error for MalformedData {}
+
+fn malformed<T>(reason: String) -> Result<T, Box<dyn Error>> {
+    Err(Box::new(MalformedData(reason)))
+}
+
+/// Writes blocks in binary format.
+///
+/// File layout:
+///
+/// ```text
+/// magic(8) | version(1) | compression(1) | chunk*
+/// chunk := first_number(u64) | count(u32) | payload_len(u32) | crc32(u32) | payload
+/// payload := compress((block_len(u32) | molecule encoded block)*)
+/// ```
+///
+/// All integers are little endian. The checksum covers the compressed payload.
+pub struct BinaryWriter<W: Write> {
+    inner: W,
+    compression: Compression,
+    pending: Vec<BlockView>,
+}
+
+impl<W: Write> BinaryWriter<W> {
+    pub fn new(mut inner: W, compression: Compression) -> io::Result<Self> {
+        inner.write_all(BINARY_MAGIC)?;
+        inner.write_all(&[BINARY_VERSION, compression.to_u8()])?;
+        Ok(BinaryWriter {
+            inner,
+            compression,
+            pending: Vec::with_capacity(CHUNK_SIZE),
+        })
+    }
+
+    pub fn write_block(&mut self, block: BlockView) -> io::Result<()> {
+        self.pending.push(block);
+        if self.pending.len() >= CHUNK_SIZE {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_chunk()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn flush_chunk(&mut self) -> io::Result<()> {
+        let first_number = match self.pending.first() {
+            Some(block) => block.number(),
+            None => return Ok(()),
+        };
+        let count = self.pending.len() as u32;
+        let mut raw = Vec::new();
+        for block in self.pending.drain(..) {
+            let data = block.data();
+            raw.extend_from_slice(&(data.as_slice().len() as u32).to_le_bytes());
+            raw.extend_from_slice(data.as_slice());
+        }
+        let payload = self.compression.compress(raw)?;
+
+        self.inner.write_all(&first_number.to_le_bytes())?;
+        self.inner.write_all(&count.to_le_bytes())?;
+        self.inner
+            .write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.inner
+            .write_all(&crc32fast::hash(&payload).to_le_bytes())?;
+        self.inner.write_all(&payload)
+    }
+}
+
+/// Chunk header of the binary format.
+#[derive(Copy, Clone, Debug)]
+pub struct ChunkHeader {
+    pub first_number: u64,
+    pub count: u32,
+    pub payload_len: u32,
+    pub checksum: u32,
+}
+
+impl ChunkHeader {
+    /// Number of the last block in this chunk.
+    pub fn last_number(&self) -> u64 {
+        self.first_number + u64::from(self.count).saturating_sub(1)
+    }
+
+    /// Total bytes the chunk occupies in the file.
+    pub fn size(&self) -> u64 {
+        CHUNK_HEADER_SIZE as u64 + u64::from(self.payload_len)
+    }
+}
+
+/// Reads blocks from binary format, see [`BinaryWriter`](struct.BinaryWriter.html) for the layout.
+pub struct BinaryReader<R: Read> {
+    inner: R,
+    compression: Compression,
+}
+
+impl<R: Read> BinaryReader<R> {
+    /// Creates the reader, the magic bytes must already be consumed.
+    pub fn new(mut inner: R) -> Result<Self, Box<dyn Error>> {
+        let mut header = [0u8; BINARY_HEADER_SIZE - BINARY_MAGIC.len()];
+        inner.read_exact(&mut header)?;
+        if header[0] != BINARY_VERSION {
+            return malformed(format!("unsupported version {}", header[0]));
+        }
+        let compression = match Compression::from_u8(header[1]) {
+            Some(compression) => compression,
+            None => return malformed(format!("unknown compression {}", header[1])),
+        };
+        Ok(BinaryReader { inner, compression })
+    }
+
+    /// Reads the next chunk header, returns `None` at the end of file.
+    pub fn next_chunk(&mut self) -> Result<Option<ChunkHeader>, Box<dyn Error>> {
+        let mut buf = [0u8; CHUNK_HEADER_SIZE];
+        let mut filled = 0;
+        while filled < CHUNK_HEADER_SIZE {
+            match self.inner.read(&mut buf[filled..])? {
+                0 if filled == 0 => return Ok(None),
+                0 => return malformed("truncated chunk header".to_string()),
+                n => filled += n,
+            }
+        }
+        let mut first_number = [0u8; 8];
+        let mut count = [0u8; 4];
+        let mut payload_len = [0u8; 4];
+        let mut checksum = [0u8; 4];
+        first_number.copy_from_slice(&buf[0..8]);
+        count.copy_from_slice(&buf[8..12]);
+        payload_len.copy_from_slice(&buf[12..16]);
+        checksum.copy_from_slice(&buf[16..20]);
+        Ok(Some(ChunkHeader {
+            first_number: u64::from_le_bytes(first_number),
+            count: u32::from_le_bytes(count),
+            payload_len: u32::from_le_bytes(payload_len),
+            checksum: u32::from_le_bytes(checksum),
+        }))
+    }
+
+    /// Discards the payload of the chunk.
+    pub fn skip_chunk(&mut self, header: &ChunkHeader) -> Result<(), Box<dyn Error>> {
+        let skipped = io::copy(
+            &mut (&mut self.inner).take(u64::from(header.payload_len)),
+            &mut io::sink(),
+        )?;
+        if skipped != u64::from(header.payload_len) {
+            return malformed("truncated chunk payload".to_string());
+        }
+        Ok(())
+    }
+
+    /// Reads, verifies and decodes the payload of the chunk.
+    pub fn read_chunk(&mut self, header: &ChunkHeader) -> Result<Vec<BlockView>, Box<dyn Error>> {
+        let mut payload = vec![0u8; header.payload_len as usize];
+        self.inner.read_exact(&mut payload)?;
+        if crc32fast::hash(&payload) != header.checksum {
+            return malformed(format!(
+                "checksum mismatch in chunk starting at block {}",
+                header.first_number
+            ));
+        }
+        let raw = self.compression.decompress(payload)?;
+
+        let mut blocks = Vec::with_capacity(header.count as usize);
+        let mut offset = 0;
+        while offset < raw.len() {
+            if offset + 4 > raw.len() {
+                return malformed("truncated block length".to_string());
+            }
+            let mut len = [0u8; 4];
+            len.copy_from_slice(&raw[offset..offset + 4]);
+            let len = u32::from_le_bytes(len) as usize;
+            offset += 4;
+            if offset + len > raw.len() {
+                return malformed("truncated block".to_string());
+            }
+            let block = packed::Block::from_slice(&raw[offset..offset + len])
+                .map_err(|err| MalformedData(err.to_string()))?;
+            blocks.push(block.into_view());
+            offset += len;
+        }
+        if blocks.len() != header.count as usize {
+            return malformed(format!(
+                "chunk starting at block {} expects {} blocks, found {}",
+                header.first_number,
+                header.count,
+                blocks.len()
+            ));
+        }
+        Ok(blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_types::core::{BlockBuilder, HeaderBuilder};
+    use std::io::Cursor;
+
+    fn blocks(count: u64) -> Vec<BlockView> {
+        (0..count)
+            .map(|number| {
+                BlockBuilder::default()
+                    .header(HeaderBuilder::default().number(number.pack()).build())
+                    .build()
+            })
+            .collect()
+    }
+
+    fn roundtrip(compression: Compression) {
+        let expected = blocks(CHUNK_SIZE as u64 + 10);
+        let mut writer = BinaryWriter::new(Vec::new(), compression).unwrap();
+        for block in expected.iter() {
+            writer.write_block(block.clone()).unwrap();
+        }
+        let data = writer.finish().unwrap();
+
+        let mut cursor = Cursor::new(data);
+        let mut magic = [0u8; 8];
+        cursor.read_exact(&mut magic).unwrap();
+        assert_eq!(&magic, BINARY_MAGIC);
+        let mut reader = BinaryReader::new(cursor).unwrap();
+
+        let mut actual = Vec::new();
+        while let Some(header) = reader.next_chunk().unwrap() {
+            actual.extend(reader.read_chunk(&header).unwrap());
+        }
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn binary_roundtrip() {
+        roundtrip(Compression::None);
+        roundtrip(Compression::Zstd);
+        roundtrip(Compression::Snappy);
+    }
+
+    #[test]
+    fn binary_checksum_mismatch() {
+        let mut writer = BinaryWriter::new(Vec::new(), Compression::None).unwrap();
+        for block in blocks(3) {
+            writer.write_block(block).unwrap();
+        }
+        let mut data = writer.finish().unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+
+        let mut reader = BinaryReader::new(Cursor::new(&data[BINARY_MAGIC.len()..])).unwrap();
+        let header = reader.next_chunk().unwrap().unwrap();
+        assert!(reader.read_chunk(&header).is_err());
+    }
+}
@@ -1,72 +1,301 @@
-use ckb_chain::chain::ChainController;
+use crate::format::{BinaryReader, BINARY_MAGIC};
+use ckb_chain::{chain::ChainController, switch::Switch};
+use ckb_chain_spec::consensus::Consensus;
+use ckb_channel::{self as channel, Receiver, Sender};
 use ckb_jsonrpc_types::BlockView as JsonBlock;
+use ckb_logger::info;
+use ckb_metrics::metrics;
+use ckb_shared::{shared::Shared, Snapshot};
+use ckb_store::ChainStore;
 use ckb_types::core;
+use ckb_verification::{BlockVerifier, NonContextualBlockTxsVerifier, Verifier};
 #[cfg(feature = "progress_bar")]
 use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
 use std::error::Error;
 use std::fs;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Read, Seek, SeekFrom};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Number of JSON lines decoded and verified together.
+const JSON_BATCH_SIZE: usize = 1000;
+/// Number of verified batches buffered ahead of the chain.
+const PIPELINE_DEPTH: usize = 4;
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+struct Batch {
+    blocks: Vec<core::BlockView>,
+    bytes: u64,
+}
+
+type BatchResult = Result<Batch, String>;
 
 /// Export block date from file to database.
+///
+/// Both the JSON and the binary format produced by `Export` are accepted, the format is
+/// detected from the file header. Blocks which are already in the main chain are skipped, so
+/// an interrupted import can be resumed by running it again on the same file.
+///
+/// Decoding and non-contextual verification run on the rayon thread pool ahead of the chain
+/// service, which only does the contextual verification.
 pub struct Import {
     /// source file contains block data
     source: PathBuf,
     chain: ChainController,
+    shared: Shared,
+    /// verification switch passed to the chain service
+    switch: Switch,
 }
 
 impl Import {
-    pub fn new(chain: ChainController, source: PathBuf) -> Self {
-        Import { chain, source }
+    pub fn new(chain: ChainController, shared: Shared, source: PathBuf) -> Self {
+        Import {
+            chain,
+            shared,
+            source,
+            switch: Switch::NONE,
+        }
     }
 
-    pub fn execute(self) -> Result<(), Box<dyn Error>> {
-        self.read_from_json()
+    pub fn switch(mut self, switch: Switch) -> Self {
+        self.switch = switch;
+        self
     }
 
     #[cfg(not(feature = "progress_bar"))]
-    pub fn read_from_json(&self) -> Result<(), Box<dyn Error>> {
-        let f = fs::File::open(&self.source)?;
-        let reader = io::BufReader::new(f);
-
-        for line in reader.lines() {
-            let s = line?;
-            let block: JsonBlock = serde_json::from_str(&s)?;
-            let block: Arc<core::BlockView> = Arc::new(block.into());
-            if !block.is_genesis() {
-                self.chain
-                    .process_block(block)
-                    .expect("import occur malformation data");
-            }
-        }
-        Ok(())
+    pub fn execute(self) -> Result<(), Box<dyn Error>> {
+        self.read(|_| {})
     }
 
     #[cfg(feature = "progress_bar")]
-    pub fn read_from_json(&self) -> Result<(), Box<dyn Error>> {
+    pub fn execute(self) -> Result<(), Box<dyn Error>> {
         let metadata = fs::metadata(&self.source)?;
-        let f = fs::File::open(&self.source)?;
-        let reader = io::BufReader::new(f);
         let progress_bar = ProgressBar::new(metadata.len() as u64);
         progress_bar.set_style(
             ProgressStyle::default_bar()
                 .template("[{elapsed_precise}] {bar:50.cyan/blue} {bytes:>6}/{total_bytes:6} {msg}")
                 .progress_chars("##-"),
         );
-        for line in reader.lines() {
-            let s = line?;
-            let block: JsonBlock = serde_json::from_str(&s)?;
-            let block: Arc<core::BlockView> = Arc::new(block.into());
-            if !block.is_genesis() {
+        self.read(|bytes| progress_bar.inc(bytes))?;
+        progress_bar.finish_with_message("done!");
+        Ok(())
+    }
+
+    fn read<F: FnMut(u64)>(&self, mut inc: F) -> Result<(), Box<dyn Error>> {
+        let mut f = fs::File::open(&self.source)?;
+        let mut magic = [0u8; 8];
+        let is_binary = match f.read_exact(&mut magic) {
+            Ok(()) => &magic == BINARY_MAGIC,
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => false,
+            Err(err) => return Err(err.into()),
+        };
+        if !is_binary {
+            f.seek(SeekFrom::Start(0))?;
+        }
+
+        let (sender, receiver) = channel::bounded(PIPELINE_DEPTH);
+        let decoder = Decoder {
+            consensus: self.shared.consensus().clone(),
+            snapshot: Arc::clone(&self.shared.snapshot()),
+            verify: !self.switch.disable_non_contextual(),
+            sender,
+        };
+        let handle = thread::Builder::new()
+            .name("ImportDecoder".to_string())
+            .spawn(move || {
+                if is_binary {
+                    decoder.read_from_binary(f)
+                } else {
+                    decoder.read_from_json(f)
+                }
+            })?;
+
+        let ret = self.process_batches(&receiver, &mut inc);
+        // Unblock the decoder if the chain returns early
+        drop(receiver);
+        handle.join().expect("import decoder thread panic");
+        ret
+    }
+
+    fn process_batches<F: FnMut(u64)>(
+        &self,
+        receiver: &Receiver<BatchResult>,
+        inc: &mut F,
+    ) -> Result<(), Box<dyn Error>> {
+        let switch = chain_switch(self.switch);
+        let start = Instant::now();
+        let mut last_report = start;
+        let mut imported = 0u64;
+        let mut tip_number = self.shared.snapshot().tip_number();
+
+        for batch in receiver {
+            let batch = batch?;
+            for block in batch.blocks {
+                let number = block.number();
+                let hash = block.hash();
                 self.chain
-                    .process_block(block)
-                    .expect("import occur malformation data");
+                    .internal_process_block(Arc::new(block), switch)
+                    .map_err(|err| format!("import block {} {} failed: {}", number, hash, err))?;
+                imported += 1;
+                tip_number = number;
+            }
+            inc(batch.bytes);
+
+            if last_report.elapsed() >= REPORT_INTERVAL {
+                let elapsed = start.elapsed().as_secs_f64();
+                info!(
+                    "imported {} blocks, tip {}, {:.2} blocks/s",
+                    imported,
+                    tip_number,
+                    imported as f64 / elapsed
+                );
+                last_report = Instant::now();
             }
-            progress_bar.inc(s.as_bytes().len() as u64);
+            metrics!(gauge, "ckb-import.tip_number", tip_number as i64);
+            metrics!(gauge, "ckb-import.imported", imported as i64);
         }
-        progress_bar.finish_with_message("done!");
+
+        info!(
+            "import finished, {} blocks imported in {:.2}s, tip {}",
+            imported,
+            start.elapsed().as_secs_f64(),
+            tip_number
+        );
         Ok(())
     }
 }
+
+// Non-contextual verification is already done by the decoder. It is skipped through a flag
+// out of DISABLE_ALL, so the chain only skips scripts if the user passed disable-all.
+fn chain_switch(switch: Switch) -> Switch {
+    switch | Switch::NON_CONTEXTUAL_VERIFIED
+}
+
+/// Reads the source file and verifies blocks on the rayon thread pool.
+struct Decoder {
+    consensus: Consensus,
+    snapshot: Arc<Snapshot>,
+    verify: bool,
+    sender: Sender<BatchResult>,
+}
+
+impl Decoder {
+    fn read_from_json(self, f: fs::File) {
+        let mut lines = io::BufReader::new(f).lines();
+        loop {
+            let mut batch = Vec::with_capacity(JSON_BATCH_SIZE);
+            for line in lines.by_ref().take(JSON_BATCH_SIZE) {
+                match line {
+                    Ok(line) => batch.push(line),
+                    Err(err) => {
+                        let _ = self.sender.send(Err(err.to_string()));
+                        return;
+                    }
+                }
+            }
+            if batch.is_empty() {
+                return;
+            }
+
+            let bytes = batch.iter().map(|s| s.len() as u64 + 1).sum();
+            let blocks = batch
+                .par_iter()
+                .map(|s| {
+                    let block: JsonBlock = serde_json::from_str(s).map_err(|e| e.to_string())?;
+                    Ok(block.into())
+                })
+                .collect::<Result<Vec<core::BlockView>, String>>()
+                .and_then(|blocks| self.verify(blocks))
+                .map(|blocks| Batch { blocks, bytes });
+            if !self.send(blocks) {
+                return;
+            }
+        }
+    }
+
+    fn read_from_binary(self, f: fs::File) {
+        let mut reader = match BinaryReader::new(io::BufReader::new(f)) {
+            Ok(reader) => reader,
+            Err(err) => {
+                let _ = self.sender.send(Err(err.to_string()));
+                return;
+            }
+        };
+        loop {
+            let header = match reader.next_chunk() {
+                Ok(Some(header)) => header,
+                Ok(None) => return,
+                Err(err) => {
+                    let _ = self.sender.send(Err(err.to_string()));
+                    return;
+                }
+            };
+            let blocks = reader
+                .read_chunk(&header)
+                .map_err(|err| err.to_string())
+                .and_then(|blocks| self.verify(blocks))
+                .map(|blocks| Batch {
+                    blocks,
+                    bytes: header.size(),
+                });
+            if !self.send(blocks) {
+                return;
+            }
+        }
+    }
+
+    /// Drops blocks already in the main chain and runs non-contextual verification on the rest.
+    fn verify(&self, blocks: Vec<core::BlockView>) -> Result<Vec<core::BlockView>, String> {
+        let blocks: Vec<_> = blocks
+            .into_iter()
+            .filter(|block| !block.is_genesis() && !self.snapshot.is_main_chain(&block.hash()))
+            .collect();
+        if self.verify {
+            blocks.par_iter().try_for_each(|block| {
+                BlockVerifier::new(&self.consensus)
+                    .verify(block)
+                    .and_then(|_| NonContextualBlockTxsVerifier::new(&self.consensus).verify(block))
+                    .map(|_| ())
+                    .map_err(|err| {
+                        format!(
+                            "block {} {} failed verification: {}",
+                            block.number(),
+                            block.hash(),
+                            err
+                        )
+                    })
+            })?;
+        }
+        Ok(blocks)
+    }
+
+    /// Returns false when the decoder should stop.
+    fn send(&self, batch: BatchResult) -> bool {
+        let is_err = batch.is_err();
+        self.sender.send(batch).is_ok() && !is_err
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_switch() {
+        let switch: Switch =
+            "disable-epoch,disable-uncles,disable-two-phase-commit,disable-daoheader,disable-reward"
+                .parse()
+                .unwrap();
+        let switch = chain_switch(switch);
+        assert!(switch.skip_non_contextual());
+        assert!(!switch.disable_all(), "scripts must still run");
+
+        assert!(chain_switch(Switch::NONE).skip_non_contextual());
+        assert!(!chain_switch(Switch::NONE).disable_all());
+        assert!(chain_switch(Switch::DISABLE_ALL).disable_all());
+    }
+}
@@ -1,15 +1,30 @@
 //! # The Instrument Library
 //!
-//! Instruments for ckb for working with `Export`, `Import`
+//! Instruments for ckb for working with `Export`, `Import`, `Backup` and `DBCheck`
 //!
 //! - [Export](instrument::export::Export) provide block data
 //!   export function.
 //! - [Import](instrument::import::Import) import block data which
 //!   export from `Export`.
+//! - [Format](instrument::format::Format) and
+//!   [Compression](instrument::format::Compression) select the layout of exported data.
+//! - [Backup](instrument::backup::Backup) creates checkpoints of the databases of a running
+//!   node.
+//! - [DBCheck](instrument::db_check::DBCheck) checks and repairs the columns derived from the
+//!   main chain blocks.
 
+mod backup;
+mod db_check;
 mod export;
+mod format;
 mod import;
 
+pub use crate::backup::{
+    verify_restored_backup, Backup, BACKUP_MANIFEST, VERIFIED_BACKUP_MANIFEST,
+};
+pub use crate::db_check::{DBCheck, DBCheckReport, Inconsistency};
 pub use crate::export::Export;
+pub use crate::format::{Compression, Format};
 pub use crate::import::Import;
+#[cfg(feature = "progress_bar")]
 pub use indicatif::{ProgressBar, ProgressStyle};
@@ -126,3 +126,21 @@ impl From<TransactionTemplate> for packed::Transaction {
         data.into()
     }
 }
+
+/// The detailed reason why a block fails the verification
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct BlockVerificationError {
+    /// The category of the error, e.g. `Header`, `Block`, `Transaction`, `Script`
+    pub kind: String,
+    /// The failed check, e.g. `Epoch` for a header, `TransactionsRoot` for a block
+    pub check: String,
+    /// The specific reason of the failed check, e.g. `NumberMismatch` for `Epoch`
+    pub reason: Option<String>,
+    /// The index of the offending transaction in the block
+    pub tx_index: Option<Uint32>,
+    /// The verification error of the offending transaction
+    pub tx_error: Option<Box<BlockVerificationError>>,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+    pub message: String,
+}
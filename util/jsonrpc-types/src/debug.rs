@@ -1,3 +1,5 @@
+use crate::{BlockNumber, Timestamp};
+use ckb_types::H256;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Default, Serialize, Deserialize, Debug)]
@@ -12,3 +14,24 @@ pub struct MainLoggerConfig {
     pub to_file: Option<bool>,
     pub color: Option<bool>,
 }
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct BackupManifest {
+    // the tip block of the backed up chain database
+    pub tip_hash: H256,
+    pub tip_number: BlockNumber,
+    // when the backup is created
+    pub created_at: Timestamp,
+    // the databases in the backup, relative to the backup directory
+    pub databases: Vec<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct ConfigReloadReport {
+    // the changed options which have been applied to the running node
+    pub applied: Vec<String>,
+    // the changed options which take effect only after restarting the node
+    pub restart_required: Vec<String>,
+    // the changed options which failed to be applied, along with the reasons
+    pub failed: Vec<String>,
+}
@@ -17,7 +17,7 @@ mod uints;
 
 pub use self::alert::{Alert, AlertMessage};
 pub use self::block_template::{
-    BlockTemplate, CellbaseTemplate, TransactionTemplate, UncleTemplate,
+    BlockTemplate, BlockVerificationError, CellbaseTemplate, TransactionTemplate, UncleTemplate,
 };
 pub use self::blockchain::{
     Block, BlockEconomicState, BlockIssuance, BlockReward, BlockView, CellDep, CellInput,
@@ -28,7 +28,7 @@ pub use self::blockchain::{
 pub use self::bytes::JsonBytes;
 pub use self::cell::{CellOutputWithOutPoint, CellWithStatus};
 pub use self::chain_info::ChainInfo;
-pub use self::debug::{ExtraLoggerConfig, MainLoggerConfig};
+pub use self::debug::{BackupManifest, ConfigReloadReport, ExtraLoggerConfig, MainLoggerConfig};
 pub use self::experiment::{DryRunResult, EstimateResult};
 pub use self::fixed_bytes::Byte32;
 pub use self::indexer::{
@@ -38,7 +38,7 @@ pub use self::net::{
     BannedAddr, LocalNode, LocalNodeProtocol, NodeAddress, PeerSyncState, RemoteNode,
     RemoteNodeProtocol, SyncState,
 };
-pub use self::pool::{OutputsValidator, TxPoolInfo};
+pub use self::pool::{OutputsValidator, TxLifecycle, TxLifecycleStatus, TxPoolInfo};
 pub use self::proposal_short_id::ProposalShortId;
 pub use self::sync::PeerState;
 pub use self::uints::{Uint128, Uint32, Uint64};
@@ -1,4 +1,4 @@
-use crate::{BlockNumber, Timestamp, Uint64};
+use crate::{BlockNumber, Timestamp, Uint32, Uint64};
 use ckb_types::H256;
 use serde::{Deserialize, Serialize};
 
@@ -29,6 +29,52 @@ impl OutputsValidator {
     }
 }
 
+/// Lifecycle status of a transaction
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TxLifecycleStatus {
+    /// Transaction on pool, not proposed
+    Pending,
+    /// Transaction on pool, proposed
+    Proposed,
+    /// Transaction commit on block
+    Committed,
+    /// Transaction rejected by pool, or removed from pool before committed
+    Rejected,
+    /// Transaction not found in pool, chain or the recent rejections
+    Unknown,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct TxLifecycle {
+    pub status: TxLifecycleStatus,
+    /// The time the tx pool received the transaction
+    pub received_at: Option<Timestamp>,
+    pub proposed_block_number: Option<BlockNumber>,
+    pub proposed_block_hash: Option<H256>,
+    pub committed_block_number: Option<BlockNumber>,
+    pub committed_block_hash: Option<H256>,
+    /// The index of the transaction in the committed block
+    pub committed_tx_index: Option<Uint32>,
+    /// Why the transaction was rejected or removed
+    pub reason: Option<String>,
+}
+
+impl TxLifecycle {
+    pub fn with_status(status: TxLifecycleStatus) -> Self {
+        TxLifecycle {
+            status,
+            received_at: None,
+            proposed_block_number: None,
+            proposed_block_hash: None,
+            committed_block_number: None,
+            committed_block_hash: None,
+            committed_tx_index: None,
+            reason: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -13,6 +13,12 @@ pub struct Config {
     pub log_to_file: bool,
     pub log_to_stdout: bool,
     pub emit_sentry_breadcrumbs: Option<bool>,
+    /// Output format of every record, `"text"` or `"json"`.
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Rotation policy shared by the main log file and all the extra log files.
+    #[serde(default)]
+    pub rotation: RotationConfig,
     #[serde(default)]
     pub extra: HashMap<String, ExtraLoggerConfig>,
 }
@@ -22,6 +28,51 @@ pub struct ExtraLoggerConfig {
     pub filter: String,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human readable lines, colored on stdout when `color` is enabled.
+    Text,
+    /// One JSON object per record with `timestamp`, `level`, `target`, `thread` and `message`.
+    Json,
+}
+
+/// Log files are rotated when any of the enabled limits is reached.
+///
+/// The rotated files are renamed to `<name>.log.1`, `<name>.log.2`, ..., the larger the suffix
+/// the older the file, and only the newest `max_files` of them are kept.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RotationConfig {
+    /// Rotate the file once it grows beyond this size in bytes, disabled when absent.
+    pub max_size: Option<u64>,
+    /// Rotate the file once it has been written for this many seconds, disabled when absent.
+    pub max_age_secs: Option<u64>,
+    /// How many rotated files to keep.
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+}
+
+const fn default_max_files() -> usize {
+    7
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat::Text
+    }
+}
+
+impl Default for RotationConfig {
+    fn default() -> Self {
+        RotationConfig {
+            max_size: None,
+            max_age_secs: None,
+            max_files: default_max_files(),
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -32,6 +83,8 @@ impl Default for Config {
             log_to_file: false,
             log_to_stdout: true,
             emit_sentry_breadcrumbs: None,
+            format: Default::default(),
+            rotation: Default::default(),
             extra: Default::default(),
         }
     }
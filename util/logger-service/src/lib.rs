@@ -1,19 +1,25 @@
+mod rotating_file;
+
 use ansi_term::Colour;
 use backtrace::Backtrace;
 use chrono::prelude::{DateTime, Local};
+use chrono::SecondsFormat;
 use ckb_channel::{self, unbounded};
 use env_logger::filter::{Builder, Filter};
 use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
 use once_cell::sync::OnceCell;
 use regex::Regex;
+use serde_json::json;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::io::Write;
 use std::path::PathBuf;
-use std::{fs, panic, process, sync, thread};
+use std::{panic, process, sync, thread};
 
-use ckb_logger_config::Config;
+use ckb_logger_config::{Config, LogFormat};
 use ckb_util::{strings, Mutex, RwLock};
 
+use crate::rotating_file::RotatingFile;
+
 static CONTROL_HANDLE: OnceCell<ckb_channel::Sender<Message>> = OnceCell::new();
 static RE: OnceCell<regex::Regex> = OnceCell::new();
 
@@ -40,13 +46,14 @@ pub struct Logger {
     handle: Mutex<Option<thread::JoinHandle<()>>>,
     filter: sync::Arc<RwLock<Filter>>,
     emit_sentry_breadcrumbs: bool,
+    format: LogFormat,
     extra_loggers: sync::Arc<RwLock<HashMap<String, ExtraLogger>>>,
 }
 
 #[derive(Debug)]
 pub struct MainLogger {
     file_path: PathBuf,
-    file: Option<fs::File>,
+    file: Option<RotatingFile>,
     to_stdout: bool,
     to_file: bool,
     color: bool,
@@ -128,11 +135,13 @@ impl Logger {
             log_dir,
             log_to_file,
             log_to_stdout,
+            format,
+            rotation,
             ..
         } = config;
         let mut main_logger = {
             let file = if log_to_file {
-                match Self::open_log_file(&file_path) {
+                match RotatingFile::open(file_path.clone(), rotation.clone()) {
                     Err(err) => {
                         eprintln!("Error: {}", err);
                         process::exit(1);
@@ -183,7 +192,8 @@ impl Logger {
                 .keys()
                 .map(|name| {
                     let file_path = log_dir.clone().join(name.to_owned() + ".log");
-                    Self::open_log_file(&file_path).map(|file| (name.to_owned(), file))
+                    RotatingFile::open(file_path, rotation.clone())
+                        .map(|file| (name.to_owned(), file))
                 })
                 .collect::<Result<HashMap<_, _>, _>>();
             if let Err(err) = extra_files_res {
@@ -205,34 +215,34 @@ impl Logger {
                             extras,
                             data,
                         }) => {
-                            let removed_color = if (is_match
-                                && (!main_logger.color || main_logger.to_file))
+                            // JSON records are never colored.
+                            let removed_color = if format == LogFormat::Json {
+                                Cow::Borrowed(data.as_str())
+                            } else if (is_match && (!main_logger.color || main_logger.to_file))
                                 || !extras.is_empty()
                             {
-                                sanitize_color(data.as_ref())
+                                Cow::Owned(sanitize_color(data.as_ref()))
                             } else {
-                                "".to_owned()
+                                Cow::Borrowed("")
                             };
                             if is_match {
                                 if main_logger.to_stdout {
                                     let output = if main_logger.color {
                                         data.as_str()
                                     } else {
-                                        removed_color.as_str()
+                                        removed_color.as_ref()
                                     };
                                     println!("{}", output);
                                 }
                                 if main_logger.to_file {
-                                    if let Some(mut file) = main_logger.file.as_ref() {
-                                        let _ = file.write_all(removed_color.as_bytes());
-                                        let _ = file.write_all(b"\n");
+                                    if let Some(file) = main_logger.file.as_mut() {
+                                        file.write_line(&removed_color);
                                     };
                                 }
                             }
                             for name in extras {
-                                if let Some(mut file) = extra_files.get(&name) {
-                                    let _ = file.write_all(removed_color.as_bytes());
-                                    let _ = file.write_all(b"\n");
+                                if let Some(file) = extra_files.get_mut(&name) {
+                                    file.write_line(&removed_color);
                                 }
                             }
                             continue;
@@ -253,8 +263,11 @@ impl Logger {
                                 main_logger.to_file = to_file;
                                 if main_logger.to_file {
                                     if main_logger.file.is_none() {
-                                        main_logger.file =
-                                            Self::open_log_file(&main_logger.file_path).ok();
+                                        main_logger.file = RotatingFile::open(
+                                            main_logger.file_path.clone(),
+                                            rotation.clone(),
+                                        )
+                                        .ok();
                                     }
                                 } else {
                                     main_logger.file = None;
@@ -266,7 +279,7 @@ impl Logger {
                         }
                         Ok(Message::UpdateExtraLogger(name, filter)) => {
                             let file = log_dir.clone().join(name.clone() + ".log");
-                            let file_res = Self::open_log_file(&file);
+                            let file_res = RotatingFile::open(file, rotation.clone());
                             if let Ok(file) = file_res {
                                 extra_files.insert(name.clone(), file);
                                 extra_loggers_for_update
@@ -296,24 +309,11 @@ impl Logger {
             handle: Mutex::new(Some(tb)),
             filter,
             emit_sentry_breadcrumbs: config.emit_sentry_breadcrumbs.unwrap_or_default(),
+            format,
             extra_loggers,
         }
     }
 
-    fn open_log_file(file_path: &PathBuf) -> Result<fs::File, String> {
-        fs::OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(&file_path)
-            .map_err(|err| {
-                format!(
-                    "Cannot write to log file given: {:?} since {}",
-                    file_path.as_os_str(),
-                    err
-                )
-            })
-    }
-
     fn build_filter(filter_str: &str) -> Filter {
         Builder::new()
             .parse(&convert_compatible_crate_name(filter_str))
@@ -412,24 +412,27 @@ impl Log for Logger {
 
             let thread = thread::current();
             let thread_name = thread.name().unwrap_or("*unnamed*");
-
-            let with_color = {
-                let thread_name = format!("{}", Colour::Blue.bold().paint(thread_name));
-                let dt: DateTime<Local> = Local::now();
-                let timestamp = dt.format("%Y-%m-%d %H:%M:%S%.3f %Z").to_string();
-                format!(
-                    "{} {} {} {}  {}",
-                    Colour::Black.bold().paint(timestamp),
-                    thread_name,
-                    record.level(),
-                    record.target(),
-                    record.args()
-                )
+            let dt: DateTime<Local> = Local::now();
+
+            let data = match self.format {
+                LogFormat::Text => {
+                    let thread_name = format!("{}", Colour::Blue.bold().paint(thread_name));
+                    let timestamp = dt.format("%Y-%m-%d %H:%M:%S%.3f %Z").to_string();
+                    format!(
+                        "{} {} {} {}  {}",
+                        Colour::Black.bold().paint(timestamp),
+                        thread_name,
+                        record.level(),
+                        record.target(),
+                        record.args()
+                    )
+                }
+                LogFormat::Json => json_record(&dt, thread_name, record),
             };
             let _ = self.sender.send(Message::Record {
                 is_match,
                 extras,
-                data: with_color,
+                data,
             });
         }
     }
@@ -441,6 +444,17 @@ impl Log for Logger {
     }
 }
 
+fn json_record(dt: &DateTime<Local>, thread_name: &str, record: &Record) -> String {
+    json!({
+        "timestamp": dt.to_rfc3339_opts(SecondsFormat::Millis, false),
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "thread": thread_name,
+        "message": record.args().to_string(),
+    })
+    .to_string()
+}
+
 fn sanitize_color(s: &str) -> String {
     let re = RE.get_or_init(|| Regex::new("\x1b\\[[^m]+m").expect("Regex compile success"));
     re.replace_all(s, "").to_string()
@@ -502,3 +516,31 @@ fn setup_panic_logger() {
     };
     panic::set_hook(Box::new(panic_logger));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_record() {
+        let dt: DateTime<Local> = Local::now();
+        let data = json_record(
+            &dt,
+            "GlobalRt-1",
+            &Record::builder()
+                .args(format_args!("hello \"world\""))
+                .level(log::Level::Warn)
+                .target("ckb-sync")
+                .build(),
+        );
+        let value: serde_json::Value = serde_json::from_str(&data).unwrap();
+        assert_eq!(value["level"], "WARN");
+        assert_eq!(value["target"], "ckb-sync");
+        assert_eq!(value["thread"], "GlobalRt-1");
+        assert_eq!(value["message"], "hello \"world\"");
+        assert_eq!(
+            value["timestamp"],
+            dt.to_rfc3339_opts(SecondsFormat::Millis, false)
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use ckb_logger_config::RotationConfig;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// A log file which is rotated according to a `RotationConfig`.
+///
+/// The limits are checked before each write, so an idle file is only rotated when the next
+/// record arrives.
+#[derive(Debug)]
+pub(crate) struct RotatingFile {
+    path: PathBuf,
+    file: Option<fs::File>,
+    size: u64,
+    opened_at: SystemTime,
+    rotation: RotationConfig,
+}
+
+impl RotatingFile {
+    pub(crate) fn open(path: PathBuf, rotation: RotationConfig) -> Result<Self, String> {
+        let (file, size, opened_at) = open_log_file(&path).map_err(|err| {
+            format!(
+                "Cannot write to log file given: {:?} since {}",
+                path.as_os_str(),
+                err
+            )
+        })?;
+        Ok(RotatingFile {
+            path,
+            file: Some(file),
+            size,
+            opened_at,
+            rotation,
+        })
+    }
+
+    pub(crate) fn write_line(&mut self, line: &str) {
+        let len = line.len() as u64 + 1;
+        if self.should_rotate(len) {
+            if let Err(err) = self.rotate() {
+                eprintln!(
+                    "Error: failed to rotate log file {:?} since {}",
+                    self.path.as_os_str(),
+                    err
+                );
+            }
+        }
+        if let Some(file) = self.file.as_mut() {
+            let _ = file.write_all(line.as_bytes());
+            let _ = file.write_all(b"\n");
+            self.size += len;
+        }
+    }
+
+    fn should_rotate(&self, incoming: u64) -> bool {
+        // Never rotate an empty file, otherwise a single oversized record would leave an empty
+        // file behind on every write.
+        if self.size == 0 {
+            return false;
+        }
+        let exceeds_size = self
+            .rotation
+            .max_size
+            .map(|max_size| self.size + incoming > max_size)
+            .unwrap_or(false);
+        let exceeds_age = self
+            .rotation
+            .max_age_secs
+            .map(|max_age| {
+                self.opened_at
+                    .elapsed()
+                    .map(|elapsed| elapsed >= Duration::from_secs(max_age))
+                    .unwrap_or(false)
+            })
+            .unwrap_or(false);
+        exceeds_size || exceeds_age
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Close the current file first, renaming an opened file fails on Windows.
+        self.file = None;
+        let result = self.rotate_files();
+        // Reopen the log file even if the rotation fails, so the following records are still
+        // written. After a failure the limits are counted again from now on, instead of
+        // retrying the rotation on every write.
+        let (file, size, opened_at) = open_log_file(&self.path)?;
+        self.file = Some(file);
+        if result.is_ok() {
+            self.size = size;
+            self.opened_at = opened_at;
+        } else {
+            self.size = 0;
+            self.opened_at = SystemTime::now();
+        }
+        result
+    }
+
+    fn rotate_files(&self) -> io::Result<()> {
+        let max_files = self.rotation.max_files;
+        if max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = rotated_path(&self.path, max_files);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for index in (1..max_files).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        Ok(())
+    }
+}
+
+fn open_log_file(path: &Path) -> io::Result<(fs::File, u64, SystemTime)> {
+    let file = fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)?;
+    let metadata = file.metadata()?;
+    // Fall back to now on the platforms which do not record the creation time, the age of
+    // the file is then counted since the logger opens it.
+    let opened_at = metadata.created().unwrap_or_else(|_| SystemTime::now());
+    Ok((file, metadata.len(), opened_at))
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_rotate_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.log");
+        let rotation = RotationConfig {
+            max_size: Some(10),
+            max_age_secs: None,
+            max_files: 2,
+        };
+        let mut file = RotatingFile::open(path.clone(), rotation).unwrap();
+        for line in &["line-1", "line-2", "line-3", "line-4"] {
+            file.write_line(line);
+        }
+
+        assert_eq!(read(&path), "line-4\n");
+        assert_eq!(read(&rotated_path(&path, 1)), "line-3\n");
+        assert_eq!(read(&rotated_path(&path, 2)), "line-2\n");
+        assert!(!rotated_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn test_rotate_without_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.log");
+        let rotation = RotationConfig {
+            max_size: Some(10),
+            max_age_secs: None,
+            max_files: 0,
+        };
+        let mut file = RotatingFile::open(path.clone(), rotation).unwrap();
+        file.write_line("line-1");
+        file.write_line("line-2");
+
+        assert_eq!(read(&path), "line-2\n");
+        assert!(!rotated_path(&path, 1).exists());
+    }
+
+    #[test]
+    fn test_rotate_by_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.log");
+        let rotation = RotationConfig {
+            max_size: None,
+            max_age_secs: Some(0),
+            max_files: 1,
+        };
+        let mut file = RotatingFile::open(path.clone(), rotation).unwrap();
+        file.write_line("line-1");
+        file.write_line("line-2");
+
+        assert_eq!(read(&path), "line-2\n");
+        assert_eq!(read(&rotated_path(&path, 1)), "line-1\n");
+    }
+
+    #[test]
+    fn test_keep_writing_after_failed_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.log");
+        let rotation = RotationConfig {
+            max_size: Some(10),
+            max_age_secs: None,
+            max_files: 1,
+        };
+        // a directory in place of the rotated file fails the rotation
+        fs::create_dir(rotated_path(&path, 1)).unwrap();
+        fs::write(rotated_path(&path, 1).join("keep"), "").unwrap();
+        let mut file = RotatingFile::open(path.clone(), rotation).unwrap();
+        file.write_line("line-1");
+        file.write_line("line-2");
+        file.write_line("line-3");
+
+        assert_eq!(read(&path), "line-1\nline-2\nline-3\n");
+        assert!(rotated_path(&path, 1).is_dir());
+    }
+
+    #[test]
+    fn test_disabled_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.log");
+        let mut file = RotatingFile::open(path.clone(), RotationConfig::default()).unwrap();
+        file.write_line("line-1");
+        file.write_line("line-2");
+
+        assert_eq!(read(&path), "line-1\nline-2\n");
+        assert!(!rotated_path(&path, 1).exists());
+    }
+}
@@ -6,13 +6,111 @@ use ckb_error::Error;
 use ckb_logger::debug;
 use ckb_store::ChainStore;
 use ckb_types::{
-    core::{BlockReward, Capacity, HeaderView},
-    packed::{Byte32, CellbaseWitness, ProposalShortId, Script},
+    core::{BlockNumber, BlockReward, Capacity, HeaderView},
+    packed::{Byte32, CellOutput, CellbaseWitness, ProposalShortId, Script, SplitCellbaseWitness},
     prelude::*,
 };
 use std::cmp;
 use std::collections::HashSet;
 
+/// The locks which the finalized block reward goes to, specified in the target cellbase witness.
+///
+/// `lock` is the lock of `CellbaseWitness`; once the split reward switch is activated,
+/// `SplitCellbaseWitness` is also accepted and the reward is split across `shares` by weight.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RewardTarget {
+    pub lock: Script,
+    pub shares: Vec<(Script, u32)>,
+}
+
+impl RewardTarget {
+    pub fn new(lock: Script) -> Self {
+        RewardTarget {
+            lock,
+            shares: Vec::new(),
+        }
+    }
+
+    /// Parse the cellbase witness of the block `number`,
+    /// `SplitCellbaseWitness` is only recognized if the switch is activated at `number`.
+    pub fn from_witness(
+        consensus: &Consensus,
+        number: BlockNumber,
+        witness: &[u8],
+    ) -> Option<Self> {
+        if consensus.is_split_reward_enabled(number) {
+            if let Ok(split) = SplitCellbaseWitness::from_slice(witness) {
+                let shares = split
+                    .shares()
+                    .into_iter()
+                    .map(|share| (share.lock(), share.weight().unpack()))
+                    .collect();
+                return Some(RewardTarget {
+                    lock: split.lock(),
+                    shares,
+                });
+            }
+        }
+        CellbaseWitness::from_slice(witness)
+            .ok()
+            .map(|witness| RewardTarget::new(witness.lock()))
+    }
+
+    /// Build the cellbase outputs which collect the `total` reward.
+    ///
+    /// The reward is split across the shares in proportion to their weights, the rounding
+    /// remainder goes to the first share. If any share is insufficient to create a cell, the
+    /// whole reward goes to `lock` instead; if even that is insufficient, there is no output.
+    pub fn outputs(&self, total: Capacity) -> Result<Vec<CellOutput>, Error> {
+        let total_weight: u64 = self
+            .shares
+            .iter()
+            .map(|(_, weight)| u64::from(*weight))
+            .sum();
+        if total_weight > 0 {
+            let mut capacities: Vec<u64> = self
+                .shares
+                .iter()
+                .map(|(_, weight)| {
+                    (u128::from(total.as_u64()) * u128::from(*weight) / u128::from(total_weight))
+                        as u64
+                })
+                .collect();
+            let distributed: u64 = capacities.iter().sum();
+            capacities[0] += total.as_u64() - distributed;
+
+            let outputs = self
+                .shares
+                .iter()
+                .zip(capacities.into_iter())
+                .map(|((lock, _), capacity)| {
+                    CellOutput::new_builder()
+                        .capacity(capacity.pack())
+                        .lock(lock.clone())
+                        .build()
+                })
+                .collect::<Vec<_>>();
+            let mut insufficient = false;
+            for output in &outputs {
+                insufficient |= output.is_lack_of_capacity(Capacity::zero())?;
+            }
+            if !insufficient {
+                return Ok(outputs);
+            }
+        }
+
+        let output = CellOutput::new_builder()
+            .capacity(total.pack())
+            .lock(self.lock.clone())
+            .build();
+        if output.is_lack_of_capacity(Capacity::zero())? {
+            Ok(Vec::new())
+        } else {
+            Ok(vec![output])
+        }
+    }
+}
+
 pub struct RewardCalculator<'a, CS> {
     pub consensus: &'a Consensus,
     pub store: &'a CS,
@@ -29,6 +127,15 @@ impl<'a, CS: ChainStore<'a>> RewardCalculator<'a, CS> {
         &self,
         parent: &HeaderView,
     ) -> Result<(Script, BlockReward), Error> {
+        self.block_reward_target_to_finalize(parent)
+            .map(|(target, block_reward)| (target.lock, block_reward))
+    }
+
+    /// Same as `block_reward_to_finalize`, but returns all the locks which share the block reward.
+    pub fn block_reward_target_to_finalize(
+        &self,
+        parent: &HeaderView,
+    ) -> Result<(RewardTarget, BlockReward), Error> {
         let block_number = parent.number() + 1;
         let target_number = self
             .consensus
@@ -54,14 +161,17 @@ impl<'a, CS: ChainStore<'a>> RewardCalculator<'a, CS> {
             .and_then(|hash| self.store.get_block_header(&hash))
             .expect("block hash checked before involving get_ancestor");
         self.block_reward_internal(target, &parent)
+            .map(|(reward_target, block_reward)| (reward_target.lock, block_reward))
     }
 
     fn block_reward_internal(
         &self,
         target: &HeaderView,
         parent: &HeaderView,
-    ) -> Result<(Script, BlockReward), Error> {
-        let target_lock = CellbaseWitness::from_slice(
+    ) -> Result<(RewardTarget, BlockReward), Error> {
+        let reward_target = RewardTarget::from_witness(
+            self.consensus,
+            target.number(),
             &self
                 .store
                 .get_cellbase(&target.hash())
@@ -71,8 +181,7 @@ impl<'a, CS: ChainStore<'a>> RewardCalculator<'a, CS> {
                 .expect("target witness exist")
                 .raw_data(),
         )
-        .expect("cellbase loaded from store should has non-empty witness")
-        .lock();
+        .expect("cellbase loaded from store should has non-empty witness");
 
         let txs_fees = self.txs_fees(target)?;
         let proposal_reward = self.proposal_reward(parent, target)?;
@@ -103,7 +212,7 @@ impl<'a, CS: ChainStore<'a>> RewardCalculator<'a, CS> {
             proposal_reward,
         };
 
-        Ok((target_lock, block_reward))
+        Ok((reward_target, block_reward))
     }
 
     /// Miner get (tx_fee - 40% of tx fee) for tx commitment.
@@ -260,14 +369,20 @@ impl<'a, CS: ChainStore<'a>> RewardCalculator<'a, CS> {
 
 #[cfg(test)]
 mod tests {
-    use super::RewardCalculator;
+    use super::{RewardCalculator, RewardTarget};
     use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder, ProposalWindow};
     use ckb_db::RocksDB;
     use ckb_occupied_capacity::AsCapacity;
     use ckb_store::{ChainDB, ChainStore, COLUMNS};
     use ckb_types::{
-        core::{BlockBuilder, BlockExt, HeaderBuilder, TransactionBuilder},
-        packed::ProposalShortId,
+        bytes::Bytes,
+        core::{
+            capacity_bytes, BlockBuilder, BlockExt, Capacity, HeaderBuilder, TransactionBuilder,
+        },
+        packed::{
+            CellbaseWitness, ProposalShortId, RewardShare, RewardShareVec, Script,
+            SplitCellbaseWitness,
+        },
         prelude::*,
     };
     use std::collections::HashSet;
@@ -557,4 +672,96 @@ mod tests {
 
         assert_eq!(proposal_reward, expected.as_capacity());
     }
+
+    fn lock(arg: u8) -> Script {
+        Script::new_builder()
+            .args(Bytes::from(vec![arg; 20]).pack())
+            .build()
+    }
+
+    #[test]
+    fn test_reward_target_from_witness() {
+        let shares = RewardShareVec::new_builder()
+            .push(
+                RewardShare::new_builder()
+                    .lock(lock(2))
+                    .weight(1u32.pack())
+                    .build(),
+            )
+            .push(
+                RewardShare::new_builder()
+                    .lock(lock(3))
+                    .weight(3u32.pack())
+                    .build(),
+            )
+            .build();
+        let split = SplitCellbaseWitness::new_builder()
+            .lock(lock(1))
+            .shares(shares)
+            .build();
+        let plain = CellbaseWitness::new_builder().lock(lock(1)).build();
+
+        let disabled = Consensus::default();
+        let enabled = ConsensusBuilder::default()
+            .split_reward_activation(Some(10))
+            .build();
+
+        assert_eq!(
+            RewardTarget::from_witness(&disabled, 10, plain.as_slice()),
+            Some(RewardTarget::new(lock(1)))
+        );
+        assert_eq!(
+            RewardTarget::from_witness(&disabled, 10, split.as_slice()),
+            None
+        );
+        assert_eq!(
+            RewardTarget::from_witness(&enabled, 9, split.as_slice()),
+            None
+        );
+        assert_eq!(
+            RewardTarget::from_witness(&enabled, 10, split.as_slice()),
+            Some(RewardTarget {
+                lock: lock(1),
+                shares: vec![(lock(2), 1), (lock(3), 3)],
+            })
+        );
+        assert_eq!(
+            RewardTarget::from_witness(&enabled, 10, plain.as_slice()),
+            Some(RewardTarget::new(lock(1)))
+        );
+    }
+
+    #[test]
+    fn test_reward_target_outputs() {
+        let target = RewardTarget {
+            lock: lock(1),
+            shares: vec![(lock(2), 1), (lock(3), 2)],
+        };
+
+        // the rounding remainder goes to the first share
+        let total = Capacity::shannons(capacity_bytes!(1000).as_u64() + 1);
+        let outputs = target.outputs(total).unwrap();
+        let capacities = outputs
+            .iter()
+            .map(|output| output.capacity().unpack())
+            .collect::<Vec<Capacity>>();
+        assert_eq!(
+            capacities,
+            vec![
+                Capacity::shannons(33_333_333_334),
+                Capacity::shannons(66_666_666_667),
+            ]
+        );
+        assert_eq!(outputs[0].lock(), lock(2));
+        assert_eq!(outputs[1].lock(), lock(3));
+
+        // a share is insufficient to create a cell, the whole reward goes to the lock
+        let outputs = target.outputs(capacity_bytes!(100)).unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].lock(), lock(1));
+        assert_eq!(outputs[0].capacity().unpack(), capacity_bytes!(100));
+
+        // insufficient to create any cell
+        assert!(target.outputs(capacity_bytes!(10)).unwrap().is_empty());
+    }
 }
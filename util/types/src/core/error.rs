@@ -31,6 +31,20 @@ pub enum OutPointError {
     ImmatureHeader(Byte32),
 }
 
+impl OutPointError {
+    /// The variant name
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutPointError::Dead(..) => "Dead",
+            OutPointError::Unknown(..) => "Unknown",
+            OutPointError::OutOfOrder(..) => "OutOfOrder",
+            OutPointError::InvalidDepGroup(..) => "InvalidDepGroup",
+            OutPointError::InvalidHeader(..) => "InvalidHeader",
+            OutPointError::ImmatureHeader(..) => "ImmatureHeader",
+        }
+    }
+}
+
 impl From<OutPointError> for Error {
     fn from(error: OutPointError) -> Self {
         error.context(ErrorKind::OutPoint).into()
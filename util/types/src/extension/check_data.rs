@@ -96,6 +96,12 @@ impl<'r> packed::RelayTransactionsReader<'r> {
     }
 }
 
+impl<'r> packed::RelayTransactionPackageReader<'r> {
+    pub fn check_data(&self) -> bool {
+        self.transactions().check_data()
+    }
+}
+
 impl<'r> packed::SendBlockReader<'r> {
     pub fn check_data(&self) -> bool {
         self.block().check_data()
@@ -1,12 +1,12 @@
 use crate::header_verifier::HeaderResolver;
 pub use crate::transaction_verifier::NonContextualTransactionVerifier;
 use crate::{BlockErrorKind, CellbaseError, Verifier};
-use ckb_chain_spec::consensus::Consensus;
+use ckb_chain_spec::consensus::{Consensus, MAX_REWARD_SHARES};
 use ckb_error::Error;
 use ckb_store::ChainStore;
 use ckb_types::{
     core::{BlockView, HeaderView},
-    packed::{CellInput, CellbaseWitness},
+    packed::{CellInput, CellbaseWitness, SplitCellbaseWitness},
     prelude::*,
 };
 use std::collections::HashSet;
@@ -31,18 +31,20 @@ impl<'a> Verifier for BlockVerifier<'a> {
         let max_block_bytes = self.consensus.max_block_bytes();
         BlockProposalsLimitVerifier::new(max_block_proposals_limit).verify(target)?;
         BlockBytesVerifier::new(max_block_bytes).verify(target)?;
-        CellbaseVerifier::new().verify(target)?;
+        CellbaseVerifier::new(self.consensus).verify(target)?;
         DuplicateVerifier::new().verify(target)?;
         MerkleRootVerifier::new().verify(target)
     }
 }
 
 #[derive(Clone)]
-pub struct CellbaseVerifier {}
+pub struct CellbaseVerifier<'a> {
+    consensus: &'a Consensus,
+}
 
-impl CellbaseVerifier {
-    pub fn new() -> Self {
-        CellbaseVerifier {}
+impl<'a> CellbaseVerifier<'a> {
+    pub fn new(consensus: &'a Consensus) -> Self {
+        CellbaseVerifier { consensus }
     }
 
     pub fn verify(&self, block: &BlockView) -> Result<(), Error> {
@@ -67,9 +69,18 @@ impl CellbaseVerifier {
             return Err((CellbaseError::InvalidPosition).into());
         }
 
-        // cellbase outputs/outputs_data len must le 1
-        if cellbase_transaction.outputs().len() > 1
-            || cellbase_transaction.outputs_data().len() > 1
+        // cellbase outputs/outputs_data len must le 1,
+        // or le MAX_REWARD_SHARES once the split reward is enabled
+        let split_reward_enabled = self
+            .consensus
+            .is_split_reward_enabled(block.header().number());
+        let max_outputs = if split_reward_enabled {
+            MAX_REWARD_SHARES
+        } else {
+            1
+        };
+        if cellbase_transaction.outputs().len() > max_outputs
+            || cellbase_transaction.outputs_data().len() > max_outputs
             || cellbase_transaction.outputs().len() != cellbase_transaction.outputs_data().len()
         {
             return Err((CellbaseError::InvalidOutputQuantity).into());
@@ -78,18 +89,17 @@ impl CellbaseVerifier {
         // cellbase output data must empty
         if !cellbase_transaction
             .outputs_data()
-            .get(0)
-            .map(|data| data.is_empty())
-            .unwrap_or(true)
+            .into_iter()
+            .all(|data| data.is_empty())
         {
             return Err((CellbaseError::InvalidOutputData).into());
         }
 
-        if cellbase_transaction
+        if !cellbase_transaction
             .witnesses()
             .get(0)
-            .and_then(|witness| CellbaseWitness::from_slice(&witness.raw_data()).ok())
-            .is_none()
+            .map(|witness| is_valid_cellbase_witness(&witness.raw_data(), split_reward_enabled))
+            .unwrap_or(false)
         {
             return Err((CellbaseError::InvalidWitness).into());
         }
@@ -114,6 +124,24 @@ impl CellbaseVerifier {
     }
 }
 
+fn is_valid_cellbase_witness(witness: &[u8], split_reward_enabled: bool) -> bool {
+    if CellbaseWitness::from_slice(witness).is_ok() {
+        return true;
+    }
+    split_reward_enabled
+        && SplitCellbaseWitness::from_slice(witness)
+            .map(|split| {
+                let shares = split.shares();
+                !shares.is_empty()
+                    && shares.len() <= MAX_REWARD_SHARES
+                    && shares.into_iter().all(|share| {
+                        let weight: u32 = share.weight().unpack();
+                        weight > 0
+                    })
+            })
+            .unwrap_or(false)
+}
+
 #[derive(Clone)]
 pub struct DuplicateVerifier {}
 
@@ -10,17 +10,17 @@ use ckb_chain_spec::consensus::Consensus;
 use ckb_dao::DaoCalculator;
 use ckb_error::Error;
 use ckb_logger::error_target;
-use ckb_reward_calculator::RewardCalculator;
+use ckb_reward_calculator::{RewardCalculator, RewardTarget};
 use ckb_store::ChainStore;
 use ckb_traits::{BlockMedianTimeContext, HeaderProvider};
 use ckb_types::{
     core::error::OutPointError,
     core::{
         cell::{HeaderChecker, ResolvedTransaction},
-        BlockNumber, BlockReward, BlockView, Capacity, Cycle, EpochExt, EpochNumberWithFraction,
-        HeaderView, TransactionView,
+        BlockNumber, BlockReward, BlockView, Cycle, EpochExt, EpochNumberWithFraction, HeaderView,
+        TransactionView,
     },
-    packed::{Byte32, CellOutput, Script},
+    packed::Byte32,
     prelude::*,
 };
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
@@ -46,8 +46,11 @@ impl<'a, CS: ChainStore<'a>> VerifyContext<'a, CS> {
         VerifyContext { store, consensus }
     }
 
-    fn finalize_block_reward(&self, parent: &HeaderView) -> Result<(Script, BlockReward), Error> {
-        RewardCalculator::new(self.consensus, self.store).block_reward_to_finalize(parent)
+    fn finalize_block_reward(
+        &self,
+        parent: &HeaderView,
+    ) -> Result<(RewardTarget, BlockReward), Error> {
+        RewardCalculator::new(self.consensus, self.store).block_reward_target_to_finalize(parent)
     }
 
     fn next_epoch_ext(&self, last_epoch: &EpochExt, header: &HeaderView) -> Option<EpochExt> {
@@ -247,14 +250,10 @@ impl<'a, 'b, CS: ChainStore<'a>> RewardVerifier<'a, 'b, CS> {
         let no_finalization_target =
             (self.parent.number() + 1) <= self.context.consensus.finalization_delay_length();
 
-        let (target_lock, block_reward) = self.context.finalize_block_reward(self.parent)?;
-        let output = CellOutput::new_builder()
-            .capacity(block_reward.total.pack())
-            .lock(target_lock.clone())
-            .build();
-        let insufficient_reward_to_create_cell = output.is_lack_of_capacity(Capacity::zero())?;
+        let (target, block_reward) = self.context.finalize_block_reward(self.parent)?;
+        let expected_outputs = target.outputs(block_reward.total)?;
 
-        if no_finalization_target || insufficient_reward_to_create_cell {
+        if no_finalization_target || expected_outputs.is_empty() {
             let ret = if cellbase.transaction.outputs().is_empty() {
                 Ok(())
             } else {
@@ -263,20 +262,27 @@ impl<'a, 'b, CS: ChainStore<'a>> RewardVerifier<'a, 'b, CS> {
             return ret;
         }
 
-        if !insufficient_reward_to_create_cell {
-            if cellbase.transaction.outputs_capacity()? != block_reward.total {
-                return Err((CellbaseError::InvalidRewardAmount).into());
-            }
-            if cellbase
-                .transaction
-                .outputs()
-                .get(0)
-                .expect("cellbase should have output")
-                .lock()
-                != target_lock
-            {
-                return Err((CellbaseError::InvalidRewardTarget).into());
-            }
+        if cellbase.transaction.outputs_capacity()? != block_reward.total {
+            return Err((CellbaseError::InvalidRewardAmount).into());
+        }
+        let outputs = cellbase.transaction.outputs();
+        if outputs.len() != expected_outputs.len()
+            || outputs
+                .clone()
+                .into_iter()
+                .zip(expected_outputs.iter())
+                .any(|(output, expected)| output.lock() != expected.lock())
+        {
+            return Err((CellbaseError::InvalidRewardTarget).into());
+        }
+        if outputs
+            .into_iter()
+            .zip(expected_outputs.iter())
+            .any(|(output, expected)| {
+                output.capacity().as_slice() != expected.capacity().as_slice()
+            })
+        {
+            return Err((CellbaseError::InvalidRewardAmount).into());
         }
 
         Ok(())
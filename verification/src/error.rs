@@ -291,6 +291,83 @@ pub enum EpochError {
     NumberMismatch { expected: u64, actual: u64 },
 }
 
+impl CommitError {
+    /// The variant name, reported in the block verification errors of the RPC
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommitError::AncestorNotFound => "AncestorNotFound",
+            CommitError::Invalid => "Invalid",
+        }
+    }
+}
+
+impl CellbaseError {
+    /// The variant name, reported in the block verification errors of the RPC
+    pub fn name(&self) -> &'static str {
+        match self {
+            CellbaseError::InvalidInput => "InvalidInput",
+            CellbaseError::InvalidRewardAmount => "InvalidRewardAmount",
+            CellbaseError::InvalidRewardTarget => "InvalidRewardTarget",
+            CellbaseError::InvalidWitness => "InvalidWitness",
+            CellbaseError::InvalidTypeScript => "InvalidTypeScript",
+            CellbaseError::InvalidOutputQuantity => "InvalidOutputQuantity",
+            CellbaseError::InvalidQuantity => "InvalidQuantity",
+            CellbaseError::InvalidPosition => "InvalidPosition",
+            CellbaseError::InvalidOutputData => "InvalidOutputData",
+        }
+    }
+}
+
+impl UnclesError {
+    /// The variant name, reported in the block verification errors of the RPC
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnclesError::OverCount { .. } => "OverCount",
+            UnclesError::InvalidDepth { .. } => "InvalidDepth",
+            UnclesError::InvalidHash { .. } => "InvalidHash",
+            UnclesError::InvalidNumber => "InvalidNumber",
+            UnclesError::InvalidTarget => "InvalidTarget",
+            UnclesError::InvalidDifficultyEpoch => "InvalidDifficultyEpoch",
+            UnclesError::ProposalsHash => "ProposalsHash",
+            UnclesError::ProposalDuplicate => "ProposalDuplicate",
+            UnclesError::Duplicate(..) => "Duplicate",
+            UnclesError::DoubleInclusion(..) => "DoubleInclusion",
+            UnclesError::DescendantLimit => "DescendantLimit",
+            UnclesError::ExceededMaximumProposalsLimit => "ExceededMaximumProposalsLimit",
+        }
+    }
+}
+
+impl PowError {
+    /// The variant name, reported in the block verification errors of the RPC
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowError::Boundary { .. } => "Boundary",
+            PowError::InvalidNonce => "InvalidNonce",
+        }
+    }
+}
+
+impl TimestampError {
+    /// The variant name, reported in the block verification errors of the RPC
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimestampError::BlockTimeTooOld { .. } => "BlockTimeTooOld",
+            TimestampError::BlockTimeTooNew { .. } => "BlockTimeTooNew",
+        }
+    }
+}
+
+impl EpochError {
+    /// The variant name, reported in the block verification errors of the RPC
+    pub fn name(&self) -> &'static str {
+        match self {
+            EpochError::TargetMismatch { .. } => "TargetMismatch",
+            EpochError::NumberMismatch { .. } => "NumberMismatch",
+        }
+    }
+}
+
 impl TransactionError {
     pub fn is_malformed_tx(&self) -> bool {
         match self {
@@ -308,6 +385,23 @@ impl TransactionError {
             | TransactionError::MismatchedVersion { .. } => false,
         }
     }
+
+    /// The variant name, reported in the block verification errors of the RPC
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionError::InsufficientCellCapacity { .. } => "InsufficientCellCapacity",
+            TransactionError::OutputsSumOverflow { .. } => "OutputsSumOverflow",
+            TransactionError::Empty { .. } => "Empty",
+            TransactionError::DuplicateCellDeps { .. } => "DuplicateCellDeps",
+            TransactionError::DuplicateHeaderDeps { .. } => "DuplicateHeaderDeps",
+            TransactionError::OutputsDataLengthMismatch { .. } => "OutputsDataLengthMismatch",
+            TransactionError::InvalidSince { .. } => "InvalidSince",
+            TransactionError::Immature { .. } => "Immature",
+            TransactionError::CellbaseImmaturity { .. } => "CellbaseImmaturity",
+            TransactionError::MismatchedVersion { .. } => "MismatchedVersion",
+            TransactionError::ExceededMaximumBlockBytes { .. } => "ExceededMaximumBlockBytes",
+        }
+    }
 }
 
 impl fmt::Display for HeaderError {
@@ -24,7 +24,9 @@ pub use crate::error::{
     PowError, TimestampError, TransactionError, UnclesError, UnknownParentError,
 };
 pub use crate::genesis_verifier::GenesisVerifier;
-pub use crate::header_verifier::{HeaderResolver, HeaderVerifier};
+pub use crate::header_verifier::{
+    HeaderResolver, HeaderVerifier, NumberVerifier, TimestampVerifier, VersionVerifier,
+};
 pub use crate::transaction_verifier::{
     ContextualTransactionVerifier, NonContextualTransactionVerifier, ScriptVerifier, Since,
     SinceMetric, TimeRelativeTransactionVerifier, TransactionVerifier,
@@ -4,6 +4,7 @@ use super::super::block_verifier::{
 };
 use super::super::contextual_block_verifier::EpochVerifier;
 use crate::{BlockErrorKind, CellbaseError, EpochError};
+use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder};
 use ckb_error::assert_error_eq;
 use ckb_types::{
     bytes::Bytes,
@@ -12,7 +13,10 @@ use ckb_types::{
         TransactionBuilder, TransactionView,
     },
     h256,
-    packed::{Byte32, CellInput, CellOutputBuilder, OutPoint, ProposalShortId, Script},
+    packed::{
+        Byte32, CellInput, CellOutputBuilder, OutPoint, ProposalShortId, RewardShare,
+        RewardShareVec, Script, SplitCellbaseWitness,
+    },
     prelude::*,
     utilities::DIFF_TWO,
     H256,
@@ -111,7 +115,8 @@ pub fn test_block_without_cellbase() {
         .header(HeaderBuilder::default().number(1u64.pack()).build())
         .transaction(TransactionBuilder::default().build())
         .build();
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert_error_eq!(
         verifier.verify(&block).unwrap_err(),
         CellbaseError::InvalidQuantity,
@@ -128,7 +133,8 @@ pub fn test_block_with_one_cellbase_at_first() {
         .transaction(transaction)
         .build();
 
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert!(verifier.verify(&block).is_ok());
 }
 
@@ -139,7 +145,8 @@ pub fn test_block_with_correct_cellbase_number() {
         .transaction(create_cellbase_transaction_with_block_number(2))
         .build();
 
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert!(verifier.verify(&block).is_ok());
 }
 
@@ -150,7 +157,8 @@ pub fn test_block_with_incorrect_cellbase_number() {
         .transaction(create_cellbase_transaction_with_block_number(3))
         .build();
 
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert_error_eq!(
         verifier.verify(&block).unwrap_err(),
         CellbaseError::InvalidInput,
@@ -165,7 +173,8 @@ pub fn test_block_with_one_cellbase_at_last() {
         .transaction(create_cellbase_transaction())
         .build();
 
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert_error_eq!(
         verifier.verify(&block).unwrap_err(),
         CellbaseError::InvalidPosition,
@@ -178,7 +187,8 @@ pub fn test_cellbase_with_non_empty_output_data() {
         .header(HeaderBuilder::default().number(2u64.pack()).build())
         .transaction(create_cellbase_transaction_with_non_empty_output_data())
         .build();
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert_error_eq!(
         verifier.verify(&block).unwrap_err(),
         CellbaseError::InvalidOutputData,
@@ -187,6 +197,7 @@ pub fn test_cellbase_with_non_empty_output_data() {
 
 #[test]
 pub fn test_cellbase_without_output() {
+    let consensus = Consensus::default();
     // without_output
     let cellbase_without_output = TransactionBuilder::default()
         .input(CellInput::new_cellbase_input(2u64))
@@ -196,7 +207,7 @@ pub fn test_cellbase_without_output() {
         .header(HeaderBuilder::default().number(2u64.pack()).build())
         .transaction(cellbase_without_output)
         .build();
-    let result = CellbaseVerifier::new().verify(&block);
+    let result = CellbaseVerifier::new(&consensus).verify(&block);
     assert!(result.is_ok(), "Unexpected error {:?}", result);
 
     // only output_data
@@ -209,7 +220,7 @@ pub fn test_cellbase_without_output() {
         .header(HeaderBuilder::default().number(2u64.pack()).build())
         .transaction(cellbase_without_output)
         .build();
-    let result = CellbaseVerifier::new().verify(&block);
+    let result = CellbaseVerifier::new(&consensus).verify(&block);
     assert_error_eq!(result.unwrap_err(), CellbaseError::InvalidOutputQuantity);
 
     // only output
@@ -226,7 +237,7 @@ pub fn test_cellbase_without_output() {
         .header(HeaderBuilder::default().number(2u64.pack()).build())
         .transaction(cellbase_without_output)
         .build();
-    let result = CellbaseVerifier::new().verify(&block);
+    let result = CellbaseVerifier::new(&consensus).verify(&block);
     assert_error_eq!(result.unwrap_err(), CellbaseError::InvalidOutputQuantity);
 }
 
@@ -236,7 +247,8 @@ pub fn test_cellbase_with_two_output() {
         .header(HeaderBuilder::default().number(2u64.pack()).build())
         .transaction(create_cellbase_transaction_with_two_output())
         .build();
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert_error_eq!(
         verifier.verify(&block).unwrap_err(),
         CellbaseError::InvalidOutputQuantity,
@@ -249,13 +261,69 @@ pub fn test_cellbase_with_two_output_data() {
         .header(HeaderBuilder::default().number(2u64.pack()).build())
         .transaction(create_cellbase_transaction_with_two_output_data())
         .build();
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert_error_eq!(
         verifier.verify(&block).unwrap_err(),
         CellbaseError::InvalidOutputQuantity,
     )
 }
 
+fn create_split_cellbase_transaction(number: BlockNumber, weights: &[u32]) -> TransactionView {
+    let shares = weights
+        .iter()
+        .map(|weight| RewardShare::new_builder().weight((*weight).pack()).build())
+        .collect::<Vec<_>>();
+    let witness = SplitCellbaseWitness::new_builder()
+        .shares(RewardShareVec::new_builder().set(shares).build())
+        .build();
+    let output = CellOutputBuilder::default()
+        .capacity(capacity_bytes!(100).pack())
+        .build();
+    TransactionBuilder::default()
+        .input(CellInput::new_cellbase_input(number))
+        .outputs(vec![output; weights.len()])
+        .outputs_data(vec![Bytes::new().pack(); weights.len()])
+        .witness(witness.as_bytes().pack())
+        .build()
+}
+
+#[test]
+pub fn test_cellbase_with_split_reward() {
+    let block = BlockBuilder::default()
+        .header(HeaderBuilder::default().number(2u64.pack()).build())
+        .transaction(create_split_cellbase_transaction(2, &[1, 2]))
+        .build();
+
+    let consensus = ConsensusBuilder::default()
+        .split_reward_activation(Some(3))
+        .build();
+    assert_error_eq!(
+        CellbaseVerifier::new(&consensus)
+            .verify(&block)
+            .unwrap_err(),
+        CellbaseError::InvalidOutputQuantity,
+    );
+
+    let consensus = ConsensusBuilder::default()
+        .split_reward_activation(Some(2))
+        .build();
+    let result = CellbaseVerifier::new(&consensus).verify(&block);
+    assert!(result.is_ok(), "Unexpected error {:?}", result);
+
+    // share with zero weight
+    let block = BlockBuilder::default()
+        .header(HeaderBuilder::default().number(2u64.pack()).build())
+        .transaction(create_split_cellbase_transaction(2, &[0]))
+        .build();
+    assert_error_eq!(
+        CellbaseVerifier::new(&consensus)
+            .verify(&block)
+            .unwrap_err(),
+        CellbaseError::InvalidWitness,
+    );
+}
+
 #[test]
 pub fn test_block_with_duplicated_txs() {
     let tx = create_normal_transaction();
@@ -331,7 +399,8 @@ pub fn test_block_with_two_cellbases() {
         .transaction(create_cellbase_transaction())
         .build();
 
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert_error_eq!(
         verifier.verify(&block).unwrap_err(),
         CellbaseError::InvalidQuantity,
@@ -349,7 +418,8 @@ pub fn test_cellbase_with_less_reward() {
         .transaction(transaction)
         .build();
 
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert!(verifier.verify(&block).is_ok());
 }
 
@@ -364,7 +434,8 @@ pub fn test_cellbase_with_fee() {
         .transaction(transaction)
         .build();
 
-    let verifier = CellbaseVerifier::new();
+    let consensus = Consensus::default();
+    let verifier = CellbaseVerifier::new(&consensus);
     assert!(verifier.verify(&block).is_ok());
 }
 
@@ -4,6 +4,7 @@ use crate::TransactionError;
 use ckb_chain_spec::consensus::Consensus;
 use ckb_dao::DaoCalculator;
 use ckb_error::Error;
+use ckb_metrics::{metrics, Timer};
 use ckb_script::TransactionScriptsVerifier;
 use ckb_store::{data_loader_wrapper::DataLoaderWrapper, ChainStore};
 use ckb_traits::BlockMedianTimeContext;
@@ -277,7 +278,15 @@ impl<'a, CS: ChainStore<'a>> ScriptVerifier<'a, CS> {
 
     pub fn verify(&self, max_cycles: Cycle) -> Result<Cycle, Error> {
         let data_loader = DataLoaderWrapper::new(self.chain_store);
-        TransactionScriptsVerifier::new(&self.resolved_transaction, &data_loader).verify(max_cycles)
+        let timer = Timer::start();
+        let ret = TransactionScriptsVerifier::new(&self.resolved_transaction, &data_loader)
+            .verify(max_cycles);
+        let result = if ret.is_ok() { "ok" } else { "error" };
+        metrics!(timing, "ckb-script.verify", timer.stop(), "result" => result);
+        if let Ok(cycles) = ret {
+            metrics!(value, "ckb-script.cycles", cycles);
+        }
+        ret
     }
 }
 